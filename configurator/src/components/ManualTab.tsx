@@ -181,6 +181,8 @@ const apps: ManualAppData[] = [
       "MIDI Channel 2",
       "MIDI Channel 3",
       "MIDI Channel 4",
+      "Transpose Jack",
+      "Transpose Range",
     ],
     storage: [
       "Sequences (Gate/CV)",
@@ -191,7 +193,7 @@ const apps: ManualAppData[] = [
       "Ranges",
       "Octaves",
     ],
-    text: "4x16 step sequencer app featuring four independent sequencers, each represented by a distinct color. Each sequencer has two pages, and you can navigate between them using Shift + Buttons. The CV/Gate outputs are paired per sequencer: jacks 1&2 for sequencer 1, 3&4 for sequencer 2, and so on. MIDI channels for each sequencer can be set individually in the parameters. Faders are used to set note values, buttons define the gate pattern, and long button presses enable legato. Shift modifies settings for the selected sequencer: Shift + Fader 1 sets step length, Fader 2 sets gate length, Fader 3 selects octave, Fader 4 defines the sequence range (1–5 octaves), and Fader 5 sets the sequence resolution (32ndT, 32nd, 16thT, 16th, 8thT, 8th, 4thT, 4th). In Shift mode, resolution type is color-coded on sequence LEDs: orange for triplet divisions and blue for straight divisions. Buttons are used to select pages, with two pages available per sequencer. The output of each sequencer is quantized to the scale set in the global quantizer.",
+    text: "4x16 step sequencer app featuring four independent sequencers, each represented by a distinct color. Each sequencer has two pages, and you can navigate between them using Shift + Buttons. The CV/Gate outputs are paired per sequencer: jacks 1&2 for sequencer 1, 3&4 for sequencer 2, and so on. MIDI channels for each sequencer can be set individually in the parameters. Faders are used to set note values, buttons define the gate pattern, and long button presses enable legato. Shift modifies settings for the selected sequencer: Shift + Fader 1 sets step length, Fader 2 sets gate length, Fader 3 selects octave, Fader 4 defines the sequence range (1–5 octaves), Fader 5 sets the sequence resolution (32ndT, 32nd, 16thT, 16th, 8thT, 8th, 4thT, 4th), Fader 6 sets the probability of the last pressed step playing, and Fader 7 sets the playback direction, from bottom to top: forward, reverse, ping-pong and random. Random never plays the same step twice in a row. In Shift mode, resolution type is color-coded on sequence LEDs: orange for triplet divisions and blue for straight divisions. Buttons are used to select pages, with two pages available per sequencer. Shift + long press on a button randomizes the notes and gates of that page, and Shift + double press clears them. The output of each sequencer is quantized to the scale set in the global quantizer. Setting the 'Transpose Jack' parameter to a channel (1–16, 0 is off) transposes all four sequencers by the V/Oct voltage on that jack, rounded to semitones and applied before quantization, for key changes in song mode. The jack has to be an input of the app on that channel, and 'Transpose Range' should match its range so a bipolar CV can also transpose down.",
     channels: [
       {
         jackTitle: "CV Output",
@@ -279,7 +281,9 @@ const apps: ManualAppData[] = [
         jackDescription: "Quantized output",
         faderTitle: "Note",
         faderDescription: "Sets the note at this step",
-
+        faderPlusShiftTitle: "Step probability",
+        faderPlusShiftDescription:
+          "Sets the chance of the last pressed step playing",
         fnTitle: "Gate/Legato",
         fnDescription:
           "Short press sets a gate or rest, long press sets a legato",
@@ -294,6 +298,9 @@ const apps: ManualAppData[] = [
         jackDescription: "Quantized output",
         faderTitle: "Note",
         faderDescription: "Sets the note at this step",
+        faderPlusShiftTitle: "Direction",
+        faderPlusShiftDescription:
+          "Sets the playback direction: forward, reverse, ping-pong or random",
         fnTitle: "Gate/Legato",
         fnDescription:
           "Short press sets a gate or rest, long press sets a legato",
@@ -1183,6 +1190,918 @@ Fader functions vary by output mode. Drums / Euclidean / DnB descriptions are sh
       },
     ],
   },
+  {
+    appId: 24,
+    title: "Random Gates",
+    description: "Evolving random gate patterns with per-output density",
+    color: "Pink",
+    icon: "die",
+    params: [
+      "MIDI Channel",
+      "MIDI Note 1",
+      "GATE %",
+      "Regenerate",
+      "Divisions",
+      "Color",
+    ],
+    storage: ["Density", "Muted", "Resolution"],
+    text: "This app generates random 16-step gate patterns on four outputs, in the spirit of the Trigger Riot. Each fader sets the density of its output: at the bottom the output never fires, at the top every step fires. Moving a fader rerolls the pattern of its output only. All patterns are rerolled together every few cycles, set by the 'Regenerate' parameter (in 16-step cycles), so the rhythm keeps evolving while staying in time with the clock. Shift + any button rerolls all patterns immediately. MIDI notes are sent starting at 'MIDI Note 1' and counting up for each output. Shift + Fader 1 sets the clock resolution; while adjusting it, the bottom LED of channel 1 is orange for triplet divisions and blue for straight divisions.",
+    channels: [
+      {
+        jackTitle: "Gate output 1",
+        jackDescription: "Outputs random 10V gates",
+        faderTitle: "Density 1",
+        faderDescription: "Chance of a gate on each step of output 1",
+        faderPlusShiftTitle: "Resolution",
+        faderPlusShiftDescription:
+          "Sets clock resolution for all outputs depending on the 'Divisions' parameter",
+        fnTitle: "Mute 1",
+        fnDescription: "Mutes output 1",
+        fnPlusShiftTitle: "Regenerate",
+        fnPlusShiftDescription: "Rerolls all patterns",
+        ledTop: "Gate 1 activity",
+        ledBottom: "",
+        ledBottomPlusShift: "Resolution type (orange: triplet, blue: straight)",
+      },
+      {
+        jackTitle: "Gate output 2",
+        jackDescription: "Outputs random 10V gates",
+        faderTitle: "Density 2",
+        faderDescription: "Chance of a gate on each step of output 2",
+        fnTitle: "Mute 2",
+        fnDescription: "Mutes output 2",
+        fnPlusShiftTitle: "Regenerate",
+        fnPlusShiftDescription: "Rerolls all patterns",
+        ledTop: "Gate 2 activity",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Gate output 3",
+        jackDescription: "Outputs random 10V gates",
+        faderTitle: "Density 3",
+        faderDescription: "Chance of a gate on each step of output 3",
+        fnTitle: "Mute 3",
+        fnDescription: "Mutes output 3",
+        fnPlusShiftTitle: "Regenerate",
+        fnPlusShiftDescription: "Rerolls all patterns",
+        ledTop: "Gate 3 activity",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Gate output 4",
+        jackDescription: "Outputs random 10V gates",
+        faderTitle: "Density 4",
+        faderDescription: "Chance of a gate on each step of output 4",
+        fnTitle: "Mute 4",
+        fnDescription: "Mutes output 4",
+        fnPlusShiftTitle: "Regenerate",
+        fnPlusShiftDescription: "Rerolls all patterns",
+        ledTop: "Gate 4 activity",
+        ledBottom: "",
+      },
+    ],
+  },
+  {
+    appId: 25,
+    title: "Transpose Quantizer",
+    description: "Quantizer with a CV input for transposition",
+    color: "Blue",
+    icon: "quantize",
+    params: ["Transpose", "Range", "Transpose Range", "Color"],
+    storage: ["Transpose offset", "Bypass"],
+    text: "This app quantizes the CV on jack 1 to the global scale and outputs it on jack 3. The V/Oct voltage on jack 2 transposes it, rounded to semitones, so a quantized melody can be moved around by a sequencer or another quantizer. The 'Transpose' parameter sets how: 'Before' adds the semitones to the input before quantizing, so the result stays in the scale. 'After' adds them to the quantized note, keeping the intervals of the melody even if it leaves the scale. 'Scale Degrees' moves the quantized note up or down the scale by one tone per semitone of the transpose CV. Set 'Transpose Range' to -5V to 5V to also transpose down. Fader 2 adds up to an octave of transposition on top of the CV, in semitones or scale degrees, and button 2 bypasses the transposition.",
+    channels: [
+      {
+        jackTitle: "Input",
+        jackDescription: "CV to quantize",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Positive input",
+        ledBottom: "Negative input",
+      },
+      {
+        jackTitle: "Transpose",
+        jackDescription: "V/Oct transposition, in semitones or scale degrees",
+        faderTitle: "Offset",
+        faderDescription: "Transposition added to the CV, up to an octave",
+        fnTitle: "Bypass",
+        fnDescription: "Turns the transposition off",
+        ledTop: "Positive transpose CV",
+        ledBottom: "Negative transpose CV",
+      },
+      {
+        jackTitle: "Output",
+        jackDescription: "Transposed and quantized CV",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Positive output",
+        ledBottom: "Negative output",
+      },
+    ],
+  },
+  {
+    appId: 26,
+    title: "Sample & Hold",
+    description: "Stepped and slewed random voltages, clocked or free running",
+    color: "Green",
+    icon: "random",
+    params: ["Range", "Divisions", "Color"],
+    storage: ["Rate", "Slew", "Clocked", "Muted"],
+    text: "This app is a classic random sample and hold. A new random voltage is picked either on clock or at a free running rate, and held until the next one. Jack 1 outputs the stepped voltage, Jack 2 outputs the same voltage smoothed by an adjustable slew, so the two outputs can be used together for stepped and gliding modulation. Fader 1 sets the rate: in clocked mode it selects the clock resolution depending on the 'Divisions' parameter, in free mode it sets the time between steps. Fader 2 sets the slew amount of Jack 2; at the bottom the slewed output follows the steps directly. Button 1 switches between clocked (bright) and free (dim) mode. Button 2 mutes both outputs.",
+    channels: [
+      {
+        jackTitle: "Stepped output",
+        jackDescription: "Outputs the held random voltage",
+        faderTitle: "Rate",
+        faderDescription: "Clock resolution or free running rate",
+        fnTitle: "Clocked / Free",
+        fnDescription: "Toggles between clocked and free running mode",
+        ledTop: "Positive level indicator",
+        ledBottom: "Negative level indicator",
+        ledBottomPlusShift: "Resolution type (orange: triplet, blue: straight)",
+      },
+      {
+        jackTitle: "Slewed output",
+        jackDescription: "Outputs the held random voltage with slew",
+        faderTitle: "Slew",
+        faderDescription: "Sets how long the output glides to a new step",
+        fnTitle: "Mute",
+        fnDescription: "Mutes both outputs",
+        ledTop: "Positive level indicator",
+        ledBottom: "Negative level indicator",
+      },
+    ],
+  },
+  {
+    appId: 27,
+    title: "Euclid+",
+    description: "Four channel Euclidean sequencer",
+    color: "Orange",
+    icon: "euclid",
+    params: [
+      "MIDI Channel",
+      "MIDI Note 1",
+      "GATE %",
+      "Resolution",
+      "Color",
+      "Reset on stop",
+    ],
+    storage: ["Fill", "Length", "Rotation", "Muted"],
+    text: "This app is a four channel Euclidean sequencer. Each output plays its own Euclidean rhythm with an independent length, fill and rotation, all running at the clock resolution set by the 'Resolution' parameter. Each fader sets the number of beats (fill) of its channel. Shift + Fader sets the rotation and holding a channel's button while moving its fader sets the length from 1 to 32 steps. Tapping a button mutes its output. Each output also sends a MIDI note, starting at 'MIDI Note 1' and counting up by one semitone per channel.",
+    channels: [
+      {
+        jackTitle: "Trigger 1 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Fill",
+        faderDescription: "Amount of beats in the sequence",
+        faderPlusShiftTitle: "Rotation",
+        faderPlusShiftDescription: "Rotates the sequence",
+        faderPlusFnTitle: "Length",
+        faderPlusFnDescription: "Sets the length of the sequence (1 to 32 steps)",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the output",
+        ledTop: "Gate 1 activity",
+        ledTopPlusShift: "Rotation",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Trigger 2 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Fill",
+        faderDescription: "Amount of beats in the sequence",
+        faderPlusShiftTitle: "Rotation",
+        faderPlusShiftDescription: "Rotates the sequence",
+        faderPlusFnTitle: "Length",
+        faderPlusFnDescription: "Sets the length of the sequence (1 to 32 steps)",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the output",
+        ledTop: "Gate 2 activity",
+        ledTopPlusShift: "Rotation",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Trigger 3 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Fill",
+        faderDescription: "Amount of beats in the sequence",
+        faderPlusShiftTitle: "Rotation",
+        faderPlusShiftDescription: "Rotates the sequence",
+        faderPlusFnTitle: "Length",
+        faderPlusFnDescription: "Sets the length of the sequence (1 to 32 steps)",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the output",
+        ledTop: "Gate 3 activity",
+        ledTopPlusShift: "Rotation",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Trigger 4 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Fill",
+        faderDescription: "Amount of beats in the sequence",
+        faderPlusShiftTitle: "Rotation",
+        faderPlusShiftDescription: "Rotates the sequence",
+        faderPlusFnTitle: "Length",
+        faderPlusFnDescription: "Sets the length of the sequence (1 to 32 steps)",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the output",
+        ledTop: "Gate 4 activity",
+        ledTopPlusShift: "Rotation",
+        ledBottom: "",
+      },
+    ],
+  },
+  {
+    appId: 28,
+    title: "ADSR Envelope",
+    description: "Gate or MIDI triggered ADSR envelope",
+    color: "Yellow",
+    icon: "ad-env",
+    params: ["Use MIDI", "MIDI Channel", "Color"],
+    storage: ["ADSR faders", "Curve", "Attenuation"],
+    text: "This app is a classic ADSR envelope generator. The envelope starts on a gate at Jack 1, while holding Button 1 or on an incoming MIDI note, and moves to its release stage once the gate ends. Faders 1 to 4 set attack time, decay time, sustain level and release time. Times range from 1 ms to about 4 sec. Set the sustain fader to the bottom to get an AD envelope that ignores the gate length. Jack 2 outputs the envelope, Jack 3 outputs an inverted copy and Jack 4 sends a short trigger when the envelope has finished. Button 4 selects the envelope curve: linear (yellow), logarithmic (cyan) or exponential (pink). Shift + Fader 4 attenuates both envelope outputs.",
+    channels: [
+      {
+        jackTitle: "Gate Input",
+        jackDescription: "Gate is detected if the voltage is above 1V",
+        faderTitle: "Attack time",
+        faderDescription: "Sets the attack time from 1 ms to 4 sec",
+        fnTitle: "Manual gate",
+        fnDescription: "Opens the gate while held",
+        ledTop: "Output level in attack stage",
+        ledBottom: "Gate state",
+      },
+      {
+        jackTitle: "Envelope Output",
+        jackDescription: "0-10V output range",
+        faderTitle: "Decay time",
+        faderDescription: "Sets the decay time from 1 ms to 4 sec",
+        ledTop: "Output level in decay stage",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Inverted Output",
+        jackDescription: "Outputs the inverted envelope",
+        faderTitle: "Sustain level",
+        faderDescription: "Level held while the gate is on",
+        ledTop: "Output level in sustain stage",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "End of cycle",
+        jackDescription: "Outputs a trigger when the envelope finishes",
+        faderTitle: "Release time",
+        faderDescription: "Sets the release time from 1 ms to 4 sec",
+        faderPlusShiftTitle: "Attenuation",
+        faderPlusShiftDescription: "Reduces the output range",
+        fnTitle: "Curve selection",
+        fnDescription: "Linear (yellow), logarithmic (cyan), exponential (pink)",
+        ledTop: "Output level in release stage",
+        ledTopPlusShift: "Attenuation level in red",
+        ledBottom: "",
+      },
+    ],
+  },
+  {
+    appId: 29,
+    title: "Burst",
+    description: "Clock synced trigger bursts and echoes",
+    color: "Cyan",
+    icon: "sequence",
+    params: ["Mode", "Divisions", "Color"],
+    storage: ["Count", "Spacing", "Decay", "Muted"],
+    text: "This app turns a single trigger into a burst of evenly spaced triggers. A trigger on Jack 1 (or a press on Button 1) fires right away on Jack 2 and is followed by more triggers, synced to the clock. Fader 1 sets the number of triggers in a burst, from 1 to 16. Fader 2 sets the spacing between them, depending on the 'Divisions' parameter. A new trigger restarts the burst. In 'Tap delay' mode the repeats become echoes: each one is less likely to fire than the one before and the first echo that doesn't fire ends the delay. Shift + Fader 1 sets how slowly the echoes decay. Button 2 mutes the output.",
+    channels: [
+      {
+        jackTitle: "Trigger input",
+        jackDescription: "Trigger is detected if the voltage is above 1V",
+        faderTitle: "Count",
+        faderDescription: "Number of triggers in a burst",
+        faderPlusShiftTitle: "Decay",
+        faderPlusShiftDescription: "How slowly echoes fade out in tap delay mode",
+        fnTitle: "Manual trigger",
+        fnDescription: "Starts a burst",
+        ledTop: "Trigger input state",
+        ledBottom: "",
+        ledBottomPlusShift: "Decay level in red",
+      },
+      {
+        jackTitle: "Burst output",
+        jackDescription: "Outputs 10V triggers",
+        faderTitle: "Spacing",
+        faderDescription: "Clock division between triggers",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the output",
+        ledTop: "Output trigger",
+        ledBottom: "",
+      },
+    ],
+  },
+  {
+    appId: 30,
+    title: "Trigger Grid",
+    description: "8 track, 16 step trigger sequencer",
+    color: "Violet",
+    icon: "note-grid",
+    params: [
+      "MIDI Channel",
+      "MIDI Note 1",
+      "GATE %",
+      "Divisions",
+      "Color",
+    ],
+    storage: ["Grid", "Probability", "Resolution", "Length"],
+    text: "This app is a drum style trigger sequencer with 8 tracks of 16 steps, each track playing on its own jack. The buttons show the steps of the selected track, 8 at a time, and toggle them on or off. The playing step is shown in white. Shift + Button selects a track; pressing Shift and the button of the selected track again flips between steps 1 to 8 and steps 9 to 16. Each fader sets the probability for the steps of its track to fire, at the top every step plays. Shift + Fader 1 sets the clock resolution, depending on the 'Divisions' parameter, and Shift + Fader 2 sets the sequence length. Each track also sends a MIDI note, starting at 'MIDI Note 1' and counting up by one semitone per track.",
+    channels: [
+      {
+        jackTitle: "Track 1 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Probability",
+        faderDescription: "Chance for the steps of track 1 to fire",
+        faderPlusShiftTitle: "Resolution",
+        faderPlusShiftDescription: "Clock resolution of the grid",
+        fnTitle: "Step 1 / 9",
+        fnDescription: "Toggles the step on the selected track",
+        fnPlusShiftTitle: "Select track 1",
+        fnPlusShiftDescription: "Press again to flip the page",
+        ledTop: "Track 1 activity",
+        ledBottom: "Selected track (white: steps 9 to 16)",
+      },
+      {
+        jackTitle: "Track 2 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Probability",
+        faderDescription: "Chance for the steps of track 2 to fire",
+        faderPlusShiftTitle: "Length",
+        faderPlusShiftDescription: "Sequence length from 1 to 16 steps",
+        fnTitle: "Step 2 / 10",
+        fnDescription: "Toggles the step on the selected track",
+        fnPlusShiftTitle: "Select track 2",
+        fnPlusShiftDescription: "Press again to flip the page",
+        ledTop: "Track 2 activity",
+        ledBottom: "Selected track (white: steps 9 to 16)",
+      },
+      {
+        jackTitle: "Track 3 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Probability",
+        faderDescription: "Chance for the steps of track 3 to fire",
+        fnTitle: "Step 3 / 11",
+        fnDescription: "Toggles the step on the selected track",
+        fnPlusShiftTitle: "Select track 3",
+        fnPlusShiftDescription: "Press again to flip the page",
+        ledTop: "Track 3 activity",
+        ledBottom: "Selected track (white: steps 9 to 16)",
+      },
+      {
+        jackTitle: "Track 4 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Probability",
+        faderDescription: "Chance for the steps of track 4 to fire",
+        fnTitle: "Step 4 / 12",
+        fnDescription: "Toggles the step on the selected track",
+        fnPlusShiftTitle: "Select track 4",
+        fnPlusShiftDescription: "Press again to flip the page",
+        ledTop: "Track 4 activity",
+        ledBottom: "Selected track (white: steps 9 to 16)",
+      },
+      {
+        jackTitle: "Track 5 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Probability",
+        faderDescription: "Chance for the steps of track 5 to fire",
+        fnTitle: "Step 5 / 13",
+        fnDescription: "Toggles the step on the selected track",
+        fnPlusShiftTitle: "Select track 5",
+        fnPlusShiftDescription: "Press again to flip the page",
+        ledTop: "Track 5 activity",
+        ledBottom: "Selected track (white: steps 9 to 16)",
+      },
+      {
+        jackTitle: "Track 6 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Probability",
+        faderDescription: "Chance for the steps of track 6 to fire",
+        fnTitle: "Step 6 / 14",
+        fnDescription: "Toggles the step on the selected track",
+        fnPlusShiftTitle: "Select track 6",
+        fnPlusShiftDescription: "Press again to flip the page",
+        ledTop: "Track 6 activity",
+        ledBottom: "Selected track (white: steps 9 to 16)",
+      },
+      {
+        jackTitle: "Track 7 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Probability",
+        faderDescription: "Chance for the steps of track 7 to fire",
+        fnTitle: "Step 7 / 15",
+        fnDescription: "Toggles the step on the selected track",
+        fnPlusShiftTitle: "Select track 7",
+        fnPlusShiftDescription: "Press again to flip the page",
+        ledTop: "Track 7 activity",
+        ledBottom: "Selected track (white: steps 9 to 16)",
+      },
+      {
+        jackTitle: "Track 8 Out",
+        jackDescription: "Outputs 10V gates",
+        faderTitle: "Probability",
+        faderDescription: "Chance for the steps of track 8 to fire",
+        fnTitle: "Step 8 / 16",
+        fnDescription: "Toggles the step on the selected track",
+        fnPlusShiftTitle: "Select track 8",
+        fnPlusShiftDescription: "Press again to flip the page",
+        ledTop: "Track 8 activity",
+        ledBottom: "Selected track (white: steps 9 to 16)",
+      },
+    ],
+  },
+  {
+    appId: 31,
+    title: "Note Box",
+    description: "Fader selected note as CV and MIDI",
+    color: "Cyan",
+    icon: "note-box",
+    params: ["Octave", "Span", "MIDI Channel", "Color"],
+    storage: ["Note", "Note on"],
+    text: "This app is a manual note source, handy as a drone or for tuning oscillators. The fader selects a note within the number of semitones set by 'Span', starting at the octave set by 'Octave'. The note is quantized to the global scale and sent as 1V/oct CV on the jack at all times. The button turns a held MIDI note on or off; while it is on, moving the fader to a new note sends the new note right away.",
+    channels: [
+      {
+        jackTitle: "CV output",
+        jackDescription: "Outputs the selected note as 1V/oct CV",
+        faderTitle: "Note",
+        faderDescription: "Selects the note within the span",
+        fnTitle: "MIDI note",
+        fnDescription: "Turns the held MIDI note on (bright) or off (dim)",
+        ledTop: "Note position within the span",
+        ledBottom: "",
+      },
+    ],
+  },
+  {
+    appId: 32,
+    title: "Coin Toss",
+    description: "Bernoulli gate, clocked coin toss between two outputs",
+    color: "Yellow",
+    icon: "die",
+    params: ["Complement", "MIDI Channel", "MIDI Note 1", "GATE %", "Divisions", "Color"],
+    storage: ["Probability", "Division", "Mute"],
+    text: "On every clock division, this app tosses a coin. With 'Complement' on, a gate goes to jack 1 (heads) with the probability set on fader 1, and to jack 2 (tails) otherwise, so exactly one output fires on every toss. With 'Complement' off, jack 2 tosses its own coin with the same probability. Each output also sends a MIDI note, starting at 'MIDI Note 1'. The gate length is set by 'GATE %'.",
+    channels: [
+      {
+        jackTitle: "Gate output (heads)",
+        jackDescription: "Fires with the set probability on every toss",
+        faderTitle: "Probability",
+        faderDescription: "Chance of heads, from never to always",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the heads output",
+        ledTop: "Gate activity",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Gate output (tails)",
+        jackDescription: "Fires when heads doesn't, or on its own toss with 'Complement' off",
+        faderTitle: "Clock division",
+        faderDescription: "Sets how often the coin is tossed",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the tails output",
+        ledTop: "Gate activity",
+        ledBottom: "",
+      },
+    ],
+  },
+  {
+    appId: 33,
+    title: "Soft Random",
+    description: "Smoothly gliding random CV",
+    color: "Green",
+    icon: "soft-random",
+    params: ["Range", "MIDI Channel", "MIDI CC", "Color"],
+    storage: ["Rate", "Depth", "Mute"],
+    text: "This app generates random CV that glides smoothly from one random value to the next, halfway between an LFO and a random source. The fader sets how fast it moves to each new value, from about a minute down to a few milliseconds. Shift + fader sets the depth of the output. The output is also sent as a MIDI CC.",
+    channels: [
+      {
+        jackTitle: "CV output",
+        jackDescription: "Smooth random CV",
+        faderTitle: "Rate",
+        faderDescription: "Sets how fast the output glides to each new random value",
+        faderPlusShiftTitle: "Depth",
+        faderPlusShiftDescription: "Scales the output",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the output",
+        ledTop: "Output level",
+        ledTopPlusShift: "Depth",
+        ledBottom: "Negative output level",
+      },
+    ],
+  },
+  {
+    appId: 34,
+    title: "Scale Quantizer",
+    description: "Quantizer with its own scale and a trigger on note change",
+    color: "Blue",
+    icon: "quantize",
+    params: ["Scale", "Tonic", "Range", "Color"],
+    storage: ["Semitone offset", "Octave offset", "Trigger length", "Toggles"],
+    text: "This app quantizes the CV on jack 1 and outputs it on jack 2. Unlike the Quantizer app, it can use its own scale: set the 'Scale' parameter to any scale and pick its tonic, or leave it on 'Global' to follow the global quantizer. Fader 1 transposes the input by up to an octave in semitones and fader 2 shifts it by -5 to +4 octaves, both before quantization. Buttons 1 and 2 bypass these offsets. Jack 3 outputs a trigger every time the quantized note changes, handy to fire an envelope for every new note. Fader 3 sets the length of the trigger and button 3 mutes it.",
+    channels: [
+      {
+        jackTitle: "CV input",
+        jackDescription: "CV to quantize",
+        faderTitle: "Semitone",
+        faderDescription: "Transposes the input by 0 to 12 semitones",
+        fnTitle: "Bypass",
+        fnDescription: "Bypasses the semitone offset",
+        ledTop: "Semitone offset",
+        ledBottom: "None",
+      },
+      {
+        jackTitle: "CV output",
+        jackDescription: "Quantized CV",
+        faderTitle: "Octave",
+        faderDescription: "Shifts the input by -5 to +4 octaves",
+        fnTitle: "Bypass",
+        fnDescription: "Bypasses the octave offset",
+        ledTop: "Output level",
+        ledBottom: "Negative output level",
+      },
+      {
+        jackTitle: "Trigger output",
+        jackDescription: "Trigger on every note change",
+        faderTitle: "Trigger length",
+        faderDescription: "Sets the trigger length from 1ms to 256ms",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the trigger output",
+        ledTop: "Trigger",
+        ledBottom: "None",
+      },
+    ],
+  },
+  {
+    appId: 35,
+    title: "Square Seq",
+    description: "8 step sequencer with velocity and accent",
+    color: "Orange",
+    icon: "sequence-square",
+    params: [
+      "Length",
+      "Resolution",
+      "GATE %",
+      "Octave",
+      "MIDI Channel",
+      "Color",
+    ],
+    storage: ["Steps", "Page", "Layer"],
+    text: "A compact 8 step sequencer on 4 channels. The steps are edited as two pages of four: hold Shift and press button 1 or 2 to select the page. Each fader sets the note of a step on the current page and, while holding Shift, its velocity. Shift and button 4 keep the faders on the velocities, then holding Shift edits the notes. The app remembers the page and layer it was left on. Buttons turn the gate of a step on or off. Jack 1 outputs the quantized pitch over 2 octaves starting at the octave set in the parameters, jack 2 the gate and jack 3 the velocity of the playing step. Steps with a velocity above 75% are accented and also fire a gate on jack 4. Notes are sent over MIDI with their velocity. Holding Shift and button 3 stutters the last played note in 1/32 notes, while the sequence keeps running silently underneath.",
+    channels: [
+      {
+        jackTitle: "Pitch output",
+        jackDescription: "Quantized pitch of the playing step",
+        faderTitle: "Note",
+        faderDescription: "Sets the note of step 1 or 5",
+        faderPlusShiftTitle: "Velocity",
+        faderPlusShiftDescription: "Sets the velocity of step 1 or 5",
+        fnTitle: "Gate",
+        fnDescription: "Turns the gate of step 1 or 5 on or off",
+        fnPlusShiftTitle: "Select page 1",
+        ledTop: "Note of the step",
+        ledTopPlusShift: "Velocity of the step",
+        ledBottom: "Playing step",
+      },
+      {
+        jackTitle: "Gate output",
+        jackDescription: "Gate of the playing step",
+        faderTitle: "Note",
+        faderDescription: "Sets the note of step 2 or 6",
+        faderPlusShiftTitle: "Velocity",
+        faderPlusShiftDescription: "Sets the velocity of step 2 or 6",
+        fnTitle: "Gate",
+        fnDescription: "Turns the gate of step 2 or 6 on or off",
+        fnPlusShiftTitle: "Select page 2",
+        ledTop: "Note of the step",
+        ledTopPlusShift: "Velocity of the step",
+        ledBottom: "Playing step",
+      },
+      {
+        jackTitle: "Velocity output",
+        jackDescription: "Velocity of the playing step",
+        faderTitle: "Note",
+        faderDescription: "Sets the note of step 3 or 7",
+        faderPlusShiftTitle: "Velocity",
+        faderPlusShiftDescription: "Sets the velocity of step 3 or 7",
+        fnTitle: "Gate",
+        fnDescription: "Turns the gate of step 3 or 7 on or off",
+        fnPlusShiftTitle: "Stutter",
+        ledTop: "Note of the step",
+        ledTopPlusShift: "Velocity of the step",
+        ledBottom: "Playing step",
+      },
+      {
+        jackTitle: "Accent output",
+        jackDescription: "Gate of accented steps",
+        faderTitle: "Note",
+        faderDescription: "Sets the note of step 4 or 8",
+        faderPlusShiftTitle: "Velocity",
+        faderPlusShiftDescription: "Sets the velocity of step 4 or 8",
+        fnTitle: "Gate",
+        fnDescription: "Turns the gate of step 4 or 8 on or off",
+        fnPlusShiftTitle: "Keep velocity layer",
+        ledTop: "Note of the step",
+        ledTopPlusShift: "Velocity of the step",
+        ledBottom: "Playing step",
+      },
+    ],
+  },
+  {
+    appId: 36,
+    title: "Stepped",
+    description: "Fader snapping to evenly spaced voltages",
+    color: "Cyan",
+    icon: "knob-round",
+    params: ["Steps", "Range", "MIDI Channel", "MIDI CC", "Color"],
+    storage: ["Step", "Mute"],
+    text: "This app turns the fader into a stepped knob: its travel is split into 2 to 16 detents, set with the 'Steps' parameter, and the output jumps between evenly spaced voltages covering the whole range. The fader has to move a little past the edge of a detent before it snaps to the next one, so the output never flickers between two steps. The button LED flashes white on every new step. Handy to pick discrete voltages, like selecting a wavetable, a clock division or a mode on another module. The current step is also sent as a MIDI CC.",
+    channels: [
+      {
+        jackTitle: "CV output",
+        jackDescription: "Voltage of the current step",
+        faderTitle: "Step",
+        faderDescription: "Selects the step",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the output",
+        ledTop: "Output level",
+        ledBottom: "Negative output level",
+      },
+    ],
+  },
+  {
+    appId: 37,
+    title: "Stereo Spread",
+    description: "Widens the output of another channel into a stereo pair",
+    color: "Cyan",
+    icon: "stereo",
+    params: ["Source channel", "Range", "Color"],
+    storage: ["Width", "Delay", "Toggles"],
+    text: "This app follows the output of any other channel, set with the 'Source channel' parameter, and turns it into a stereo pair. Fader 1 sets the width: the left and right outputs move apart from the source by up to a quarter of the range each, so an LFO or envelope sent to the left and right sides of a stereo patch no longer sits in the middle. Fader 2 delays the right side by up to 63ms, like the Haas effect does for audio, for movement that lands a little later on one side. Button 1 swaps the sides and button 2 collapses the pair back to mono. Set the 'Range' parameter to the range of the source app.",
+    channels: [
+      {
+        jackTitle: "Left output",
+        jackDescription: "Left side of the pair",
+        faderTitle: "Width",
+        faderDescription: "Sets how far apart the two sides are",
+        fnTitle: "Swap",
+        fnDescription: "Swaps the left and right sides",
+        ledTop: "Left output level",
+        ledBottom: "Negative left output level",
+      },
+      {
+        jackTitle: "Right output",
+        jackDescription: "Right side of the pair, delayed",
+        faderTitle: "Delay",
+        faderDescription: "Delays the right side by 0 to 63ms",
+        fnTitle: "Mono",
+        fnDescription: "Collapses the pair to mono",
+        ledTop: "Right output level",
+        ledBottom: "Negative right output level",
+      },
+    ],
+  },
+  {
+    appId: 38,
+    title: "Note Repeat",
+    description: "Retriggers held MIDI notes in time with the clock",
+    color: "Yellow",
+    icon: "note",
+    params: ["MIDI In", "MIDI Channel", "Color", "MIDI Out"],
+    storage: ["Rate", "Gate length", "Repeat", "Muted"],
+    text: "This app adds the note repeat of a groovebox to a MIDI keyboard or pad controller. A note received on the set MIDI channel plays right away, and as long as it is held it is retriggered at the rate set with fader 1, from 1/4 notes on the left to 1/32 notes on the right, counted from when the note was pressed. When several notes are held, the last one pressed repeats. Jack 1 puts out the pitch of the note (1V/oct, C0 at 0V) and jack 2 the gate, and every hit is also sent to the MIDI outputs. Fader 2 sets the gate length of the repeats. Button 1 turns the repeat off, which lets the notes through as they are played, and button 2 mutes the app. Repeats need a running clock.",
+    channels: [
+      {
+        jackTitle: "Pitch output",
+        jackDescription: "1V/oct pitch of the repeated note",
+        faderTitle: "Rate",
+        faderDescription: "Sets the repeat rate from 1/4 to 1/32 notes",
+        fnTitle: "Repeat",
+        fnDescription: "Turns the note repeat on or off",
+        ledTop: "Repeat rate",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Gate output",
+        jackDescription: "Gate for every hit",
+        faderTitle: "Gate length",
+        faderDescription: "Sets the gate length of the repeats",
+        fnTitle: "Mute",
+        fnDescription: "Mutes the gate and MIDI output",
+        ledTop: "Gate",
+        ledBottom: "",
+      },
+    ],
+  },
+  {
+    appId: 39,
+    title: "MIDI Monitor",
+    description: "Shows the MIDI activity on four channels",
+    color: "White",
+    icon: "note",
+    params: ["MIDI In", "First MIDI Channel"],
+    storage: [],
+    text: "This app helps to find out what a MIDI setup actually sends. Each of its four channels follows one MIDI channel, starting from the 'First MIDI Channel' parameter, on the MIDI inputs picked with 'MIDI In'. The top LED flashes for every note on, in a color for its pitch class going around the color wheel from red for C to pink for B, so octaves light up the same. The bottom LED flashes for control changes and NRPN. The buttons flash white on every beat of the clock, green when the clock starts and red when it stops. The app has no outputs.",
+    channels: [
+      {
+        jackTitle: "",
+        jackDescription: "",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "Clock",
+        fnDescription: "Flashes on the clock and transport",
+        ledTop: "Note ons on the first MIDI channel",
+        ledBottom: "CCs on the first MIDI channel",
+      },
+      {
+        jackTitle: "",
+        jackDescription: "",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "Clock",
+        fnDescription: "Flashes on the clock and transport",
+        ledTop: "Note ons on the second MIDI channel",
+        ledBottom: "CCs on the second MIDI channel",
+      },
+      {
+        jackTitle: "",
+        jackDescription: "",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "Clock",
+        fnDescription: "Flashes on the clock and transport",
+        ledTop: "Note ons on the third MIDI channel",
+        ledBottom: "CCs on the third MIDI channel",
+      },
+      {
+        jackTitle: "",
+        jackDescription: "",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "Clock",
+        fnDescription: "Flashes on the clock and transport",
+        ledTop: "Note ons on the fourth MIDI channel",
+        ledBottom: "CCs on the fourth MIDI channel",
+      },
+    ],
+  },
+  {
+    appId: 40,
+    title: "Poly MIDI to CV",
+    description: "Plays MIDI notes on four pitch and gate voices",
+    color: "Violet",
+    icon: "note",
+    params: ["MIDI In", "MIDI Channel", "Color"],
+    storage: ["Glide", "Gate length"],
+    text: "This app turns a MIDI keyboard into four voices of pitch and gate, for playing chords on four oscillators or envelopes. Each voice takes up two channels: the first jack puts out the 1V/oct pitch (C0 at 0V) and the second one the gate. Notes go to the voices in turn, so a note can ring out on its voice while the next ones play on the others. When all four voices are held, the oldest note is stolen. A voice keeps the pitch of its last note after it is released. Fader 1 sets the glide of all voices and fader 2 the gate length, from 5ms to about a second. With fader 2 all the way up, the gates stay open for as long as the notes are held.",
+    channels: [
+      {
+        jackTitle: "Voice 1 pitch",
+        jackDescription: "1V/oct pitch of voice 1",
+        faderTitle: "Glide",
+        faderDescription: "Sets how long the pitch glides to a new note",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Pitch",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Voice 1 gate",
+        jackDescription: "Gate of voice 1",
+        faderTitle: "Gate length",
+        faderDescription: "Sets how long the gates stay open, all the way up they follow the held notes",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Gate",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Voice 2 pitch",
+        jackDescription: "1V/oct pitch of voice 2",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Pitch",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Voice 2 gate",
+        jackDescription: "Gate of voice 2",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Gate",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Voice 3 pitch",
+        jackDescription: "1V/oct pitch of voice 3",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Pitch",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Voice 3 gate",
+        jackDescription: "Gate of voice 3",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Gate",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Voice 4 pitch",
+        jackDescription: "1V/oct pitch of voice 4",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Pitch",
+        ledBottom: "",
+      },
+      {
+        jackTitle: "Voice 4 gate",
+        jackDescription: "Gate of voice 4",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Gate",
+        ledBottom: "",
+      },
+    ],
+  },
+  {
+    appId: 41,
+    title: "Chord Lock",
+    description: "Quantize CV to the notes held on MIDI",
+    color: "Green",
+    icon: "quantize",
+    params: ["MIDI In", "MIDI Channel", "Range", "Color"],
+    storage: ["Latch"],
+    text: "This app quantizes CV not to a scale but to the notes held on a MIDI keyboard. Jack 1 is the input and Jack 2 the quantized output. Hold a chord and the output only plays the notes of that chord, in every octave. With no notes held, the output is quantized chromatically. Button 1 toggles latch: while it is lit, the last chord stays after its keys are released, until the next chord is played.",
+    channels: [
+      {
+        jackTitle: "Input",
+        jackDescription: "CV to quantize",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "Latch",
+        fnDescription: "Keeps the last chord after its keys are released",
+        ledTop: "Positive input",
+        ledBottom: "Negative input",
+      },
+      {
+        jackTitle: "Output",
+        jackDescription: "CV quantized to the held notes",
+        faderTitle: "",
+        faderDescription: "",
+        fnTitle: "",
+        fnDescription: "",
+        ledTop: "Positive output",
+        ledBottom: "Negative output",
+      },
+    ],
+  },
+  {
+    appId: 42,
+    title: "Attenuverter",
+    description: "Gain, offset and invert for the output of another channel",
+    color: "Rose",
+    icon: "attenuate",
+    params: ["Source channel", "Range", "Color"],
+    storage: ["Offset", "Gain", "Invert"],
+    text: "This app follows the output of any other channel, set with the 'Source channel' parameter, and puts it out again with a gain and an offset, without patching a cable. The fader adds a DC offset of up to half the range up or down, it has no effect in the middle. Shift + Fader sets the gain: fully up passes the source as it is, fully down silences it. In the -5V to 5V range the gain scales around 0V, in the 0-10V range towards 0V. The button inverts the source before the gain, around 0V in the -5V to 5V range and within the range for 0-10V. Set the 'Range' parameter to the range of the source app.",
+    channels: [
+      {
+        jackTitle: "Output",
+        jackDescription: "Source with gain and offset",
+        faderTitle: "Offset",
+        faderDescription: "Moves the output up or down",
+        faderPlusShiftTitle: "Gain",
+        faderPlusShiftDescription: "Scales the source",
+        fnTitle: "Invert",
+        fnDescription: "Inverts the source",
+        ledTop: "Positive output",
+        ledBottom: "Negative output",
+        ledTopPlusShift: "Gain in red",
+      },
+    ],
+  },
 ];
 
 export const ManualTab = () => {
@@ -47,6 +47,7 @@ export interface Inputs {
   quantizerKey: Key["tag"];
   quantizerTonic: Note["tag"];
   takeoverMode: latch.TakeoverMode["tag"];
+  outputSlew: number;
   // MIDI USB
   midiUsbMode: MidiOutMode["tag"];
   midiUsbSendClock: boolean;
@@ -65,6 +66,7 @@ export interface Inputs {
   midiOut2SendTransport: boolean;
   midiOut2SourceUsb: boolean;
   midiOut2SourceDin: boolean;
+  midiActiveSensing: boolean;
 }
 
 const SettingsForm = ({ config }: SettingsFormProps) => {
@@ -118,6 +120,7 @@ const SettingsForm = ({ config }: SettingsFormProps) => {
       quantizerTonic: config.quantizer.tonic.tag,
       ledBrightness: config.led_brightness,
       takeoverMode: config.takeover_mode.tag,
+      outputSlew: config.output_slew,
       // MIDI USB
       midiUsbMode: midiUsb.mode,
       midiUsbSendClock: midiUsb.sendClock,
@@ -136,6 +139,7 @@ const SettingsForm = ({ config }: SettingsFormProps) => {
       midiOut2SendTransport: midiOut2.sendTransport,
       midiOut2SourceUsb: midiOut2.sourceUsb,
       midiOut2SourceDin: midiOut2.sourceDin,
+      midiActiveSensing: config.midi.active_sensing,
     },
   });
   const [saved, setSaved] = useState<boolean>(false);
@@ -301,11 +305,13 @@ const transformFormToGlobalConfig = (formValues: Inputs): GlobalConfig => {
     led_brightness: formValues.ledBrightness,
     midi: {
       outs: midiOutsArray,
+      active_sensing: formValues.midiActiveSensing,
     },
     quantizer: {
       key: { tag: formValues.quantizerKey },
       tonic: { tag: formValues.quantizerTonic },
     },
     takeover_mode: { tag: formValues.takeoverMode },
+    output_slew: formValues.outputSlew,
   };
 };
@@ -21,6 +21,7 @@ import { ParamMidiMode } from "./ParamMidiMode.tsx";
 import { ParamMidiNote } from "./ParamMidiNote.tsx";
 import { ParamMidiNrpn } from "./ParamMidiNrpn.tsx";
 import { ParamMidiOut } from "./ParamMidiOut.tsx";
+import { ParamScaleMask } from "./ParamScaleMask.tsx";
 
 interface Props {
   defaultValue:
@@ -202,6 +203,16 @@ export const AppParam = ({
         />
       );
     }
+    case "ScaleMask": {
+      return (
+        <ParamScaleMask
+          {...param.value}
+          defaultValue={defaultValue as number}
+          paramIndex={paramIndex}
+          control={control}
+        />
+      );
+    }
     default: {
       return null;
     }
@@ -10,8 +10,16 @@ interface Props {
   name: string;
   paramIndex: number;
   register: UseFormRegister<FieldValues>;
+  step: number;
 }
 
+// Number of decimals needed to show values on the step grid. The step is an
+// f32, so drop the float noise before counting.
+const stepPrecision = (step: number) =>
+  step > 0
+    ? (Number(step.toPrecision(6)).toString().split(".")[1]?.length ?? 0)
+    : undefined;
+
 export const ParamF32 = ({
   defaultValue,
   max,
@@ -19,16 +27,21 @@ export const ParamF32 = ({
   name,
   paramIndex,
   register,
+  step,
 }: Props) => (
   <Input
-    defaultValue={defaultValue}
+    defaultValue={
+      stepPrecision(step) === undefined
+        ? defaultValue
+        : Number(defaultValue).toFixed(stepPrecision(step))
+    }
     {...register(`param-f32-${paramIndex}`, { valueAsNumber: true })}
     {...inputProps}
     max={max}
     min={min}
     type="number"
     inputMode="decimal"
-    step="any"
+    step={step > 0 ? step : "any"}
     label={name}
   />
 );
@@ -10,6 +10,7 @@ interface Props {
   name: string;
   defaultValue: string;
   register: UseFormRegister<FieldValues>;
+  step: number;
 }
 
 export const ParamI32 = ({
@@ -19,6 +20,7 @@ export const ParamI32 = ({
   name,
   paramIndex,
   register,
+  step,
 }: Props) => (
   <Input
     defaultValue={defaultValue}
@@ -26,6 +28,7 @@ export const ParamI32 = ({
     {...inputProps}
     min={min}
     max={max}
+    step={step > 0 ? step : 1}
     type="number"
     label={name}
   />
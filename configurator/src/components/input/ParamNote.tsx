@@ -4,6 +4,8 @@ import { type Note } from "@atov/fp-config";
 import { Select, SelectItem } from "@heroui/select";
 
 import { selectProps } from "./defaultProps";
+import { useStore } from "../../store";
+import { formatNote } from "../../utils/utils";
 
 interface Props {
   defaultValue: string;
@@ -22,13 +24,14 @@ export const ParamNote = ({
   register,
   variants,
 }: Props) => {
+  const useFlats = useStore((state) => state.useFlats);
   const items = useMemo(
     () =>
       variants.map((variant) => ({
         key: variant.tag,
-        value: variant.tag.replace("Sharp", "♯"),
+        value: formatNote(variant.tag, useFlats),
       })),
-    [variants],
+    [variants, useFlats],
   );
   return (
     <Select
@@ -0,0 +1,83 @@
+import { useCallback } from "react";
+import { type FieldValues, Controller, type Control } from "react-hook-form";
+import { CheckboxGroup, Checkbox } from "@heroui/checkbox";
+
+// Scale degrees from the tonic (MSB) to the major seventh (LSB)
+const SCALE_DEGREES = [
+  "1",
+  "♭2",
+  "2",
+  "♭3",
+  "3",
+  "4",
+  "♭5",
+  "5",
+  "♭6",
+  "6",
+  "♭7",
+  "7",
+];
+
+interface Props {
+  defaultValue: number;
+  name: string;
+  paramIndex: number;
+  control: Control<FieldValues>;
+}
+
+export const ParamScaleMask = ({
+  defaultValue,
+  name,
+  paramIndex,
+  control,
+}: Props) => {
+  const getSelectedKeys = (value: number) => {
+    const selected: string[] = [];
+    SCALE_DEGREES.forEach((_, i) => {
+      if ((value >> (11 - i)) & 1) {
+        selected.push(i.toString());
+      }
+    });
+    return selected;
+  };
+
+  const updateValue = useCallback(
+    (selected: string[]) =>
+      selected.reduce((mask, key) => mask | (1 << (11 - Number(key))), 0),
+    [],
+  );
+
+  return (
+    <Controller
+      name={`param-ScaleMask-${paramIndex}`}
+      control={control}
+      defaultValue={defaultValue}
+      render={({ field: { onChange, value } }) => (
+        <CheckboxGroup
+          className="max-w-100"
+          label={name}
+          value={getSelectedKeys(value)}
+          onValueChange={(selected: string[]) =>
+            onChange(updateValue(selected))
+          }
+          classNames={{
+            label: "text-sm font-semibold text-white",
+          }}
+          orientation="horizontal"
+        >
+          {SCALE_DEGREES.map((degree, i) => (
+            <Checkbox
+              classNames={{
+                label: "text-sm",
+              }}
+              key={i}
+              value={i.toString()}
+            >
+              {degree}
+            </Checkbox>
+          ))}
+        </CheckboxGroup>
+      )}
+    />
+  );
+};
@@ -203,7 +203,9 @@ export const Configurator = () => (
     </List>
     <p>
       You can use these AUX jacks as reset sources even when syncing to MIDI or
-      the internal clock.
+      the internal clock. A reset pulse takes effect right away, even in the
+      middle of a pattern: all clocked apps jump back to their first step and
+      the next clock tick plays it.
     </p>
     <List>
       <li>
@@ -288,6 +290,12 @@ export const Configurator = () => (
       <br />
       You can set this behavior in the Settings tab.
     </p>
+    <p>
+      A second Faderpunk set to <strong>Follower</strong> works as an output
+      expander: the values, notes and gates the apps of the leader send over
+      I²C come out of the jack with the same number on the follower. Make sure
+      the apps on the follower don't use these jacks themselves.
+    </p>
 
     <H4 id="settings-aux">AUX Jacks</H4>
     <p>
@@ -146,6 +146,11 @@ export const Interface = () => (
         <strong>Hold scene and then press Shift</strong> →{" "}
         <strong>Starts/stops</strong> the internal clock
       </li>
+      <li>
+        <strong>Hold Shift and tap Scene</strong> → <strong>Tap tempo</strong>{" "}
+        for the internal clock. From the second tap on, the BPM follows the
+        average of the last four taps. Wait two seconds to start over.
+      </li>
     </List>
     <p>
       These shortcuts allow quick access to essential performance parameters
@@ -414,6 +419,10 @@ export const Interface = () => (
         sent through these outputs as well if enabled in the clock routing
         settings.
         <br />
+        For gear that expects it, <strong>Active Sensing</strong> can be sent
+        on both outputs every 270ms by enabling it in the MIDI settings.
+        Incoming Active Sensing is ignored.
+        <br />
         These connectors follow the <strong>Type A</strong> MIDI standard.
       </li>
     </List>
@@ -507,6 +516,15 @@ export const Interface = () => (
         <strong>Miscellaneous</strong> section of the{" "}
         <strong>Configurator</strong> Settings tab.
       </li>
+      <li>
+        <strong>Output Slew</strong>
+        <br />
+        For gear that dislikes sudden voltage jumps, a global slew can be set
+        for all CV outputs in the <strong>Miscellaneous</strong> section of the{" "}
+        <strong>Configurator</strong> Settings tab. It sets the time in
+        milliseconds a jump across the full range takes. At 0 (the default) the
+        outputs jump right away. Gate and clock outputs are never slewed.
+      </li>
     </List>
   </>
 );
@@ -97,5 +97,40 @@ export const Troubleshooting = () => (
       back up any layouts or settings you want to keep by exporting them from
       the Configurator before performing a factory reset.
     </p>
+
+    <H3 id="self-test">Hardware Self-Test</H3>
+    <p>
+      To check the LEDs and jacks of your Faderpunk, hold the{" "}
+      <strong>first and last channel buttons</strong> (Channel 1 and Channel 16)
+      while connecting the USB cable. The self-test then runs through these
+      steps on all channels at once:
+    </p>
+    <List>
+      <li>All LEDs light up red, green, blue and white</li>
+      <li>All jacks pulse four times as gate outputs</li>
+      <li>All jacks ramp from 0V up to 10V and back down</li>
+    </List>
+    <p className="mt-4">
+      Patch the jacks into a scope or an LED module to check them. When the
+      self-test is done all LEDs flash green and the device restarts. Your
+      settings and layouts are left untouched.
+    </p>
+
+    <p className="mt-4">
+      To track down a fault on a single LED or jack, also hold{" "}
+      <strong>Shift</strong> while connecting the USB cable. The self-test then
+      goes through the channels one at a time:
+    </p>
+    <List>
+      <li>Every LED lights up white on its own</li>
+      <li>Every jack goes high once as a gate output</li>
+      <li>
+        Every jack is held at 0V, 1V, 5V and 10V for 1.5 seconds each, long
+        enough to measure with a multimeter. All other jacks stay at 0V
+      </li>
+    </List>
+    <p className="mt-4">
+      The top LED of the channel under test lights up during the jack steps.
+    </p>
   </>
 );
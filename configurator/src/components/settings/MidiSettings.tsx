@@ -151,6 +151,18 @@ export const MidiSettings = () => {
             </div>
           );
         })}
+        <div className="grid grid-cols-4 items-start gap-x-16 px-4">
+          <ControlledSwitch
+            name="midiActiveSensing"
+            control={control}
+            switchProps={{
+              color: "secondary",
+              classNames: switchClassNames,
+            }}
+          >
+            Active Sensing (Out 1 &amp; 2)
+          </ControlledSwitch>
+        </div>
       </div>
     </div>
   );
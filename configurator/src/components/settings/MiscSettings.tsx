@@ -74,6 +74,25 @@ export const MiscSettings = () => {
         >
           {(item) => <SelectItem>{item.value}</SelectItem>}
         </ControlledSelect>
+        <ControlledSlider
+          name="outputSlew"
+          control={control}
+          label={
+            <div className="flex items-center gap-1">
+              <span>Output Slew (ms)</span>
+              <Tooltip
+                content="Time a full scale jump takes on all CV outputs, 0 is off"
+                showArrow={true}
+              >
+                <button type="button" className="cursor-help">
+                  <Icon className="h-4 w-4" name="info" />
+                </button>
+              </Tooltip>
+            </div>
+          }
+          minValue={0}
+          maxValue={1000}
+        />
       </div>
     </div>
   );
@@ -1,5 +1,7 @@
+import { useMemo } from "react";
 import type { Key, Note } from "@atov/fp-config";
 import { SelectItem } from "@heroui/select";
+import { Switch } from "@heroui/switch";
 
 import type { Inputs } from "../SettingsTab";
 import { useFormContext } from "react-hook-form";
@@ -9,6 +11,8 @@ import {
   QUANTIZER_TONIC_COLORS,
 } from "../../utils/class-helpers";
 import { ControlledSelect } from "./ControlledFields";
+import { useStore } from "../../store";
+import { formatNote } from "../../utils/utils";
 
 interface QuantizerKeyItem {
   key: Key["tag"];
@@ -39,7 +43,7 @@ const keyItems: QuantizerKeyItem[] = [
   { key: "HungarianMin", value: "Hungarian Minor" },
 ];
 
-const tonicItems: QuantizerTonicItem[] = [
+const tonics: Note["tag"][] = [
   "C",
   "CSharp",
   "D",
@@ -52,13 +56,20 @@ const tonicItems: QuantizerTonicItem[] = [
   "A",
   "ASharp",
   "B",
-].map((note) => ({
-  key: note as Note["tag"],
-  value: note.replace("Sharp", "♯"),
-}));
+];
 
 export const QuantizerSettings = () => {
   const { control } = useFormContext<Inputs>();
+  const useFlats = useStore((state) => state.useFlats);
+  const setUseFlats = useStore((state) => state.setUseFlats);
+  const tonicItems: QuantizerTonicItem[] = useMemo(
+    () =>
+      tonics.map((note) => ({
+        key: note,
+        value: formatNote(note, useFlats),
+      })),
+    [useFlats],
+  );
 
   return (
     <div className="mb-12">
@@ -116,6 +127,17 @@ export const QuantizerSettings = () => {
             </SelectItem>
           )}
         </ControlledSelect>
+        <Switch
+          isSelected={useFlats}
+          onValueChange={setUseFlats}
+          color="secondary"
+          classNames={{
+            base: "flex-col-reverse items-start justify-start w-full",
+            label: "ms-0 mb-2 text-sm font-medium",
+          }}
+        >
+          Show flats
+        </Switch>
       </div>
     </div>
   );
@@ -27,9 +27,14 @@ interface State {
   setLayout: (layout: AppLayout) => void;
   setParams: (id: number, newParams: Value[]) => void;
   setAllParams: (newParams: ParamValues) => void;
+  setUseFlats: (useFlats: boolean) => void;
   usbDevice: USBDevice | undefined;
+  useFlats: boolean;
 }
 
+// Note spelling is a display preference of this browser, not of the device
+const USE_FLATS_KEY = "fp-use-flats";
+
 const initialState = {
   apps: undefined,
   config: undefined,
@@ -101,4 +106,9 @@ export const useStore = create<State>((set) => ({
   setParams: (id, newParams) =>
     set(({ params }) => ({ params: new Map(params).set(id, newParams) })),
   setAllParams: (newParams) => set({ params: newParams }),
+  setUseFlats: (useFlats) => {
+    localStorage.setItem(USE_FLATS_KEY, String(useFlats));
+    set({ useFlats });
+  },
+  useFlats: localStorage.getItem(USE_FLATS_KEY) === "true",
 }));
@@ -112,6 +112,51 @@ export const setAppParams = async (
   return response.value[1];
 };
 
+export const resetAppParams = async (dev: USBDevice, layoutId: number) => {
+  const response = await sendAndReceive(dev, {
+    tag: "ResetAppParams",
+    value: { layout_id: layoutId },
+  });
+
+  if (response.tag !== "AppState") {
+    throw new Error(
+      `Could not reset app params. Unexpected repsonse tag: ${response.tag}`,
+    );
+  }
+
+  return response.value[1];
+};
+
+export const copyAppParams = async (
+  dev: USBDevice,
+  fromLayoutId: number,
+  toLayoutId: number,
+  scenes: boolean,
+) => {
+  const response = await sendAndReceive(dev, {
+    tag: "CopyAppParams",
+    value: {
+      from_layout_id: fromLayoutId,
+      to_layout_id: toLayoutId,
+      scenes,
+    },
+  });
+
+  if (response.tag === "CopyRejected") {
+    throw new Error(
+      "Params can only be copied between apps of the same kind",
+    );
+  }
+
+  if (response.tag !== "AppState") {
+    throw new Error(
+      `Could not copy app params. Unexpected repsonse tag: ${response.tag}`,
+    );
+  }
+
+  return response.value[1];
+};
+
 export const getAllAppParams = async (
   dev: USBDevice,
 ): Promise<Map<number, Value[]>> => {
@@ -271,6 +316,54 @@ export const setLayout = async (
   return transformLayout(response, allApps);
 };
 
+export const getLayoutSlots = async (dev: USBDevice) => {
+  const response = await sendAndReceive(dev, {
+    tag: "GetLayoutSlots",
+  });
+
+  if (response.tag !== "LayoutSlots") {
+    throw new Error(
+      `Could not fetch layout slots. Unexpected repsonse tag: ${response.tag}`,
+    );
+  }
+
+  return response.value;
+};
+
+export const saveLayoutSlot = async (dev: USBDevice, slot: number) => {
+  const response = await sendAndReceive(dev, {
+    tag: "SaveLayoutSlot",
+    value: slot,
+  });
+
+  if (response.tag !== "LayoutSlots") {
+    throw new Error(
+      `Could not save layout slot. Unexpected repsonse tag: ${response.tag}`,
+    );
+  }
+
+  return response.value;
+};
+
+export const loadLayoutSlot = async (
+  dev: USBDevice,
+  slot: number,
+  allApps: AllApps,
+): Promise<AppLayout> => {
+  const response = await sendAndReceive(dev, {
+    tag: "LoadLayoutSlot",
+    value: slot,
+  });
+
+  if (response.tag !== "Layout") {
+    throw new Error(
+      `Could not load layout slot. Unexpected repsonse tag: ${response.tag}`,
+    );
+  }
+
+  return transformLayout(response, allApps);
+};
+
 export const saveLayout = (
   layout: AppLayout,
   params: ParamValues,
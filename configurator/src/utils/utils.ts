@@ -25,6 +25,17 @@ export const pascalToKebab = (str: string): string => {
   return camelized.replace(/([A-Z])/g, "-$1").toLowerCase();
 };
 
+const FLAT_NAMES: Partial<Record<Note["tag"], string>> = {
+  CSharp: "D♭",
+  DSharp: "E♭",
+  FSharp: "G♭",
+  GSharp: "A♭",
+  ASharp: "B♭",
+};
+
+export const formatNote = (note: Note["tag"], flats: boolean): string =>
+  (flats && FLAT_NAMES[note]) || note.replace("Sharp", "♯");
+
 export const getSlots = (app: App, startChannel: number) => {
   if (app.channels > 1) {
     return `${startChannel + 1}-${startChannel + Number(app.channels)}`;
@@ -85,18 +96,21 @@ export const getDefaultValue = (val: Value) => {
     case "MidiNrpn": {
       return val.value;
     }
+    case "ScaleMask": {
+      return val.value;
+    }
   }
 };
 
 const getParamValue = (
   paramType: Value["tag"],
-  value: string | boolean | boolean[],
+  value: string | number | boolean | boolean[],
 ): Value | undefined => {
   switch (paramType) {
     case "i32":
       return { tag: "i32", value: parseInt(value as string, 10) };
     case "f32":
-      return { tag: "f32", value: parseInt(value as string, 10) };
+      return { tag: "f32", value: Number(value) };
     case "bool":
       return { tag: "bool", value: value as boolean };
     case "Enum":
@@ -139,13 +153,15 @@ const getParamValue = (
       return { tag: "MidiMode", value: { tag: value as MidiModeTag } };
     case "MidiNrpn":
       return { tag: "MidiNrpn", value: value as boolean };
+    case "ScaleMask":
+      return { tag: "ScaleMask", value: Number(value) };
     default:
       return undefined;
   }
 };
 
 export const transformParamFormValues = (
-  values: Record<string, string | boolean | boolean[]>,
+  values: Record<string, string | number | boolean | boolean[]>,
 ) => {
   const entries = Object.entries(values);
   const result: FixedLengthArray<Value | undefined, 16> = [
@@ -189,12 +189,14 @@ const defaultGlobalConfig: GlobalConfig = {
         mode: { tag: "Local" },
       },
     ],
+    active_sensing: false,
   },
   quantizer: {
     key: { tag: "Chromatic" },
     tonic: { tag: "C" },
   },
   takeover_mode: { tag: "Pickup" },
+  output_slew: 0,
 };
 
 // Lenient schema that validates structure but allows any valid tag values
@@ -221,12 +223,14 @@ const globalConfigSchema = z.object({
         }),
       )
       .length(3),
+    active_sensing: z.boolean().default(false),
   }),
   quantizer: z.object({
     key: taggedObjectSchema,
     tonic: taggedObjectSchema,
   }),
   takeover_mode: taggedObjectSchema,
+  output_slew: z.number().int().min(0).max(1000).default(0),
 });
 
 export const parseGlobalConfigFromFile = (
@@ -257,9 +261,11 @@ export const parseGlobalConfigFromFile = (
         validated.midi.outs[1],
         validated.midi.outs[2],
       ] as GlobalConfig["midi"]["outs"],
+      active_sensing: validated.midi.active_sensing,
     },
     quantizer: validated.quantizer as GlobalConfig["quantizer"],
     takeover_mode: validated.takeover_mode as GlobalConfig["takeover_mode"],
+    output_slew: validated.output_slew,
   };
 
   return config;
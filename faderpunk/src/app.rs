@@ -1,9 +1,13 @@
-use core::cell::RefCell;
+use core::{
+    cell::{Cell, RefCell},
+    future::Future,
+    pin::pin,
+};
 
 use embassy_futures::select::{select, Either};
 use embassy_rp::clocks::RoscRng;
 use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
-use embassy_time::Timer;
+use embassy_time::{Duration, Instant, Timer};
 use max11300::config::{
     ConfigMode0, ConfigMode3, ConfigMode5, ConfigMode7, Mode, Port, ADCRANGE, AVR, DACRANGE,
     NSAMPLES,
@@ -12,22 +16,36 @@ use midly::{live::LiveEvent, num::u4, MidiMessage, PitchBend};
 use portable_atomic::Ordering;
 
 use libfp::{
+    input_filter::InputFilter,
     latch::AnalogLatch,
-    quantizer::{Pitch, QuantizerState},
-    utils::{scale_bits_12_7, scale_bits_14_12},
-    Brightness, ClockDivision, Color, Key, MidiCc, MidiChannel, MidiIn, MidiNote, MidiOut, Note,
-    Range, TakeoverMode,
+    mpe::{MpeTracker, MpeVoice},
+    quantizer::{
+        Pitch, QuantizeMode, Quantizer as ScaleQuantizer, QuantizerState, ScaleCrossfade,
+        TransposeMode,
+    },
+    utils::{
+        clickless, match_cc, match_note_on, probability_passes, scale_bits_12_7, scale_bits_14_12,
+        scale_input, split_unsigned_value,
+    },
+    Brightness, ClockDivision, ClockSrc, Color, Curve, GateMode, GatePolarity, Key, MidiCc,
+    MidiChannel, MidiIn, MidiNote, MidiOut, Note, NoteEvent, Range, TakeoverMode, VelocityCurve,
+    GLOBAL_CHANNELS,
 };
 
 use crate::{
     events::{EventPubSubChannel, InputEvent},
     tasks::{
         buttons::{is_channel_button_pressed, is_shift_button_pressed},
-        clock::{ClockSubscriber, CLOCK_PUBSUB, TICK_COUNTER},
+        clock::{
+            get_external_bpm, is_transport_running, ClockSubscriber, CLOCK_PUBSUB, TICK_COUNTER,
+        },
         global_config::get_global_config,
         i2c::{I2cLeaderMessage, I2cLeaderSender},
         leds::{set_led_mode, LedMode, LedMsg},
-        max::{MaxCmd, MaxSender, MAX_CHANNEL, MAX_VALUES_ADC, MAX_VALUES_DAC, MAX_VALUES_FADER},
+        max::{
+            MaxCmd, MaxSender, GATE_ACTIVE_LOW, GATE_PULSE_ENDS, MAX_CHANNEL, MAX_VALUES_ADC,
+            MAX_VALUES_DAC, MAX_VALUES_FADER,
+        },
         midi::{
             AppMidiSender, MidiEvent, MidiEventSource, MidiMsg, MidiPubSubChannel,
             MidiPubSubSubscriber,
@@ -37,10 +55,12 @@ use crate::{
 };
 
 pub use crate::{
-    storage::{AppParams, AppStorage, Arr, ManagedStorage, ParamStore},
+    storage::{AppParams, AppStorage, ManagedStorage, ParamStore},
     tasks::{clock::ClockEvent, leds::Led},
 };
 
+const DOUBLE_PRESS_TIME: Duration = Duration::from_millis(300);
+
 #[derive(Clone, Copy)]
 pub struct Leds<const N: usize> {
     start_channel: usize,
@@ -59,6 +79,15 @@ impl<const N: usize> Leds<N> {
             LedMsg::Set(LedMode::Static(color, brightness)),
         );
     }
+
+    /// Show a 0 to 4095 value on the top and bottom LED as a meter, bipolar around the center.
+    /// The top LED lights up above the center, the bottom one below.
+    pub fn set_meter(&self, chan: usize, value: u16, color: Color) {
+        let [top, bottom] = split_unsigned_value(value);
+        self.set(chan, Led::Top, color, Brightness::Custom(top));
+        self.set(chan, Led::Bottom, color, Brightness::Custom(bottom));
+    }
+
     pub fn set_mode(&self, chan: usize, position: Led, mode: LedMode) {
         let channel = self.start_channel + chan.clamp(0, N - 1);
         set_led_mode(channel, position, LedMsg::Set(mode));
@@ -89,50 +118,126 @@ impl<const N: usize> Leds<N> {
 pub struct InJack {
     channel: usize,
     range: Range,
+    // Input gain and offset, see `with_calibration`
+    calibration: Option<(f32, i16)>,
 }
 
 impl InJack {
     fn new(channel: usize, range: Range) -> Self {
-        Self { channel, range }
+        Self {
+            channel,
+            range,
+            calibration: None,
+        }
+    }
+
+    /// Scale the input by `gain` and then shift it by `offset`, e.g. to map a source into the
+    /// 0-4095 space with a custom window. The result is clamped to 12 bits.
+    #[allow(dead_code)]
+    pub fn with_calibration(mut self, gain: f32, offset: i16) -> Self {
+        self.calibration = Some((gain, offset));
+        self
     }
 
     pub fn get_value(&self) -> u16 {
         let val = MAX_VALUES_ADC[self.channel].load(Ordering::Relaxed);
-        match self.range {
+        let val = match self.range {
             Range::_0_5V => val.saturating_mul(2),
             _ => val,
+        };
+        match self.calibration {
+            Some((gain, offset)) => scale_input(val, gain, offset),
+            None => val,
         }
     }
+
+    /// Read the input through a filter for noisy sources, call it once per sample
+    #[allow(dead_code)]
+    pub fn filtered(&self, filter: &mut InputFilter) -> u16 {
+        filter.push(self.get_value())
+    }
 }
 
 pub struct GateJack {
     channel: usize,
+    mode: Cell<GateMode>,
+    polarity: Cell<GatePolarity>,
+    // Last gate state set by the app, before applying the polarity
+    gate: Cell<bool>,
 }
 
 impl GateJack {
-    fn new(channel: usize) -> Self {
-        Self { channel }
+    fn new(channel: usize, mode: GateMode) -> Self {
+        GATE_PULSE_ENDS[channel].store(0, Ordering::Relaxed);
+        GATE_ACTIVE_LOW[channel].store(false, Ordering::Relaxed);
+        Self {
+            channel,
+            mode: Cell::new(mode),
+            polarity: Cell::new(GatePolarity::default()),
+            gate: Cell::new(false),
+        }
     }
 
-    pub async fn set_high(&self) {
+    async fn write_gpo(&self, gate: bool) {
+        self.gate.set(gate);
         let port = Port::try_from(self.channel).unwrap();
-        MAX_CHANNEL.sender().send(MaxCmd::GpoSetHigh { port }).await;
+        let cmd = if self.polarity.get().gpo_high(gate) {
+            MaxCmd::GpoSetHigh { port }
+        } else {
+            MaxCmd::GpoSetLow { port }
+        };
+        MAX_CHANNEL.sender().send(cmd).await;
+    }
+
+    /// Turn the gate on. In auto mode it turns itself off after the pulse length.
+    pub async fn set_high(&self) {
+        self.write_gpo(true).await;
+        if let Some(end) = self.mode.get().pulse_end(Instant::now()) {
+            GATE_PULSE_ENDS[self.channel].store(end.as_ticks().max(1), Ordering::Relaxed);
+        }
     }
 
     pub async fn set_low(&self) {
-        let port = Port::try_from(self.channel).unwrap();
-        MAX_CHANNEL.sender().send(MaxCmd::GpoSetLow { port }).await;
+        GATE_PULSE_ENDS[self.channel].store(0, Ordering::Relaxed);
+        self.write_gpo(false).await;
+    }
+
+    /// Switch between manual gates and auto triggers
+    #[allow(dead_code)]
+    pub fn set_mode(&self, mode: GateMode) {
+        self.mode.set(mode);
+    }
+
+    /// Set the polarity of the gate. The jack is updated right away, so an inverted gate that
+    /// is off goes high.
+    #[allow(dead_code)]
+    pub async fn set_polarity(&self, polarity: GatePolarity) {
+        if polarity != self.polarity.get() {
+            self.polarity.set(polarity);
+            GATE_ACTIVE_LOW[self.channel]
+                .store(polarity == GatePolarity::ActiveLow, Ordering::Relaxed);
+            // An auto trigger that already ended is off
+            let pulse_pending = GATE_PULSE_ENDS[self.channel].load(Ordering::Relaxed) != 0;
+            let gate = self.gate.get() && (self.mode.get() == GateMode::Manual || pulse_pending);
+            self.write_gpo(gate).await;
+        }
     }
 }
 
 pub struct OutJack {
     channel: usize,
     range: Range,
+    // Last value written by `set_value_smoothed`
+    smoothed: Cell<Option<u16>>,
 }
 
 impl OutJack {
     fn new(channel: usize, range: Range) -> Self {
-        Self { channel, range }
+        Self {
+            channel,
+            range,
+            smoothed: Cell::new(None),
+        }
     }
 
     pub fn set_value(&self, value: u16) {
@@ -142,6 +247,35 @@ impl OutJack {
         };
         MAX_VALUES_DAC[self.channel].store(val, Ordering::Relaxed);
     }
+
+    /// Move the output towards `target` without clicks, see `clickless`. Meant to be called
+    /// every tick: the output lags behind jumps of the target, moving a sixteenth of the way
+    /// each call, so a full-scale jump takes up to 100 ticks to settle. The first call goes
+    /// straight to the target. Returns the value that was written.
+    pub fn set_value_smoothed(&self, target: u16) -> u16 {
+        let value = self
+            .smoothed
+            .get()
+            .map_or(target, |prev| clickless(prev, target));
+        self.smoothed.set(Some(value));
+        self.set_value(value);
+        value
+    }
+}
+
+/// Reads back the value an output jack is set to, in the counts of its own range
+pub struct OutJackReader {
+    channel: usize,
+}
+
+impl OutJackReader {
+    fn new(channel: usize) -> Self {
+        Self { channel }
+    }
+
+    pub fn get_value(&self) -> u16 {
+        MAX_VALUES_DAC[self.channel].load(Ordering::Relaxed)
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -229,6 +363,27 @@ impl<const N: usize> Buttons<N> {
         }
     }
 
+    /// Returns the number of the button that was pressed twice within `DOUBLE_PRESS_TIME`
+    pub async fn wait_for_any_double_press(&self) -> (usize, bool) {
+        let mut subscriber = self.event_pubsub.subscriber().unwrap();
+        let mut last_press: Option<(usize, Instant)> = None;
+
+        loop {
+            if let InputEvent::ButtonDown(channel) = subscriber.next_message_pure().await {
+                if (self.start_channel..self.start_channel + N).contains(&channel) {
+                    let chan = channel - self.start_channel;
+                    let now = Instant::now();
+                    if let Some((last_chan, at)) = last_press {
+                        if last_chan == chan && now - at <= DOUBLE_PRESS_TIME {
+                            return (chan, self.is_shift_pressed());
+                        }
+                    }
+                    last_press = Some((chan, now));
+                }
+            }
+        }
+    }
+
     pub fn is_button_pressed(&self, chan: usize) -> bool {
         let chan = chan.clamp(0, N - 1);
         is_channel_button_pressed(self.start_channel + chan)
@@ -323,17 +478,67 @@ impl Clock {
                 ClockEvent::Stop => {
                     return ClockEvent::Stop;
                 }
-                clock_event @ ClockEvent::Start | clock_event @ ClockEvent::Reset => {
+                clock_event @ (ClockEvent::Start | ClockEvent::Continue | ClockEvent::Reset) => {
                     return clock_event;
                 }
             }
         }
     }
 
+    /// Like `wait_for_event`, but with `manual` set the sequencer is stepped by hand: ticks of
+    /// the clock are dropped and a `Tick` is returned when `step` completes instead. Transport
+    /// events still come through. The ticker doesn't move for manual ticks, see
+    /// `libfp::utils::ManualTicks`.
+    pub async fn wait_for_event_or_step(
+        &mut self,
+        division: ClockDivision,
+        manual: bool,
+        step: impl Future,
+    ) -> ClockEvent {
+        if !manual {
+            return self.wait_for_event(division).await;
+        }
+        let mut step = pin!(step);
+        loop {
+            match select(self.wait_for_event(division), &mut step).await {
+                Either::First(ClockEvent::Tick) => {}
+                Either::First(clock_event) => return clock_event,
+                Either::Second(_) => return ClockEvent::Tick,
+            }
+        }
+    }
+
     #[allow(dead_code)]
     pub fn get_ticker(&self) -> fn() -> u64 {
         ticks
     }
+
+    /// Whether the clock is running, it is as soon as `Start` comes in and stops with `Stop`.
+    /// Analog clocks have no transport, they tick even while the clock is stopped.
+    #[allow(dead_code)]
+    pub fn is_running(&self) -> bool {
+        is_transport_running()
+    }
+
+    /// Current tempo. That is the internal BPM, or the measured one when following an external
+    /// clock. `None` while the external clock hasn't been measured yet.
+    #[allow(dead_code)]
+    pub fn get_bpm(&self) -> Option<f32> {
+        let config = get_global_config();
+        if config.clock.clock_src == ClockSrc::Internal {
+            Some(config.clock.internal_bpm)
+        } else {
+            get_external_bpm()
+        }
+    }
+
+    /// Current tempo like `get_bpm`, falling back to the internal BPM until an external clock
+    /// has been measured
+    #[allow(dead_code)]
+    pub fn current_bpm(&self) -> f32 {
+        self.get_bpm()
+            .unwrap_or_else(|| get_global_config().clock.internal_bpm)
+    }
 }
 
 #[allow(dead_code)]
@@ -360,13 +565,30 @@ impl<const N: usize> I2cOutput<N> {
         }
     }
 
+    fn channel(&self, chan: usize) -> usize {
+        self.start_channel + chan.clamp(0, N - 1)
+    }
+
     pub fn send_fader_value(&self, chan: usize, value: u16, range: Range) {
-        let chan = chan.clamp(0, N - 1);
-        let msg = I2cLeaderMessage::FaderValue(self.start_channel + chan, value, range);
+        let msg = I2cLeaderMessage::FaderValue(self.channel(chan), value, range);
         // Use try_send to avoid blocking the caller if the I2C channel is full.
         // Dropping occasional updates is fine — the next update will send the current value.
         let _ = self.i2c_sender.try_send(msg);
     }
+
+    /// Send a note as 1V/oct CV to the channel's output on the I2C devices
+    #[allow(dead_code)]
+    pub fn send_note(&self, chan: usize, note: MidiNote) {
+        let msg = I2cLeaderMessage::Note(self.channel(chan), note);
+        let _ = self.i2c_sender.try_send(msg);
+    }
+
+    /// Send a gate as 0V/5V CV to the channel's output on the I2C devices
+    #[allow(dead_code)]
+    pub fn send_gate(&self, chan: usize, high: bool) {
+        let msg = I2cLeaderMessage::Gate(self.channel(chan), high);
+        let _ = self.i2c_sender.try_send(msg);
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -376,6 +598,7 @@ pub struct MidiOutput {
     midi_out: MidiOut,
     midi_sender: AppMidiSender,
     nrpn_mode: bool,
+    velocity_curve: VelocityCurve,
 }
 
 impl MidiOutput {
@@ -392,9 +615,16 @@ impl MidiOutput {
             midi_out,
             midi_sender,
             nrpn_mode,
+            velocity_curve: VelocityCurve::default(),
         }
     }
 
+    /// Shape the velocity of all note ons, e.g. for gear with a non-linear velocity response
+    pub fn with_velocity_curve(mut self, curve: Curve) -> Self {
+        self.velocity_curve = VelocityCurve(curve);
+        self
+    }
+
     async fn send_midi_msg(&self, msg: MidiMessage) {
         let event = LiveEvent::Midi {
             channel: self.midi_channel,
@@ -424,7 +654,7 @@ impl MidiOutput {
     pub async fn send_note_on(&self, note_number: MidiNote, velocity: u16) {
         let msg = MidiMessage::NoteOn {
             key: note_number.into(),
-            vel: scale_bits_12_7(velocity),
+            vel: scale_bits_12_7(self.velocity_curve.apply(velocity)),
         };
         self.send_midi_msg(msg).await;
     }
@@ -513,6 +743,13 @@ impl MidiInput {
         }
     }
 
+    fn try_next_event(&mut self) -> Option<MidiEvent> {
+        self.din_sub
+            .as_mut()
+            .and_then(|din| din.try_next_message_pure())
+            .or_else(|| self.usb_sub.as_mut()?.try_next_message_pure())
+    }
+
     /// Wait for the next standard MIDI message on this channel (skips NRPN events).
     pub async fn wait_for_message(&mut self) -> MidiMessage {
         loop {
@@ -525,6 +762,46 @@ impl MidiInput {
         }
     }
 
+    /// Wait for a control change of `cc` on this channel and return its 12-bit value. With NRPN
+    /// the CC is matched against the parameter number.
+    #[allow(dead_code)]
+    pub async fn wait_for_cc(&mut self, cc: MidiCc) -> u16 {
+        loop {
+            match self.wait_for_event().await {
+                AppMidiEvent::Message(message) => {
+                    if let Some(value) = match_cc(&message, cc) {
+                        return value;
+                    }
+                }
+                AppMidiEvent::Nrpn { param, value } if param == cc.as_u16() => {
+                    return value;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Wait for a note on this channel and return it with its 12-bit velocity. Note ons with
+    /// velocity 0 are skipped as note offs.
+    #[allow(dead_code)]
+    pub async fn wait_for_note_on(&mut self) -> (MidiNote, u16) {
+        loop {
+            if let Some(note) = match_note_on(&self.wait_for_message().await) {
+                return note;
+            }
+        }
+    }
+
+    /// Wait for the next note on or off on this channel. Note ons with velocity 0 come out as
+    /// note offs.
+    pub async fn next_note_event(&mut self) -> NoteEvent {
+        loop {
+            if let Some(event) = NoteEvent::from_message(&self.wait_for_message().await) {
+                return event;
+            }
+        }
+    }
+
     /// Wait for any MIDI event (standard message or NRPN) on this channel.
     pub async fn wait_for_event(&mut self) -> AppMidiEvent {
         loop {
@@ -548,6 +825,76 @@ impl MidiInput {
             }
         }
     }
+
+    /// Wait for any MIDI event (standard message or NRPN) on any channel, returns it with the
+    /// channel it came in on.
+    pub async fn wait_for_any_channel_event(&mut self) -> (u4, AppMidiEvent) {
+        loop {
+            match self.next_event().await {
+                MidiEvent::Live(LiveEvent::Midi { channel, message }) => {
+                    return (channel, AppMidiEvent::Message(message));
+                }
+                MidiEvent::Nrpn {
+                    channel,
+                    param,
+                    value,
+                } => {
+                    return (
+                        channel,
+                        AppMidiEvent::Nrpn {
+                            param,
+                            value: scale_bits_14_12(value),
+                        },
+                    );
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// MIDI input from an MPE controller, with one voice per member channel
+pub struct MpeInput<const N: usize> {
+    input: MidiInput,
+    tracker: MpeTracker<N>,
+}
+
+impl<const N: usize> MpeInput<N> {
+    fn handle_event(&mut self, event: MidiEvent) -> Option<usize> {
+        match event {
+            MidiEvent::Live(LiveEvent::Midi { channel, message }) => {
+                self.tracker.update(channel, &message)
+            }
+            _ => None,
+        }
+    }
+
+    /// Handle the messages received since the last poll and return the voices. Needs to be
+    /// polled regularly, otherwise messages are dropped once the MIDI queue is full.
+    #[allow(dead_code)]
+    pub fn poll_voices(&mut self) -> [Option<MpeVoice>; N] {
+        while let Some(event) = self.input.try_next_event() {
+            self.handle_event(event);
+        }
+        self.tracker.voices()
+    }
+
+    /// Wait until a voice changes and return its index
+    #[allow(dead_code)]
+    pub async fn wait_for_change(&mut self) -> usize {
+        loop {
+            let event = self.input.next_event().await;
+            if let Some(idx) = self.handle_event(event) {
+                return idx;
+            }
+        }
+    }
+
+    /// Release all voices
+    #[allow(dead_code)]
+    pub fn reset(&mut self) {
+        self.tracker.reset();
+    }
 }
 
 pub struct Global<T: Sized> {
@@ -612,11 +959,19 @@ impl Die {
         let random_u16 = u16::from_le_bytes([b1, b2]);
         random_u16 % 4096
     }
+
+    /// Returns `true` with the given 8-bit probability (`255` is always).
+    pub fn roll_bool(&self, probability: u8) -> bool {
+        probability_passes(probability, self.roll())
+    }
 }
 
 pub struct Quantizer {
     range: Range,
     state: RefCell<QuantizerState>,
+    // Scale of this app only, overrides the global quantizer
+    scale: Option<ScaleQuantizer>,
+    crossfade: Option<RefCell<ScaleCrossfade>>,
 }
 
 impl Quantizer {
@@ -624,25 +979,96 @@ impl Quantizer {
         Self {
             range,
             state: RefCell::new(QuantizerState::default()),
+            scale: None,
+            crossfade: None,
         }
     }
+
+    pub fn with_scale(range: Range, key: Key, tonic: Note) -> Self {
+        Self {
+            range,
+            state: RefCell::new(QuantizerState::default()),
+            scale: Some(ScaleQuantizer::new(key, tonic)),
+            crossfade: None,
+        }
+    }
+
+    /// Quantize to the pitch classes in `mask` instead of the global scale, e.g. the notes held
+    /// on a MIDI input. An empty mask is chromatic.
+    pub fn set_active_notes(&mut self, mask: u16) {
+        self.scale
+            .get_or_insert_with(ScaleQuantizer::default)
+            .set_active_notes(mask);
+    }
+
+    /// Which scale tone inputs between two tones go to
+    pub fn set_mode(&self, mode: QuantizeMode) {
+        self.state.borrow_mut().set_mode(mode);
+    }
+
+    /// Ramp to the new note over `fade_ms` when the scale changes, for `get_quantized_counts`
+    pub fn set_scale_crossfade(&mut self, fade_ms: u16) {
+        self.crossfade = (fade_ms > 0).then(|| RefCell::new(ScaleCrossfade::new(fade_ms)));
+    }
+
     /// Quantize a note
     pub async fn get_quantized_note(&self, value: u16) -> Pitch {
         let value = value.clamp(0, 4095);
+        if let Some(scale) = &self.scale {
+            let mut state = self.state.borrow_mut();
+            return scale.get_quantized_note(&mut state, value, self.range);
+        }
         let quantizer = QUANTIZER.get().lock().await;
         let mut state = self.state.borrow_mut();
         quantizer.get_quantized_note(&mut state, value, self.range)
     }
+    /// Quantize a note transposed by `steps`, semitones or scale degrees depending on `mode`
+    pub async fn get_transposed_note(&self, value: u16, steps: i32, mode: TransposeMode) -> Pitch {
+        let value = value.clamp(0, 4095);
+        if let Some(scale) = &self.scale {
+            let mut state = self.state.borrow_mut();
+            return scale.get_transposed_note(&mut state, value, self.range, steps, mode);
+        }
+        let quantizer = QUANTIZER.get().lock().await;
+        let mut state = self.state.borrow_mut();
+        quantizer.get_transposed_note(&mut state, value, self.range, steps, mode)
+    }
+    /// Quantize a value to output counts, crossfading on scale changes if set up. `dt_ms` is the
+    /// time since the last call.
+    pub async fn get_quantized_counts(&self, value: u16, dt_ms: f32) -> u16 {
+        let value = value.clamp(0, 4095);
+        let (pitch, version) = if let Some(scale) = &self.scale {
+            let mut state = self.state.borrow_mut();
+            (
+                scale.get_quantized_note(&mut state, value, self.range),
+                scale.version(),
+            )
+        } else {
+            let quantizer = QUANTIZER.get().lock().await;
+            let mut state = self.state.borrow_mut();
+            (
+                quantizer.get_quantized_note(&mut state, value, self.range),
+                quantizer.version(),
+            )
+        };
+        let counts = pitch.as_counts(self.range);
+        match &self.crossfade {
+            Some(crossfade) => crossfade.borrow_mut().process(counts, version, dt_ms),
+            None => counts,
+        }
+    }
     /// Get Quantizer scale
     #[allow(dead_code)]
     pub async fn get_scale(&self) -> (Key, Note) {
+        if let Some(scale) = &self.scale {
+            return (scale.get_key(), scale.get_tonic());
+        }
         let quantizer = QUANTIZER.get().lock().await;
         (quantizer.get_key(), quantizer.get_tonic())
     }
 }
 
 #[derive(Debug)]
-#[allow(dead_code)]
 pub enum AppError {
     DeserializeFailed,
 }
@@ -727,6 +1153,18 @@ impl<const N: usize> App<N> {
         InJack::new(self.start_channel + chan, range)
     }
 
+    /// Read the input of any of the global channels without reconfiguring it.
+    /// The jack has to be set up as an input by the app it belongs to.
+    pub fn use_global_in_jack(&self, channel: usize, range: Range) -> InJack {
+        InJack::new(channel.clamp(0, GLOBAL_CHANNELS - 1), range)
+    }
+
+    /// Follow the output of any of the global channels, e.g. to mirror another app.
+    /// The value only makes sense while the channel is set up as an output.
+    pub fn use_global_out_jack(&self, channel: usize) -> OutJackReader {
+        OutJackReader::new(channel.clamp(0, GLOBAL_CHANNELS - 1))
+    }
+
     pub async fn make_out_jack(&self, chan: usize, range: Range) -> OutJack {
         let chan = chan.clamp(0, N - 1);
         let dac_range = match range {
@@ -744,7 +1182,17 @@ impl<const N: usize> App<N> {
         self.reconfigure_jack(chan, Mode::Mode3(ConfigMode3), Some(level))
             .await;
 
-        GateJack::new(self.start_channel + chan)
+        GateJack::new(self.start_channel + chan, GateMode::Manual)
+    }
+
+    /// Make a gate jack in auto mode: every `set_high` is a trigger that goes low by itself
+    /// after `length_ms`
+    pub async fn make_trigger_jack(&self, chan: usize, level: u16, length_ms: u16) -> GateJack {
+        let chan = chan.clamp(0, N - 1);
+        self.reconfigure_jack(chan, Mode::Mode3(ConfigMode3), Some(level))
+            .await;
+
+        GateJack::new(self.start_channel + chan, GateMode::Auto(length_ms))
     }
 
     pub async fn delay_millis(&self, millis: u64) {
@@ -780,6 +1228,11 @@ impl<const N: usize> App<N> {
         Quantizer::new(range)
     }
 
+    /// Quantizer with its own scale, independent of the global quantizer
+    pub fn use_scale_quantizer(&self, range: Range, key: Key, tonic: Note) -> Quantizer {
+        Quantizer::with_scale(range, key, tonic)
+    }
+
     pub fn use_midi_input(&self, midi_in: MidiIn, midi_channel: MidiChannel) -> MidiInput {
         MidiInput::new(
             midi_in,
@@ -789,6 +1242,19 @@ impl<const N: usize> App<N> {
         )
     }
 
+    /// MPE input with `VOICES` voices, played on the member channels from `first_channel` up
+    #[allow(dead_code)]
+    pub fn use_mpe_input<const VOICES: usize>(
+        &self,
+        midi_in: MidiIn,
+        first_channel: MidiChannel,
+    ) -> MpeInput<VOICES> {
+        MpeInput {
+            input: self.use_midi_input(midi_in, first_channel),
+            tracker: MpeTracker::new(first_channel.into()),
+        }
+    }
+
     pub fn use_midi_output(
         &self,
         midi_out: MidiOut,
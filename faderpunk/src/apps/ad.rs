@@ -4,12 +4,15 @@ use embassy_futures::{
 };
 use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
 use heapless::Vec;
-use midly::MidiMessage;
 use serde::{Deserialize, Serialize};
 
 use libfp::{
-    ext::FromValue, latch::LatchLayer, utils::attenuate, AppIcon, Brightness, Color, Config, Curve,
-    MidiChannel, MidiIn, Param, Range, Value, APP_MAX_PARAMS,
+    edge::{Edge, EdgeDetector, GATE_MIN_WIDTH},
+    ext::FromValue,
+    latch::LatchLayer,
+    utils::attenuate,
+    AppIcon, Brightness, Color, Config, Curve, MidiChannel, MidiIn, NoteEvent, Param, Range, Value,
+    APP_MAX_PARAMS,
 };
 
 use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
@@ -29,7 +32,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
 })
 .add_param(Param::bool {
     name: "MIDI retrigger",
-});
+})
+.finalize();
 
 pub struct Params {
     midi_in: MidiIn,
@@ -125,7 +129,7 @@ pub async fn run(
     let minispeed = 10.0;
 
     let mut vals: f32 = 0.0;
-    let mut oldinputval = 0;
+    let mut gate_edge = EdgeDetector::default().with_min_width(GATE_MIN_WIDTH);
     let mut env_state = 0;
 
     let color = [Color::Yellow, Color::Cyan, Color::Pink];
@@ -169,15 +173,11 @@ pub async fn run(
             let times = times_glob.get();
             let curve_setting = storage.query(|s| s.curve_saved);
 
-            let inputval = input.get_value();
-            if inputval >= 406 && oldinputval < 406 {
-                // catching rising edge
-                gate_on_glob.modify(|note_num| *note_num + 1);
-            }
-            if inputval <= 406 && oldinputval > 406 {
-                gate_on_glob.modify(|note_num| (*note_num - 1).max(0));
+            match gate_edge.update(input.get_value()) {
+                Some(Edge::Rising) => gate_on_glob.modify(|note_num| *note_num + 1),
+                Some(Edge::Falling) => gate_on_glob.modify(|note_num| (*note_num - 1).max(0)),
+                None => {}
             }
-            oldinputval = inputval;
 
             if gate_on_glob.get() > 0 && !old_gate {
                 env_state = 1;
@@ -395,19 +395,10 @@ pub async fn run(
     let midi_handler = async {
         let mut midi_in = app.use_midi_input(midi_in, midi_chan);
         loop {
-            match midi_in.wait_for_message().await {
-                MidiMessage::NoteOn { key: _, vel } => {
-                    if vel > 0 {
-                        gate_on_glob.modify(|note_num| *note_num + 1);
-                    } else {
-                        gate_on_glob.modify(|note_num| (*note_num - 1).max(0));
-                    }
-                }
-                MidiMessage::NoteOff { .. } => {
-                    gate_on_glob.modify(|note_num| (*note_num - 1).max(0));
-                }
-                _ => {}
-            }
+            match midi_in.next_note_event().await {
+                NoteEvent::On { .. } => gate_on_glob.modify(|note_num| *note_num + 1),
+                NoteEvent::Off { .. } => gate_on_glob.modify(|note_num| (*note_num - 1).max(0)),
+            };
         }
     };
 
@@ -0,0 +1,300 @@
+use embassy_futures::{
+    join::{join4, join5},
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    edge::{EdgeDetector, GATE_MIN_WIDTH},
+    envelope::{Envelope, EnvelopeSettings, EnvelopeStage},
+    ext::FromValue,
+    latch::LatchLayer,
+    utils::attenuate,
+    AppIcon, Brightness, Color, Config, Curve, MidiChannel, MidiIn, NoteEvent, Param, Range, Value,
+    APP_MAX_PARAMS,
+};
+
+use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
+
+pub const CHANNELS: usize = 4;
+pub const PARAMS: usize = 3;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+/// Shortest stage time in milliseconds
+const MIN_TIME: f32 = 1.0;
+/// Length of the end of cycle trigger in milliseconds
+const EOC_LENGTH: u16 = 10;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "ADSR Envelope",
+    "Gate or MIDI triggered ADSR envelope",
+    Color::Yellow,
+    AppIcon::AdEnv,
+)
+.add_param(Param::MidiIn)
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.finalize();
+
+pub struct Params {
+    midi_in: MidiIn,
+    midi_channel: MidiChannel,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            midi_in: MidiIn::from_value(values[0]),
+            midi_channel: MidiChannel::from_value(values[1]),
+            color: Color::from_value(values[2]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.midi_in.into()).unwrap();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    fader_saved: [u16; 4],
+    curve_saved: Curve,
+    att_saved: u16,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            fader_saved: [1000, 2000, 3000, 2000],
+            curve_saved: Curve::Linear,
+            att_saved: 4095,
+        }
+    }
+}
+
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            midi_in: MidiIn([false, false]),
+            midi_channel: MidiChannel::default(),
+            color: Color::Yellow,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+/// Stage time in milliseconds for a given fader value
+fn stage_time(value: u16) -> f32 {
+    Curve::Exponential.at(value) as f32 + MIN_TIME
+}
+
+fn envelope_settings(faders: [u16; 4]) -> EnvelopeSettings {
+    EnvelopeSettings {
+        attack: stage_time(faders[0]),
+        decay: stage_time(faders[1]),
+        sustain: faders[2],
+        release: stage_time(faders[3]),
+    }
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (midi_in, midi_chan, led_color) = params.query(|p| (p.midi_in, p.midi_channel, p.color));
+
+    let buttons = app.use_buttons();
+    let faders = app.use_faders();
+    let leds = app.use_leds();
+
+    let input = app.make_in_jack(0, Range::_0_10V).await;
+    let output = app.make_out_jack(1, Range::_0_10V).await;
+    let inverted = app.make_out_jack(2, Range::_0_10V).await;
+    let eoc = app.make_trigger_jack(3, 4095, EOC_LENGTH).await;
+
+    let midi_gates_glob = app.make_global(0_u8);
+    let glob_latch_layer = app.make_global(LatchLayer::Main);
+
+    let curve_colors = [Color::Yellow, Color::Cyan, Color::Pink];
+    let update_curve_led = |curve: Curve| {
+        leds.set(3, Led::Button, curve_colors[curve as usize], LED_BRIGHTNESS);
+    };
+    update_curve_led(storage.query(|s| s.curve_saved));
+
+    let main_loop = async {
+        let mut envelope = Envelope::new();
+        let mut gate_edge = EdgeDetector::default().with_min_width(GATE_MIN_WIDTH);
+
+        loop {
+            app.delay_millis(1).await;
+            let latch_layer = glob_latch_layer.set(LatchLayer::from(buttons.is_shift_pressed()));
+
+            gate_edge.update(input.get_value());
+            let gate = gate_edge.is_high()
+                || (buttons.is_button_pressed(0) && !buttons.is_shift_pressed())
+                || midi_gates_glob.get() > 0;
+            envelope.gate(gate);
+
+            let (fader_values, curve, att) =
+                storage.query(|s| (s.fader_saved, s.curve_saved, s.att_saved));
+            let was_running = envelope.stage() != EnvelopeStage::Idle;
+            envelope.update(&envelope_settings(fader_values));
+
+            if was_running && envelope.stage() == EnvelopeStage::Idle {
+                eoc.set_high().await;
+            }
+
+            let outval = attenuate(envelope.output(curve), att);
+            output.set_value(outval);
+            inverted.set_value(att.saturating_sub(outval));
+
+            let stage_led = match envelope.stage() {
+                EnvelopeStage::Attack => Some(0),
+                EnvelopeStage::Decay => Some(1),
+                EnvelopeStage::Sustain => Some(2),
+                EnvelopeStage::Release => Some(3),
+                EnvelopeStage::Idle => None,
+            };
+            for chan in 0..CHANNELS {
+                if latch_layer == LatchLayer::Alt && chan == 3 {
+                    leds.set(
+                        chan,
+                        Led::Top,
+                        Color::Red,
+                        Brightness::Custom((att / 16) as u8),
+                    );
+                } else if stage_led == Some(chan) {
+                    leds.set(
+                        chan,
+                        Led::Top,
+                        led_color,
+                        Brightness::Custom((outval / 16) as u8),
+                    );
+                } else {
+                    leds.unset(chan, Led::Top);
+                }
+            }
+            if gate {
+                leds.set(0, Led::Bottom, Color::Red, Brightness::High);
+            } else {
+                leds.unset(0, Led::Bottom);
+            }
+        }
+    };
+
+    let fader_handler = async {
+        let mut latch: [_; 4] = core::array::from_fn(|i| app.make_latch(faders.get_value_at(i)));
+
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            let latch_layer = glob_latch_layer.get();
+            let target_value = match latch_layer {
+                LatchLayer::Main => storage.query(|s| s.fader_saved[chan]),
+                LatchLayer::Alt if chan == 3 => storage.query(|s| s.att_saved),
+                _ => continue,
+            };
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), latch_layer, target_value)
+            {
+                storage.modify_and_save(|s| match latch_layer {
+                    LatchLayer::Main => s.fader_saved[chan] = new_value,
+                    _ => s.att_saved = new_value,
+                });
+            }
+        }
+    };
+
+    let button_handler = async {
+        loop {
+            let (chan, is_shift_pressed) = buttons.wait_for_any_down().await;
+            if chan == 3 && !is_shift_pressed {
+                let curve = storage.modify_and_save(|s| {
+                    s.curve_saved = s.curve_saved.cycle();
+                    s.curve_saved
+                });
+                update_curve_led(curve);
+            }
+        }
+    };
+
+    let midi_handler = async {
+        let mut midi_in = app.use_midi_input(midi_in, midi_chan);
+        loop {
+            match midi_in.next_note_event().await {
+                NoteEvent::On { .. } => midi_gates_glob.modify(|count| count.saturating_add(1)),
+                NoteEvent::Off { .. } => midi_gates_glob.modify(|count| count.saturating_sub(1)),
+            };
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    update_curve_led(storage.query(|s| s.curve_saved));
+                }
+                SceneEvent::SaveScene(scene) => storage.save_to_scene(scene).await,
+            }
+        }
+    };
+
+    if midi_in.is_none() {
+        join4(main_loop, fader_handler, button_handler, scene_handler).await;
+    } else {
+        join5(
+            main_loop,
+            fader_handler,
+            button_handler,
+            midi_handler,
+            scene_handler,
+        )
+        .await;
+    }
+}
@@ -0,0 +1,237 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    utils::{attenuvert_cv, clickless},
+    AppIcon, Brightness, Color, Config, Param, Range, Value, APP_MAX_PARAMS, GLOBAL_CHANNELS,
+};
+
+use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
+
+pub const CHANNELS: usize = 1;
+pub const PARAMS: usize = 3;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Attenuverter",
+    "Gain, offset and invert for the output of another channel",
+    Color::Rose,
+    AppIcon::Attenuate,
+)
+.add_param(Param::i32 {
+    name: "Source channel",
+    min: 1,
+    max: GLOBAL_CHANNELS as i32,
+    step: 1,
+})
+.add_param(Param::Range {
+    name: "Range",
+    variants: &[Range::_0_10V, Range::_Neg5_5V],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.finalize();
+
+pub struct Params {
+    source: i32,
+    range: Range,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            source: i32::from_value(values[0]),
+            range: Range::from_value(values[1]),
+            color: Color::from_value(values[2]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.source.into()).unwrap();
+        vec.push(self.range.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    offset_saved: u16,
+    gain_saved: u16,
+    invert_saved: bool,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            offset_saved: 2048,
+            gain_saved: 4095,
+            invert_saved: false,
+        }
+    }
+}
+
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            // Follow the channel right before this app
+            source: app.start_channel.max(1) as i32,
+            range: Range::_Neg5_5V,
+            color: Color::Rose,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (source, range, led_color) = params.query(|p| {
+        (
+            p.source.clamp(1, GLOBAL_CHANNELS as i32) as usize - 1,
+            p.range,
+            p.color,
+        )
+    });
+
+    let fader = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let input = app.use_global_out_jack(source);
+    let output = app.make_out_jack(0, range).await;
+
+    let glob_latch_layer = app.make_global(LatchLayer::Main);
+
+    let update_invert_led = |invert: bool| {
+        if invert {
+            leds.set(0, Led::Button, led_color, LED_BRIGHTNESS);
+        } else {
+            leds.unset(0, Led::Button);
+        }
+    };
+    update_invert_led(storage.query(|s| s.invert_saved));
+
+    let main_loop = async {
+        let (mut offset, mut gain) = storage.query(|s| (s.offset_saved, s.gain_saved));
+        loop {
+            app.delay_millis(1).await;
+            let latch_layer = glob_latch_layer.set(LatchLayer::from(buttons.is_shift_pressed()));
+
+            let (offset_saved, gain_saved, invert) =
+                storage.query(|s| (s.offset_saved, s.gain_saved, s.invert_saved));
+            offset = clickless(offset, offset_saved);
+            gain = clickless(gain, gain_saved);
+
+            let out = attenuvert_cv(input.get_value(), gain, offset, invert, range.is_bipolar());
+            output.set_value(out);
+
+            if latch_layer == LatchLayer::Alt {
+                leds.set(
+                    0,
+                    Led::Top,
+                    Color::Red,
+                    Brightness::Custom((gain_saved / 16) as u8),
+                );
+                leds.unset(0, Led::Bottom);
+            } else if range.is_bipolar() {
+                leds.set_meter(0, out, led_color);
+            } else {
+                leds.set(0, Led::Top, led_color, Brightness::Custom((out / 16) as u8));
+            }
+        }
+    };
+
+    let button_handler = async {
+        loop {
+            buttons.wait_for_down(0).await;
+            let invert = storage.modify_and_save(|s| {
+                s.invert_saved = !s.invert_saved;
+                s.invert_saved
+            });
+            update_invert_led(invert);
+        }
+    };
+
+    let fader_handler = async {
+        let mut latch = app.make_latch(fader.get_value());
+        loop {
+            fader.wait_for_change().await;
+            let latch_layer = glob_latch_layer.get();
+            let target_value = match latch_layer {
+                LatchLayer::Main => storage.query(|s| s.offset_saved),
+                LatchLayer::Alt => storage.query(|s| s.gain_saved),
+                LatchLayer::Third => continue,
+            };
+            if let Some(new_value) = latch.update(fader.get_value(), latch_layer, target_value) {
+                storage.modify_and_save(|s| match latch_layer {
+                    LatchLayer::Main => s.offset_saved = new_value,
+                    _ => s.gain_saved = new_value,
+                });
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    update_invert_led(storage.query(|s| s.invert_saved));
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    join4(main_loop, button_handler, fader_handler, scene_handler).await;
+}
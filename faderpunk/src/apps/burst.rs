@@ -0,0 +1,323 @@
+use embassy_futures::{
+    join::join5,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    burst::{echo_probability, Burst},
+    edge::{Edge, EdgeDetector},
+    ext::FromValue,
+    latch::LatchLayer,
+    utils::{resolution_for_mode, value_to_index, value_to_resolution},
+    AppIcon, Brightness, ClockDivision, Color, Config, Param, Range, Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{
+    App, AppParams, AppStorage, ClockEvent, Led, ManagedStorage, ParamStore, SceneEvent,
+};
+
+pub const CHANNELS: usize = 2;
+pub const PARAMS: usize = 3;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+const MAX_COUNT: usize = 16;
+/// Length of an output trigger in milliseconds
+const TRIGGER_LENGTH: u32 = 10;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Burst",
+    "Clock synced trigger bursts and echoes",
+    Color::Cyan,
+    AppIcon::Sequence,
+)
+.add_param(Param::Enum {
+    name: "Mode",
+    variants: &["Burst", "Tap delay"],
+})
+.add_param(Param::Enum {
+    name: "Divisions",
+    variants: &["Straight", "Triplets", "Both"],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.finalize();
+
+pub struct Params {
+    mode: usize,
+    division_mode: usize,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            mode: usize::from_value(values[0]),
+            division_mode: usize::from_value(values[1]),
+            color: Color::from_value(values[2]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.mode.into()).unwrap();
+        vec.push(self.division_mode.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    count_saved: u16,
+    spacing_saved: u16,
+    decay_saved: u16,
+    mute_saved: bool,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            count_saved: 1000,
+            spacing_saved: 3000,
+            decay_saved: 3000,
+            mute_saved: false,
+        }
+    }
+}
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            mode: 0,
+            division_mode: 2,
+            color: Color::Cyan,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+/// Number of triggers in a burst for a given fader value
+fn burst_count(value: u16) -> u8 {
+    value_to_index(value, MAX_COUNT) as u8 + 1
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (mode, division_mode, led_color) = params.query(|p| (p.mode, p.division_mode, p.color));
+    let tap_delay = mode == 1;
+
+    let mut clock = app.use_clock();
+    let die = app.use_die();
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let input = app.make_in_jack(0, Range::_0_10V).await;
+    let output = app.make_gate_jack(1, 4095).await;
+
+    let resolution = resolution_for_mode(division_mode);
+
+    let burst_glob = app.make_global(Burst::new());
+    let fire_glob = app.make_global(false);
+    let glob_muted = app.make_global(false);
+    let glob_latch_layer = app.make_global(LatchLayer::Main);
+
+    let update_mute_led = |muted: bool| {
+        if muted {
+            leds.unset(1, Led::Button);
+        } else {
+            leds.set(1, Led::Button, led_color, LED_BRIGHTNESS);
+        }
+    };
+
+    let muted = storage.query(|s| s.mute_saved);
+    glob_muted.set(muted);
+    update_mute_led(muted);
+    leds.set(0, Led::Button, led_color, LED_BRIGHTNESS);
+
+    let start_burst = || {
+        let (count, spacing) = storage.query(|s| (s.count_saved, s.spacing_saved));
+        let spacing = value_to_resolution(spacing, resolution);
+        let mut burst = burst_glob.get();
+        if burst.start(burst_count(count), spacing) {
+            fire_glob.set(true);
+        }
+        burst_glob.set(burst);
+    };
+
+    let clock_fut = async {
+        loop {
+            match clock.wait_for_event(ClockDivision::_1).await {
+                ClockEvent::Tick => {
+                    let mut burst = burst_glob.get();
+                    if let Some(repeat) = burst.tick() {
+                        let fires = if tap_delay {
+                            let decay = storage.query(|s| s.decay_saved);
+                            die.roll() < echo_probability(decay, repeat)
+                        } else {
+                            true
+                        };
+                        if fires {
+                            fire_glob.set(true);
+                        } else {
+                            // An echo that didn't make it ends the delay
+                            burst.stop();
+                        }
+                    }
+                    burst_glob.set(burst);
+                }
+                ClockEvent::Stop | ClockEvent::Reset => {
+                    burst_glob.modify(|b| {
+                        let mut b = *b;
+                        b.stop();
+                        b
+                    });
+                }
+                _ => {}
+            }
+        }
+    };
+
+    let button_fut = async {
+        loop {
+            let (chan, _) = buttons.wait_for_any_down().await;
+            if chan == 0 {
+                start_burst();
+            } else {
+                let muted = glob_muted.toggle();
+                storage.modify_and_save(|s| s.mute_saved = muted);
+                update_mute_led(muted);
+            }
+        }
+    };
+
+    let fader_fut = async {
+        let mut latch = [
+            app.make_latch(faders.get_value_at(0)),
+            app.make_latch(faders.get_value_at(1)),
+        ];
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            let latch_layer = glob_latch_layer.get();
+            let target_value = match (chan, latch_layer) {
+                (0, LatchLayer::Main) => storage.query(|s| s.count_saved),
+                (0, LatchLayer::Alt) => storage.query(|s| s.decay_saved),
+                (1, LatchLayer::Main) => storage.query(|s| s.spacing_saved),
+                _ => continue,
+            };
+
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), latch_layer, target_value)
+            {
+                storage.modify_and_save(|s| match (chan, latch_layer) {
+                    (0, LatchLayer::Main) => s.count_saved = new_value,
+                    (0, _) => s.decay_saved = new_value,
+                    _ => s.spacing_saved = new_value,
+                });
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    let muted = storage.query(|s| s.mute_saved);
+                    glob_muted.set(muted);
+                    update_mute_led(muted);
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    let timed_loop = async {
+        let mut trigger_edge = EdgeDetector::default();
+        let mut trigger_timer: u32 = 0;
+        loop {
+            app.delay_millis(1).await;
+            let latch_layer = glob_latch_layer.set(LatchLayer::from(buttons.is_shift_pressed()));
+
+            if trigger_edge.update(input.get_value()) == Some(Edge::Rising) {
+                start_burst();
+            }
+            if trigger_edge.is_high() {
+                leds.set(0, Led::Top, led_color, Brightness::High);
+            } else {
+                leds.unset(0, Led::Top);
+            }
+
+            if fire_glob.get() {
+                fire_glob.set(false);
+                if !glob_muted.get() {
+                    output.set_high().await;
+                    leds.set(1, Led::Top, led_color, Brightness::High);
+                    trigger_timer = TRIGGER_LENGTH;
+                }
+            } else if trigger_timer > 0 {
+                trigger_timer -= 1;
+                if trigger_timer == 0 {
+                    output.set_low().await;
+                    leds.unset(1, Led::Top);
+                }
+            }
+
+            if latch_layer == LatchLayer::Alt {
+                let decay = storage.query(|s| s.decay_saved);
+                leds.set(
+                    0,
+                    Led::Bottom,
+                    Color::Red,
+                    Brightness::Custom((decay / 16) as u8),
+                );
+            } else {
+                leds.unset(0, Led::Bottom);
+            }
+        }
+    };
+
+    join5(clock_fut, button_fut, fader_fut, scene_handler, timed_loop).await;
+}
@@ -0,0 +1,212 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue, quantizer::HeldNotes, AppIcon, Brightness, Color, Config, MidiChannel, MidiIn,
+    NoteEvent, Param, Range, Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
+
+pub const CHANNELS: usize = 2;
+pub const PARAMS: usize = 4;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Chord Lock",
+    "Quantize CV to the notes held on MIDI",
+    Color::Green,
+    AppIcon::Quantize,
+)
+.add_param(Param::MidiIn)
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::Range {
+    name: "Range",
+    variants: &[Range::_0_10V, Range::_Neg5_5V],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.finalize();
+
+pub struct Params {
+    midi_in: MidiIn,
+    midi_channel: MidiChannel,
+    range: Range,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            midi_in: MidiIn::from_value(values[0]),
+            midi_channel: MidiChannel::from_value(values[1]),
+            range: Range::from_value(values[2]),
+            color: Color::from_value(values[3]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.midi_in.into()).unwrap();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.range.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Storage {
+    // Keep the last chord after its keys are released
+    latch: bool,
+}
+
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            midi_in: MidiIn::default(),
+            midi_channel: MidiChannel::default(),
+            range: Range::_Neg5_5V,
+            color: Color::Green,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (midi_in, midi_chan, range, led_color) =
+        params.query(|p| (p.midi_in, p.midi_channel, p.range, p.color));
+
+    let mut midi_in = app.use_midi_input(midi_in, midi_chan);
+    let mut quantizer = app.use_quantizer(range);
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let input = app.make_in_jack(0, range).await;
+    let output = app.make_out_jack(1, range).await;
+
+    let glob_held = app.make_global(HeldNotes::default());
+
+    let set_latch = |latch: bool| {
+        glob_held.modify(|h| {
+            let mut h = *h;
+            h.set_latch(latch);
+            h
+        });
+        if latch {
+            leds.set(0, Led::Button, led_color, Brightness::Mid);
+        } else {
+            leds.unset(0, Led::Button);
+        }
+    };
+    set_latch(storage.query(|s| s.latch));
+
+    let midi_handler = async {
+        loop {
+            let event = midi_in.next_note_event().await;
+            glob_held.modify(|h| {
+                let mut h = *h;
+                match event {
+                    NoteEvent::On { note, .. } => h.note_on(note),
+                    NoteEvent::Off { note, .. } => h.note_off(note),
+                }
+                h
+            });
+        }
+    };
+
+    let button_handler = async {
+        loop {
+            let (chan, _) = buttons.wait_for_any_down().await;
+            if chan == 0 {
+                let latch = storage.modify_and_save(|s| {
+                    s.latch = !s.latch;
+                    s.latch
+                });
+                set_latch(latch);
+            }
+        }
+    };
+
+    let main_loop = async {
+        let mut mask = None;
+        loop {
+            app.delay_millis(1).await;
+
+            // Nothing held quantizes chromatically
+            let held = glob_held.get().mask();
+            if mask != Some(held) {
+                quantizer.set_active_notes(held);
+                mask = Some(held);
+            }
+
+            let in_val = input.get_value();
+            let out = quantizer.get_quantized_note(in_val).await.as_counts(range);
+            output.set_value(out);
+
+            leds.set_meter(0, in_val, led_color);
+            leds.set_meter(1, out, led_color);
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    set_latch(storage.query(|s| s.latch));
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    join4(midi_handler, button_handler, main_loop, scene_handler).await;
+}
@@ -37,6 +37,7 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     name: "GATE %",
     min: 1,
     max: 100,
+    step: 1,
 })
 .add_param(Param::Enum {
     name: "Divisions",
@@ -55,7 +56,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
         Color::Yellow,
     ],
 })
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     midi_channel: MidiChannel,
@@ -15,7 +15,7 @@ use libfp::{
     latch::LatchLayer,
     utils::{
         attenuate_bipolar, rescale_12bit_int, resolution_for_mode, resolution_with_input_offset,
-        split_unsigned_value, value_to_resolution,
+        value_to_resolution,
     },
     AppIcon, Brightness, ClockDivision, Color, Config, MidiChannel, MidiNote, MidiOut, Param,
     Range, Value, APP_MAX_PARAMS,
@@ -43,6 +43,7 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     name: "GATE %",
     min: 1,
     max: 100,
+    step: 1,
 })
 .add_param(Param::Enum {
     name: "Divisions",
@@ -61,7 +62,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
         Color::Yellow,
     ],
 })
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     midi_channel: MidiChannel,
@@ -478,9 +480,7 @@ pub async fn run(
             };
             in_val_glob.set(in_val);
 
-            let led_in = split_unsigned_value(in_val);
-            leds.set(0, Led::Top, led_color, Brightness::Custom(led_in[0]));
-            leds.set(0, Led::Bottom, led_color, Brightness::Custom(led_in[1]));
+            leds.set_meter(0, in_val, led_color);
 
             if latch_layer == LatchLayer::Main {
                 if in_mute {
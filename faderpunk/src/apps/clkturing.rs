@@ -10,8 +10,12 @@ use heapless::Vec;
 use serde::{Deserialize, Serialize};
 
 use libfp::{
-    ext::FromValue, latch::LatchLayer, AppIcon, Brightness, Color, Config, Curve, MidiCc,
-    MidiChannel, MidiMode, MidiNote, MidiOut, Param, Range, Value, APP_MAX_PARAMS,
+    edge::{Edge, EdgeDetector},
+    ext::FromValue,
+    latch::LatchLayer,
+    turing::{turing_flips, turing_step, turing_value},
+    AppIcon, Brightness, Color, Config, Curve, MidiCc, MidiChannel, MidiMode, MidiNote, MidiOut,
+    Param, Range, Value, APP_MAX_PARAMS,
 };
 
 use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
@@ -49,7 +53,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
 })
 .add_param(Param::MidiNote { name: "Base note" })
 .add_param(Param::MidiNrpn)
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     midi_channel: MidiChannel,
@@ -194,25 +199,25 @@ pub async fn run(
 
     let fut1 = async {
         let mut att_reg: u16;
-        let mut oldinputval = 0;
+        let mut clock_edge = EdgeDetector::default();
 
         loop {
             app.delay_millis(1).await;
             let length = length_glob.get();
 
-            let inputval = input.get_value();
-            if inputval >= 406 && oldinputval < 406 {
+            let edge = clock_edge.update(input.get_value());
+            if edge == Some(Edge::Rising) {
                 register = register_glob.get();
                 let prob = prob_glob.get();
-                let rand = die.roll().clamp(100, 3900);
+                let flip = turing_flips(prob, die.roll());
 
-                let rotation = rotate_select_bit(register, prob, rand, length);
-                register = rotation.0;
+                // The feedback tap sits one step past the loop length here
+                register = turing_step(register, length + 1, flip);
                 storage.modify_and_save(|s| {
                     s.register_saved = register;
                 });
 
-                let register_scalled = scale_to_12bit(register, length as u8);
+                let register_scalled = turing_value(register, length as u8);
                 att_reg = (register_scalled as u32
                     * curve.at(storage.query(|s| s.att_saved)) as u32
                     / 4095) as u16;
@@ -246,7 +251,7 @@ pub async fn run(
                 leds.set(0, Led::Bottom, Color::Red, Brightness::High);
             }
 
-            if inputval <= 406 && oldinputval > 406 {
+            if edge == Some(Edge::Falling) {
                 leds.set(0, Led::Bottom, Color::Red, Brightness::Off);
 
                 if let MidiMode::Note = midi_mode {
@@ -256,7 +261,6 @@ pub async fn run(
 
                 register_glob.set(register);
             }
-            oldinputval = inputval;
         }
     };
 
@@ -350,37 +354,3 @@ pub async fn run(
 
     join5(fut1, fut2, fut3, fut4, scene_handler).await;
 }
-
-fn rotate_select_bit(x: u16, a: u16, b: u16, bit_index: u16) -> (u16, bool) {
-    let bit_index = (15 - bit_index).clamp(0, 15);
-
-    // Extract the original bit
-    let original_bit = ((x >> bit_index) & 1) as u8;
-    let mut bit = original_bit;
-
-    // Invert the bit if a > b
-    if a > b {
-        bit ^= 1;
-    }
-
-    // Shift x right by 1
-    let shifted = x >> 1;
-
-    // Insert the (possibly inverted) bit into the MSB
-    let result = shifted | ((bit as u16) << 15);
-
-    // Return the new value and whether the bit was flipped
-    let flipped = bit != original_bit;
-    (result, flipped)
-}
-
-fn scale_to_12bit(input: u16, x: u8) -> u16 {
-    let x = x.clamp(1, 16);
-
-    // Shift to keep the top `x` bits
-    let top_x_bits = input >> (16 - x);
-
-    // Scale to 12-bit
-    let max_x_val = (1 << x) - 1;
-    ((top_x_bits as u32 * 4095) / max_x_val as u32) as u16
-}
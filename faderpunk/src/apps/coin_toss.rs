@@ -0,0 +1,338 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    utils::{bernoulli_gate, random_gate_fires, resolution_for_mode, value_to_resolution},
+    AppIcon, Brightness, ClockDivision, Color, Config, MidiChannel, MidiNote, MidiOut, Param,
+    Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{
+    App, AppParams, AppStorage, ClockEvent, Led, ManagedStorage, ParamStore, SceneEvent,
+};
+
+pub const CHANNELS: usize = 2;
+pub const PARAMS: usize = 7;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Coin Toss",
+    "Bernoulli gate, clocked coin toss between two outputs",
+    Color::Yellow,
+    AppIcon::Die,
+)
+.add_param(Param::bool { name: "Complement" })
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::MidiNote {
+    name: "MIDI Note 1",
+})
+.add_param(Param::i32 {
+    name: "GATE %",
+    min: 1,
+    max: 100,
+    step: 1,
+})
+.add_param(Param::Enum {
+    name: "Divisions",
+    variants: &["Straight", "Triplets", "Both"],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.add_param(Param::MidiOut)
+.finalize();
+
+pub struct Params {
+    complement: bool,
+    midi_channel: MidiChannel,
+    note: MidiNote,
+    gatel: i32,
+    division_mode: usize,
+    color: Color,
+    midi_out: MidiOut,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            complement: bool::from_value(values[0]),
+            midi_channel: MidiChannel::from_value(values[1]),
+            note: MidiNote::from_value(values[2]),
+            gatel: i32::from_value(values[3]),
+            division_mode: usize::from_value(values[4]),
+            color: Color::from_value(values[5]),
+            midi_out: MidiOut::from_value(values[6]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.complement.into()).unwrap();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.note.into()).unwrap();
+        vec.push(self.gatel.into()).unwrap();
+        vec.push(self.division_mode.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec.push(self.midi_out.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    prob_saved: u16,
+    div_saved: u16,
+    mute_saved: [bool; 2],
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            prob_saved: 2048,
+            div_saved: 3000,
+            mute_saved: [false; 2],
+        }
+    }
+}
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            complement: true,
+            midi_channel: MidiChannel::default(),
+            note: MidiNote::from(36),
+            gatel: 50,
+            division_mode: 2,
+            color: Color::Yellow,
+            midi_out: MidiOut::default(),
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (complement, midi_out, midi_chan, base_note, gatel, division_mode, led_color) = params
+        .query(|p| {
+            (
+                p.complement,
+                p.midi_out,
+                p.midi_channel,
+                p.note,
+                p.gatel as u32,
+                p.division_mode,
+                p.color,
+            )
+        });
+
+    let mut clock = app.use_clock();
+    let ticks = clock.get_ticker();
+    let die = app.use_die();
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let midi = app.use_midi_output(midi_out, midi_chan, false);
+
+    let resolution = resolution_for_mode(division_mode);
+    let notes = [base_note, base_note + MidiNote::from(1)];
+
+    let div_glob = app.make_global(6_u32);
+    let muted_glob = app.make_global([false; 2]);
+
+    let jacks = [
+        app.make_gate_jack(0, 4095).await,
+        app.make_gate_jack(1, 4095).await,
+    ];
+
+    let update_mute_leds = |muted: [bool; 2]| {
+        for (chan, &mute) in muted.iter().enumerate() {
+            if mute {
+                leds.unset(chan, Led::Button);
+            } else {
+                leds.set(chan, Led::Button, led_color, LED_BRIGHTNESS);
+            }
+        }
+    };
+
+    let (div, mute) = storage.query(|s| (s.div_saved, s.mute_saved));
+    div_glob.set(value_to_resolution(div, resolution));
+    muted_glob.set(mute);
+    update_mute_leds(mute);
+
+    let fut1 = async {
+        let mut gate_on = [false; 2];
+        let mut cached_div = div_glob.get();
+        let mut cached_gate_step = (cached_div * gatel / 100).clamp(1, cached_div - 1);
+        let mut tick_origin = ticks() as u32;
+
+        loop {
+            match clock.wait_for_event(ClockDivision::_1).await {
+                ClockEvent::Reset | ClockEvent::Stop => {
+                    tick_origin = ticks() as u32;
+                    for (chan, on) in gate_on.iter_mut().enumerate() {
+                        if *on {
+                            midi.send_note_off(notes[chan]).await;
+                            *on = false;
+                        }
+                        jacks[chan].set_low().await;
+                        leds.unset(chan, Led::Top);
+                    }
+                }
+                ClockEvent::Tick => {
+                    let div = div_glob.get();
+                    if div != cached_div {
+                        cached_div = div;
+                        cached_gate_step = (cached_div * gatel / 100).clamp(1, cached_div - 1);
+                    }
+                    let clkn = (ticks() as u32).wrapping_sub(tick_origin);
+
+                    if clkn.is_multiple_of(cached_div) {
+                        let prob = storage.query(|s| s.prob_saved);
+                        let fires = if complement {
+                            let (heads, tails) = bernoulli_gate(prob, die.roll());
+                            [heads, tails]
+                        } else {
+                            [
+                                random_gate_fires(prob, die.roll()),
+                                random_gate_fires(prob, die.roll()),
+                            ]
+                        };
+                        let muted = muted_glob.get();
+                        for (chan, on) in gate_on.iter_mut().enumerate() {
+                            if fires[chan] && !muted[chan] {
+                                jacks[chan].set_high().await;
+                                midi.send_note_on(notes[chan], 4095).await;
+                                leds.set(chan, Led::Top, led_color, Brightness::High);
+                                *on = true;
+                            }
+                        }
+                    }
+
+                    if clkn % cached_div == cached_gate_step {
+                        for (chan, on) in gate_on.iter_mut().enumerate() {
+                            if *on {
+                                midi.send_note_off(notes[chan]).await;
+                                jacks[chan].set_low().await;
+                                leds.unset(chan, Led::Top);
+                                *on = false;
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    };
+
+    let fut2 = async {
+        loop {
+            let (chan, _) = buttons.wait_for_any_down().await;
+            let muted = muted_glob.modify(|m| {
+                let mut m = *m;
+                m[chan] = !m[chan];
+                m
+            });
+            storage.modify_and_save(|s| s.mute_saved = muted);
+            if muted[chan] {
+                jacks[chan].set_low().await;
+            }
+            update_mute_leds(muted);
+        }
+    };
+
+    let fut3 = async {
+        let mut latch = [
+            app.make_latch(faders.get_value_at(0)),
+            app.make_latch(faders.get_value_at(1)),
+        ];
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            let target_value = match chan {
+                0 => storage.query(|s| s.prob_saved),
+                _ => storage.query(|s| s.div_saved),
+            };
+
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), LatchLayer::Main, target_value)
+            {
+                if chan == 0 {
+                    storage.modify_and_save(|s| s.prob_saved = new_value);
+                } else {
+                    div_glob.set(value_to_resolution(new_value, resolution));
+                    storage.modify_and_save(|s| s.div_saved = new_value);
+                }
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    let (div, mute) = storage.query(|s| (s.div_saved, s.mute_saved));
+                    div_glob.set(value_to_resolution(div, resolution));
+                    muted_glob.set(mute);
+                    for (chan, &m) in mute.iter().enumerate() {
+                        if m {
+                            jacks[chan].set_low().await;
+                        }
+                    }
+                    update_mute_leds(mute);
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    join4(fut1, fut2, fut3, scene_handler).await;
+}
@@ -7,7 +7,7 @@ use heapless::Vec;
 use libfp::{
     ext::FromValue,
     latch::LatchLayer,
-    utils::{attenuate, attenuate_bipolar, clickless, slew_2, split_unsigned_value},
+    utils::{attenuate, attenuate_bipolar, clickless, curve_fader, slew_2},
     AppIcon, Brightness, Color, MidiCc, MidiChannel, MidiOut, APP_MAX_PARAMS,
 };
 use serde::{Deserialize, Serialize};
@@ -66,7 +66,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
 })
 .add_param(Param::MidiCc { name: "Button CC" })
 .add_param(Param::MidiNrpn)
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     curve: Curve,
@@ -301,14 +302,8 @@ pub async fn run(
                 } else {
                     0
                 }
-            } else if !bipolar {
-                fad_val = clickless(fad_val, curve.at(main_layer_value));
-                fad_val
-            } else if main_layer_value > 2047 {
-                fad_val = clickless(fad_val, curve.at((main_layer_value - 2047) * 2) / 2 + 2047);
-                fad_val
             } else {
-                fad_val = clickless(fad_val, 2047 - curve.at((2047 - main_layer_value) * 2) / 2);
+                fad_val = clickless(fad_val, curve_fader(curve, main_layer_value, bipolar));
                 fad_val
             };
             let mut attenuated = if bipolar {
@@ -343,9 +338,7 @@ pub async fn run(
             match latch_active_layer {
                 LatchLayer::Main => {
                     if bipolar {
-                        let led1 = split_unsigned_value(out);
-                        leds.set(0, Led::Top, led_color, Brightness::Custom(led1[0]));
-                        leds.set(0, Led::Bottom, led_color, Brightness::Custom(led1[1]));
+                        leds.set_meter(0, out, led_color);
                     } else {
                         leds.set(
                             0,
@@ -428,7 +421,7 @@ pub async fn run(
     let save_handler = async {
         loop {
             app.delay_secs(1).await;
-            storage.save().await;
+            storage.save_now().await;
         }
     };
 
@@ -6,7 +6,7 @@ use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
 use heapless::Vec;
 use libfp::{
     latch::LatchLayer,
-    utils::{attenuate, attenuate_bipolar, split_unsigned_value},
+    utils::{cv_to_cc_value, midi_value_changed},
     AppIcon, Brightness, Color, MidiCc, MidiChannel, MidiOut, APP_MAX_PARAMS,
 };
 use serde::{Deserialize, Serialize};
@@ -48,7 +48,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     ],
 })
 .add_param(Param::MidiNrpn)
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     range: Range,
@@ -175,7 +176,7 @@ pub async fn run(
     let input = app.make_in_jack(0, range).await;
 
     let fut1 = async {
-        let mut old_midi = 0;
+        let mut old_val = 0;
 
         loop {
             app.delay_millis(1).await;
@@ -183,15 +184,8 @@ pub async fn run(
                 glob_latch_layer.set(LatchLayer::from(buttons.is_shift_pressed()));
 
             let input_val = if !muted_glob.get() {
-                if !range.is_bipolar() {
-                    (attenuate(input.get_value() * 2, storage.query(|s| s.att_saved))
-                        + storage.query(|s| s.offset_saved))
-                    .clamp(0, 4095)
-                } else {
-                    (attenuate_bipolar(input.get_value(), storage.query(|s| s.att_saved)) as i16
-                        + (storage.query(|s| s.offset_saved) as i16 - 2047))
-                        .clamp(0, 4095) as u16
-                }
+                let (att, offset) = storage.query(|s| (s.att_saved, s.offset_saved));
+                cv_to_cc_value(input.get_value(), att, offset, range.is_bipolar())
             } else if range.is_bipolar() {
                 2047
             } else {
@@ -199,9 +193,7 @@ pub async fn run(
             };
             if latch_active_layer == LatchLayer::Main {
                 if range.is_bipolar() {
-                    let led1 = split_unsigned_value(input_val);
-                    leds.set(0, Led::Top, led_color, Brightness::Custom(led1[0]));
-                    leds.set(0, Led::Bottom, led_color, Brightness::Custom(led1[1]));
+                    leds.set_meter(0, input_val, led_color);
                 } else {
                     leds.set(
                         0,
@@ -219,9 +211,9 @@ pub async fn run(
                 );
             }
 
-            if old_midi != input_val / 32 {
+            if midi_value_changed(old_val, input_val) {
                 midi.send_cc(midi_cc, input_val).await;
-                old_midi = input_val / 32;
+                old_val = input_val;
             }
         }
     };
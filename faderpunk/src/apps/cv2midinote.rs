@@ -5,8 +5,10 @@ use embassy_futures::{
 use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
 use heapless::Vec;
 use libfp::{
-    latch::LatchLayer, utils::split_unsigned_value, AppIcon, Brightness, Color, MidiChannel,
-    MidiNote, MidiOut, APP_MAX_PARAMS,
+    edge::{Edge, EdgeDetector, GATE_MIN_WIDTH},
+    latch::LatchLayer,
+    utils::{split_unsigned_value, transpose_cv},
+    AppIcon, Brightness, Color, MidiChannel, MidiNote, MidiOut, APP_MAX_PARAMS,
 };
 use serde::{Deserialize, Serialize};
 
@@ -36,6 +38,7 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     name: "Delay (ms)",
     min: 0,
     max: 10,
+    step: 1,
 })
 .add_param(Param::Color {
     name: "Color",
@@ -50,7 +53,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
         Color::Yellow,
     ],
 })
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     range: Range,
@@ -170,7 +174,7 @@ pub async fn run(
     }
 
     let fut1 = async {
-        let mut old_gatein = 0;
+        let mut gate_edge = EdgeDetector::default().with_min_width(GATE_MIN_WIDTH);
         let mut midi_out = MidiNote::from(0);
         let mut note_on = false;
         let mut note = 0;
@@ -178,22 +182,16 @@ pub async fn run(
         loop {
             app.delay_millis(1).await;
 
-            let gatein = gate_in.get_value();
+            let edge = gate_edge.update(gate_in.get_value());
 
-            if gatein >= 406 && old_gatein < 406 {
-                // catching rising edge
+            if edge == Some(Edge::Rising) {
                 if !muted_glob.get() {
                     app.delay_millis(delay as u64).await;
-                    note = input.get_value() as i32;
-                    let oct = (storage.query(|s| s.fader_saved[1]) as i32 * 10 / 4095 - 5) * 410;
-                    let st = if !storage.query(|s| s.offset_toggle) {
-                        (storage.query(|s| s.fader_saved[0]) as i32 * 12 / 4095) * 410 / 12
-                    } else {
-                        0
-                    };
-                    note = (note + oct + st).clamp(0, 4095);
+                    let (saved, offset_off) = storage.query(|s| (s.fader_saved, s.offset_toggle));
+                    let semitone = if !offset_off { saved[0] } else { 0 };
+                    note = transpose_cv(input.get_value(), saved[1], semitone);
 
-                    midi_out = quantizer.get_quantized_note(note as u16).await.as_midi();
+                    midi_out = quantizer.get_quantized_note(note).await.as_midi();
 
                     midi.send_note_on(midi_out, 4095).await;
                     note_on = true;
@@ -210,8 +208,7 @@ pub async fn run(
                 leds.set(1, Led::Top, led_color, Brightness::Mid);
             }
 
-            if gatein <= 406 && old_gatein > 406 {
-                // catching falling edge
+            if edge == Some(Edge::Falling) {
                 if note_on {
                     midi.send_note_off(midi_out).await;
                     note_on = false;
@@ -224,8 +221,6 @@ pub async fn run(
                 }
                 leds.unset(1, Led::Top);
             }
-
-            old_gatein = gatein;
         }
     };
 
@@ -42,6 +42,7 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     name: "GATE %",
     min: 1,
     max: 100,
+    step: 1,
 })
 .add_param(Param::Color {
     name: "Color",
@@ -56,7 +57,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
         Color::Yellow,
     ],
 })
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     midi_channel: MidiChannel,
@@ -0,0 +1,450 @@
+use embassy_futures::{
+    join::join5,
+    select::{select, select3, Either},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use enum_ordinalize::Ordinalize;
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    fp_grids_lib::{OutputMode, PatternGenerator, K_NUM_PARTS},
+    latch::LatchLayer,
+    utils::{
+        euclidean_fill_from_value, euclidean_length_from_value, euclidean_rotation_from_value,
+        ButtonTaps,
+    },
+    AppIcon, Brightness, ClockDivision, Color, Config, MidiChannel, MidiNote, MidiOut, Param,
+    Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{
+    App, AppParams, AppStorage, ClockEvent, Led, ManagedStorage, ParamStore, SceneEvent,
+};
+
+pub const CHANNELS: usize = 4;
+pub const PARAMS: usize = 7;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+const MAX_LENGTH: u8 = 32;
+/// Clock divisions (in 24 ppqn ticks) for the "Resolution" param
+const RESOLUTIONS: [u32; 6] = [3, 4, 6, 8, 12, 24];
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Euclid+",
+    "Four channel Euclidean sequencer",
+    Color::Orange,
+    AppIcon::Euclid,
+)
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::MidiNote {
+    name: "MIDI Note 1",
+})
+.add_param(Param::i32 {
+    name: "GATE %",
+    min: 1,
+    max: 100,
+    step: 1,
+})
+.add_param(Param::Enum {
+    name: "Resolution",
+    variants: &["1/32", "1/16T", "1/16", "1/8T", "1/8", "1/4"],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.add_param(Param::bool {
+    name: "Reset on stop",
+})
+.add_param(Param::MidiOut)
+.finalize();
+
+pub struct Params {
+    midi_channel: MidiChannel,
+    midi_out: MidiOut,
+    note: MidiNote,
+    gatel: i32,
+    resolution: usize,
+    color: Color,
+    reset_on_stop: bool,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            midi_channel: MidiChannel::from_value(values[0]),
+            note: MidiNote::from_value(values[1]),
+            gatel: i32::from_value(values[2]),
+            resolution: usize::from_value(values[3]),
+            color: Color::from_value(values[4]),
+            reset_on_stop: bool::from_value(values[5]),
+            midi_out: MidiOut::from_value(values[6]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.note.into()).unwrap();
+        vec.push(self.gatel.into()).unwrap();
+        vec.push(self.resolution.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec.push(self.reset_on_stop.into()).unwrap();
+        vec.push(self.midi_out.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    fill_saved: [u16; 4],
+    length_saved: [u16; 4],
+    rotation_saved: [u16; 4],
+    mute_saved: [bool; 4],
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            fill_saved: [2048; 4],
+            // Length of 16 steps
+            length_saved: [2000; 4],
+            rotation_saved: [0; 4],
+            mute_saved: [false; 4],
+        }
+    }
+}
+impl AppStorage for Storage {}
+
+#[derive(Clone, Copy, Default)]
+struct Rhythm {
+    length: u8,
+    fill: u8,
+    rotation: u8,
+}
+
+impl Rhythm {
+    fn from_storage(storage: &Storage, chan: usize) -> Self {
+        let length = euclidean_length_from_value(storage.length_saved[chan], MAX_LENGTH);
+        Self {
+            length,
+            fill: euclidean_fill_from_value(storage.fill_saved[chan], length),
+            rotation: euclidean_rotation_from_value(storage.rotation_saved[chan], length),
+        }
+    }
+}
+
+/// Map an output to its generator and the part within that generator
+fn generator_part(chan: usize) -> (usize, usize) {
+    (chan / K_NUM_PARTS, chan % K_NUM_PARTS)
+}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            midi_channel: MidiChannel::default(),
+            midi_out: MidiOut([false, false, false]),
+            note: MidiNote::from(36),
+            gatel: 50,
+            resolution: 2,
+            color: Color::Orange,
+            reset_on_stop: false,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (midi_out, midi_chan, base_note, gatel, resolution, led_color, reset_on_stop) = params
+        .query(|p| {
+            (
+                p.midi_out,
+                p.midi_channel,
+                p.note,
+                p.gatel as u32,
+                p.resolution,
+                p.color,
+                p.reset_on_stop,
+            )
+        });
+
+    let mut clock = app.use_clock();
+    let ticks = clock.get_ticker();
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let midi = app.use_midi_output(midi_out, midi_chan, false);
+
+    let div = RESOLUTIONS[resolution.min(RESOLUTIONS.len() - 1)];
+    let gate_step = (div * gatel / 100).clamp(1, div - 1);
+    let notes: [MidiNote; 4] = core::array::from_fn(|i| base_note + MidiNote::from(i as u8));
+
+    let rhythm_glob = app.make_global([Rhythm::default(); 4]);
+    let muted_glob = app.make_global([false; 4]);
+    let glob_latch_layer = app.make_global(LatchLayer::Main);
+    // Holding a button moves the faders to the length layer, only a tap toggles the mute
+    let taps_glob = app.make_global(ButtonTaps::default());
+
+    let jacks = [
+        app.make_gate_jack(0, 4095).await,
+        app.make_gate_jack(1, 4095).await,
+        app.make_gate_jack(2, 4095).await,
+        app.make_gate_jack(3, 4095).await,
+    ];
+
+    let load_rhythms = || {
+        rhythm_glob
+            .set(storage.query(|s| core::array::from_fn(|chan| Rhythm::from_storage(s, chan))));
+    };
+
+    let update_mute_leds = |muted: [bool; 4]| {
+        for (chan, &mute) in muted.iter().enumerate() {
+            if mute {
+                leds.unset(chan, Led::Button);
+            } else {
+                leds.set(chan, Led::Button, led_color, LED_BRIGHTNESS);
+            }
+        }
+    };
+
+    load_rhythms();
+    let mute = storage.query(|s| s.mute_saved);
+    muted_glob.set(mute);
+    update_mute_leds(mute);
+
+    let fut1 = async {
+        let mut generators = [PatternGenerator::new(); 2];
+        for generator in generators.iter_mut() {
+            generator.set_output_mode(OutputMode::OutputModeEuclidean);
+            generator.set_gate_mode(true);
+            generator.set_global_chaos(false);
+        }
+        let mut gate_on = [false; 4];
+        let mut tick_origin = ticks() as u32;
+
+        loop {
+            match clock.wait_for_event(ClockDivision::_1).await {
+                event @ (ClockEvent::Reset | ClockEvent::Stop) => {
+                    if matches!(event, ClockEvent::Reset) || reset_on_stop {
+                        tick_origin = ticks() as u32;
+                    }
+                    for (chan, on) in gate_on.iter_mut().enumerate() {
+                        if *on {
+                            midi.send_note_off(notes[chan]).await;
+                            *on = false;
+                        }
+                        jacks[chan].set_low().await;
+                        leds.set(chan, Led::Top, led_color, Brightness::Off);
+                    }
+                }
+                ClockEvent::Tick => {
+                    let clkn = (ticks() as u32).wrapping_sub(tick_origin);
+
+                    if clkn.is_multiple_of(div) {
+                        let rhythms = rhythm_glob.get();
+                        for (chan, rhythm) in rhythms.iter().enumerate() {
+                            let (gen, part) = generator_part(chan);
+                            let generator = &mut generators[gen];
+                            generator.set_length(part, rhythm.length);
+                            generator.set_offset(part, rhythm.rotation);
+                            generator.settings_
+                                [OutputMode::OutputModeEuclidean.ordinal() as usize]
+                                .density[part] = rhythm.fill;
+                        }
+                        for generator in generators.iter_mut() {
+                            generator.tick(clkn, div);
+                        }
+
+                        let muted = muted_glob.get();
+                        for (chan, on) in gate_on.iter_mut().enumerate() {
+                            let (gen, part) = generator_part(chan);
+                            let fires = rhythms[chan].fill > 0
+                                && generators[gen].get_trigger_state() & (1 << part) != 0;
+                            if fires && !muted[chan] {
+                                jacks[chan].set_high().await;
+                                midi.send_note_on(notes[chan], 4095).await;
+                                *on = true;
+                            }
+                            if glob_latch_layer.get() == LatchLayer::Main {
+                                let brightness = if *on {
+                                    Brightness::High
+                                } else {
+                                    Brightness::Off
+                                };
+                                leds.set(chan, Led::Top, led_color, brightness);
+                            }
+                        }
+                    }
+
+                    if clkn % div == gate_step {
+                        for (chan, on) in gate_on.iter_mut().enumerate() {
+                            if *on {
+                                midi.send_note_off(notes[chan]).await;
+                                jacks[chan].set_low().await;
+                                *on = false;
+                            }
+                            if glob_latch_layer.get() == LatchLayer::Main {
+                                leds.set(chan, Led::Top, led_color, Brightness::Off);
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    };
+
+    let fut2 = async {
+        loop {
+            let chan = match select(buttons.wait_for_any_down(), buttons.wait_for_any_up()).await {
+                Either::First((chan, shift)) => {
+                    if !shift {
+                        taps_glob.modify(|t| {
+                            let mut t = *t;
+                            t.press(chan);
+                            t
+                        });
+                    }
+                    continue;
+                }
+                Either::Second((chan, _)) => chan,
+            };
+            let mut taps = taps_glob.get();
+            let tapped = taps.release(chan);
+            taps_glob.set(taps);
+            if !tapped {
+                continue;
+            }
+            let muted = muted_glob.modify(|m| {
+                let mut m = *m;
+                m[chan] = !m[chan];
+                m
+            });
+            storage.modify_and_save(|s| s.mute_saved = muted);
+            if muted[chan] {
+                jacks[chan].set_low().await;
+            }
+            update_mute_leds(muted);
+        }
+    };
+
+    let fut3 = async {
+        let mut latch: [_; 4] = core::array::from_fn(|i| app.make_latch(faders.get_value_at(i)));
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            taps_glob.modify(|t| {
+                let mut t = *t;
+                t.fader_moved();
+                t
+            });
+            let latch_layer = glob_latch_layer.get();
+
+            let target_value = match latch_layer {
+                LatchLayer::Main => storage.query(|s| s.fill_saved[chan]),
+                LatchLayer::Alt => storage.query(|s| s.rotation_saved[chan]),
+                LatchLayer::Third => storage.query(|s| s.length_saved[chan]),
+            };
+
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), latch_layer, target_value)
+            {
+                storage.modify_and_save(|s| match latch_layer {
+                    LatchLayer::Main => s.fill_saved[chan] = new_value,
+                    LatchLayer::Alt => s.rotation_saved[chan] = new_value,
+                    LatchLayer::Third => s.length_saved[chan] = new_value,
+                });
+                load_rhythms();
+
+                let rhythm = rhythm_glob.get()[chan];
+                let shown = match latch_layer {
+                    LatchLayer::Main => None,
+                    LatchLayer::Alt => Some((rhythm.rotation, rhythm.length, Color::Red)),
+                    LatchLayer::Third => Some((rhythm.length, MAX_LENGTH, Color::Green)),
+                };
+                if let Some((value, max, color)) = shown {
+                    let brightness = (value as u32 * 255 / max.max(1) as u32) as u8;
+                    leds.set(chan, Led::Top, color, Brightness::Custom(brightness));
+                }
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    load_rhythms();
+                    let mute = storage.query(|s| s.mute_saved);
+                    muted_glob.set(mute);
+                    for (chan, &m) in mute.iter().enumerate() {
+                        if m {
+                            jacks[chan].set_low().await;
+                        }
+                    }
+                    update_mute_leds(mute);
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    let shift = async {
+        loop {
+            app.delay_millis(1).await;
+            let any_button_pressed = (0..CHANNELS).any(|chan| buttons.is_button_pressed(chan));
+            let latch_active_layer = if buttons.is_shift_pressed() {
+                LatchLayer::Alt
+            } else if any_button_pressed {
+                LatchLayer::Third
+            } else {
+                LatchLayer::Main
+            };
+            glob_latch_layer.set(latch_active_layer);
+        }
+    };
+
+    join5(fut1, fut2, fut3, scene_handler, shift).await;
+}
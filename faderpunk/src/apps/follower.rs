@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use libfp::{
     ext::FromValue,
     latch::LatchLayer,
-    utils::{attenuverter, slew_limiter, split_signed_value, split_unsigned_value},
+    utils::{attenuverter, slew_limiter, split_signed_value},
     AppIcon, Brightness, Color, Config, Curve, Param, Range, Value, APP_MAX_PARAMS,
 };
 
@@ -42,7 +42,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
 .add_param(Param::Range {
     name: "Range",
     variants: &[Range::_0_10V, Range::_Neg5_5V],
-});
+})
+.finalize();
 
 pub struct Params {
     color: Color,
@@ -174,13 +175,9 @@ pub async fn run(
 
             if latch_active_layer == LatchLayer::Main {
                 if range.is_bipolar() {
-                    let slew_led = split_unsigned_value(oldval as u16);
-                    leds.set(0, Led::Top, led_color, Brightness::Custom(slew_led[0]));
-                    leds.set(0, Led::Bottom, led_color, Brightness::Custom(slew_led[1]));
+                    leds.set_meter(0, oldval as u16, led_color);
 
-                    let out_led = split_unsigned_value(outval);
-                    leds.set(1, Led::Top, led_color, Brightness::Custom(out_led[0]));
-                    leds.set(1, Led::Bottom, led_color, Brightness::Custom(out_led[1]));
+                    leds.set_meter(1, outval, led_color);
                 } else {
                     leds.set(
                         0,
@@ -202,9 +199,7 @@ pub async fn run(
                 let off_led = split_signed_value(offset);
                 leds.set(0, Led::Top, Color::Red, Brightness::Custom(off_led[0]));
                 leds.set(0, Led::Bottom, Color::Red, Brightness::Custom(off_led[1]));
-                let att_led = split_unsigned_value(att);
-                leds.set(1, Led::Top, Color::Red, Brightness::Custom(att_led[0]));
-                leds.set(1, Led::Bottom, Color::Red, Brightness::Custom(att_led[1]));
+                leds.set_meter(1, att, Color::Red);
                 if storage.query(|s| s.offset_saved) == 2047 {
                     leds.unset(0, Led::Button);
                 } else {
@@ -224,9 +219,7 @@ pub async fn run(
                     Brightness::Custom((storage.query(|s| s.gain_saved) / 16) as u8),
                 );
 
-                let out_led = split_unsigned_value(outval);
-                leds.set(1, Led::Top, led_color, Brightness::Custom(out_led[0]));
-                leds.set(1, Led::Bottom, led_color, Brightness::Custom(out_led[1]));
+                leds.set_meter(1, outval, led_color);
                 leds.set(0, Led::Button, led_color, BUTTON_BRIGHTNESS);
                 leds.set(1, Led::Button, led_color, BUTTON_BRIGHTNESS);
             }
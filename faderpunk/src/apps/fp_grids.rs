@@ -32,23 +32,38 @@
 //! | Fader 1      | BD Density            | Drums Map X           |
 //! | LED 1 Top    | Gate active           |                       |
 //! | LED 1 Bottom | BD Density            |                       |
-//! | Fn 1         | Mute Trigger 1        |                       |
+//! | Fn 1         | Mute Trigger 1        | Add chain snapshot    |
 //! | Jack 2       | Trigger 2 (SD) Out    |                       |
 //! | Fader 2      | SD Density            | Drums Map Y           |
 //! | LED 2 Top    | Gate active           |                       |
 //! | LED 2 Bottom | SD Density            |                       |
-//! | Fn 2         | Mute Trigger 2        |                       |
+//! | Fn 2         | Mute Trigger 2        | Clear chain           |
 //! | Jack 3       | Trigger 3 (HH) Out    |                       |
 //! | Fader 3      | HH Density            |                       |
 //! | LED 3 Top    | Gate active           |                       |
 //! | LED 3 Bottom | HH Density            |                       |
 //! | Fn 3         | Mute Trigger 3        |                       |
-//! | Jack 4       | Accent Out            |                       |
+//! | Jack 4       | Accent Out / Level CV |                       |
 //! | Fader 4      | Chaos / Randomness    | Clock Division        |
 //! | LED 4 Top    | Accent active         |                       |
 //! | LED 4 Bottom | Chaos level           |                       |
 //! | Fn 4         | Mute Accent           | Cycle output mode     |
 //!
+//! The "Jack 4" parameter can turn the accent output into a CV of the drum map level (0-10V) of
+//! one part at the current step, a velocity-like signal that also follows the chaos setting.
+//! In the other modes the level output stays at 0V.
+//!
+//! With the "Live Chaos" parameter on, chaos also moves the map position at random on every
+//! step, by up to half the chaos amount in each direction.
+//!
+//! With the "Manual Step" parameter on, the clock no longer moves the pattern on. Instead Fn 4
+//! plays the next step with a short gate, in every mode, and no longer mutes the accent. Starting
+//! or resetting the clock goes back to the first step.
+//!
+//! Song mode: Shift + Fn 1 adds the current map X/Y and densities as a snapshot to a chain of up
+//! to 8. While the chain has snapshots it plays them in turn, moving on every "Chain Bars" bars.
+//! The chain starts over from the first snapshot when the clock starts or is reset.
+//!
 //! ## Euclidean Mode
 //!
 //! | Control      | Main                  | + Shift (Alt)         |
@@ -113,25 +128,30 @@ use heapless::Vec;
 use libfp::{
     ext::FromValue,
     fp_grids_lib::{
-        OutputMode, PatternGenerator, PatternModeSettings, SequencerState, DNB_NUM_PATTERNS,
-        K_NUM_PARTS,
+        ChainSnapshot, OutputMode, PatternGenerator, PatternModeSettings, Storage,
+        DNB_NUM_PATTERNS, K_NUM_PARTS,
     },
     latch::LatchLayer,
-    utils::scale_bits_12_8,
+    utils::{euclidean_fill_from_value, euclidean_length_from_value, scale_bits_12_8, ManualTicks},
     AppIcon, Brightness, ClockDivision, Color, Config, Curve, MidiChannel, MidiNote, MidiOut,
-    Param, Value, APP_MAX_PARAMS,
+    Param, Range, Value, APP_MAX_PARAMS,
 };
 
-use serde::{Deserialize, Serialize};
-
 use crate::app::{
-    App, AppParams, AppStorage, ClockEvent, Global, Led, ManagedStorage, ParamStore, SceneEvent,
+    App, AppParams, AppStorage, ClockEvent, GateJack, Global, Led, ManagedStorage, OutJack,
+    ParamStore, SceneEvent,
 };
 
 pub const CHANNELS: usize = 4; // Number of used faderpunk channels
-pub const PARAMS: usize = 9; // NUmber of app configuration parameters
+pub const PARAMS: usize = 14; // NUmber of app configuration parameters
+/// Params stored by firmware before the Jack 4 mode, which ended with the MIDI out
+const LEGACY_PARAMS: usize = 9;
 
 const DIV_SIXTEENTH_NOTE_COLOR: Color = Color::Yellow;
+/// Longest Euclidean sequence selectable from a fader
+const EUCLIDEAN_MAX_LENGTH: u8 = 16;
+/// Gate length of a step played by hand in manual step mode
+const MANUAL_STEP_GATE_MS: u64 = 50;
 
 // App configuration visible to the configurator
 pub static CONFIG: Config<PARAMS> = Config::new(
@@ -156,16 +176,19 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     name: "MIDI Velocity",
     min: 1,
     max: 127,
+    step: 1,
 })
 .add_param(Param::i32 {
     name: "MIDI Accent Vel",
     min: 1,
     max: 127,
+    step: 1,
 })
 .add_param(Param::i32 {
     name: "GATE %",
     min: 1,
     max: 100,
+    step: 1,
 })
 .add_param(Param::Color {
     name: "Color",
@@ -180,7 +203,26 @@ pub static CONFIG: Config<PARAMS> = Config::new(
         Color::Yellow,
     ],
 })
-.add_param(Param::MidiOut);
+.add_param(Param::Enum {
+    name: "Jack 4",
+    variants: &["Accent", "Level 1", "Level 2", "Level 3"],
+})
+.add_param(Param::i32 {
+    name: "Chain Bars",
+    min: 1,
+    max: 16,
+    step: 1,
+})
+.add_param(Param::bool { name: "Live Chaos" })
+.add_param(Param::bool {
+    name: "Manual Step",
+})
+.add_param(Param::Curve {
+    name: "Velocity Curve",
+    variants: &[Curve::Linear, Curve::Logarithmic, Curve::Exponential],
+})
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     midi_channel: MidiChannel,
@@ -192,6 +234,11 @@ pub struct Params {
     accent: i32,
     gatel: i32,
     color: Color,
+    jack4: usize,
+    chain_bars: i32,
+    live_chaos: bool,
+    manual_step: bool,
+    velocity_curve: Curve,
 }
 
 impl Default for Params {
@@ -206,12 +253,31 @@ impl Default for Params {
             accent: 127,
             gatel: 50,
             color: Color::Orange,
+            jack4: 0,
+            chain_bars: 4,
+            live_chaos: false,
+            manual_step: false,
+            velocity_curve: Curve::Linear,
         }
     }
 }
 
 impl AppParams for Params {
     fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() == LEGACY_PARAMS {
+            return Some(Self {
+                midi_channel: MidiChannel::from_value(values[0]),
+                note1: MidiNote::from_value(values[1]),
+                note2: MidiNote::from_value(values[2]),
+                note3: MidiNote::from_value(values[3]),
+                velocity: i32::from_value(values[4]),
+                accent: i32::from_value(values[5]),
+                gatel: i32::from_value(values[6]),
+                color: Color::from_value(values[7]),
+                midi_out: MidiOut::from_value(values[8]),
+                ..Self::default()
+            });
+        }
         if values.len() < PARAMS {
             return None;
         }
@@ -224,7 +290,12 @@ impl AppParams for Params {
             accent: i32::from_value(values[5]),
             gatel: i32::from_value(values[6]),
             color: Color::from_value(values[7]),
-            midi_out: MidiOut::from_value(values[8]),
+            jack4: usize::from_value(values[8]),
+            chain_bars: i32::from_value(values[9]),
+            live_chaos: bool::from_value(values[10]),
+            manual_step: bool::from_value(values[11]),
+            velocity_curve: Curve::from_value(values[12]),
+            midi_out: MidiOut::from_value(values[13]),
         })
     }
 
@@ -238,41 +309,21 @@ impl AppParams for Params {
         vec.push(self.accent.into()).unwrap();
         vec.push(self.gatel.into()).unwrap();
         vec.push(self.color.into()).unwrap();
+        vec.push(self.jack4.into()).unwrap();
+        vec.push(self.chain_bars.into()).unwrap();
+        vec.push(self.live_chaos.into()).unwrap();
+        vec.push(self.manual_step.into()).unwrap();
+        vec.push(self.velocity_curve.into()).unwrap();
         vec.push(self.midi_out.into()).unwrap();
         vec
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct Storage {
-    fader_saved: [u16; K_NUM_PARTS + 1],
-    shift_fader_saved: [u16; K_NUM_PARTS],
-    #[serde(default)]
-    euclidean_offset_saved: [u8; K_NUM_PARTS],
-    div_fader_saved: u16, // 0 - 4095 range, maps to index into 'resolution' clock div array (same as euclid.rs)
-    mute_saved: [bool; K_NUM_PARTS + 1], // 3 triggers + accent
-    drum_mode: u8,        // 0 = Drums Mode, 1 = Euclidean mode, 2 = DnB Mode
-    generator_state: SequencerState, // Internal generator state, use to restore after app re-spawn
-    note_on: [bool; K_NUM_PARTS],
-    accent_on: bool,
-}
-
-impl Default for Storage {
-    fn default() -> Self {
-        Self {
-            fader_saved: [2047, 2047, 2047, 0 /* zero chaos */],
-            shift_fader_saved: [2047; K_NUM_PARTS],
-            euclidean_offset_saved: [0; K_NUM_PARTS],
-            div_fader_saved: 3000,
-            mute_saved: [false; K_NUM_PARTS + 1],
-            drum_mode: 0,
-            generator_state: SequencerState::default(),
-            note_on: [false; K_NUM_PARTS],
-            accent_on: false,
-        }
+impl AppStorage for Storage {
+    fn decode_legacy(data: &[u8]) -> Option<Self> {
+        Storage::decode_legacy(data)
     }
 }
-impl AppStorage for Storage {}
 
 #[embassy_executor::task(pool_size = 16/CHANNELS)]
 pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
@@ -289,6 +340,11 @@ pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMut
             accent: 127,
             gatel: 50,
             color: Color::Orange,
+            jack4: 0,
+            chain_bars: 4,
+            live_chaos: false,
+            manual_step: false,
+            velocity_curve: Curve::Linear,
         },
     );
     let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
@@ -329,6 +385,11 @@ pub async fn run(
         velocityi32,
         accent_velocityi32,
         led_color,
+        jack4,
+        chain_bars,
+        live_chaos,
+        manual_step,
+        velocity_curve,
     ) = params.query(|p| {
         (
             p.midi_out,
@@ -340,6 +401,11 @@ pub async fn run(
             p.velocity,
             p.accent,
             p.color,
+            p.jack4,
+            p.chain_bars.clamp(1, 16) as u8,
+            p.live_chaos,
+            p.manual_step,
+            p.velocity_curve,
         )
     });
     let alt_led_color = if led_color == Color::Blue {
@@ -355,14 +421,19 @@ pub async fn run(
     let midi_velocity = ((velocityi32.abs().clamp(1, 127) as u32 * 4095) / 127) as u16;
     let accent_velocity = ((accent_velocityi32.abs().clamp(1, 127) as u32 * 4095) / 127) as u16;
 
-    let midi = app.use_midi_output(midi_out, midi_channel, false);
+    let midi = app
+        .use_midi_output(midi_out, midi_channel, false)
+        .with_velocity_curve(velocity_curve);
     let notes = [note1, note2, note3];
     let jack = [
         app.make_gate_jack(0, 4095).await,
         app.make_gate_jack(1, 4095).await,
         app.make_gate_jack(2, 4095).await,
-        app.make_gate_jack(3, 4095).await,
     ];
+    let accent_jack = match jack4 {
+        1..=K_NUM_PARTS => Jack4::Level(app.make_out_jack(3, Range::_0_10V).await, jack4 - 1),
+        _ => Jack4::Accent(app.make_gate_jack(3, 4095).await),
+    };
     let resolution = [384, 192, 96, 48, 24, 16, 12, 8, 6, 4, 3, 2];
     let div_glob = app.make_global(6); // = 1/16th note
     let glob_latch_layer = app.make_global(LatchLayer::Main);
@@ -403,7 +474,16 @@ pub async fn run(
         },
     );
 
-    reset_all_outputs(midi, leds, notes, &jack, &note_on_glob, &accent_on_glob).await;
+    reset_all_outputs(
+        midi,
+        leds,
+        notes,
+        &jack,
+        &accent_jack,
+        &note_on_glob,
+        &accent_on_glob,
+    )
+    .await;
 
     let main_loop = async {
         let mut clock = app.use_clock();
@@ -412,6 +492,7 @@ pub async fn run(
         let mut output_mode = output_mode_glob.get();
         let mut dnb_pattern = dnb_pattern_glob.get();
         let mut tick_origin = ticks() as u32;
+        let mut manual_ticks = ManualTicks::default();
         let ghost_note = notes[1];
         let ghost_velocity = (midi_velocity - (midi_velocity / 4)).clamp(1, 127);
 
@@ -419,19 +500,18 @@ pub async fn run(
         generator.set_seed(die.roll());
         generator.set_output_mode(output_mode);
         generator.set_global_chaos(true);
-        update_generator_from_parameters(
-            &mut generator,
-            &GeneratorUpdateContext {
-                drums_density_glob: &drums_density_glob,
-                drums_map_x_glob: &drums_map_x_glob,
-                drums_map_y_glob: &drums_map_y_glob,
-                euclidean_length_glob: &euclidean_length_glob,
-                euclidean_fill_glob: &euclidean_fill_glob,
-                euclidean_offset_glob: &euclidean_offset_glob,
-                chaos_glob: &chaos_glob,
-                dnb_pattern_glob: &dnb_pattern_glob,
-            },
-        );
+        generator.set_live_chaos(live_chaos);
+        let generator_ctx = GeneratorUpdateContext {
+            drums_density_glob: &drums_density_glob,
+            drums_map_x_glob: &drums_map_x_glob,
+            drums_map_y_glob: &drums_map_y_glob,
+            euclidean_length_glob: &euclidean_length_glob,
+            euclidean_fill_glob: &euclidean_fill_glob,
+            euclidean_offset_glob: &euclidean_offset_glob,
+            chaos_glob: &chaos_glob,
+            dnb_pattern_glob: &dnb_pattern_glob,
+        };
+        update_generator_from_parameters(&mut generator, &generator_ctx);
         let (gen_state_, restored_note_on_, restored_accent_on_) =
             storage.query(|s| (s.generator_state, s.note_on, s.accent_on));
         // Note: sequence_step and euclidean_step from gen_state_ are not used after restore —
@@ -449,37 +529,65 @@ pub async fn run(
         }
 
         loop {
-            match clock.wait_for_event(ClockDivision::_1).await {
+            // In manual step mode Fn 4 plays the next step instead of the clock
+            match clock
+                .wait_for_event_or_step(ClockDivision::_1, manual_step, async {
+                    while buttons.wait_for_down(K_NUM_PARTS).await {}
+                })
+                .await
+            {
                 ClockEvent::Reset => {
                     // defmt::info!("[{}] Clock reset!", ticks());
                     tick_origin = ticks() as u32;
+                    manual_ticks.reset();
                     output_mode = output_mode_glob.get();
-                    reset_all_outputs(midi, leds, notes, &jack, &note_on_glob, &accent_on_glob)
-                        .await;
+                    reset_all_outputs(
+                        midi,
+                        leds,
+                        notes,
+                        &jack,
+                        &accent_jack,
+                        &note_on_glob,
+                        &accent_on_glob,
+                    )
+                    .await;
 
                     generator.set_seed(die.roll());
                     generator.set_output_mode(output_mode);
                     generator.reset();
+                    storage.modify_and_save(|s| s.chain.restart());
                     dnb_vary_pattern_glob.set(false);
                     dnb_reset_pattern_glob.set(false);
                 }
                 ClockEvent::Stop => {
                     // defmt::info!("[{}] Clock stop", ticks());
                     // Prevent hanging notes / gate CVs if clock is stopped
-                    reset_all_outputs(midi, leds, notes, &jack, &note_on_glob, &accent_on_glob)
-                        .await;
+                    reset_all_outputs(
+                        midi,
+                        leds,
+                        notes,
+                        &jack,
+                        &accent_jack,
+                        &note_on_glob,
+                        &accent_on_glob,
+                    )
+                    .await;
                     dnb_vary_pattern_glob.set(false);
                     dnb_reset_pattern_glob.set(false);
                 }
                 ClockEvent::Start => {
                     // defmt::info!("[{}] Clock start", ticks());
                     tick_origin = ticks() as u32;
+                    manual_ticks.reset();
                     generator.reset();
+                    storage.modify_and_save(|s| s.chain.restart());
                     // Ensure initial DnB pattern is generated at start of sequence
                     if output_mode == OutputMode::OutputModeDnB {
                         generator.queue_dnb_pattern_change(dnb_pattern_glob.get());
                     }
                 }
+                // Resume the pattern where it stopped
+                ClockEvent::Continue => {}
                 // Assume always 24PPQN
                 ClockEvent::Tick => {
                     let muted = storage.query(|s| s.mute_saved);
@@ -489,7 +597,11 @@ pub async fn run(
                         OutputMode::OutputModeDnB => generator.get_dnb_24ppqn_pattern_division(),
                     };
 
-                    let clkn = (ticks() as u32).wrapping_sub(tick_origin);
+                    let clkn = if manual_step {
+                        manual_ticks.step(div)
+                    } else {
+                        (ticks() as u32).wrapping_sub(tick_origin)
+                    };
                     // If we have reached the next sequence step, or on the first step
                     if clkn.is_multiple_of(div) {
                         // If output mode has changed since last step, change generator mode and reset the sequence
@@ -517,6 +629,21 @@ pub async fn run(
                         // Advance sequence step derived from absolute tick count / division
                         generator.tick(clkn, div);
 
+                        // Song mode: at the start of a bar the chain may switch to its next snapshot
+                        if output_mode == OutputMode::OutputModeDrums
+                            && generator.is_on_first_beat()
+                        {
+                            if let Some(snapshot) =
+                                storage.modify_and_save(|s| s.chain.next_bar(chain_bars))
+                            {
+                                drums_map_x_glob.set(snapshot.x);
+                                drums_map_y_glob.set(snapshot.y);
+                                drums_density_glob.set(snapshot.density);
+                                update_generator_from_parameters(&mut generator, &generator_ctx);
+                                generator.retrigger();
+                            }
+                        }
+
                         // Get generator state and handle individual triggers
                         // State byte bits:
                         // 0: Trigger 1
@@ -561,7 +688,7 @@ pub async fn run(
                         // If accent triggered
                         if is_accent & !muted[3] {
                             // Accent fired
-                            jack[3].set_high().await;
+                            accent_jack.set_high().await;
                             accent_on_glob.set(true);
                             leds.set(3, Led::Top, led_color, Brightness::Mid);
                             if output_mode == OutputMode::OutputModeDnB {
@@ -569,21 +696,17 @@ pub async fn run(
                                 midi.send_note_on(ghost_note, ghost_velocity).await;
                             }
                         }
+                        if let Jack4::Level(level_jack, level_part) = &accent_jack {
+                            let level = if muted[3] {
+                                0
+                            } else {
+                                generator.get_last_level(*level_part)
+                            };
+                            level_jack.set_value(level as u16 * 4095 / 255);
+                        }
 
                         // Update generator with parameter changes
-                        update_generator_from_parameters(
-                            &mut generator,
-                            &GeneratorUpdateContext {
-                                drums_density_glob: &drums_density_glob,
-                                drums_map_x_glob: &drums_map_x_glob,
-                                drums_map_y_glob: &drums_map_y_glob,
-                                euclidean_length_glob: &euclidean_length_glob,
-                                euclidean_fill_glob: &euclidean_fill_glob,
-                                euclidean_offset_glob: &euclidean_offset_glob,
-                                chaos_glob: &chaos_glob,
-                                dnb_pattern_glob: &dnb_pattern_glob,
-                            },
-                        );
+                        update_generator_from_parameters(&mut generator, &generator_ctx);
 
                         // Save generator state in case app is re-spawned
                         storage.modify_and_save(|s| {
@@ -605,7 +728,10 @@ pub async fn run(
                     }
 
                     // If reached end of gate length between sequence steps
-                    if clkn % div == (div * gatel as u32 / 100).clamp(1, div - 1) {
+                    if manual_step {
+                        app.delay_millis(MANUAL_STEP_GATE_MS).await;
+                    }
+                    if manual_step || clkn % div == (div * gatel as u32 / 100).clamp(1, div - 1) {
                         let mut note_on_ = note_on_glob.get();
                         for (part, note) in notes.iter().enumerate().take(K_NUM_PARTS) {
                             if note_on_[part] {
@@ -619,7 +745,7 @@ pub async fn run(
                         // Accent jack
                         if accent_on_glob.get() {
                             accent_on_glob.set(false);
-                            jack[3].set_low().await;
+                            accent_jack.set_low().await;
                             leds.unset(3, Led::Top);
                             if output_mode == OutputMode::OutputModeDnB {
                                 midi.send_note_off(ghost_note).await;
@@ -794,7 +920,7 @@ pub async fn run(
                                     let mut fill_ = euclidean_fill_glob.get();
                                     let length_value = storage.query(|s| s.shift_fader_saved[chan]);
                                     let length = euclidean_length_from_fader(length_value);
-                                    fill_[chan] = euclidean_fill_from_fader(new_value, length);
+                                    fill_[chan] = euclidean_fill_from_value(new_value, length);
                                     euclidean_fill_glob.set(fill_);
                                     storage.modify_and_save(|s| s.fader_saved[chan] = new_value);
                                     fader_led_value = new_value;
@@ -813,7 +939,7 @@ pub async fn run(
                                     let mut fill_ = euclidean_fill_glob.get();
                                     let stored_fill_value = storage.query(|s| s.fader_saved[chan]);
                                     fill_[chan] =
-                                        euclidean_fill_from_fader(stored_fill_value, mapped_length);
+                                        euclidean_fill_from_value(stored_fill_value, mapped_length);
                                     euclidean_fill_glob.set(fill_);
                                     storage.modify_and_save(|s| {
                                         s.shift_fader_saved[chan] = new_value;
@@ -1014,13 +1140,13 @@ pub async fn run(
                     } else {
                         leds.set(part, Led::Button, led_color, Brightness::Mid);
                     }
-                } else if part == K_NUM_PARTS {
+                } else if part == K_NUM_PARTS && !manual_step {
                     // accent mute
                     let mut muted_ = storage.query(|s| s.mute_saved);
                     muted_[part] = !muted_[part];
                     accent_on_glob.set(muted_[part]);
                     if muted_[part] {
-                        jack[part].set_low().await;
+                        accent_jack.clear().await;
                         leds.unset(part, Led::Top);
                         leds.unset(part, Led::Button);
                     }
@@ -1045,6 +1171,24 @@ pub async fn run(
                 offset_[part] = (offset_[part] + 1) % length;
                 euclidean_offset_glob.set(offset_);
                 storage.modify_and_save(|s| s.euclidean_offset_saved[part] = offset_[part]);
+            } else if part == 0 && output_mode_glob.get() == OutputMode::OutputModeDrums {
+                // Add the current map position and densities to the song mode chain
+                let snapshot = ChainSnapshot {
+                    x: drums_map_x_glob.get(),
+                    y: drums_map_y_glob.get(),
+                    density: drums_density_glob.get(),
+                };
+                if storage.modify_and_save(|s| s.chain.push(snapshot)) {
+                    leds.set(part, Led::Button, Color::White, Brightness::High);
+                    app.delay_millis(100).await;
+                    leds.unset(part, Led::Button);
+                }
+            } else if part == 1 && output_mode_glob.get() == OutputMode::OutputModeDrums {
+                // Clear the song mode chain
+                storage.modify_and_save(|s| s.chain.clear());
+                leds.set(part, Led::Button, Color::Red, Brightness::High);
+                app.delay_millis(100).await;
+                leds.unset(part, Led::Button);
             } else if part == 0 && output_mode_glob.get() == OutputMode::OutputModeDnB {
                 // DnB pattern will be varied on next sequencer step
                 dnb_vary_pattern_glob.set(true);
@@ -1181,8 +1325,16 @@ pub async fn run(
                             dnb_pattern_glob: &dnb_pattern_glob,
                         },
                     );
-                    reset_all_outputs(midi, leds, notes, &jack, &note_on_glob, &accent_on_glob)
-                        .await;
+                    reset_all_outputs(
+                        midi,
+                        leds,
+                        notes,
+                        &jack,
+                        &accent_jack,
+                        &note_on_glob,
+                        &accent_on_glob,
+                    )
+                    .await;
                 }
 
                 SceneEvent::SaveScene(scene) => {
@@ -1195,11 +1347,42 @@ pub async fn run(
     join5(main_loop, fader_fut, buttons_fut, shift_fut, scene_handler).await;
 }
 
+/// Jack 4 either fires the accent gate or puts out the drum map level of one part as CV
+enum Jack4 {
+    Accent(GateJack),
+    Level(OutJack, usize),
+}
+
+impl Jack4 {
+    /// Fire the accent gate, the level is written every step instead
+    async fn set_high(&self) {
+        if let Self::Accent(jack) = self {
+            jack.set_high().await;
+        }
+    }
+
+    /// End the accent gate, the level holds until the next step
+    async fn set_low(&self) {
+        if let Self::Accent(jack) = self {
+            jack.set_low().await;
+        }
+    }
+
+    /// Turn the output off
+    async fn clear(&self) {
+        match self {
+            Self::Accent(jack) => jack.set_low().await,
+            Self::Level(jack, _) => jack.set_value(0),
+        }
+    }
+}
+
 async fn reset_all_outputs(
     midi: crate::app::MidiOutput,
     leds: crate::app::Leds<4>,
     notes: [MidiNote; 3],
-    jack: &[crate::app::GateJack; 4],
+    jack: &[GateJack; 3],
+    accent_jack: &Jack4,
     note_on_glob: &Global<[bool; 3]>,
     accent_on_glob: &Global<bool>,
 ) {
@@ -1218,7 +1401,7 @@ async fn reset_all_outputs(
         leds.unset(part, Led::Top);
     }
     note_on_glob.set([false; K_NUM_PARTS]);
-    jack[3].set_low().await;
+    accent_jack.clear().await;
     accent_on_glob.set(false);
     leds.unset(3, Led::Top);
 }
@@ -1289,7 +1472,7 @@ fn refresh_state_from_storage(
 
             let euclidean_fill_ = [faders_[0], faders_[1], faders_[2]];
             globs.euclidean_fill_glob.set(core::array::from_fn(|part| {
-                euclidean_fill_from_fader(euclidean_fill_[part], mapped_euclidean_length_[part])
+                euclidean_fill_from_value(euclidean_fill_[part], mapped_euclidean_length_[part])
             }));
             globs.euclidean_length_glob.set(mapped_euclidean_length_);
             globs.div_glob.set(resolution[div_saved_ as usize / 345]);
@@ -1441,11 +1624,7 @@ fn update_fader_leds(
 
 fn euclidean_length_from_fader(value: u16) -> u8 {
     // Same as euclid.rs: fader * 15 / 4095 + 1 → 1..16
-    (value as u32 * 15 / 4095) as u8 + 1
-}
-
-fn euclidean_fill_from_fader(value: u16, length: u8) -> u8 {
-    ((value as u32 * length as u32) / 4095) as u8
+    euclidean_length_from_value(value, EUCLIDEAN_MAX_LENGTH)
 }
 
 struct GeneratorUpdateContext<'a> {
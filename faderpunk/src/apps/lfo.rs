@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use libfp::{
     ext::FromValue,
     latch::LatchLayer,
+    lfo::{lfo_advance, lfo_free_speed, lfo_synced_speed},
     utils::{attenuate, attenuate_bipolar, split_unsigned_value},
     AppIcon, Brightness, ClockDivision, Color, Config, Curve, MidiCc, MidiChannel, MidiOut, Param,
     Range, Value, Waveform, APP_MAX_PARAMS,
@@ -38,7 +39,8 @@ pub static CONFIG: Config<PARAMS> =
         })
         .add_param(Param::MidiCc { name: "MIDI CC" })
         .add_param(Param::MidiNrpn)
-        .add_param(Param::MidiOut);
+        .add_param(Param::MidiOut)
+        .finalize();
 
 pub struct Params {
     speed_mult: usize,
@@ -164,10 +166,10 @@ pub async fn run(
     let mut last_out = 0;
 
     let update_speed = async || {
-        glob_lfo_speed.set((curve.at(storage.query(|s| s.layer_speed)) as f32) * 0.015 + 0.0682);
+        glob_lfo_speed.set(lfo_free_speed(storage.query(|s| s.layer_speed)));
 
         let div = resolution[((storage.query(|s| s.layer_speed)) as usize / 500).clamp(0, 8)];
-        glob_quant_speed.set(4095. / ((glob_count.get().max(1) as f32 * div as f32) / 24.));
+        glob_quant_speed.set(lfo_synced_speed(glob_count.get(), div));
     };
 
     update_speed().await;
@@ -198,9 +200,9 @@ pub async fn run(
             let lfo_pos = glob_lfo_pos.get();
 
             let next_pos = if sync {
-                (lfo_pos + quant_speed / speed_mult as f32) % 4096.0
+                lfo_advance(lfo_pos, quant_speed / speed_mult as f32)
             } else {
-                (lfo_pos + lfo_speed / speed_mult as f32) % 4096.0
+                lfo_advance(lfo_pos, lfo_speed / speed_mult as f32)
             };
 
             let attenuation = storage.query(|s| s.layer_attenuation);
@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use libfp::{
     ext::FromValue,
     latch::LatchLayer,
+    lfo::{lfo_advance, lfo_free_speed, lfo_synced_speed},
     utils::{attenuate, attenuate_bipolar, split_unsigned_value},
     AppIcon, Brightness, ClockDivision, Color, Config, Curve, MidiCc, MidiChannel, MidiOut, Param,
     Range, Value, Waveform, APP_MAX_PARAMS,
@@ -55,7 +56,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     ],
 })
 .add_param(Param::MidiNrpn)
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     speed_mult: usize,
@@ -199,7 +201,7 @@ pub async fn run(
 
     leds.set(1, Led::Button, color, Brightness::Mid);
 
-    glob_lfo_speed.set(curve.at(speed) as f32 * 0.015 + 0.0682);
+    glob_lfo_speed.set(lfo_free_speed(speed));
     glob_div.set(resolution[(speed as usize / 500).clamp(0, 8)]);
     let mut count = 0;
 
@@ -221,7 +223,7 @@ pub async fn run(
 
         let index_val = sum.saturating_sub(2047).min(4095) as usize / 500;
         let div = resolution[index_val.clamp(0, 8)];
-        glob_quant_speed.set(4095. / ((glob_count.get().max(1) as f32 * div as f32) / 24.));
+        glob_quant_speed.set(lfo_synced_speed(glob_count.get(), div));
     };
 
     let fut1 = async {
@@ -264,9 +266,9 @@ pub async fn run(
             let lfo_pos = glob_lfo_pos.get();
 
             let next_pos = if sync {
-                (lfo_pos + quant_speed / speed_mult as f32) % 4096.0
+                lfo_advance(lfo_pos, quant_speed / speed_mult as f32)
             } else {
-                (lfo_pos + lfo_speed / speed_mult as f32) % 4096.0
+                lfo_advance(lfo_pos, lfo_speed / speed_mult as f32)
             };
 
             let attenuation = (storage.query(|s| s.layer_attenuation) as i16
@@ -345,9 +347,7 @@ pub async fn run(
                 }
                 LatchLayer::Third => {}
             }
-            let led0 = split_unsigned_value(in_val);
-            leds.set(0, Led::Top, color_in, Brightness::Custom(led0[0]));
-            leds.set(0, Led::Bottom, color_in, Brightness::Custom(led0[1]));
+            leds.set_meter(0, in_val, color_in);
 
             glob_lfo_pos.set(next_pos);
         }
@@ -476,7 +476,7 @@ pub async fn run(
                     let speed = storage.query(|s| s.layer_speed);
                     let wave_saved = storage.query(|s| s.wave);
 
-                    glob_lfo_speed.set(curve.at(speed) as f32 * 0.015 + 0.0682);
+                    glob_lfo_speed.set(lfo_free_speed(speed));
                     glob_div.set(resolution[(speed as usize / 500).clamp(0, 8)]);
 
                     let color = get_color_for(wave_saved);
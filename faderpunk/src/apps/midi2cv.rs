@@ -5,13 +5,13 @@ use embassy_futures::{
 use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
 use heapless::Vec;
 
-use libm::expf;
 use midly::{num::u7, MidiMessage};
 use serde::{Deserialize, Serialize};
 
 use libfp::{
     ext::FromValue,
     latch::LatchLayer,
+    portamento::{GlideMode, Portamento},
     utils::{bits_7_16, clickless, scale_bits_14_12, scale_bits_7_12},
     AppIcon, Brightness, Color, Config, Curve, MidiCc, MidiChannel, MidiIn, MidiNote, Param, Range,
     Value, APP_MAX_PARAMS,
@@ -46,6 +46,7 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     name: "Bend Range",
     min: 1,
     max: 24,
+    step: 1,
 })
 .add_param(Param::MidiNote { name: "MIDI Note" })
 .add_param(Param::Color {
@@ -64,7 +65,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
 .add_param(Param::MidiIn)
 .add_param(Param::bool {
     name: "Velocity on Gate",
-});
+})
+.finalize();
 
 pub struct Params {
     mode: usize,
@@ -162,26 +164,17 @@ pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMut
     select(app_loop, app.exit_handler(exit_signal)).await;
 }
 
-/// RC filter glide calculation.
-/// Returns the coefficient for exponential approach based on glide time.
-/// At glide=0, returns 1.0 (instant). At glide=100, returns a small value for slow glide.
-fn calc_glide_coeff(glide: i32) -> f32 {
+/// Glide time for the alt layer fader value
+fn glide_ms(alt_layer_val: u16) -> u16 {
+    let glide = alt_layer_val as u32 * 100 / 4095;
     if glide == 0 {
-        1.0
+        0
     } else {
-        // RC time constant: larger glide value = slower approach
-        // With 1ms tick rate, we need coefficients that give ~150ms settling
-        // coeff = 1 - e^(-1/tau) where tau is in ticks
-        let tau = 1.0 + (glide as f32 * 0.5);
-        1.0 - expf(-1.0 / tau)
+        // Glides up to ~250ms
+        (5 + glide * 5 / 2) as u16
     }
 }
 
-/// Apply RC filter glide: moves current toward target exponentially
-fn apply_glide(current: f32, target: f32, coeff: f32) -> f32 {
-    current + (target - current) * coeff
-}
-
 pub async fn run(
     app: &App<CHANNELS>,
     params: &ParamStore<Params>,
@@ -207,10 +200,8 @@ pub async fn run(
 
     let offset_glob = app.make_global(0);
     let pitch_glob = app.make_global(0);
-    let glide_active_glob = app.make_global(false);
-    let glide_coeff_glob = app.make_global(calc_glide_coeff(
-        storage.query(|s| s.alt_layer_val) as i32 * 100 / 4095,
-    ));
+    let legato_glob = app.make_global(false);
+    let glide_ms_glob = app.make_global(glide_ms(storage.query(|s| s.alt_layer_val)));
     let buttons = app.use_buttons();
     let fader = app.use_faders();
     let leds = app.use_leds();
@@ -244,7 +235,7 @@ pub async fn run(
         if mode == 1 {
             *note_num = (*note_num - 1).max(0);
             if *note_num == 0 {
-                glide_active_glob.set(false);
+                legato_glob.set(false);
             }
         } else if mode == 2 || (mode == 6 && key == u7::from(note)) {
             *note_num = (*note_num - 1).max(0);
@@ -260,7 +251,7 @@ pub async fn run(
         let mut val;
         let mut attval;
         let mut fadval = fader.get_value();
-        let mut glide_current: f32 = 0.0;
+        let mut portamento = Portamento::new(glide_ms_glob.get(), GlideMode::Fingered);
 
         loop {
             app.delay_millis(1).await;
@@ -310,17 +301,10 @@ pub async fn run(
                         2047
                     };
 
-                    let pitch_target = pitch_glob.get() as f32;
-
-                    // Only glide when legato (glide_active is true)
-                    let glide_coeff = glide_coeff_glob.get();
-
-                    if glide_active_glob.get() {
-                        glide_current = apply_glide(glide_current, pitch_target, glide_coeff);
-                    } else {
-                        glide_current = pitch_target;
-                    }
-                    let pitch = glide_current as u16;
+                    // Only glide when legato
+                    portamento.set_glide_ms(glide_ms_glob.get());
+                    portamento.set_legato(legato_glob.get());
+                    let pitch = portamento.process(pitch_glob.get() as f32, 1.0) as u16;
 
                     outval = clickless(outval, offset);
                     let out = (pitch as i32 + outval as i32 - 2047).clamp(0, 4095) as u16;
@@ -345,13 +329,9 @@ pub async fn run(
                 }
                 5 => {
                     if !muted_glob.get() {
-                        let offset = offset_glob.get();
-                        outval = clickless(outval, offset);
-                        jack.set_value(outval);
+                        jack.set_value_smoothed(offset_glob.get());
                     } else {
-                        let offset = 2048;
-                        outval = clickless(outval, offset);
-                        jack.set_value(outval);
+                        jack.set_value_smoothed(2048);
                     }
                 }
                 _ => {}
@@ -404,8 +384,7 @@ pub async fn run(
                     LatchLayer::Alt => {
                         storage.modify_and_save(|s| s.alt_layer_val = new_value);
                         if mode == 1 {
-                            let glide = new_value as i32 * 100 / 4095;
-                            glide_coeff_glob.set(calc_glide_coeff(glide));
+                            glide_ms_glob.set(glide_ms(new_value));
                         }
                     }
                     LatchLayer::Third => {}
@@ -439,8 +418,7 @@ pub async fn run(
                         match mode {
                             1 if !muted_glob.get() => {
                                 // Legato detection: if a note is already held, enable glide
-                                let is_legato = note_num > 0;
-                                glide_active_glob.set(is_legato);
+                                legato_glob.set(note_num > 0);
                                 note_num += 1;
 
                                 let mut note_in = bits_7_16(key);
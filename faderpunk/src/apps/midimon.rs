@@ -0,0 +1,193 @@
+use embassy_futures::{
+    join::join3,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use midly::{num::u4, MidiMessage};
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue, AppIcon, Brightness, ClockDivision, Color, Config, MidiChannel, MidiIn,
+    NoteEvent, Param, Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{
+    App, AppMidiEvent, AppParams, AppStorage, ClockEvent, Global, Led, ManagedStorage, ParamStore,
+};
+
+pub const CHANNELS: usize = 4;
+pub const PARAMS: usize = 2;
+
+// How long the LEDs light up for a message, in milliseconds
+const FLASH_MS: u16 = 80;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "MIDI Monitor",
+    "Shows the MIDI activity on four channels",
+    Color::White,
+    AppIcon::Note,
+)
+.add_param(Param::MidiIn)
+.add_param(Param::MidiChannel {
+    name: "First MIDI Channel",
+})
+.finalize();
+
+pub struct Params {
+    midi_in: MidiIn,
+    first_channel: MidiChannel,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            midi_in: MidiIn::from_value(values[0]),
+            first_channel: MidiChannel::from_value(values[1]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.midi_in.into()).unwrap();
+        vec.push(self.first_channel.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Storage {}
+
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            midi_in: MidiIn::default(),
+            first_channel: MidiChannel::default(),
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(app: &App<CHANNELS>, params: &ParamStore<Params>) {
+    let (midi_in, first_channel) = params.query(|p| (p.midi_in, p.first_channel));
+
+    let mut midi_in = app.use_midi_input(midi_in, first_channel);
+    let mut clock = app.use_clock();
+    let leds = app.use_leds();
+
+    let first_channel = u4::from(first_channel).as_int();
+    // Time left until the top and bottom LEDs of each channel go off
+    let glob_top = app.make_global([0_u16; CHANNELS]);
+    let glob_bottom = app.make_global([0_u16; CHANNELS]);
+    let glob_button = app.make_global(0_u16);
+
+    let flash = |chan: usize, led: Led, color: Color, timers: &Global<[u16; CHANNELS]>| {
+        leds.set(chan, led, color, Brightness::High);
+        timers.modify(|t| {
+            let mut t = *t;
+            t[chan] = FLASH_MS;
+            t
+        });
+    };
+
+    let midi_handler = async {
+        loop {
+            let (channel, event) = midi_in.wait_for_any_channel_event().await;
+            let Some(chan) = channel
+                .as_int()
+                .checked_sub(first_channel)
+                .map(|chan| chan as usize)
+                .filter(|&chan| chan < CHANNELS)
+            else {
+                continue;
+            };
+            match event {
+                AppMidiEvent::Message(message) => match NoteEvent::from_message(&message) {
+                    Some(NoteEvent::On { note, .. }) => {
+                        let color = Color::from_pitch_class(note.pitch_class());
+                        flash(chan, Led::Top, color, &glob_top);
+                    }
+                    None if matches!(message, MidiMessage::Controller { .. }) => {
+                        flash(chan, Led::Bottom, Color::White, &glob_bottom);
+                    }
+                    _ => {}
+                },
+                AppMidiEvent::Nrpn { .. } => {
+                    flash(chan, Led::Bottom, Color::White, &glob_bottom);
+                }
+            }
+        }
+    };
+
+    let clock_handler = async {
+        loop {
+            // Flash on every beat, green when the clock starts and red when it stops
+            let color = match clock.wait_for_event(ClockDivision::_24).await {
+                ClockEvent::Tick => Color::White,
+                ClockEvent::Start | ClockEvent::Continue | ClockEvent::Reset => Color::Green,
+                ClockEvent::Stop => Color::Red,
+            };
+            for chan in 0..CHANNELS {
+                leds.set(chan, Led::Button, color, Brightness::High);
+            }
+            glob_button.set(FLASH_MS);
+        }
+    };
+
+    let led_handler = async {
+        loop {
+            app.delay_millis(1).await;
+
+            let tick = |t: &[u16; CHANNELS], led: Led| {
+                let mut t = *t;
+                for (chan, time) in t.iter_mut().enumerate() {
+                    if *time > 0 {
+                        *time -= 1;
+                        if *time == 0 {
+                            leds.unset(chan, led);
+                        }
+                    }
+                }
+                t
+            };
+            glob_top.modify(|t| tick(t, Led::Top));
+            glob_bottom.modify(|t| tick(t, Led::Bottom));
+
+            let button = glob_button.get();
+            if button > 0 {
+                glob_button.set(button - 1);
+                if button == 1 {
+                    for chan in 0..CHANNELS {
+                        leds.unset(chan, Led::Button);
+                    }
+                }
+            }
+        }
+    };
+
+    join3(midi_handler, clock_handler, led_handler).await;
+}
@@ -22,4 +22,23 @@ register_apps!(
     21 => clk_div_plus,
     22 => lfo_plus,
     23 => fp_grids,
+    24 => rnd_gates,
+    25 => transpose_quantizer,
+    26 => sample_hold,
+    27 => euclid_plus,
+    28 => adsr,
+    29 => burst,
+    30 => trigger_grid,
+    31 => note_box,
+    32 => coin_toss,
+    33 => soft_random,
+    34 => scale_quantizer,
+    35 => square_seq,
+    36 => stepped,
+    37 => stereo,
+    38 => note_repeat,
+    39 => midimon,
+    40 => poly,
+    41 => chord_lock,
+    42 => attenuvert,
 );
@@ -0,0 +1,245 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    utils::{fader_to_semitone, semitone_to_counts},
+    AppIcon, Brightness, Color, Config, MidiChannel, MidiNote, MidiOut, Param, Range, Value,
+    APP_MAX_PARAMS,
+};
+
+use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
+
+pub const CHANNELS: usize = 1;
+pub const PARAMS: usize = 5;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Note Box",
+    "Fader selected note as CV and MIDI",
+    Color::Cyan,
+    AppIcon::NoteBox,
+)
+.add_param(Param::i32 {
+    name: "Octave",
+    min: 0,
+    max: 9,
+    step: 1,
+})
+.add_param(Param::i32 {
+    name: "Span",
+    min: 1,
+    max: 120,
+    step: 1,
+})
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.add_param(Param::MidiOut)
+.finalize();
+
+pub struct Params {
+    octave: i32,
+    span: i32,
+    midi_channel: MidiChannel,
+    color: Color,
+    midi_out: MidiOut,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            octave: i32::from_value(values[0]),
+            span: i32::from_value(values[1]),
+            midi_channel: MidiChannel::from_value(values[2]),
+            color: Color::from_value(values[3]),
+            midi_out: MidiOut::from_value(values[4]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.octave.into()).unwrap();
+        vec.push(self.span.into()).unwrap();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec.push(self.midi_out.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    note_saved: u16,
+    note_on: bool,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            note_saved: 2048,
+            note_on: false,
+        }
+    }
+}
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            octave: 3,
+            span: 24,
+            midi_channel: MidiChannel::default(),
+            color: Color::Cyan,
+            midi_out: MidiOut::default(),
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let range = Range::_0_10V;
+    let (octave, span, midi_out, midi_chan, led_color) = params.query(|p| {
+        (
+            p.octave.clamp(0, 9) as u8,
+            p.span.clamp(1, 120) as u8,
+            p.midi_out,
+            p.midi_channel,
+            p.color,
+        )
+    });
+
+    let quantizer = app.use_quantizer(range);
+    let fader = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let midi = app.use_midi_output(midi_out, midi_chan, false);
+
+    let jack = app.make_out_jack(0, range).await;
+
+    let update_button_led = |note_on: bool| {
+        if note_on {
+            leds.set(0, Led::Button, led_color, LED_BRIGHTNESS);
+        } else {
+            leds.set(0, Led::Button, led_color, Brightness::Low);
+        }
+    };
+    update_button_led(storage.query(|s| s.note_on));
+
+    let fut1 = async {
+        let mut playing: Option<MidiNote> = None;
+        loop {
+            app.delay_millis(1).await;
+
+            let (value, note_on) = storage.query(|s| (s.note_saved, s.note_on));
+            let semitone = fader_to_semitone(value, span)
+                .saturating_add(octave * 12)
+                .min(120);
+            let pitch = quantizer
+                .get_quantized_note(semitone_to_counts(semitone))
+                .await;
+            jack.set_value(pitch.as_counts(range));
+
+            let note = pitch.as_midi();
+            let wanted = if note_on { Some(note) } else { None };
+            if wanted != playing {
+                if let Some(old) = playing {
+                    midi.send_note_off(old).await;
+                }
+                if let Some(new) = wanted {
+                    midi.send_note_on(new, 4095).await;
+                }
+                playing = wanted;
+            }
+
+            let brightness = (fader_to_semitone(value, span) as u32 * 255 / span as u32) as u8;
+            leds.set(0, Led::Top, led_color, Brightness::Custom(brightness));
+        }
+    };
+
+    let fut2 = async {
+        loop {
+            buttons.wait_for_down(0).await;
+            let note_on = storage.modify_and_save(|s| {
+                s.note_on = !s.note_on;
+                s.note_on
+            });
+            update_button_led(note_on);
+        }
+    };
+
+    let fut3 = async {
+        let mut latch = app.make_latch(fader.get_value());
+        loop {
+            fader.wait_for_change().await;
+            let target_value = storage.query(|s| s.note_saved);
+            if let Some(new_value) = latch.update(fader.get_value(), LatchLayer::Main, target_value)
+            {
+                storage.modify_and_save(|s| s.note_saved = new_value);
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    update_button_led(storage.query(|s| s.note_on));
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    join4(fut1, fut2, fut3, scene_handler).await;
+}
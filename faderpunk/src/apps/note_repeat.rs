@@ -0,0 +1,298 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3, Either},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use midly::num::u7;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    note_repeat::NoteRepeat,
+    square_seq::square_gate_ticks,
+    utils::{semitone_to_counts, value_to_index, value_to_resolution},
+    AppIcon, Brightness, ClockDivision, Color, Config, MidiChannel, MidiIn, MidiNote, MidiOut,
+    NoteEvent, Param, Range, Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{
+    App, AppParams, AppStorage, ClockEvent, Led, ManagedStorage, ParamStore, SceneEvent,
+};
+
+pub const CHANNELS: usize = 2;
+pub const PARAMS: usize = 4;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+// Clock ticks between repeats: 1/4, 1/8, 1/8T, 1/16, 1/16T, 1/32
+const RATES: [u16; 6] = [24, 12, 8, 6, 4, 3];
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Note Repeat",
+    "Retriggers held MIDI notes in time with the clock",
+    Color::Yellow,
+    AppIcon::Note,
+)
+.add_param(Param::MidiIn)
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.add_param(Param::MidiOut)
+.finalize();
+
+pub struct Params {
+    midi_in: MidiIn,
+    midi_channel: MidiChannel,
+    color: Color,
+    midi_out: MidiOut,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            midi_in: MidiIn::from_value(values[0]),
+            midi_channel: MidiChannel::from_value(values[1]),
+            color: Color::from_value(values[2]),
+            midi_out: MidiOut::from_value(values[3]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.midi_in.into()).unwrap();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec.push(self.midi_out.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    rate_saved: u16,
+    gate_saved: u16,
+    repeat_saved: bool,
+    mute_saved: bool,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            // 1/16
+            rate_saved: 2048,
+            gate_saved: 2048,
+            repeat_saved: true,
+            mute_saved: false,
+        }
+    }
+}
+
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            midi_in: MidiIn::default(),
+            midi_channel: MidiChannel::default(),
+            color: Color::Yellow,
+            midi_out: MidiOut::default(),
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let range = Range::_0_10V;
+    let (midi_in, midi_chan, led_color, midi_out) =
+        params.query(|p| (p.midi_in, p.midi_channel, p.color, p.midi_out));
+
+    let mut clock = app.use_clock();
+    let mut midi_in = app.use_midi_input(midi_in, midi_chan);
+    let midi = app.use_midi_output(midi_out, midi_chan, false);
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let pitch_out = app.make_out_jack(0, range).await;
+    let gate_out = app.make_gate_jack(1, 4095).await;
+
+    let update_button_leds = |repeat: bool, muted: bool| {
+        let bright = if repeat {
+            LED_BRIGHTNESS
+        } else {
+            Brightness::Low
+        };
+        leds.set(0, Led::Button, led_color, bright);
+        if muted {
+            leds.unset(1, Led::Button);
+        } else {
+            leds.set(1, Led::Button, led_color, LED_BRIGHTNESS);
+        }
+    };
+    let update_rate_led = |rate: u16| {
+        leds.set(
+            0,
+            Led::Top,
+            led_color,
+            Brightness::Custom((rate / 16) as u8),
+        );
+    };
+
+    let (repeat, muted, rate) = storage.query(|s| (s.repeat_saved, s.mute_saved, s.rate_saved));
+    update_button_leds(repeat, muted);
+    update_rate_led(rate);
+
+    let note_handler = async {
+        let mut repeat = NoteRepeat::default();
+        let mut playing: Option<MidiNote> = None;
+        loop {
+            let event = match select(
+                midi_in.next_note_event(),
+                clock.wait_for_event(ClockDivision::_1),
+            )
+            .await
+            {
+                Either::First(NoteEvent::On { note, vel }) => Some(repeat.press(note, vel)),
+                Either::First(NoteEvent::Off { note, .. }) => repeat.release(note),
+                Either::Second(ClockEvent::Tick) => {
+                    let (rate, gate, repeating) =
+                        storage.query(|s| (s.rate_saved, s.gate_saved, s.repeat_saved));
+                    if repeating {
+                        let division = value_to_resolution(rate, &RATES);
+                        let gate_percent = value_to_index(gate, 100) as u32 + 1;
+                        repeat.tick(division, square_gate_ticks(division, gate_percent))
+                    } else {
+                        None
+                    }
+                }
+                Either::Second(ClockEvent::Stop | ClockEvent::Reset) => repeat.stop(),
+                _ => None,
+            };
+
+            match event {
+                Some(NoteEvent::On { note, vel }) => {
+                    if storage.query(|s| s.mute_saved) {
+                        continue;
+                    }
+                    if let Some(old) = playing.take() {
+                        midi.send_note_off(old).await;
+                    }
+                    // 0V is C0, like the quantizer's notes
+                    let semitone = u7::from(note).as_int().saturating_sub(12);
+                    pitch_out.set_value(semitone_to_counts(semitone));
+                    midi.send_note_on(note, vel).await;
+                    gate_out.set_high().await;
+                    leds.set(1, Led::Top, led_color, Brightness::High);
+                    playing = Some(note);
+                }
+                Some(NoteEvent::Off { .. }) => {
+                    if let Some(note) = playing.take() {
+                        midi.send_note_off(note).await;
+                        gate_out.set_low().await;
+                        leds.unset(1, Led::Top);
+                    }
+                }
+                None => {}
+            }
+        }
+    };
+
+    let button_handler = async {
+        loop {
+            let (chan, _) = buttons.wait_for_any_down().await;
+            let (repeat, muted) = storage.modify_and_save(|s| {
+                if chan == 0 {
+                    s.repeat_saved = !s.repeat_saved;
+                } else {
+                    s.mute_saved = !s.mute_saved;
+                }
+                (s.repeat_saved, s.mute_saved)
+            });
+            update_button_leds(repeat, muted);
+        }
+    };
+
+    let fader_handler = async {
+        let mut latch = [
+            app.make_latch(faders.get_value_at(0)),
+            app.make_latch(faders.get_value_at(1)),
+        ];
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            let target_value = storage.query(|s| match chan {
+                0 => s.rate_saved,
+                _ => s.gate_saved,
+            });
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), LatchLayer::Main, target_value)
+            {
+                storage.modify_and_save(|s| match chan {
+                    0 => s.rate_saved = new_value,
+                    _ => s.gate_saved = new_value,
+                });
+                if chan == 0 {
+                    update_rate_led(new_value);
+                }
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    let (repeat, muted, rate) =
+                        storage.query(|s| (s.repeat_saved, s.mute_saved, s.rate_saved));
+                    update_button_leds(repeat, muted);
+                    update_rate_led(rate);
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    join4(note_handler, button_handler, fader_handler, scene_handler).await;
+}
@@ -35,11 +35,13 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     name: "Span",
     min: 1,
     max: 120,
+    step: 1,
 })
 .add_param(Param::i32 {
     name: "GATE %",
     min: 1,
     max: 100,
+    step: 1,
 })
 .add_param(Param::Enum {
     name: "Out",
@@ -58,7 +60,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
         Color::Yellow,
     ],
 })
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     midi_channel: MidiChannel,
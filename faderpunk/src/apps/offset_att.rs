@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use libfp::{
     ext::FromValue,
     latch::LatchLayer,
-    utils::{attenuverter, clickless, split_unsigned_value},
+    utils::{clickless, offset_attenuvert},
     AppIcon, Brightness, Color, Config, Param, Range, Value, APP_MAX_PARAMS,
 };
 
@@ -36,7 +36,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
         Color::Violet,
         Color::Yellow,
     ],
-});
+})
+.finalize();
 
 pub struct Params {
     color: Color,
@@ -147,20 +148,13 @@ pub async fn run(
                     2047
                 },
             );
-            let offset = offset_fad as i32 - 2047;
-
-            let mut outval = (attenuverter(inval, att) as i32 + offset).clamp(0, 4095) as u16;
-            outval = ((outval as i32 - 2047) * 2 + 2047).clamp(0, 4094) as u16;
+            let outval = offset_attenuvert(inval, att, offset_fad);
 
             output.set_value(outval);
 
-            let slew_led = split_unsigned_value(inval);
-            leds.set(0, Led::Top, led_color, Brightness::Custom(slew_led[0]));
-            leds.set(0, Led::Bottom, led_color, Brightness::Custom(slew_led[1]));
+            leds.set_meter(0, inval, led_color);
 
-            let out_led = split_unsigned_value(outval);
-            leds.set(1, Led::Top, led_color, Brightness::Custom(out_led[0]));
-            leds.set(1, Led::Bottom, led_color, Brightness::Custom(out_led[1]));
+            leds.set_meter(1, outval, led_color);
         }
     };
 
@@ -7,7 +7,8 @@ use heapless::Vec;
 use libfp::{
     ext::FromValue,
     latch::LatchLayer,
-    utils::{attenuate_bipolar, clickless, slew_2, split_unsigned_value},
+    lerp::{lerp_step, Lerp},
+    utils::{attenuate_bipolar, clickless, curve_fader, slew_2, split_unsigned_value},
     AppIcon, Brightness, Color, MidiCc, MidiChannel, MidiOut, Waveform, APP_MAX_PARAMS,
 };
 
@@ -59,7 +60,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     name: "Store state",
 })
 .add_param(Param::MidiNrpn)
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     curve: Curve,
@@ -136,6 +138,20 @@ impl Default for Storage {
 
 impl AppStorage for Storage {}
 
+impl Lerp for Storage {
+    fn lerp(&self, other: &Self, t: u16) -> Self {
+        Self {
+            muted: self.muted.lerp(&other.muted, t),
+            att_saved: self.att_saved.lerp(&other.att_saved, t),
+            fad_val: self.fad_val.lerp(&other.fad_val, t),
+            pan_val: self.pan_val.lerp(&other.pan_val, t),
+            lfo_speed: self.lfo_speed.lerp(&other.lfo_speed, t),
+            lfo_amt: self.lfo_amt.lerp(&other.lfo_amt, t),
+            wave: *lerp_step(&self.wave, &other.wave, t),
+        }
+    }
+}
+
 #[embassy_executor::task(pool_size = 16/CHANNELS)]
 pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
     let ch = app.start_channel as u8;
@@ -327,12 +343,8 @@ pub async fn run(
                 } else {
                     0
                 }
-            } else if !bipolar {
-                clickless(val_left, curve.at(pan_left))
-            } else if pan_left > 2047 {
-                clickless(val_left, curve.at((pan_left - 2047) * 2) / 2 + 2047)
             } else {
-                clickless(val_left, 2047 - curve.at((2047 - pan_left) * 2) / 2)
+                clickless(val_left, curve_fader(curve, pan_left, bipolar))
             };
 
             val_right = if muted {
@@ -341,12 +353,8 @@ pub async fn run(
                 } else {
                     0
                 }
-            } else if !bipolar {
-                clickless(val_right, curve.at(pan_right))
-            } else if pan_right > 2047 {
-                clickless(val_right, curve.at((pan_right - 2047) * 2) / 2 + 2047)
             } else {
-                clickless(val_right, 2047 - curve.at((2047 - pan_right) * 2) / 2)
+                clickless(val_right, curve_fader(curve, pan_right, bipolar))
             };
 
             // Attenuation
@@ -389,12 +397,8 @@ pub async fn run(
             match latch_active_layer {
                 LatchLayer::Main => {
                     if bipolar {
-                        let led1 = split_unsigned_value(out_l);
-                        leds.set(0, Led::Top, led_color, Brightness::Custom(led1[0]));
-                        leds.set(0, Led::Bottom, led_color, Brightness::Custom(led1[1]));
-                        let led1 = split_unsigned_value(out_r);
-                        leds.set(1, Led::Top, led_color, Brightness::Custom(led1[0]));
-                        leds.set(1, Led::Bottom, led_color, Brightness::Custom(led1[1]));
+                        leds.set_meter(0, out_l, led_color);
+                        leds.set_meter(1, out_r, led_color);
                     } else {
                         leds.set(
                             0,
@@ -525,7 +529,7 @@ pub async fn run(
                     }
                     LatchLayer::Alt => {
                         // Now we commit to storage
-                        storage.save().await;
+                        storage.save_now().await;
                     }
                     LatchLayer::Third => {}
                 }
@@ -560,24 +564,23 @@ pub async fn run(
         loop {
             match app.wait_for_scene_event().await {
                 SceneEvent::LoadScene(scene) => {
-                    storage.load_from_scene(scene).await;
-                    if save_state {
-                        let muted = storage.query(|s| s.muted);
-                        muted_glob.set(muted);
-                        if muted {
-                            leds.unset(0, Led::Button);
-                        } else {
-                            leds.set(0, Led::Button, led_color, Brightness::Mid);
-                        }
-                    }
-
-                    let speed = storage.query(|s| s.lfo_speed);
-                    let wave_saved = storage.query(|s| s.wave);
-
-                    glob_lfo_speed.set(curve.at(speed) as f32 * 0.015 + 0.0682);
-
-                    let color = get_color_for(wave_saved);
-                    leds.set(1, Led::Button, color, Brightness::Mid);
+                    storage
+                        .load_from_scene_with(scene, |s| {
+                            if save_state {
+                                muted_glob.set(s.muted);
+                                if s.muted {
+                                    leds.unset(0, Led::Button);
+                                } else {
+                                    leds.set(0, Led::Button, led_color, Brightness::Mid);
+                                }
+                            }
+
+                            glob_lfo_speed.set(curve.at(s.lfo_speed) as f32 * 0.015 + 0.0682);
+
+                            let color = get_color_for(s.wave);
+                            leds.set(1, Led::Button, color, Brightness::Mid);
+                        })
+                        .await;
                 }
                 SceneEvent::SaveScene(scene) => storage.save_to_scene(scene).await,
             }
@@ -0,0 +1,273 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use midly::num::u7;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue, latch::LatchLayer, poly::VoiceAllocator, utils::semitone_to_counts, AppIcon,
+    Brightness, Color, Config, MidiChannel, MidiIn, NoteEvent, Param, Range, Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
+
+pub const CHANNELS: usize = 8;
+pub const PARAMS: usize = 3;
+
+// A pitch and a gate jack per voice
+const VOICES: usize = CHANNELS / 2;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Poly MIDI to CV",
+    "Plays MIDI notes on four pitch and gate voices",
+    Color::Violet,
+    AppIcon::Note,
+)
+.add_param(Param::MidiIn)
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.finalize();
+
+pub struct Params {
+    midi_in: MidiIn,
+    midi_channel: MidiChannel,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            midi_in: MidiIn::from_value(values[0]),
+            midi_channel: MidiChannel::from_value(values[1]),
+            color: Color::from_value(values[2]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.midi_in.into()).unwrap();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    glide_saved: u16,
+    gate_saved: u16,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            glide_saved: 0,
+            // Gates last as long as the notes are held
+            gate_saved: 4095,
+        }
+    }
+}
+
+impl AppStorage for Storage {}
+
+/// Longest gate in milliseconds, the fader all the way up holds the gate with the note
+fn gate_length(value: u16) -> Option<u32> {
+    (value < 4095).then_some(5 + value as u32 / 4)
+}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            midi_in: MidiIn::default(),
+            midi_channel: MidiChannel::default(),
+            color: Color::Violet,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let range = Range::_0_10V;
+    let (midi_in, midi_chan, led_color) = params.query(|p| (p.midi_in, p.midi_channel, p.color));
+
+    let mut midi_in = app.use_midi_input(midi_in, midi_chan);
+    let faders = app.use_faders();
+    let leds = app.use_leds();
+
+    let pitch_outs = [
+        app.make_out_jack(0, range).await,
+        app.make_out_jack(2, range).await,
+        app.make_out_jack(4, range).await,
+        app.make_out_jack(6, range).await,
+    ];
+    let gate_outs = [
+        app.make_gate_jack(1, 4095).await,
+        app.make_gate_jack(3, 4095).await,
+        app.make_gate_jack(5, 4095).await,
+        app.make_gate_jack(7, 4095).await,
+    ];
+
+    let glob_voices = app.make_global(VoiceAllocator::<VOICES>::new());
+
+    let midi_handler = async {
+        loop {
+            let event = midi_in.next_note_event().await;
+            glob_voices.modify(|v| {
+                let mut v = *v;
+                match event {
+                    NoteEvent::On { note, vel } => {
+                        v.note_on(note, vel);
+                    }
+                    NoteEvent::Off { note, .. } => {
+                        v.note_off(note);
+                    }
+                }
+                v
+            });
+        }
+    };
+
+    let fader_handler = async {
+        let mut latch = [
+            app.make_latch(faders.get_value_at(0)),
+            app.make_latch(faders.get_value_at(1)),
+        ];
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            if chan > 1 {
+                continue;
+            }
+            let target_value = storage.query(|s| match chan {
+                0 => s.glide_saved,
+                _ => s.gate_saved,
+            });
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), LatchLayer::Main, target_value)
+            {
+                storage.modify_and_save(|s| match chan {
+                    0 => s.glide_saved = new_value,
+                    _ => s.gate_saved = new_value,
+                });
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    let main_loop = async {
+        let mut pitch = [0.0_f32; VOICES];
+        let mut triggered = [0_u32; VOICES];
+        let mut gate_time = [0_u32; VOICES];
+        let mut gate_high = [false; VOICES];
+        loop {
+            app.delay_millis(1).await;
+
+            let voices = glob_voices.get();
+            let (glide, gate) = storage.query(|s| (s.glide_saved, s.gate_saved));
+            let glide_coeff = 1.0 / (1.0 + glide as f32 / 16.0);
+            let gate_length = gate_length(gate);
+
+            for idx in 0..VOICES {
+                let Some(voice) = voices.voice(idx) else {
+                    continue;
+                };
+
+                // A new note opens the gate again after a millisecond low
+                let retrigger = voices.triggered(idx) != triggered[idx];
+                // 0V is C0, like the quantizer's notes
+                let semitone = u7::from(voice.note).as_int().saturating_sub(12);
+                let target = semitone_to_counts(semitone) as f32;
+                if triggered[idx] == 0 {
+                    // Nothing to glide from on the first note of a voice
+                    pitch[idx] = target;
+                } else {
+                    pitch[idx] += (target - pitch[idx]) * glide_coeff;
+                }
+                let out = pitch[idx] as u16;
+                pitch_outs[idx].set_value(out);
+                leds.set(
+                    idx * 2,
+                    Led::Top,
+                    led_color,
+                    Brightness::Custom((out / 16) as u8),
+                );
+
+                if retrigger {
+                    triggered[idx] = voices.triggered(idx);
+                    gate_time[idx] = 0;
+                } else {
+                    gate_time[idx] = gate_time[idx].saturating_add(1);
+                }
+                let high = !retrigger
+                    && voice.held
+                    && gate_length.is_none_or(|length| gate_time[idx] < length);
+                if high != gate_high[idx] {
+                    gate_high[idx] = high;
+                    if high {
+                        gate_outs[idx].set_high().await;
+                        leds.set(idx * 2 + 1, Led::Top, led_color, Brightness::High);
+                    } else {
+                        gate_outs[idx].set_low().await;
+                        leds.unset(idx * 2 + 1, Led::Top);
+                    }
+                }
+            }
+        }
+    };
+
+    join4(midi_handler, fader_handler, scene_handler, main_loop).await;
+}
@@ -4,10 +4,7 @@ use embassy_futures::{
 };
 use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
 use heapless::Vec;
-use libfp::{
-    ext::FromValue, latch::LatchLayer, utils::split_unsigned_value, AppIcon, Brightness, Color,
-    APP_MAX_PARAMS,
-};
+use libfp::{ext::FromValue, latch::LatchLayer, AppIcon, Brightness, Color, APP_MAX_PARAMS};
 use serde::{Deserialize, Serialize};
 
 use libfp::{Config, Param, Range, Value};
@@ -15,7 +12,7 @@ use libfp::{Config, Param, Range, Value};
 use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
 
 pub const CHANNELS: usize = 2;
-pub const PARAMS: usize = 1;
+pub const PARAMS: usize = 2;
 
 pub static CONFIG: Config<PARAMS> = Config::new(
     "Quantizer",
@@ -35,25 +32,32 @@ pub static CONFIG: Config<PARAMS> = Config::new(
         Color::Violet,
         Color::Yellow,
     ],
-});
+})
+.add_param(Param::i32 {
+    name: "Scale Fade (ms)",
+    min: 0,
+    max: 500,
+    step: 5,
+})
+.finalize();
 
 pub struct Params {
     color: Color,
+    scale_fade: i32,
 }
 
 impl AppParams for Params {
     fn from_values(values: &[Value]) -> Option<Self> {
-        if values.len() < PARAMS {
-            return None;
-        }
-        Some(Self {
-            color: Color::from_value(values[0]),
-        })
+        let color = Color::from_value(*values.first()?);
+        // Params stored before the scale fade only have the color
+        let scale_fade = values.get(1).map_or(0, |&value| i32::from_value(value));
+        Some(Self { color, scale_fade })
     }
 
     fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
         let mut vec = Vec::new();
         vec.push(self.color.into()).unwrap();
+        vec.push(self.scale_fade.into()).unwrap();
         vec
     }
 }
@@ -81,6 +85,7 @@ impl AppStorage for Storage {}
 pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
     let param_store = ParamStore::<Params>::new(app.app_id, app.layout_id, Params {
         color: Color::Blue,
+        scale_fade: 0,
     });
     let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
 
@@ -106,7 +111,7 @@ pub async fn run(
     params: &ParamStore<Params>,
     storage: &ManagedStorage<Storage>,
 ) {
-    let led_color = params.query(|p| p.color);
+    let (led_color, scale_fade) = params.query(|p| (p.color, p.scale_fade));
     let buttons = app.use_buttons();
     let faders = app.use_faders();
     let leds = app.use_leds();
@@ -114,7 +119,8 @@ pub async fn run(
     leds.set(1, Led::Button, led_color, Brightness::Mid);
 
     let range = Range::_Neg5_5V;
-    let quantizer = app.use_quantizer(range);
+    let mut quantizer = app.use_quantizer(range);
+    quantizer.set_scale_crossfade(scale_fade.clamp(0, 500) as u16);
     let _input = app.make_in_jack(0, range).await;
     let output = app.make_out_jack(1, range).await;
     for chan in 0..2 {
@@ -143,14 +149,13 @@ pub async fn run(
                 ((storage.query(|s| s.st) * 12 / 4095) as f32 * 410. / 12.) as i16
             };
 
+            // One sample per ms
             let outval = quantizer
-                .get_quantized_note((inval + oct + st).clamp(0, 4095) as u16)
+                .get_quantized_counts((inval + oct + st).clamp(0, 4095) as u16, 1.0)
                 .await;
 
-            output.set_value(outval.as_counts(range));
-            let oct_led = split_unsigned_value(outval.as_counts(range));
-            leds.set(1, Led::Top, led_color, Brightness::Custom(oct_led[0]));
-            leds.set(1, Led::Bottom, led_color, Brightness::Custom(oct_led[1]));
+            output.set_value(outval);
+            leds.set_meter(1, outval, led_color);
             leds.set(
                 0,
                 Led::Top,
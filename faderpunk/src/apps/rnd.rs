@@ -17,7 +17,7 @@ use crate::app::{
 use libfp::{
     ext::FromValue,
     latch::LatchLayer,
-    utils::{attenuate, attenuate_bipolar, slew_2, split_unsigned_value},
+    utils::{attenuate, attenuate_bipolar, slew_2},
     AppIcon, Brightness, ClockDivision, Color, Config, Curve, MidiCc, MidiChannel, MidiOut, Param,
     Range, Value, APP_MAX_PARAMS,
 };
@@ -42,7 +42,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
 })
 .add_param(Param::MidiCc { name: "MIDI CC" })
 .add_param(Param::MidiNrpn)
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     range: Range,
@@ -362,9 +363,7 @@ pub async fn run(
             if latch_active_layer == LatchLayer::Main {
                 let color = glob_button_color.get();
                 if range.is_bipolar() {
-                    let ledj = split_unsigned_value(out);
-                    leds.set(0, Led::Top, color, Brightness::Custom(ledj[0]));
-                    leds.set(0, Led::Bottom, color, Brightness::Custom(ledj[1]));
+                    leds.set_meter(0, out, color);
                 } else {
                     leds.set(
                         0,
@@ -0,0 +1,389 @@
+use embassy_futures::{
+    join::join5,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    utils::{random_gate_pattern, resolution_for_mode, value_to_resolution},
+    AppIcon, Brightness, ClockDivision, Color, Config, MidiChannel, MidiNote, MidiOut, Param,
+    Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{
+    App, AppParams, AppStorage, ClockEvent, Die, Led, ManagedStorage, ParamStore, SceneEvent,
+};
+
+pub const CHANNELS: usize = 4;
+pub const PARAMS: usize = 7;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+const STEPS: u32 = 16;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Random Gates",
+    "Evolving random gate patterns with per-output density",
+    Color::Pink,
+    AppIcon::Die,
+)
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::MidiNote {
+    name: "MIDI Note 1",
+})
+.add_param(Param::i32 {
+    name: "GATE %",
+    min: 1,
+    max: 100,
+    step: 1,
+})
+.add_param(Param::i32 {
+    name: "Regenerate",
+    min: 1,
+    max: 16,
+    step: 1,
+})
+.add_param(Param::Enum {
+    name: "Divisions",
+    variants: &["Straight", "Triplets", "Both"],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.add_param(Param::MidiOut)
+.finalize();
+
+pub struct Params {
+    midi_channel: MidiChannel,
+    midi_out: MidiOut,
+    note: MidiNote,
+    gatel: i32,
+    regen: i32,
+    division_mode: usize,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            midi_channel: MidiChannel::from_value(values[0]),
+            note: MidiNote::from_value(values[1]),
+            gatel: i32::from_value(values[2]),
+            regen: i32::from_value(values[3]),
+            division_mode: usize::from_value(values[4]),
+            color: Color::from_value(values[5]),
+            midi_out: MidiOut::from_value(values[6]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.note.into()).unwrap();
+        vec.push(self.gatel.into()).unwrap();
+        vec.push(self.regen.into()).unwrap();
+        vec.push(self.division_mode.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec.push(self.midi_out.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    density_saved: [u16; 4],
+    mute_saved: [bool; 4],
+    div_saved: u16,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            density_saved: [2000; 4],
+            mute_saved: [false; 4],
+            div_saved: 3000,
+        }
+    }
+}
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            midi_channel: MidiChannel::default(),
+            midi_out: MidiOut([false, false, false]),
+            note: MidiNote::from(36),
+            gatel: 50,
+            regen: 4,
+            division_mode: 2,
+            color: Color::Pink,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+fn roll_pattern(die: &Die, density: u16) -> u32 {
+    random_gate_pattern(STEPS as u8, density, || die.roll())
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (midi_out, midi_chan, base_note, gatel, regen, division_mode, led_color) =
+        params.query(|p| {
+            (
+                p.midi_out,
+                p.midi_channel,
+                p.note,
+                p.gatel as u32,
+                p.regen.clamp(1, 16) as u32,
+                p.division_mode,
+                p.color,
+            )
+        });
+
+    let mut clock = app.use_clock();
+    let ticks = clock.get_ticker();
+    let die = app.use_die();
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let midi = app.use_midi_output(midi_out, midi_chan, false);
+
+    let resolution = resolution_for_mode(division_mode);
+    let notes: [MidiNote; 4] = core::array::from_fn(|i| base_note + MidiNote::from(i as u8));
+
+    let div_glob = app.make_global(6_u32);
+    let muted_glob = app.make_global([false; 4]);
+    let pattern_glob = app.make_global([0_u32; 4]);
+    let glob_latch_layer = app.make_global(LatchLayer::Main);
+
+    let jacks = [
+        app.make_gate_jack(0, 4095).await,
+        app.make_gate_jack(1, 4095).await,
+        app.make_gate_jack(2, 4095).await,
+        app.make_gate_jack(3, 4095).await,
+    ];
+
+    let regenerate = || {
+        let density = storage.query(|s| s.density_saved);
+        pattern_glob.set(core::array::from_fn(|i| roll_pattern(&die, density[i])));
+    };
+
+    let update_mute_leds = |muted: [bool; 4]| {
+        for (chan, &mute) in muted.iter().enumerate() {
+            if mute {
+                leds.unset(chan, Led::Button);
+            } else {
+                leds.set(chan, Led::Button, led_color, LED_BRIGHTNESS);
+            }
+        }
+    };
+
+    let (div, mute) = storage.query(|s| (s.div_saved, s.mute_saved));
+    div_glob.set(value_to_resolution(div, resolution));
+    muted_glob.set(mute);
+    update_mute_leds(mute);
+    regenerate();
+
+    let fut1 = async {
+        let mut gate_on = [false; 4];
+        let mut cached_div = div_glob.get();
+        let mut cached_gate_step = (cached_div * gatel / 100).clamp(1, cached_div - 1);
+        let mut tick_origin = ticks() as u32;
+
+        loop {
+            match clock.wait_for_event(ClockDivision::_1).await {
+                ClockEvent::Reset | ClockEvent::Stop => {
+                    tick_origin = ticks() as u32;
+                    for (chan, on) in gate_on.iter_mut().enumerate() {
+                        if *on {
+                            midi.send_note_off(notes[chan]).await;
+                            *on = false;
+                        }
+                        jacks[chan].set_low().await;
+                        leds.set(chan, Led::Top, led_color, Brightness::Off);
+                    }
+                }
+                ClockEvent::Tick => {
+                    let div = div_glob.get();
+                    if div != cached_div {
+                        cached_div = div;
+                        cached_gate_step = (cached_div * gatel / 100).clamp(1, cached_div - 1);
+                    }
+                    let clkn = (ticks() as u32).wrapping_sub(tick_origin);
+
+                    if clkn.is_multiple_of(cached_div) {
+                        let step = (clkn / cached_div) % STEPS;
+                        let cycle = clkn / cached_div / STEPS;
+                        if step == 0 && cycle > 0 && cycle.is_multiple_of(regen) {
+                            regenerate();
+                        }
+
+                        let muted = muted_glob.get();
+                        let patterns = pattern_glob.get();
+                        for (chan, on) in gate_on.iter_mut().enumerate() {
+                            if !muted[chan] && patterns[chan] & (1 << step) != 0 {
+                                jacks[chan].set_high().await;
+                                midi.send_note_on(notes[chan], 4095).await;
+                                leds.set(chan, Led::Top, led_color, Brightness::High);
+                                *on = true;
+                            }
+                        }
+
+                        if glob_latch_layer.get() == LatchLayer::Alt {
+                            if matches!(div, 2 | 4 | 8 | 16) {
+                                leds.set(0, Led::Bottom, Color::Orange, Brightness::High);
+                            } else {
+                                leds.set(0, Led::Bottom, Color::Blue, Brightness::High);
+                            }
+                        }
+                    }
+
+                    if clkn % cached_div == cached_gate_step {
+                        for (chan, on) in gate_on.iter_mut().enumerate() {
+                            if *on {
+                                midi.send_note_off(notes[chan]).await;
+                                jacks[chan].set_low().await;
+                                leds.set(chan, Led::Top, led_color, Brightness::Off);
+                                *on = false;
+                            }
+                        }
+                        leds.set(0, Led::Bottom, led_color, Brightness::Off);
+                    }
+                }
+                _ => {}
+            }
+        }
+    };
+
+    let fut2 = async {
+        loop {
+            let (chan, shift) = buttons.wait_for_any_down().await;
+            if shift {
+                // Shift + any button rerolls all patterns right away
+                regenerate();
+                continue;
+            }
+            let muted = muted_glob.modify(|m| {
+                let mut m = *m;
+                m[chan] = !m[chan];
+                m
+            });
+            storage.modify_and_save(|s| s.mute_saved = muted);
+            if muted[chan] {
+                jacks[chan].set_low().await;
+                leds.unset(chan, Led::Top);
+            }
+            update_mute_leds(muted);
+        }
+    };
+
+    let fut3 = async {
+        let mut latch: [_; 4] = core::array::from_fn(|i| app.make_latch(faders.get_value_at(i)));
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            let latch_layer = glob_latch_layer.get();
+
+            let target_value = match latch_layer {
+                LatchLayer::Main => storage.query(|s| s.density_saved[chan]),
+                LatchLayer::Alt if chan == 0 => storage.query(|s| s.div_saved),
+                _ => continue,
+            };
+
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), latch_layer, target_value)
+            {
+                match latch_layer {
+                    LatchLayer::Main => {
+                        storage.modify_and_save(|s| s.density_saved[chan] = new_value);
+                        // Only the touched output gets a fresh pattern
+                        pattern_glob.modify(|p| {
+                            let mut p = *p;
+                            p[chan] = roll_pattern(&die, new_value);
+                            p
+                        });
+                    }
+                    LatchLayer::Alt => {
+                        div_glob.set(value_to_resolution(new_value, resolution));
+                        storage.modify_and_save(|s| s.div_saved = new_value);
+                    }
+                    LatchLayer::Third => {}
+                }
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    let (div, mute) = storage.query(|s| (s.div_saved, s.mute_saved));
+                    div_glob.set(value_to_resolution(div, resolution));
+                    muted_glob.set(mute);
+                    for (chan, &m) in mute.iter().enumerate() {
+                        if m {
+                            jacks[chan].set_low().await;
+                            leds.unset(chan, Led::Top);
+                        }
+                    }
+                    update_mute_leds(mute);
+                    regenerate();
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    let shift = async {
+        loop {
+            app.delay_millis(1).await;
+            glob_latch_layer.set(LatchLayer::from(buttons.is_shift_pressed()));
+        }
+    };
+
+    join5(fut1, fut2, fut3, scene_handler, shift).await;
+}
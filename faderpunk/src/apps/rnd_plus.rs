@@ -13,7 +13,7 @@ use crate::app::{
 use libfp::{
     ext::FromValue,
     latch::LatchLayer,
-    utils::{attenuate, attenuate_bipolar, slew_2, split_unsigned_value},
+    utils::{attenuate, attenuate_bipolar, slew_2},
     AppIcon, Brightness, ClockDivision, Color, Config, Curve, MidiCc, MidiChannel, MidiOut, Param,
     Range, Value, APP_MAX_PARAMS,
 };
@@ -46,7 +46,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
         Color::Violet,
         Color::Yellow,
     ],
-});
+})
+.finalize();
 
 pub struct Params {
     bipolar: bool,
@@ -408,9 +409,7 @@ pub async fn run(
             } else {
                 attenuate_bipolar(input.get_value(), storage.query(|s| s.in_att))
             };
-            let led_in = split_unsigned_value(in_val);
-            leds.set(0, Led::Top, led_color, Brightness::Custom(led_in[0]));
-            leds.set(0, Led::Bottom, led_color, Brightness::Custom(led_in[1]));
+            leds.set_meter(0, in_val, led_color);
             in_val_glob.set(in_val);
 
             let destination = storage.query(|s| s.dest);
@@ -475,9 +474,7 @@ pub async fn run(
                 let rnd_color = glob_button_color.get();
 
                 if bipolar {
-                    let ledj = split_unsigned_value(out);
-                    leds.set(1, Led::Top, rnd_color, Brightness::Custom(ledj[0]));
-                    leds.set(1, Led::Bottom, rnd_color, Brightness::Custom(ledj[1]));
+                    leds.set_meter(1, out, rnd_color);
                 } else {
                     leds.set(
                         1,
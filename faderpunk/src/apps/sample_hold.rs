@@ -0,0 +1,341 @@
+use embassy_futures::{
+    join::join5,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    sample_hold::SampleAndHold,
+    utils::{resolution_for_mode, value_to_resolution},
+    AppIcon, Brightness, ClockDivision, Color, Config, Curve, Param, Range, Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{
+    App, AppParams, AppStorage, ClockEvent, Led, ManagedStorage, ParamStore, SceneEvent,
+};
+
+pub const CHANNELS: usize = 2;
+pub const PARAMS: usize = 3;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Sample & Hold",
+    "Stepped and slewed random voltages, clocked or free running",
+    Color::Green,
+    AppIcon::Random,
+)
+.add_param(Param::Range {
+    name: "Range",
+    variants: &[Range::_0_10V, Range::_Neg5_5V],
+})
+.add_param(Param::Enum {
+    name: "Divisions",
+    variants: &["Straight", "Triplets", "Both"],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.finalize();
+
+pub struct Params {
+    range: Range,
+    division_mode: usize,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            range: Range::from_value(values[0]),
+            division_mode: usize::from_value(values[1]),
+            color: Color::from_value(values[2]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.range.into()).unwrap();
+        vec.push(self.division_mode.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    rate_saved: u16,
+    slew_saved: u16,
+    clocked: bool,
+    mute_saved: bool,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            rate_saved: 3000,
+            slew_saved: 2000,
+            clocked: true,
+            mute_saved: false,
+        }
+    }
+}
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            range: Range::_0_10V,
+            division_mode: 2,
+            color: Color::Green,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+/// Free running period in milliseconds for a given rate fader value
+fn free_period_millis(value: u16) -> u32 {
+    Curve::Exponential.at(4095 - value) as u32 * 5000 / 4095 + 20
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (range, division_mode, led_color) = params.query(|p| (p.range, p.division_mode, p.color));
+
+    let mut clock = app.use_clock();
+    let ticks = clock.get_ticker();
+    let die = app.use_die();
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let stepped_out = app.make_out_jack(0, range).await;
+    let slewed_out = app.make_out_jack(1, range).await;
+
+    let resolution = resolution_for_mode(division_mode);
+    let rest_value = if range.is_bipolar() { 2047 } else { 0 };
+
+    let sh = app.make_global(SampleAndHold::new(rest_value));
+    let div_glob = app.make_global(6_u32);
+    let period_glob = app.make_global(1000_u32);
+    let glob_muted = app.make_global(false);
+    let glob_latch_layer = app.make_global(LatchLayer::Main);
+
+    let set_rate = |value: u16| {
+        div_glob.set(value_to_resolution(value, resolution));
+        period_glob.set(free_period_millis(value));
+    };
+
+    let update_button_leds = |clocked: bool, muted: bool| {
+        let clocked_brightness = if clocked {
+            Brightness::High
+        } else {
+            Brightness::Low
+        };
+        leds.set(0, Led::Button, led_color, clocked_brightness);
+        if muted {
+            leds.unset(1, Led::Button);
+        } else {
+            leds.set(1, Led::Button, led_color, LED_BRIGHTNESS);
+        }
+    };
+
+    let (rate, clocked, mute) = storage.query(|s| (s.rate_saved, s.clocked, s.mute_saved));
+    set_rate(rate);
+    glob_muted.set(mute);
+    update_button_leds(clocked, mute);
+
+    let take_sample = || {
+        if !glob_muted.get() {
+            let value = die.roll();
+            sh.modify(|s| {
+                let mut s = *s;
+                s.sample(value);
+                s
+            });
+        }
+    };
+
+    let clock_fut = async {
+        let mut tick_origin = ticks() as u32;
+        loop {
+            match clock.wait_for_event(ClockDivision::_1).await {
+                ClockEvent::Reset => {
+                    tick_origin = ticks() as u32;
+                }
+                ClockEvent::Tick => {
+                    let clkn = (ticks() as u32).wrapping_sub(tick_origin);
+                    let div = div_glob.get();
+                    if storage.query(|s| s.clocked) && clkn.is_multiple_of(div) {
+                        take_sample();
+                        if glob_latch_layer.get() == LatchLayer::Alt {
+                            if matches!(div, 2 | 4 | 8 | 16) {
+                                leds.set(0, Led::Bottom, Color::Orange, Brightness::High);
+                            } else {
+                                leds.set(0, Led::Bottom, Color::Blue, Brightness::High);
+                            }
+                        }
+                    }
+                    if clkn % div == (div / 2).clamp(1, div - 1)
+                        && glob_latch_layer.get() == LatchLayer::Alt
+                    {
+                        leds.unset(0, Led::Bottom);
+                    }
+                }
+                _ => {}
+            }
+        }
+    };
+
+    let button_fut = async {
+        loop {
+            let (chan, _) = buttons.wait_for_any_down().await;
+            let clocked = if chan == 0 {
+                storage.modify_and_save(|s| {
+                    s.clocked = !s.clocked;
+                    s.clocked
+                })
+            } else {
+                let muted = glob_muted.toggle();
+                storage.modify_and_save(|s| s.mute_saved = muted);
+                if muted {
+                    sh.set(SampleAndHold::new(rest_value));
+                }
+                storage.query(|s| s.clocked)
+            };
+            update_button_leds(clocked, glob_muted.get());
+        }
+    };
+
+    let fader_fut = async {
+        let mut latch = [
+            app.make_latch(faders.get_value_at(0)),
+            app.make_latch(faders.get_value_at(1)),
+        ];
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            let latch_layer = glob_latch_layer.get();
+            let target_value = match chan {
+                0 => storage.query(|s| s.rate_saved),
+                _ => storage.query(|s| s.slew_saved),
+            };
+
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), latch_layer, target_value)
+            {
+                if chan == 0 {
+                    set_rate(new_value);
+                    storage.modify_and_save(|s| s.rate_saved = new_value);
+                } else {
+                    storage.modify_and_save(|s| s.slew_saved = new_value);
+                }
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    let (rate, clocked, mute) =
+                        storage.query(|s| (s.rate_saved, s.clocked, s.mute_saved));
+                    set_rate(rate);
+                    glob_muted.set(mute);
+                    if mute {
+                        sh.set(SampleAndHold::new(rest_value));
+                    }
+                    update_button_leds(clocked, mute);
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    let timed_loop = async {
+        let mut count: u32 = 0;
+        loop {
+            app.delay_millis(1).await;
+            glob_latch_layer.set(LatchLayer::from(buttons.is_shift_pressed()));
+
+            let (clocked, slew) = storage.query(|s| (s.clocked, s.slew_saved));
+            if !clocked {
+                count += 1;
+                if count >= period_glob.get() {
+                    count = 0;
+                    take_sample();
+                }
+            }
+
+            let state = sh.modify(|s| {
+                let mut s = *s;
+                s.update(slew);
+                s
+            });
+            let held = state.held();
+            let smooth = state.output();
+
+            stepped_out.set_value(held);
+            slewed_out.set_value(smooth);
+
+            for (chan, value) in [held, smooth].into_iter().enumerate() {
+                if chan == 0 && glob_latch_layer.get() == LatchLayer::Alt {
+                    continue;
+                }
+                if range.is_bipolar() {
+                    leds.set_meter(chan, value, led_color);
+                } else {
+                    leds.set(
+                        chan,
+                        Led::Top,
+                        led_color,
+                        Brightness::Custom((value / 16) as u8),
+                    );
+                }
+            }
+        }
+    };
+
+    join5(clock_fut, button_fut, fader_fut, scene_handler, timed_loop).await;
+}
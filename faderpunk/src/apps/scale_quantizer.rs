@@ -0,0 +1,273 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    quantizer::{scale_override, SCALE_OVERRIDE_NAMES},
+    utils::transpose_cv,
+    AppIcon, Brightness, Color, Config, Note, Param, Range, Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
+
+pub const CHANNELS: usize = 3;
+pub const PARAMS: usize = 4;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Scale Quantizer",
+    "Quantizer with its own scale and a trigger on note change",
+    Color::Blue,
+    AppIcon::Quantize,
+)
+.add_param(Param::Enum {
+    name: "Scale",
+    variants: &SCALE_OVERRIDE_NAMES,
+})
+.add_param(Param::Note {
+    name: "Tonic",
+    variants: &[
+        Note::C,
+        Note::CSharp,
+        Note::D,
+        Note::DSharp,
+        Note::E,
+        Note::F,
+        Note::FSharp,
+        Note::G,
+        Note::GSharp,
+        Note::A,
+        Note::ASharp,
+        Note::B,
+    ],
+})
+.add_param(Param::Range {
+    name: "Range",
+    variants: &[Range::_0_10V, Range::_Neg5_5V],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.finalize();
+
+pub struct Params {
+    scale: usize,
+    tonic: Note,
+    range: Range,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            scale: usize::from_value(values[0]),
+            tonic: Note::from_value(values[1]),
+            range: Range::from_value(values[2]),
+            color: Color::from_value(values[3]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.scale.into()).unwrap();
+        vec.push(self.tonic.into()).unwrap();
+        vec.push(self.range.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    st_saved: u16,
+    oct_saved: u16,
+    trig_saved: u16,
+    // Bypass the semitone and octave offsets, mute the trigger
+    toggles: [bool; 3],
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            st_saved: 0,
+            oct_saved: 2048,
+            trig_saved: 400,
+            toggles: [false; 3],
+        }
+    }
+}
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            scale: 0,
+            tonic: Note::C,
+            range: Range::_Neg5_5V,
+            color: Color::Blue,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (scale, tonic, range, led_color) = params.query(|p| (p.scale, p.tonic, p.range, p.color));
+
+    let quantizer = match scale_override(scale) {
+        Some(key) => app.use_scale_quantizer(range, key, tonic),
+        None => app.use_quantizer(range),
+    };
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let input = app.make_in_jack(0, range).await;
+    let output = app.make_out_jack(1, range).await;
+    let trigger = app.make_gate_jack(2, 4095).await;
+
+    let update_button_leds = |toggles: [bool; 3]| {
+        for (chan, &off) in toggles.iter().enumerate() {
+            if off {
+                leds.unset(chan, Led::Button);
+            } else {
+                leds.set(chan, Led::Button, led_color, LED_BRIGHTNESS);
+            }
+        }
+    };
+    update_button_leds(storage.query(|s| s.toggles));
+
+    let main_loop = async {
+        let mut last_pitch = None;
+        let mut trig_remaining = 0u16;
+        loop {
+            app.delay_millis(1).await;
+
+            let (st, oct, trig_len, toggles) =
+                storage.query(|s| (s.st_saved, s.oct_saved, s.trig_saved, s.toggles));
+            let st = if toggles[0] { 0 } else { st };
+            let oct = if toggles[1] { 2048 } else { oct };
+
+            let pitch = quantizer
+                .get_quantized_note(transpose_cv(input.get_value(), oct, st))
+                .await;
+            let out = pitch.as_counts(range);
+            output.set_value(out);
+
+            // Fire the trigger whenever the quantized note changes
+            if last_pitch.is_some_and(|last| last != pitch) && !toggles[2] {
+                trigger.set_high().await;
+                leds.set(2, Led::Top, led_color, Brightness::High);
+                trig_remaining = trig_len / 16 + 1;
+            } else if trig_remaining > 0 {
+                trig_remaining -= 1;
+                if trig_remaining == 0 {
+                    trigger.set_low().await;
+                    leds.unset(2, Led::Top);
+                }
+            }
+            last_pitch = Some(pitch);
+
+            leds.set_meter(1, out, led_color);
+            leds.set(0, Led::Top, led_color, Brightness::Custom((st / 16) as u8));
+        }
+    };
+
+    let button_handler = async {
+        loop {
+            let (chan, _) = buttons.wait_for_any_down().await;
+            let toggles = storage.modify_and_save(|s| {
+                s.toggles[chan] = !s.toggles[chan];
+                s.toggles
+            });
+            if chan == 2 && toggles[2] {
+                trigger.set_low().await;
+                leds.unset(2, Led::Top);
+            }
+            update_button_leds(toggles);
+        }
+    };
+
+    let fader_handler = async {
+        let mut latch = [
+            app.make_latch(faders.get_value_at(0)),
+            app.make_latch(faders.get_value_at(1)),
+            app.make_latch(faders.get_value_at(2)),
+        ];
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            let target_value = storage.query(|s| match chan {
+                0 => s.st_saved,
+                1 => s.oct_saved,
+                _ => s.trig_saved,
+            });
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), LatchLayer::Main, target_value)
+            {
+                storage.modify_and_save(|s| match chan {
+                    0 => s.st_saved = new_value,
+                    1 => s.oct_saved = new_value,
+                    _ => s.trig_saved = new_value,
+                });
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    update_button_leds(storage.query(|s| s.toggles));
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    join4(main_loop, button_handler, fader_handler, scene_handler).await;
+}
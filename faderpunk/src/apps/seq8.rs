@@ -1,23 +1,36 @@
 use embassy_futures::{
-    join::{join3, join5},
+    join::{join4, join5},
     select::{select, select3},
 };
 use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
 use heapless::Vec;
-use serde::{Deserialize, Serialize};
 
 use libfp::{
-    ext::FromValue, latch::LatchLayer, AppIcon, Brightness, ClockDivision, Color, Config,
-    MidiChannel, MidiNote, MidiOut, Param, Range, Value, APP_MAX_PARAMS,
+    direction::Direction,
+    ext::FromValue,
+    latch::LatchLayer,
+    quantizer::QuantizeMode,
+    seq8::{StepFlags, Storage},
+    utils::{
+        accent_gate_level, clear_page, cv_to_transpose, randomize_page, seq_step_cv, step_gate,
+        step_velocity, toggle_accent, StepGate, StopMode, TrackClock,
+    },
+    AppIcon, Brightness, ClockDivision, Color, Config, MidiChannel, MidiNote, MidiOut, Param,
+    Range, Value, APP_MAX_PARAMS,
 };
 
 use crate::app::{
-    App, AppParams, AppStorage, Arr, ClockEvent, Global, Led, ManagedStorage, ParamStore,
-    SceneEvent,
+    App, AppParams, AppStorage, ClockEvent, GateJack, Global, Led, ManagedStorage, OutJack,
+    ParamStore, SceneEvent,
 };
 
 pub const CHANNELS: usize = 8;
-pub const PARAMS: usize = 5;
+pub const PARAMS: usize = 15;
+/// Params stored by firmware before the transpose jack: the four MIDI channels and the MIDI out
+const LEGACY_PARAMS: usize = 5;
+
+/// How long the gate drops between two retriggered steps
+const RETRIGGER_GAP_MS: u64 = 2;
 
 pub static CONFIG: Config<PARAMS> = Config::new(
     "Sequencer",
@@ -37,18 +50,94 @@ pub static CONFIG: Config<PARAMS> = Config::new(
 .add_param(Param::MidiChannel {
     name: "MIDI Channel 4",
 })
-.add_param(Param::MidiOut);
+.add_param(Param::i32 {
+    name: "Transpose Jack",
+    min: 0,
+    max: 16,
+    step: 1,
+})
+.add_param(Param::Range {
+    name: "Transpose Range",
+    variants: &[Range::_0_10V, Range::_Neg5_5V],
+})
+.add_param(Param::bool {
+    name: "Retrigger 1",
+})
+.add_param(Param::bool {
+    name: "Retrigger 2",
+})
+.add_param(Param::bool {
+    name: "Retrigger 3",
+})
+.add_param(Param::bool {
+    name: "Retrigger 4",
+})
+.add_param(Param::bool {
+    name: "Hold On Stop",
+})
+.add_param(Param::bool {
+    name: "Accent Gates",
+})
+.add_param(Param::bool { name: "Free Phase" })
+.add_param(Param::Enum {
+    name: "Quantize",
+    variants: &["Nearest", "Up", "Down"],
+})
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     midi_channel1: MidiChannel,
     midi_channel2: MidiChannel,
     midi_channel3: MidiChannel,
     midi_channel4: MidiChannel,
+    transpose_jack: i32,
+    transpose_range: Range,
+    retrigger1: bool,
+    retrigger2: bool,
+    retrigger3: bool,
+    retrigger4: bool,
+    hold_on_stop: bool,
+    accent_gates: bool,
+    free_phase: bool,
+    quantize_mode: usize,
     midi_out: MidiOut,
 }
 
+impl Default for Params {
+    fn default() -> Self {
+        Self {
+            midi_channel1: MidiChannel::from(1),
+            midi_channel2: MidiChannel::from(2),
+            midi_channel3: MidiChannel::from(3),
+            midi_channel4: MidiChannel::from(4),
+            transpose_jack: 0,
+            transpose_range: Range::_0_10V,
+            retrigger1: false,
+            retrigger2: false,
+            retrigger3: false,
+            retrigger4: false,
+            hold_on_stop: false,
+            accent_gates: false,
+            free_phase: false,
+            quantize_mode: 0,
+            midi_out: MidiOut::default(),
+        }
+    }
+}
+
 impl AppParams for Params {
     fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() == LEGACY_PARAMS {
+            return Some(Self {
+                midi_channel1: MidiChannel::from_value(values[0]),
+                midi_channel2: MidiChannel::from_value(values[1]),
+                midi_channel3: MidiChannel::from_value(values[2]),
+                midi_channel4: MidiChannel::from_value(values[3]),
+                midi_out: MidiOut::from_value(values[4]),
+                ..Self::default()
+            });
+        }
         if values.len() < PARAMS {
             return None;
         }
@@ -57,7 +146,17 @@ impl AppParams for Params {
             midi_channel2: MidiChannel::from_value(values[1]),
             midi_channel3: MidiChannel::from_value(values[2]),
             midi_channel4: MidiChannel::from_value(values[3]),
-            midi_out: MidiOut::from_value(values[4]),
+            transpose_jack: i32::from_value(values[4]),
+            transpose_range: Range::from_value(values[5]),
+            retrigger1: bool::from_value(values[6]),
+            retrigger2: bool::from_value(values[7]),
+            retrigger3: bool::from_value(values[8]),
+            retrigger4: bool::from_value(values[9]),
+            hold_on_stop: bool::from_value(values[10]),
+            accent_gates: bool::from_value(values[11]),
+            free_phase: bool::from_value(values[12]),
+            quantize_mode: usize::from_value(values[13]),
+            midi_out: MidiOut::from_value(values[14]),
         })
     }
 
@@ -67,51 +166,30 @@ impl AppParams for Params {
         vec.push(self.midi_channel2.into()).unwrap();
         vec.push(self.midi_channel3.into()).unwrap();
         vec.push(self.midi_channel4.into()).unwrap();
+        vec.push(self.transpose_jack.into()).unwrap();
+        vec.push(self.transpose_range.into()).unwrap();
+        vec.push(self.retrigger1.into()).unwrap();
+        vec.push(self.retrigger2.into()).unwrap();
+        vec.push(self.retrigger3.into()).unwrap();
+        vec.push(self.retrigger4.into()).unwrap();
+        vec.push(self.hold_on_stop.into()).unwrap();
+        vec.push(self.accent_gates.into()).unwrap();
+        vec.push(self.free_phase.into()).unwrap();
+        vec.push(self.quantize_mode.into()).unwrap();
         vec.push(self.midi_out.into()).unwrap();
         vec
     }
 }
 
-#[derive(Serialize, Deserialize)]
-pub struct Storage {
-    seq: Arr<u16, 64>,
-    gateseq: Arr<bool, 64>,
-    legato_seq: Arr<bool, 64>,
-    // Alt layer - fader-scale values (0-4095)
-    length_fader: [u16; 4], // F0: derive seq_length = val/256+1
-    gate_fader: [u16; 4],   // F1: derive gate_length
-    oct_fader: [u16; 4],    // F2: derive oct = val/1000
-    range_fader: [u16; 4],  // F3: derive range = val/1000+1
-    res_fader: [u16; 4],    // F4: derive res_index = val/512
-}
-
-impl Default for Storage {
-    fn default() -> Self {
-        Self {
-            seq: Arr::new([0; 64]),
-            gateseq: Arr::new([true; 64]),
-            legato_seq: Arr::new([false; 64]),
-            // Default fader values - positioned to produce sensible defaults
-            length_fader: [3840; 4], // -> length 16 (3840/256+1 = 16)
-            gate_fader: [2032; 4],   // -> gate_length 127 (127*16 = 2032)
-            oct_fader: [0; 4],       // -> oct 0
-            range_fader: [2000; 4],  // -> range 3 (2000/1000+1 = 3)
-            res_fader: [2048; 4],    // -> res_index 4 (2048/512 = 4)
-        }
+impl AppStorage for Storage {
+    fn decode_legacy(data: &[u8]) -> Option<Self> {
+        Storage::decode_legacy(data)
     }
 }
 
-impl AppStorage for Storage {}
-
 #[embassy_executor::task(pool_size = 16/CHANNELS)]
 pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
-    let param_store = ParamStore::<Params>::new(app.app_id, app.layout_id, Params {
-        midi_channel1: MidiChannel::from(1),
-        midi_channel2: MidiChannel::from(2),
-        midi_channel3: MidiChannel::from(3),
-        midi_channel4: MidiChannel::from(4),
-        midi_out: MidiOut::default(),
-    });
+    let param_store = ParamStore::<Params>::new(app.app_id, app.layout_id, Params::default());
     let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
 
     param_store.load().await;
@@ -137,21 +215,30 @@ pub async fn run(
     storage: &ManagedStorage<Storage>,
 ) {
     let range = Range::_0_10V;
-    let (midi_out, midi_chan1, midi_chan2, midi_chan3, midi_chan4) = params.query(|p| {
-        (
-            p.midi_out,
-            p.midi_channel1,
-            p.midi_channel2,
-            p.midi_channel3,
-            p.midi_channel4,
-        )
-    });
+    let (midi_out, midi_chan1, midi_chan2, midi_chan3, midi_chan4, transpose_jack, transpose_range) =
+        params.query(|p| {
+            (
+                p.midi_out,
+                p.midi_channel1,
+                p.midi_channel2,
+                p.midi_channel3,
+                p.midi_channel4,
+                p.transpose_jack.clamp(0, 16) as usize,
+                p.transpose_range,
+            )
+        });
+    let retrigger = params.query(|p| [p.retrigger1, p.retrigger2, p.retrigger3, p.retrigger4]);
+    let stop_mode = params.query(|p| StopMode::from_hold(p.hold_on_stop));
+    let accent_gates = params.query(|p| p.accent_gates);
+    let free_phase = params.query(|p| p.free_phase);
+    let quantize_mode = params.query(|p| QuantizeMode::from_index(p.quantize_mode));
 
     let buttons = app.use_buttons();
     let faders = app.use_faders();
     let mut clk = app.use_clock();
     let ticks = clk.get_ticker();
     let led = app.use_leds();
+    let die = app.use_die();
 
     let midi = [
         app.use_midi_output(midi_out, midi_chan1, false),
@@ -167,13 +254,18 @@ pub async fn run(
         app.make_out_jack(6, Range::_0_10V).await,
     ];
     let gate_out = [
-        app.make_gate_jack(1, 4095).await,
-        app.make_gate_jack(3, 4095).await,
-        app.make_gate_jack(5, 4095).await,
-        app.make_gate_jack(7, 4095).await,
+        GateOut::new(app, 1, accent_gates).await,
+        GateOut::new(app, 3, accent_gates).await,
+        GateOut::new(app, 5, accent_gates).await,
+        GateOut::new(app, 7, accent_gates).await,
     ];
 
     let quantizer = app.use_quantizer(range);
+    quantizer.set_mode(quantize_mode);
+    // Transpose jack 0 is off, 1-16 reads the global channel
+    let transpose_in = transpose_jack
+        .checked_sub(1)
+        .map(|channel| app.use_global_in_jack(channel, transpose_range));
 
     let page_glob: Global<usize> = app.make_global(0);
     let led_flag_glob: Global<bool> = app.make_global(true);
@@ -181,6 +273,12 @@ pub async fn run(
     let seq_glob: Global<[u16; 64]> = app.make_global([0; 64]);
     let gateseq_glob: Global<[bool; 64]> = app.make_global([true; 64]);
     let legatoseq_glob: Global<[bool; 64]> = app.make_global([false; 64]);
+    let gate_prob_glob: Global<[u8; 64]> = app.make_global([255; 64]);
+    let accentseq_glob: Global<[bool; 64]> = app.make_global([false; 64]);
+    // Step whose probability Shift + fader 6 sets, the last one pressed
+    let selected_step_glob: Global<usize> = app.make_global(0);
+    // Step currently playing on each track
+    let step_glob: Global<[usize; 4]> = app.make_global([0; 4]);
 
     let seq_length_glob: Global<[u8; 4]> = app.make_global([16; 4]);
     let gatelength_glob: Global<[u8; 4]> = app.make_global([128; 4]);
@@ -200,6 +298,8 @@ pub async fn run(
         seq_saved,
         gateseq_saved,
         legato_seq_saved,
+        gate_prob_saved,
+        accent_seq_saved,
         length_faders,
         gate_faders,
         _oct_faders,
@@ -210,6 +310,8 @@ pub async fn run(
             s.seq,
             s.gateseq,
             s.legato_seq,
+            s.gate_prob,
+            s.accent_seq,
             s.length_fader,
             s.gate_fader,
             s.oct_fader,
@@ -219,8 +321,10 @@ pub async fn run(
     });
 
     seq_glob.set(seq_saved.get());
-    gateseq_glob.set(gateseq_saved.get());
-    legatoseq_glob.set(legato_seq_saved.get());
+    gateseq_glob.set(gateseq_saved.to_steps());
+    legatoseq_glob.set(legato_seq_saved.to_steps());
+    gate_prob_glob.set(gate_prob_saved.get());
+    accentseq_glob.set(accent_seq_saved.to_steps());
 
     // Derive runtime parameters from fader values
     let mut seq_length_saved = [0u8; 4];
@@ -252,7 +356,7 @@ pub async fn run(
         loop {
             let chan = faders.wait_for_any_change().await;
             let page = page_glob.get();
-            let seq_idx = page / 2;
+            let selected_step = selected_step_glob.get();
             let latch_layer = latch_layer_glob.get();
 
             // Determine target value based on layer and fader
@@ -261,7 +365,7 @@ pub async fn run(
                     let seq = seq_glob.get();
                     seq[chan + (page * 8)]
                 }
-                LatchLayer::Alt => get_alt_target(chan, seq_idx, storage),
+                LatchLayer::Alt => get_alt_target(chan, page, selected_step, storage),
                 LatchLayer::Third => 0,
             };
 
@@ -283,14 +387,16 @@ pub async fn run(
                     LatchLayer::Alt => {
                         apply_alt_update(
                             chan,
-                            seq_idx,
+                            page,
                             new_value,
                             &AltUpdateContext {
                                 storage,
                                 seq_length_glob: &seq_length_glob,
                                 gatelength_glob: &gatelength_glob,
                                 clockres_glob: &clockres_glob,
+                                gate_prob_glob: &gate_prob_glob,
                                 resolution: &resolution,
+                                selected_step,
                             },
                         );
                     }
@@ -310,6 +416,7 @@ pub async fn run(
             // let mut gateseq = gateseq_glob.get_array();
             let page = page_glob.get();
             if !is_shift_pressed {
+                selected_step_glob.set(chan + (page * 8));
                 gateseq[chan + (page * 8)] = !gateseq[chan + (page * 8)];
                 gateseq_glob.set(gateseq);
 
@@ -317,8 +424,8 @@ pub async fn run(
                 legatoseq_glob.set(legato_seq);
 
                 storage.modify_and_save(|s| {
-                    s.gateseq.set(gateseq);
-                    s.legato_seq.set(legato_seq);
+                    s.gateseq = StepFlags::from_steps(&gateseq);
+                    s.legato_seq = StepFlags::from_steps(&legato_seq);
                 });
 
                 // gateseq_glob.set_array(gateseq);
@@ -345,11 +452,60 @@ pub async fn run(
                 gateseq[chan + (page * 8)] = true;
                 gateseq_glob.set(gateseq);
 
-                storage.modify_and_save(|s| s.gateseq.set(gateseq));
-                storage.modify_and_save(|s| s.legato_seq.set(legato_seq));
+                storage.modify_and_save(|s| s.gateseq = StepFlags::from_steps(&gateseq));
+                storage.modify_and_save(|s| s.legato_seq = StepFlags::from_steps(&legato_seq));
 
                 // gateseq_glob.set_array(gateseq);
                 // gateseq_glob.save();
+            } else {
+                // Shift + long press randomizes the page
+                let mut seq = seq_glob.get();
+                let mut gateseq = gateseq_glob.get();
+                randomize_page(&mut seq, &mut gateseq, chan, || die.roll());
+                seq_glob.set(seq);
+                gateseq_glob.set(gateseq);
+                storage.modify_and_save(|s| {
+                    s.seq.set(seq);
+                    s.gateseq = StepFlags::from_steps(&gateseq);
+                });
+                led_flag_glob.set(true);
+            }
+        }
+    };
+
+    let button_double_press_handler = async {
+        loop {
+            let (chan, is_shift_pressed) = buttons.wait_for_any_double_press().await;
+
+            // Shift + double press clears the page
+            if is_shift_pressed {
+                let mut seq = seq_glob.get();
+                let mut gateseq = gateseq_glob.get();
+                let mut accent_seq = accentseq_glob.get();
+                clear_page(&mut seq, &mut gateseq, chan);
+                accent_seq[chan * 8..chan * 8 + 8].fill(false);
+                seq_glob.set(seq);
+                gateseq_glob.set(gateseq);
+                accentseq_glob.set(accent_seq);
+                storage.modify_and_save(|s| {
+                    s.seq.set(seq);
+                    s.gateseq = StepFlags::from_steps(&gateseq);
+                    s.accent_seq = StepFlags::from_steps(&accent_seq);
+                });
+                led_flag_glob.set(true);
+            } else {
+                // Double press toggles the accent of the step
+                let page = page_glob.get();
+                let mut accent_seq = accentseq_glob.get();
+                let mut gateseq = gateseq_glob.get();
+                toggle_accent(&mut accent_seq, &mut gateseq, chan + (page * 8));
+                accentseq_glob.set(accent_seq);
+                gateseq_glob.set(gateseq);
+                storage.modify_and_save(|s| {
+                    s.accent_seq = StepFlags::from_steps(&accent_seq);
+                    s.gateseq = StepFlags::from_steps(&gateseq);
+                });
+                led_flag_glob.set(true);
             }
         }
     };
@@ -366,6 +522,7 @@ pub async fn run(
             app.delay_millis(16).await;
             let clockres = clockres_glob.get();
             let clockn = ticks() as usize;
+            let steps = step_glob.get();
 
             if buttons.is_shift_pressed() {
                 let seq_length = seq_length_glob.get();
@@ -384,7 +541,7 @@ pub async fn run(
                     if n < seq_length[page / 2] {
                         bright = Brightness::Mid;
                     }
-                    if n == (clockn / clockres[page / 2]) as u8 % seq_length[page / 2] {
+                    if n as usize == steps[page / 2] {
                         bright = Brightness::High;
                     }
                     if n >= seq_length[page / 2] {
@@ -428,6 +585,7 @@ pub async fn run(
                 }
 
                 let legato_seq = legatoseq_glob.get();
+                let accent_seq = accentseq_glob.get();
 
                 for n in 0..=7 {
                     led.set(
@@ -446,6 +604,9 @@ pub async fn run(
                     if legato_seq[n + (page * 8)] {
                         led.set(n, Led::Button, color, intensities[2]);
                     }
+                    if gateseq[n + (page * 8)] && accent_seq[n + (page * 8)] {
+                        led.set(n, Led::Button, Color::Orange, intensities[2]);
+                    }
 
                     let index = seq_length[page / 2] as usize - (page % 2 * 8);
 
@@ -453,9 +614,7 @@ pub async fn run(
                         led.unset(n, Led::Button);
                     }
 
-                    if (clockn / clockres[n / 2] % seq_length[n / 2] as usize) % 16 - (n % 2) * 8
-                        < 8
-                    {
+                    if steps[n / 2] % 16 - (n % 2) * 8 < 8 {
                         //this needs changing
                         led.set(n, Led::Bottom, Color::Red, Brightness::Mid)
                     } else {
@@ -463,14 +622,9 @@ pub async fn run(
                     }
                 }
                 //runing light on buttons
-                if ((clockn / clockres[page / 2]) % seq_length[page / 2] as usize) % 16
-                    - (page % 2) * 8
-                    < 8
-                    && clockn != 0
-                {
+                if steps[page / 2] % 16 - (page % 2) * 8 < 8 && clockn != 0 {
                     led.set(
-                        (clockn / clockres[page / 2] % seq_length[page / 2] as usize) % 16
-                            - (page % 2) * 8,
+                        steps[page / 2] % 16 - (page % 2) * 8,
                         Led::Button,
                         Color::Red,
                         Brightness::Mid,
@@ -485,11 +639,18 @@ pub async fn run(
     };
 
     let clock_handler = async {
+        let mut steps = [0usize; 4];
+        // Whether a legato step still holds the gate of each track
+        let mut gate_held = [false; 4];
+        // In free phase mode the tracks keep their phase through resets and drift apart
+        let mut track_clock = [TrackClock::new(free_phase); 4];
         loop {
             let gateseq = gateseq_glob.get();
             let seq_length = seq_length_glob.get();
             let clockres = clockres_glob.get();
             let legato_seq = legatoseq_glob.get();
+            let gate_prob = gate_prob_glob.get();
+            let accent_seq = accentseq_glob.get();
 
             match clk.wait_for_event(ClockDivision::_1).await {
                 ClockEvent::Reset => {
@@ -497,40 +658,66 @@ pub async fn run(
                         midi[n].send_note_off(lastnote[n]).await;
                         gate_out[n].set_low().await;
                     }
+                    gate_held = [false; 4];
+                    track_clock.iter_mut().for_each(TrackClock::reset);
                 }
                 ClockEvent::Stop => {
+                    // Held gates and notes are released by the reset before the next start
                     for n in 0..4 {
-                        midi[n].send_note_off(lastnote[n]).await;
-                        gate_out[n].set_low().await;
+                        if !stop_mode.keeps_gate(gate_held[n]) {
+                            midi[n].send_note_off(lastnote[n]).await;
+                            gate_out[n].set_low().await;
+                            gate_held[n] = false;
+                        }
                     }
                 }
                 ClockEvent::Tick => {
-                    let clockn = ticks() as usize;
                     for n in 0..=3 {
+                        let clockn = track_clock[n].tick(ticks() as u32) as usize;
                         if clockn.is_multiple_of(clockres[n]) {
-                            let clkindex =
-                                (clockn / clockres[n] % seq_length[n] as usize) + (n * 16);
+                            let direction =
+                                Direction::from_fader(storage.query(|s| s.dir_fader[n]));
+                            steps[n] = direction.step_index(
+                                clockn / clockres[n],
+                                seq_length[n] as usize,
+                                steps[n],
+                                die.roll(),
+                            );
+                            step_glob.set(steps);
+                            let clkindex = steps[n] + (n * 16);
 
                             midi[n].send_note_off(lastnote[n]).await;
-                            if gateseq[clkindex] {
+                            let plays = gateseq[clkindex] && die.roll_bool(gate_prob[clkindex]);
+                            if step_gate(gate_held[n], plays, retrigger[n]) == StepGate::Retrigger {
+                                gate_out[n].set_low().await;
+                                app.delay_millis(RETRIGGER_GAP_MS).await;
+                            }
+                            gate_held[n] = plays;
+                            if plays {
                                 let seq = seq_glob.get();
+                                let (range_fader, oct_fader) =
+                                    storage.query(|s| (s.range_fader[n], s.oct_fader[n]));
+                                let transpose = transpose_in.as_ref().map_or(0, |jack| {
+                                    cv_to_transpose(jack.get_value(), transpose_range.is_bipolar())
+                                });
 
                                 let out = quantizer
-                                    .get_quantized_note(
-                                        (seq[clkindex] as u32
-                                            * ((storage.query(|s| s.range_fader[n]) / 1000 + 1)
-                                                as u32)
-                                            * 410
-                                            / 4095) as u16
-                                            + (storage.query(|s| s.oct_fader[n]) / 1000) * 410,
-                                    )
+                                    .get_quantized_note(seq_step_cv(
+                                        seq[clkindex],
+                                        range_fader / 1000 + 1,
+                                        oct_fader / 1000,
+                                        transpose,
+                                    ))
                                     .await;
                                 lastnote[n] = out.as_midi();
 
-                                midi[n].send_note_on(lastnote[n], 4095).await;
+                                let accent = accent_seq[clkindex];
+                                midi[n]
+                                    .send_note_on(lastnote[n], step_velocity(accent))
+                                    .await;
                                 gatelength1 = gatelength_glob.get();
                                 cv_out[n].set_value(out.as_counts(range));
-                                gate_out[n].set_high().await;
+                                gate_out[n].set_high(accent).await;
                             } else {
                                 gate_out[n].set_low().await;
                             }
@@ -538,11 +725,11 @@ pub async fn run(
                         if clockn >= gatelength1[n] as usize
                             && (clockn - gatelength1[n] as usize).is_multiple_of(clockres[n])
                         {
-                            let clkindex =
-                                (((clockn - 1) / clockres[n]) % seq_length[n] as usize) + (n * 16);
+                            let clkindex = steps[n] + (n * 16);
                             if gateseq[clkindex] && !legato_seq[clkindex] {
                                 gate_out[n].set_low().await;
                                 midi[n].send_note_off(lastnote[n]).await;
+                                gate_held[n] = false;
                             }
                         }
                     }
@@ -562,6 +749,8 @@ pub async fn run(
                         seq_saved,
                         gateseq_saved,
                         legato_seq_saved,
+                        gate_prob_saved,
+                        accent_seq_saved,
                         length_faders,
                         gate_faders,
                         res_faders,
@@ -570,6 +759,8 @@ pub async fn run(
                             s.seq,
                             s.gateseq,
                             s.legato_seq,
+                            s.gate_prob,
+                            s.accent_seq,
                             s.length_fader,
                             s.gate_fader,
                             s.res_fader,
@@ -577,8 +768,10 @@ pub async fn run(
                     });
 
                     seq_glob.set(seq_saved.get());
-                    gateseq_glob.set(gateseq_saved.get());
-                    legatoseq_glob.set(legato_seq_saved.get());
+                    gateseq_glob.set(gateseq_saved.to_steps());
+                    legatoseq_glob.set(legato_seq_saved.to_steps());
+                    gate_prob_glob.set(gate_prob_saved.get());
+                    accentseq_glob.set(accent_seq_saved.to_steps());
 
                     // Derive runtime parameters from fader values
                     let mut seq_length_saved = [0u8; 4];
@@ -601,7 +794,7 @@ pub async fn run(
         }
     };
 
-    join3(
+    join4(
         join5(
             shift_handler,
             fader_handler,
@@ -610,19 +803,58 @@ pub async fn run(
             clock_handler,
         ),
         button_long_press_handler,
+        button_double_press_handler,
         scene_handler,
     )
     .await;
 }
 
-fn get_alt_target(chan: usize, seq_idx: usize, storage: &ManagedStorage<Storage>) -> u16 {
+/// Gate output of a track, optionally as a CV gate whose level shows the step's accent
+enum GateOut {
+    Gate(GateJack),
+    Accent(OutJack),
+}
+
+impl GateOut {
+    async fn new(app: &App<CHANNELS>, chan: usize, accent: bool) -> Self {
+        if accent {
+            Self::Accent(app.make_out_jack(chan, Range::_0_10V).await)
+        } else {
+            Self::Gate(app.make_gate_jack(chan, 4095).await)
+        }
+    }
+
+    async fn set_high(&self, accent: bool) {
+        match self {
+            Self::Gate(jack) => jack.set_high().await,
+            Self::Accent(jack) => jack.set_value(accent_gate_level(accent)),
+        }
+    }
+
+    async fn set_low(&self) {
+        match self {
+            Self::Gate(jack) => jack.set_low().await,
+            Self::Accent(jack) => jack.set_value(0),
+        }
+    }
+}
+
+fn get_alt_target(
+    chan: usize,
+    page: usize,
+    selected_step: usize,
+    storage: &ManagedStorage<Storage>,
+) -> u16 {
+    let seq_idx = page / 2;
     match chan {
         0 => storage.query(|s| s.length_fader[seq_idx]),
         1 => storage.query(|s| s.gate_fader[seq_idx]),
         2 => storage.query(|s| s.oct_fader[seq_idx]),
         3 => storage.query(|s| s.range_fader[seq_idx]),
         4 => storage.query(|s| s.res_fader[seq_idx]),
-        _ => 0, // F5-F7 have no alt function
+        5 => (storage.query(|s| s.gate_prob.at(selected_step)) as u32 * 4095 / 255) as u16,
+        6 => storage.query(|s| s.dir_fader[seq_idx]),
+        _ => 0, // F7 has no alt function
     }
 }
 
@@ -631,10 +863,13 @@ struct AltUpdateContext<'a> {
     seq_length_glob: &'a Global<[u8; 4]>,
     gatelength_glob: &'a Global<[u8; 4]>,
     clockres_glob: &'a Global<[usize; 4]>,
+    gate_prob_glob: &'a Global<[u8; 64]>,
     resolution: &'a [usize; 8],
+    selected_step: usize,
 }
 
-fn apply_alt_update(chan: usize, seq_idx: usize, value: u16, ctx: &AltUpdateContext) {
+fn apply_alt_update(chan: usize, page: usize, value: u16, ctx: &AltUpdateContext) {
+    let seq_idx = page / 2;
     match chan {
         0 => {
             // Sequence length
@@ -675,9 +910,22 @@ fn apply_alt_update(chan: usize, seq_idx: usize, value: u16, ctx: &AltUpdateCont
             // Update gate length to clamp within new resolution
             let clockres = ctx.clockres_glob.get();
             let mut gatel = ctx.gatelength_glob.get();
-            gatel[seq_idx] = gatel[seq_idx].clamp(1, clockres[seq_idx] as u8);
+            gatel[seq_idx] = gatel[seq_idx].clamp(1, clockres[seq_idx] as u8 - 1);
             ctx.gatelength_glob.set(gatel);
         }
+        5 => {
+            // Probability of the selected step
+            let prob = (value as u32 * 255 / 4095) as u8;
+            let mut arr = ctx.gate_prob_glob.get();
+            arr[ctx.selected_step] = prob;
+            ctx.gate_prob_glob.set(arr);
+            ctx.storage.modify_and_save(|s| s.gate_prob.set(arr));
+        }
+        6 => {
+            // Playback direction
+            ctx.storage
+                .modify_and_save(|s| s.dir_fader[seq_idx] = value);
+        }
         _ => {}
     }
 }
@@ -9,7 +9,7 @@ use serde::{Deserialize, Serialize};
 use libfp::{
     ext::FromValue,
     latch::LatchLayer,
-    utils::{attenuverter, slew_limiter, split_signed_value, split_unsigned_value},
+    utils::{attenuverter, slew_limiter, split_signed_value},
     AppIcon, Brightness, Color, Config, Param, Range, Value, APP_MAX_PARAMS,
 };
 
@@ -38,7 +38,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
         Color::Violet,
         Color::Yellow,
     ],
-});
+})
+.finalize();
 
 pub struct Params {
     color: Color,
@@ -147,22 +148,16 @@ pub async fn run(
             output.set_value(outval);
 
             if latch_active_layer == LatchLayer::Main {
-                let slew_led = split_unsigned_value(oldval as u16);
-                leds.set(0, Led::Top, led_color, Brightness::Custom(slew_led[0]));
-                leds.set(0, Led::Bottom, led_color, Brightness::Custom(slew_led[1]));
+                leds.set_meter(0, oldval as u16, led_color);
 
-                let out_led = split_unsigned_value(outval);
-                leds.set(1, Led::Top, led_color, Brightness::Custom(out_led[0]));
-                leds.set(1, Led::Bottom, led_color, Brightness::Custom(out_led[1]));
+                leds.set_meter(1, outval, led_color);
                 leds.set(0, Led::Button, led_color, BUTTON_BRIGHTNESS);
                 leds.set(1, Led::Button, led_color, BUTTON_BRIGHTNESS);
             } else {
                 let off_led = split_signed_value(offset);
                 leds.set(0, Led::Top, Color::Red, Brightness::Custom(off_led[0]));
                 leds.set(0, Led::Bottom, Color::Red, Brightness::Custom(off_led[1]));
-                let att_led = split_unsigned_value(att);
-                leds.set(1, Led::Top, Color::Red, Brightness::Custom(att_led[0]));
-                leds.set(1, Led::Bottom, Color::Red, Brightness::Custom(att_led[1]));
+                leds.set_meter(1, att, Color::Red);
                 if storage.query(|s| s.offset_saved) == 2047 {
                     leds.unset(0, Led::Button);
                 } else {
@@ -0,0 +1,258 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    lfo::{lfo_free_speed, LFO_CYCLE},
+    soft_random::SoftRandom,
+    utils::{attenuate, attenuate_bipolar, midi_value_changed},
+    AppIcon, Brightness, Color, Config, MidiCc, MidiChannel, MidiOut, Param, Range, Value,
+    APP_MAX_PARAMS,
+};
+
+use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
+
+pub const CHANNELS: usize = 1;
+pub const PARAMS: usize = 5;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Soft Random",
+    "Smoothly gliding random CV",
+    Color::Green,
+    AppIcon::SoftRandom,
+)
+.add_param(Param::Range {
+    name: "Range",
+    variants: &[Range::_0_10V, Range::_Neg5_5V],
+})
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::MidiCc { name: "MIDI CC" })
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.add_param(Param::MidiOut)
+.finalize();
+
+pub struct Params {
+    range: Range,
+    midi_channel: MidiChannel,
+    midi_cc: MidiCc,
+    color: Color,
+    midi_out: MidiOut,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            range: Range::from_value(values[0]),
+            midi_channel: MidiChannel::from_value(values[1]),
+            midi_cc: MidiCc::from_value(values[2]),
+            color: Color::from_value(values[3]),
+            midi_out: MidiOut::from_value(values[4]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.range.into()).unwrap();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.midi_cc.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec.push(self.midi_out.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    rate_saved: u16,
+    depth_saved: u16,
+    mute_saved: bool,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            rate_saved: 2000,
+            depth_saved: 4095,
+            mute_saved: false,
+        }
+    }
+}
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            range: Range::_Neg5_5V,
+            midi_channel: MidiChannel::default(),
+            midi_cc: MidiCc::from(32u8.saturating_add(app.start_channel as u8)),
+            color: Color::Green,
+            midi_out: MidiOut::default(),
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (range, midi_out, midi_chan, midi_cc, led_color) =
+        params.query(|p| (p.range, p.midi_out, p.midi_channel, p.midi_cc, p.color));
+
+    let die = app.use_die();
+    let fader = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+    let midi = app.use_midi_output(midi_out, midi_chan, false);
+    let output = app.make_out_jack(0, range).await;
+
+    let glob_muted = app.make_global(false);
+    let glob_latch_layer = app.make_global(LatchLayer::Main);
+
+    let update_mute_led = |muted: bool| {
+        if muted {
+            leds.unset(0, Led::Button);
+        } else {
+            leds.set(0, Led::Button, led_color, LED_BRIGHTNESS);
+        }
+    };
+
+    let mute = storage.query(|s| s.mute_saved);
+    glob_muted.set(mute);
+    update_mute_led(mute);
+
+    let fut1 = async {
+        loop {
+            buttons.wait_for_down(0).await;
+            let muted = glob_muted.toggle();
+            storage.modify_and_save(|s| s.mute_saved = muted);
+            update_mute_led(muted);
+        }
+    };
+
+    let fut2 = async {
+        let mut latch = app.make_latch(fader.get_value());
+        loop {
+            fader.wait_for_change().await;
+            let latch_layer = glob_latch_layer.get();
+            let target_value = match latch_layer {
+                LatchLayer::Main => storage.query(|s| s.rate_saved),
+                LatchLayer::Alt => storage.query(|s| s.depth_saved),
+                LatchLayer::Third => continue,
+            };
+            if let Some(new_value) = latch.update(fader.get_value(), latch_layer, target_value) {
+                storage.modify_and_save(|s| match latch_layer {
+                    LatchLayer::Main => s.rate_saved = new_value,
+                    _ => s.depth_saved = new_value,
+                });
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    let mute = storage.query(|s| s.mute_saved);
+                    glob_muted.set(mute);
+                    update_mute_led(mute);
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    let timed_loop = async {
+        let mut rnd = SoftRandom::new(2047);
+        let mut last_out = 0;
+        loop {
+            app.delay_millis(1).await;
+            let latch_layer = glob_latch_layer.set(LatchLayer::from(buttons.is_shift_pressed()));
+
+            let (rate, depth) = storage.query(|s| (s.rate_saved, s.depth_saved));
+            let value = rnd.tick(lfo_free_speed(rate) / LFO_CYCLE, || die.roll());
+
+            let out = if glob_muted.get() {
+                if range.is_bipolar() {
+                    2047
+                } else {
+                    0
+                }
+            } else if range.is_bipolar() {
+                attenuate_bipolar(value, depth)
+            } else {
+                attenuate(value, depth)
+            };
+            output.set_value(out);
+
+            if midi_value_changed(last_out, out) {
+                midi.send_cc(midi_cc, out).await;
+            }
+            last_out = out;
+
+            if latch_layer == LatchLayer::Alt {
+                leds.set(
+                    0,
+                    Led::Top,
+                    Color::Red,
+                    Brightness::Custom((depth / 16) as u8),
+                );
+                leds.unset(0, Led::Bottom);
+            } else if range.is_bipolar() {
+                leds.set_meter(0, out, led_color);
+            } else {
+                leds.set(0, Led::Top, led_color, Brightness::Custom((out / 16) as u8));
+            }
+        }
+    };
+
+    join4(fut1, fut2, scene_handler, timed_loop).await;
+}
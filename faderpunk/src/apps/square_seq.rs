@@ -0,0 +1,418 @@
+use embassy_futures::{
+    join::join5,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    edge::Edge,
+    ext::FromValue,
+    latch::LatchLayer,
+    square_seq::{
+        square_gate_ticks, square_step_index, Playhead, Step, SQUARE_PAGE_STEPS, SQUARE_STEPS,
+    },
+    stutter::{Stutter, STUTTER_TICKS},
+    utils::seq_step_cv,
+    AppIcon, Brightness, ClockDivision, Color, Config, MidiChannel, MidiNote, MidiOut, Param,
+    Range, Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{
+    App, AppParams, AppStorage, ClockEvent, Led, ManagedStorage, ParamStore, SceneEvent,
+};
+
+pub const CHANNELS: usize = 4;
+pub const PARAMS: usize = 7;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+// Clock ticks per step for each resolution
+const RESOLUTION: [u32; 6] = [24, 12, 8, 6, 4, 3];
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Square Seq",
+    "8 step sequencer with velocity and accent",
+    Color::Orange,
+    AppIcon::SequenceSquare,
+)
+.add_param(Param::i32 {
+    name: "Length",
+    min: 1,
+    max: 8,
+    step: 1,
+})
+.add_param(Param::Enum {
+    name: "Resolution",
+    variants: &["1/4", "1/8", "1/8T", "1/16", "1/16T", "1/32"],
+})
+.add_param(Param::i32 {
+    name: "GATE %",
+    min: 1,
+    max: 100,
+    step: 1,
+})
+.add_param(Param::i32 {
+    name: "Octave",
+    min: 0,
+    max: 8,
+    step: 1,
+})
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.add_param(Param::MidiOut)
+.finalize();
+
+pub struct Params {
+    length: i32,
+    resolution: usize,
+    gatel: i32,
+    octave: i32,
+    midi_channel: MidiChannel,
+    color: Color,
+    midi_out: MidiOut,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            length: i32::from_value(values[0]),
+            resolution: usize::from_value(values[1]),
+            gatel: i32::from_value(values[2]),
+            octave: i32::from_value(values[3]),
+            midi_channel: MidiChannel::from_value(values[4]),
+            color: Color::from_value(values[5]),
+            midi_out: MidiOut::from_value(values[6]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.length.into()).unwrap();
+        vec.push(self.resolution.into()).unwrap();
+        vec.push(self.gatel.into()).unwrap();
+        vec.push(self.octave.into()).unwrap();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec.push(self.midi_out.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Storage {
+    steps: [Step; SQUARE_STEPS],
+    // Where editing was left off
+    page: u8,
+    layer: LatchLayer,
+}
+
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            length: 8,
+            resolution: 3,
+            gatel: 50,
+            octave: 3,
+            midi_channel: MidiChannel::default(),
+            color: Color::Orange,
+            midi_out: MidiOut::default(),
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let range = Range::_0_10V;
+    let (length, resolution, gatel, octave, midi_out, midi_chan, led_color) = params.query(|p| {
+        (
+            p.length.clamp(1, SQUARE_STEPS as i32) as usize,
+            p.resolution.min(RESOLUTION.len() - 1),
+            p.gatel.clamp(1, 100) as u32,
+            p.octave.clamp(0, 8) as u16,
+            p.midi_out,
+            p.midi_channel,
+            p.color,
+        )
+    });
+    let division = RESOLUTION[resolution];
+    let gate_ticks = square_gate_ticks(division, gatel);
+
+    let mut clock = app.use_clock();
+    let ticks = clock.get_ticker();
+    let quantizer = app.use_quantizer(range);
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let midi = app.use_midi_output(midi_out, midi_chan, false);
+
+    let cv_out = app.make_out_jack(0, range).await;
+    let gate_out = app.make_gate_jack(1, 4095).await;
+    let velocity_out = app.make_out_jack(2, range).await;
+    let accent_out = app.make_gate_jack(3, 4095).await;
+
+    let playing_glob = app.make_global(None::<usize>);
+
+    let clock_handler = async {
+        let mut playhead = Playhead::default();
+        let mut stutter = Stutter::default();
+        let mut tick_origin = ticks() as u32;
+        let mut note_on: Option<MidiNote> = None;
+        // Note and step of the last gate, repeated by the stutter
+        let mut last_played: Option<(MidiNote, Step)> = None;
+
+        loop {
+            match clock.wait_for_event(ClockDivision::_1).await {
+                ClockEvent::Reset | ClockEvent::Stop => {
+                    tick_origin = ticks() as u32;
+                    playhead.reset();
+                    playing_glob.set(None);
+                    if let Some(note) = note_on.take() {
+                        midi.send_note_off(note).await;
+                    }
+                    gate_out.set_low().await;
+                    accent_out.set_low().await;
+                }
+                ClockEvent::Tick => {
+                    let clkn = (ticks() as u32).wrapping_sub(tick_origin);
+
+                    // Holding shift + button 3 stutters the last gate
+                    let engaged = buttons.is_shift_pressed() && buttons.is_button_pressed(2);
+                    if stutter.set_engaged(engaged) == Some(Edge::Falling) {
+                        if let Some(note) = note_on.take() {
+                            midi.send_note_off(note).await;
+                        }
+                        gate_out.set_low().await;
+                        accent_out.set_low().await;
+                    }
+                    match stutter.tick(STUTTER_TICKS) {
+                        Some(Edge::Rising) => {
+                            if let Some((note, step)) = last_played {
+                                if let Some(note) = note_on.take() {
+                                    midi.send_note_off(note).await;
+                                }
+                                midi.send_note_on(note, step.velocity).await;
+                                note_on = Some(note);
+                                gate_out.set_high().await;
+                                if step.is_accent() {
+                                    accent_out.set_high().await;
+                                }
+                            }
+                        }
+                        Some(Edge::Falling) => {
+                            if let Some(note) = note_on.take() {
+                                midi.send_note_off(note).await;
+                            }
+                            gate_out.set_low().await;
+                            accent_out.set_low().await;
+                        }
+                        None => {}
+                    }
+
+                    if clkn.is_multiple_of(division) {
+                        let pos = playhead.advance(length);
+                        playing_glob.set(Some(pos));
+                        let step = storage.query(|s| s.steps[pos]);
+                        // The sequence keeps running under the stutter, but stays silent
+                        if step.gate && !stutter.is_engaged() {
+                            let pitch = quantizer
+                                .get_quantized_note(seq_step_cv(step.note, 2, octave, 0))
+                                .await;
+                            cv_out.set_value(pitch.as_counts(range));
+                            velocity_out.set_value(step.velocity);
+                            if let Some(note) = note_on.take() {
+                                midi.send_note_off(note).await;
+                            }
+                            midi.send_note_on(pitch.as_midi(), step.velocity).await;
+                            note_on = Some(pitch.as_midi());
+                            last_played = Some((pitch.as_midi(), step));
+                            gate_out.set_high().await;
+                            if step.is_accent() {
+                                accent_out.set_high().await;
+                            }
+                        }
+                    }
+
+                    if clkn % division == gate_ticks && !stutter.is_engaged() {
+                        if let Some(note) = note_on.take() {
+                            midi.send_note_off(note).await;
+                        }
+                        gate_out.set_low().await;
+                        accent_out.set_low().await;
+                    }
+                }
+                _ => {}
+            }
+        }
+    };
+
+    let button_handler = async {
+        loop {
+            let (chan, is_shift_pressed) = buttons.wait_for_any_down().await;
+            if is_shift_pressed {
+                if chan < SQUARE_STEPS / SQUARE_PAGE_STEPS {
+                    // Shift + button 1 or 2 selects the page
+                    storage.modify_and_save(|s| s.page = chan as u8);
+                } else if chan == CHANNELS - 1 {
+                    // Shift + button 4 keeps editing the velocities
+                    storage.modify_and_save(|s| {
+                        s.layer = match s.layer {
+                            LatchLayer::Main => LatchLayer::Alt,
+                            _ => LatchLayer::Main,
+                        }
+                    });
+                }
+            } else {
+                storage.modify_and_save(|s| {
+                    let idx = square_step_index(s.page as usize, chan);
+                    s.steps[idx].gate = !s.steps[idx].gate;
+                });
+            }
+        }
+    };
+
+    let fader_handler = async {
+        let mut latch: [_; CHANNELS] =
+            core::array::from_fn(|chan| app.make_latch(faders.get_value_at(chan)));
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            let (page, stored_layer) = storage.query(|s| (s.page as usize, s.layer));
+            let latch_layer = LatchLayer::from_stored(stored_layer, buttons.is_shift_pressed());
+            let idx = square_step_index(page, chan);
+            let target_value = storage.query(|s| match latch_layer {
+                LatchLayer::Main => s.steps[idx].note,
+                _ => s.steps[idx].velocity,
+            });
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), latch_layer, target_value)
+            {
+                storage.modify_and_save(|s| match latch_layer {
+                    LatchLayer::Main => s.steps[idx].note = new_value,
+                    _ => s.steps[idx].velocity = new_value,
+                });
+            }
+        }
+    };
+
+    let led_handler = async {
+        loop {
+            app.delay_millis(16).await;
+            let playing = playing_glob.get();
+            let shift = buttons.is_shift_pressed();
+            let (steps, page, stored_layer) =
+                storage.query(|s| (s.steps, s.page as usize, s.layer));
+            let latch_layer = LatchLayer::from_stored(stored_layer, shift);
+
+            for chan in 0..CHANNELS {
+                let idx = square_step_index(page, chan);
+                let step = steps[idx];
+                if latch_layer == LatchLayer::Main {
+                    leds.set(
+                        chan,
+                        Led::Top,
+                        led_color,
+                        Brightness::Custom((step.note / 16) as u8),
+                    );
+                } else {
+                    leds.set(
+                        chan,
+                        Led::Top,
+                        Color::Red,
+                        Brightness::Custom((step.velocity / 16) as u8),
+                    );
+                }
+                if shift {
+                    // Button 1 and 2 show the pages, button 4 the kept velocity layer
+                    if chan < SQUARE_STEPS / SQUARE_PAGE_STEPS {
+                        let bright = if chan == page {
+                            Brightness::High
+                        } else {
+                            Brightness::Low
+                        };
+                        leds.set(chan, Led::Button, Color::White, bright);
+                    } else if chan == CHANNELS - 1 && stored_layer == LatchLayer::Alt {
+                        leds.set(chan, Led::Button, Color::Red, Brightness::High);
+                    } else {
+                        leds.unset(chan, Led::Button);
+                    }
+                } else if idx >= length {
+                    leds.unset(chan, Led::Button);
+                } else if step.gate {
+                    leds.set(chan, Led::Button, led_color, LED_BRIGHTNESS);
+                } else {
+                    leds.set(chan, Led::Button, led_color, Brightness::Low);
+                }
+                if playing == Some(idx) {
+                    leds.set(chan, Led::Bottom, Color::Red, Brightness::Mid);
+                } else {
+                    leds.unset(chan, Led::Bottom);
+                }
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    join5(
+        clock_handler,
+        button_handler,
+        fader_handler,
+        led_handler,
+        scene_handler,
+    )
+    .await;
+}
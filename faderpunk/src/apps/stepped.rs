@@ -0,0 +1,264 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    utils::{detent_value, snap_detent},
+    AppIcon, Brightness, Color, Config, MidiCc, MidiChannel, MidiOut, Param, Range, Value,
+    APP_MAX_PARAMS,
+};
+
+use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
+
+pub const CHANNELS: usize = 1;
+pub const PARAMS: usize = 6;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+// How long the button LED flashes when the fader snaps to a new detent
+const FLASH_MS: u16 = 40;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Stepped",
+    "Fader snapping to evenly spaced voltages",
+    Color::Cyan,
+    AppIcon::KnobRound,
+)
+.add_param(Param::i32 {
+    name: "Steps",
+    min: 2,
+    max: 16,
+    step: 1,
+})
+.add_param(Param::Range {
+    name: "Range",
+    variants: &[Range::_0_10V, Range::_Neg5_5V],
+})
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::MidiCc { name: "MIDI CC" })
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.add_param(Param::MidiOut)
+.finalize();
+
+pub struct Params {
+    steps: i32,
+    range: Range,
+    midi_channel: MidiChannel,
+    midi_cc: MidiCc,
+    color: Color,
+    midi_out: MidiOut,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            steps: i32::from_value(values[0]),
+            range: Range::from_value(values[1]),
+            midi_channel: MidiChannel::from_value(values[2]),
+            midi_cc: MidiCc::from_value(values[3]),
+            color: Color::from_value(values[4]),
+            midi_out: MidiOut::from_value(values[5]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.steps.into()).unwrap();
+        vec.push(self.range.into()).unwrap();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.midi_cc.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec.push(self.midi_out.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Storage {
+    detent_saved: u8,
+    mute_saved: bool,
+}
+
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            steps: 5,
+            range: Range::_0_10V,
+            midi_channel: MidiChannel::default(),
+            midi_cc: MidiCc::from(32u8.saturating_add(app.start_channel as u8)),
+            color: Color::Cyan,
+            midi_out: MidiOut::default(),
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (steps, range, midi_out, midi_chan, midi_cc, led_color) = params.query(|p| {
+        (
+            p.steps.clamp(2, 16) as usize,
+            p.range,
+            p.midi_out,
+            p.midi_channel,
+            p.midi_cc,
+            p.color,
+        )
+    });
+
+    let fader = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+    let midi = app.use_midi_output(midi_out, midi_chan, false);
+    let output = app.make_out_jack(0, range).await;
+
+    let glob_muted = app.make_global(false);
+    let glob_flash = app.make_global(0_u16);
+
+    let update_mute_led = |muted: bool| {
+        if muted {
+            leds.unset(0, Led::Button);
+        } else {
+            leds.set(0, Led::Button, led_color, LED_BRIGHTNESS);
+        }
+    };
+
+    let mute = storage.query(|s| s.mute_saved);
+    glob_muted.set(mute);
+    update_mute_led(mute);
+
+    let button_handler = async {
+        loop {
+            buttons.wait_for_down(0).await;
+            let muted = glob_muted.toggle();
+            storage.modify_and_save(|s| s.mute_saved = muted);
+            update_mute_led(muted);
+        }
+    };
+
+    let fader_handler = async {
+        let mut latch = app.make_latch(fader.get_value());
+        loop {
+            fader.wait_for_change().await;
+            let current = storage.query(|s| s.detent_saved as usize);
+            // The middle of the current detent is where the fader picks it up
+            let target_value = ((2 * current + 1) * 4096 / (2 * steps)).min(4095) as u16;
+            if let Some(new_value) = latch.update(fader.get_value(), LatchLayer::Main, target_value)
+            {
+                let detent = snap_detent(new_value, steps, current);
+                if detent != current {
+                    storage.modify_and_save(|s| s.detent_saved = detent as u8);
+                    glob_flash.set(FLASH_MS);
+                }
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    let mute = storage.query(|s| s.mute_saved);
+                    glob_muted.set(mute);
+                    update_mute_led(mute);
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    let main_loop = async {
+        let mut last_detent = None;
+        loop {
+            app.delay_millis(1).await;
+
+            let detent = storage.query(|s| s.detent_saved as usize).min(steps - 1);
+            let muted = glob_muted.get();
+            let target = if muted {
+                if range.is_bipolar() {
+                    2047
+                } else {
+                    0
+                }
+            } else {
+                detent_value(detent, steps)
+            };
+            let out = output.set_value_smoothed(target);
+
+            if !muted && last_detent != Some(detent) {
+                midi.send_cc(midi_cc, target).await;
+                last_detent = Some(detent);
+            } else if muted {
+                last_detent = None;
+            }
+
+            if range.is_bipolar() {
+                leds.set_meter(0, out, led_color);
+            } else {
+                leds.set(0, Led::Top, led_color, Brightness::Custom((out / 16) as u8));
+            }
+
+            // Flash the button on every new detent, like the click of a knob
+            let flash = glob_flash.get();
+            if flash > 0 {
+                let flash = glob_flash.set(flash - 1);
+                if flash > 0 {
+                    leds.set(0, Led::Button, Color::White, Brightness::High);
+                } else {
+                    update_mute_led(muted);
+                }
+            }
+        }
+    };
+
+    join4(button_handler, fader_handler, scene_handler, main_loop).await;
+}
@@ -0,0 +1,247 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    stereo::{stereo_offset, HaasDelay, STEREO_MAX_DELAY},
+    AppIcon, Brightness, Color, Config, Param, Range, Value, APP_MAX_PARAMS, GLOBAL_CHANNELS,
+};
+
+use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
+
+pub const CHANNELS: usize = 2;
+pub const PARAMS: usize = 3;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Stereo Spread",
+    "Widens the output of another channel into a stereo pair",
+    Color::Cyan,
+    AppIcon::Stereo,
+)
+.add_param(Param::i32 {
+    name: "Source channel",
+    min: 1,
+    max: GLOBAL_CHANNELS as i32,
+    step: 1,
+})
+.add_param(Param::Range {
+    name: "Range",
+    variants: &[Range::_0_10V, Range::_Neg5_5V],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.finalize();
+
+pub struct Params {
+    source: i32,
+    range: Range,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            source: i32::from_value(values[0]),
+            range: Range::from_value(values[1]),
+            color: Color::from_value(values[2]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.source.into()).unwrap();
+        vec.push(self.range.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    width_saved: u16,
+    delay_saved: u16,
+    // Swap the sides, collapse to mono
+    toggles: [bool; 2],
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            width_saved: 2048,
+            delay_saved: 0,
+            toggles: [false; 2],
+        }
+    }
+}
+
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            // Follow the channel right before this app
+            source: app.start_channel.max(1) as i32,
+            range: Range::_0_10V,
+            color: Color::Cyan,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (source, range, led_color) = params.query(|p| {
+        (
+            p.source.clamp(1, GLOBAL_CHANNELS as i32) as usize - 1,
+            p.range,
+            p.color,
+        )
+    });
+
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let input = app.use_global_out_jack(source);
+    let left_out = app.make_out_jack(0, range).await;
+    let right_out = app.make_out_jack(1, range).await;
+
+    let update_button_leds = |toggles: [bool; 2]| {
+        for (chan, &on) in toggles.iter().enumerate() {
+            if on {
+                leds.set(chan, Led::Button, led_color, LED_BRIGHTNESS);
+            } else {
+                leds.unset(chan, Led::Button);
+            }
+        }
+    };
+    update_button_leds(storage.query(|s| s.toggles));
+
+    let main_loop = async {
+        let mut delay = HaasDelay::new(input.get_value());
+        loop {
+            app.delay_millis(1).await;
+
+            let (width, delay_time, toggles) =
+                storage.query(|s| (s.width_saved, s.delay_saved, s.toggles));
+            let width = if toggles[1] { 0 } else { width };
+            let samples = delay_time as usize * (STEREO_MAX_DELAY - 1) / 4095;
+
+            let (early, late) = stereo_offset(input.get_value(), width);
+            let late = delay.tick(late, samples);
+            let (left, right) = if toggles[0] {
+                (late, early)
+            } else {
+                (early, late)
+            };
+            left_out.set_value(left);
+            right_out.set_value(right);
+
+            for (chan, out) in [left, right].into_iter().enumerate() {
+                if range.is_bipolar() {
+                    leds.set_meter(chan, out, led_color);
+                } else {
+                    leds.set(
+                        chan,
+                        Led::Top,
+                        led_color,
+                        Brightness::Custom((out / 16) as u8),
+                    );
+                }
+            }
+        }
+    };
+
+    let button_handler = async {
+        loop {
+            let (chan, _) = buttons.wait_for_any_down().await;
+            let toggles = storage.modify_and_save(|s| {
+                s.toggles[chan] = !s.toggles[chan];
+                s.toggles
+            });
+            update_button_leds(toggles);
+        }
+    };
+
+    let fader_handler = async {
+        let mut latch = [
+            app.make_latch(faders.get_value_at(0)),
+            app.make_latch(faders.get_value_at(1)),
+        ];
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            let target_value = storage.query(|s| match chan {
+                0 => s.width_saved,
+                _ => s.delay_saved,
+            });
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), LatchLayer::Main, target_value)
+            {
+                storage.modify_and_save(|s| match chan {
+                    0 => s.width_saved = new_value,
+                    _ => s.delay_saved = new_value,
+                });
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    update_button_leds(storage.query(|s| s.toggles));
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    join4(main_loop, button_handler, fader_handler, scene_handler).await;
+}
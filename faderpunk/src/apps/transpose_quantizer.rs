@@ -0,0 +1,232 @@
+use embassy_futures::{
+    join::join4,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue, latch::LatchLayer, quantizer::TransposeMode, utils::cv_to_transpose, AppIcon,
+    Brightness, Color, Config, Param, Range, Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{App, AppParams, AppStorage, Led, ManagedStorage, ParamStore, SceneEvent};
+
+pub const CHANNELS: usize = 3;
+pub const PARAMS: usize = 4;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Transpose Quantizer",
+    "Quantizer with a CV input for transposition",
+    Color::Blue,
+    AppIcon::Quantize,
+)
+.add_param(Param::Enum {
+    name: "Transpose",
+    variants: &["Before", "After", "Scale Degrees"],
+})
+.add_param(Param::Range {
+    name: "Range",
+    variants: &[Range::_0_10V, Range::_Neg5_5V],
+})
+.add_param(Param::Range {
+    name: "Transpose Range",
+    variants: &[Range::_0_10V, Range::_Neg5_5V],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.finalize();
+
+pub struct Params {
+    mode: usize,
+    range: Range,
+    transpose_range: Range,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            mode: usize::from_value(values[0]),
+            range: Range::from_value(values[1]),
+            transpose_range: Range::from_value(values[2]),
+            color: Color::from_value(values[3]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.mode.into()).unwrap();
+        vec.push(self.range.into()).unwrap();
+        vec.push(self.transpose_range.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+pub struct Storage {
+    // Steps added to the transpose CV, up to an octave or seven scale degrees
+    offset_saved: u16,
+    bypass: bool,
+}
+
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            mode: 0,
+            range: Range::_Neg5_5V,
+            transpose_range: Range::_Neg5_5V,
+            color: Color::Blue,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (mode, range, transpose_range, led_color) = params.query(|p| {
+        (
+            TransposeMode::from_index(p.mode),
+            p.range,
+            p.transpose_range,
+            p.color,
+        )
+    });
+    // A fader offset of a full octave, in the unit of the mode
+    let max_offset = if mode == TransposeMode::Degrees {
+        7
+    } else {
+        12
+    };
+
+    let quantizer = app.use_quantizer(range);
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let input = app.make_in_jack(0, range).await;
+    let transpose = app.make_in_jack(1, transpose_range).await;
+    let output = app.make_out_jack(2, range).await;
+
+    let update_button_led = |bypass: bool| {
+        if bypass {
+            leds.unset(1, Led::Button);
+        } else {
+            leds.set(1, Led::Button, led_color, LED_BRIGHTNESS);
+        }
+    };
+    update_button_led(storage.query(|s| s.bypass));
+
+    let main_loop = async {
+        loop {
+            app.delay_millis(1).await;
+
+            let (offset, bypass) = storage.query(|s| (s.offset_saved, s.bypass));
+            let transpose_value = transpose.get_value();
+            let steps = if bypass {
+                0
+            } else {
+                cv_to_transpose(transpose_value, transpose_range.is_bipolar())
+                    + offset as i32 * max_offset / 4095
+            };
+
+            let in_val = input.get_value();
+            let out = quantizer
+                .get_transposed_note(in_val, steps, mode)
+                .await
+                .as_counts(range);
+            output.set_value(out);
+
+            leds.set_meter(0, in_val, led_color);
+            leds.set_meter(1, transpose_value, led_color);
+            leds.set_meter(2, out, led_color);
+        }
+    };
+
+    let button_handler = async {
+        loop {
+            let (chan, _) = buttons.wait_for_any_down().await;
+            if chan == 1 {
+                let bypass = storage.modify_and_save(|s| {
+                    s.bypass = !s.bypass;
+                    s.bypass
+                });
+                update_button_led(bypass);
+            }
+        }
+    };
+
+    let fader_handler = async {
+        let mut latch = app.make_latch(faders.get_value_at(1));
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            if chan != 1 {
+                continue;
+            }
+            let target_value = storage.query(|s| s.offset_saved);
+            if let Some(new_value) =
+                latch.update(faders.get_value_at(1), LatchLayer::Main, target_value)
+            {
+                storage.modify_and_save(|s| s.offset_saved = new_value);
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    update_button_led(storage.query(|s| s.bypass));
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    join4(main_loop, button_handler, fader_handler, scene_handler).await;
+}
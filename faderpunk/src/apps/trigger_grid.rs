@@ -0,0 +1,381 @@
+use embassy_futures::{
+    join::join5,
+    select::{select, select3},
+};
+use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
+use heapless::Vec;
+use serde::{Deserialize, Serialize};
+
+use libfp::{
+    ext::FromValue,
+    latch::LatchLayer,
+    trigger_grid::{grid_page, grid_step, grid_step_at, TriggerGrid, GRID_PAGE_STEPS, GRID_STEPS},
+    utils::{random_gate_fires, resolution_for_mode, value_to_index, value_to_resolution},
+    AppIcon, Brightness, ClockDivision, Color, Config, MidiChannel, MidiNote, MidiOut, Param,
+    Value, APP_MAX_PARAMS,
+};
+
+use crate::app::{
+    App, AppParams, AppStorage, ClockEvent, Led, ManagedStorage, ParamStore, SceneEvent,
+};
+
+pub const CHANNELS: usize = 8;
+pub const PARAMS: usize = 6;
+
+const LED_BRIGHTNESS: Brightness = Brightness::Mid;
+
+pub static CONFIG: Config<PARAMS> = Config::new(
+    "Trigger Grid",
+    "8 track, 16 step trigger sequencer",
+    Color::Violet,
+    AppIcon::NoteGrid,
+)
+.add_param(Param::MidiChannel {
+    name: "MIDI Channel",
+})
+.add_param(Param::MidiNote {
+    name: "MIDI Note 1",
+})
+.add_param(Param::i32 {
+    name: "GATE %",
+    min: 1,
+    max: 100,
+    step: 1,
+})
+.add_param(Param::Enum {
+    name: "Divisions",
+    variants: &["Straight", "Triplets", "Both"],
+})
+.add_param(Param::Color {
+    name: "Color",
+    variants: &[
+        Color::Blue,
+        Color::Green,
+        Color::Rose,
+        Color::Orange,
+        Color::Cyan,
+        Color::Pink,
+        Color::Violet,
+        Color::Yellow,
+    ],
+})
+.add_param(Param::MidiOut)
+.finalize();
+
+pub struct Params {
+    midi_channel: MidiChannel,
+    midi_out: MidiOut,
+    note: MidiNote,
+    gatel: i32,
+    division_mode: usize,
+    color: Color,
+}
+
+impl AppParams for Params {
+    fn from_values(values: &[Value]) -> Option<Self> {
+        if values.len() < PARAMS {
+            return None;
+        }
+        Some(Self {
+            midi_channel: MidiChannel::from_value(values[0]),
+            note: MidiNote::from_value(values[1]),
+            gatel: i32::from_value(values[2]),
+            division_mode: usize::from_value(values[3]),
+            color: Color::from_value(values[4]),
+            midi_out: MidiOut::from_value(values[5]),
+        })
+    }
+
+    fn to_values(&self) -> Vec<Value, APP_MAX_PARAMS> {
+        let mut vec = Vec::new();
+        vec.push(self.midi_channel.into()).unwrap();
+        vec.push(self.note.into()).unwrap();
+        vec.push(self.gatel.into()).unwrap();
+        vec.push(self.division_mode.into()).unwrap();
+        vec.push(self.color.into()).unwrap();
+        vec.push(self.midi_out.into()).unwrap();
+        vec
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    grid: TriggerGrid,
+    prob_saved: [u16; 8],
+    div_saved: u16,
+    length_saved: u16,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            grid: TriggerGrid::new(),
+            prob_saved: [4095; 8],
+            div_saved: 3000,
+            length_saved: 4095,
+        }
+    }
+}
+impl AppStorage for Storage {}
+
+#[embassy_executor::task(pool_size = 16/CHANNELS)]
+pub async fn wrapper(app: App<CHANNELS>, exit_signal: &'static Signal<NoopRawMutex, bool>) {
+    let param_store = ParamStore::<Params>::new(
+        app.app_id,
+        app.layout_id,
+        Params {
+            midi_channel: MidiChannel::default(),
+            midi_out: MidiOut([false, false, false]),
+            note: MidiNote::from(36),
+            gatel: 50,
+            division_mode: 2,
+            color: Color::Violet,
+        },
+    );
+    let storage = ManagedStorage::<Storage>::new(app.app_id, app.layout_id);
+
+    param_store.load().await;
+    storage.load().await;
+
+    let app_loop = async {
+        loop {
+            select3(
+                run(&app, &param_store, &storage),
+                param_store.param_handler(),
+                storage.saver_task(),
+            )
+            .await;
+        }
+    };
+
+    select(app_loop, app.exit_handler(exit_signal)).await;
+}
+
+/// Sequence length (1 - 16 steps) for a given fader value
+fn grid_length(value: u16) -> usize {
+    value_to_index(value, GRID_STEPS) + 1
+}
+
+pub async fn run(
+    app: &App<CHANNELS>,
+    params: &ParamStore<Params>,
+    storage: &ManagedStorage<Storage>,
+) {
+    let (midi_out, midi_chan, base_note, gatel, division_mode, led_color) = params.query(|p| {
+        (
+            p.midi_out,
+            p.midi_channel,
+            p.note,
+            p.gatel as u32,
+            p.division_mode,
+            p.color,
+        )
+    });
+
+    let mut clock = app.use_clock();
+    let ticks = clock.get_ticker();
+    let die = app.use_die();
+    let faders = app.use_faders();
+    let buttons = app.use_buttons();
+    let leds = app.use_leds();
+
+    let midi = app.use_midi_output(midi_out, midi_chan, false);
+
+    let resolution = resolution_for_mode(division_mode);
+    let notes: [MidiNote; 8] = core::array::from_fn(|i| base_note + MidiNote::from(i as u8));
+
+    let div_glob = app.make_global(6_u32);
+    let length_glob = app.make_global(GRID_STEPS);
+    let track_glob = app.make_global(0_usize);
+    let page_glob = app.make_global(0_usize);
+    let playhead_glob = app.make_global(None::<usize>);
+    let glob_latch_layer = app.make_global(LatchLayer::Main);
+
+    let jacks = [
+        app.make_gate_jack(0, 4095).await,
+        app.make_gate_jack(1, 4095).await,
+        app.make_gate_jack(2, 4095).await,
+        app.make_gate_jack(3, 4095).await,
+        app.make_gate_jack(4, 4095).await,
+        app.make_gate_jack(5, 4095).await,
+        app.make_gate_jack(6, 4095).await,
+        app.make_gate_jack(7, 4095).await,
+    ];
+
+    let render_grid = || {
+        let grid = storage.query(|s| s.grid);
+        let track = track_glob.get();
+        let page = page_glob.get();
+        let playhead = playhead_glob.get();
+        let length = length_glob.get();
+
+        for button in 0..GRID_PAGE_STEPS {
+            let step = grid_step(page, button);
+            if playhead == Some(step) {
+                leds.set(button, Led::Button, Color::White, Brightness::High);
+            } else if step < length && grid.is_set(track, step) {
+                leds.set(button, Led::Button, led_color, LED_BRIGHTNESS);
+            } else {
+                leds.unset(button, Led::Button);
+            }
+        }
+        for chan in 0..CHANNELS {
+            if chan == track {
+                // Second page is shown in white
+                let color = if page == 0 { led_color } else { Color::White };
+                leds.set(chan, Led::Bottom, color, LED_BRIGHTNESS);
+            } else {
+                leds.unset(chan, Led::Bottom);
+            }
+        }
+    };
+
+    let (div, length) = storage.query(|s| (s.div_saved, s.length_saved));
+    div_glob.set(value_to_resolution(div, resolution));
+    length_glob.set(grid_length(length));
+    render_grid();
+
+    let fut1 = async {
+        let mut gate_on = [false; 8];
+        let mut cached_div = div_glob.get();
+        let mut cached_gate_step = (cached_div * gatel / 100).clamp(1, cached_div - 1);
+        let mut tick_origin = ticks() as u32;
+
+        loop {
+            match clock.wait_for_event(ClockDivision::_1).await {
+                ClockEvent::Reset | ClockEvent::Stop => {
+                    tick_origin = ticks() as u32;
+                    for (chan, on) in gate_on.iter_mut().enumerate() {
+                        if *on {
+                            midi.send_note_off(notes[chan]).await;
+                            *on = false;
+                        }
+                        jacks[chan].set_low().await;
+                        leds.unset(chan, Led::Top);
+                    }
+                    playhead_glob.set(None);
+                    render_grid();
+                }
+                ClockEvent::Tick => {
+                    let div = div_glob.get();
+                    if div != cached_div {
+                        cached_div = div;
+                        cached_gate_step = (cached_div * gatel / 100).clamp(1, cached_div - 1);
+                    }
+                    let clkn = (ticks() as u32).wrapping_sub(tick_origin);
+
+                    if let Some(step) = grid_step_at(clkn, cached_div, length_glob.get()) {
+                        let (grid, prob) = storage.query(|s| (s.grid, s.prob_saved));
+                        let triggers = grid.triggers_at(step);
+                        for (chan, on) in gate_on.iter_mut().enumerate() {
+                            if triggers & (1 << chan) != 0
+                                && random_gate_fires(prob[chan], die.roll())
+                            {
+                                jacks[chan].set_high().await;
+                                midi.send_note_on(notes[chan], 4095).await;
+                                leds.set(chan, Led::Top, led_color, Brightness::High);
+                                *on = true;
+                            }
+                        }
+                        playhead_glob.set(Some(step));
+                        render_grid();
+                    }
+
+                    if clkn % cached_div == cached_gate_step {
+                        for (chan, on) in gate_on.iter_mut().enumerate() {
+                            if *on {
+                                midi.send_note_off(notes[chan]).await;
+                                jacks[chan].set_low().await;
+                                leds.unset(chan, Led::Top);
+                                *on = false;
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    };
+
+    let fut2 = async {
+        loop {
+            let (chan, shift) = buttons.wait_for_any_down().await;
+            if shift {
+                // Shift + button selects a track, selecting it again flips the page
+                if track_glob.get() == chan {
+                    page_glob.modify(|page| (page + 1) % (GRID_STEPS / GRID_PAGE_STEPS));
+                } else {
+                    track_glob.set(chan);
+                    page_glob.set(grid_page(playhead_glob.get().unwrap_or(0)));
+                }
+            } else {
+                let step = grid_step(page_glob.get(), chan);
+                let track = track_glob.get();
+                storage.modify_and_save(|s| {
+                    s.grid.toggle(track, step);
+                });
+            }
+            render_grid();
+        }
+    };
+
+    let fut3 = async {
+        let mut latch: [_; 8] = core::array::from_fn(|i| app.make_latch(faders.get_value_at(i)));
+        loop {
+            let chan = faders.wait_for_any_change().await;
+            let latch_layer = glob_latch_layer.get();
+
+            let target_value = match (latch_layer, chan) {
+                (LatchLayer::Main, _) => storage.query(|s| s.prob_saved[chan]),
+                (LatchLayer::Alt, 0) => storage.query(|s| s.div_saved),
+                (LatchLayer::Alt, 1) => storage.query(|s| s.length_saved),
+                _ => continue,
+            };
+
+            if let Some(new_value) =
+                latch[chan].update(faders.get_value_at(chan), latch_layer, target_value)
+            {
+                match (latch_layer, chan) {
+                    (LatchLayer::Main, _) => {
+                        storage.modify_and_save(|s| s.prob_saved[chan] = new_value);
+                    }
+                    (_, 0) => {
+                        div_glob.set(value_to_resolution(new_value, resolution));
+                        storage.modify_and_save(|s| s.div_saved = new_value);
+                    }
+                    _ => {
+                        length_glob.set(grid_length(new_value));
+                        storage.modify_and_save(|s| s.length_saved = new_value);
+                        render_grid();
+                    }
+                }
+            }
+        }
+    };
+
+    let scene_handler = async {
+        loop {
+            match app.wait_for_scene_event().await {
+                SceneEvent::LoadScene(scene) => {
+                    storage.load_from_scene(scene).await;
+                    let (div, length) = storage.query(|s| (s.div_saved, s.length_saved));
+                    div_glob.set(value_to_resolution(div, resolution));
+                    length_glob.set(grid_length(length));
+                    render_grid();
+                }
+                SceneEvent::SaveScene(scene) => {
+                    storage.save_to_scene(scene).await;
+                }
+            }
+        }
+    };
+
+    let shift = async {
+        loop {
+            app.delay_millis(1).await;
+            glob_latch_layer.set(LatchLayer::from(buttons.is_shift_pressed()));
+        }
+    };
+
+    join5(fut1, fut2, fut3, scene_handler, shift).await;
+}
@@ -11,8 +11,11 @@ use heapless::Vec;
 use serde::{Deserialize, Serialize};
 
 use libfp::{
-    ext::FromValue, latch::LatchLayer, AppIcon, Brightness, ClockDivision, Color, Config, Curve,
-    MidiCc, MidiChannel, MidiMode, MidiNote, MidiOut, Param, Range, Value, APP_MAX_PARAMS,
+    ext::FromValue,
+    latch::LatchLayer,
+    turing::{turing_flips, turing_step, turing_value},
+    AppIcon, Brightness, ClockDivision, Color, Config, Curve, MidiCc, MidiChannel, MidiMode,
+    MidiNote, MidiOut, Param, Range, Value, APP_MAX_PARAMS,
 };
 
 use crate::app::{
@@ -38,6 +41,7 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     name: "GATE %",
     min: 1,
     max: 100,
+    step: 1,
 })
 .add_param(Param::Color {
     name: "Color",
@@ -57,7 +61,8 @@ pub static CONFIG: Config<PARAMS> = Config::new(
     variants: &[Range::_0_10V, Range::_0_5V, Range::_Neg5_5V],
 })
 .add_param(Param::MidiNrpn)
-.add_param(Param::MidiOut);
+.add_param(Param::MidiOut)
+.finalize();
 
 pub struct Params {
     midi_mode: MidiMode,
@@ -245,12 +250,11 @@ pub async fn run(
                             }
                         }
                         let prob = prob_glob.get();
-                        let rand = die.roll().clamp(100, 3900);
+                        let flip = turing_flips(prob, die.roll());
 
-                        let rotation = rotate_select_bit(register, prob, rand, length);
-                        register = rotation.0;
+                        register = turing_step(register, length, flip);
 
-                        let register_scalled = scale_to_12bit(register, length as u8);
+                        let register_scalled = turing_value(register, length as u8);
                         att_reg = ((register_scalled as u32
                             * curve.at(storage.query(|s| s.att_saved)) as u32)
                             / 4095) as u16;
@@ -413,37 +417,3 @@ pub async fn run(
 
     join5(fut1, fut2, fut3, fut4, scene_handler).await;
 }
-
-fn rotate_select_bit(x: u16, a: u16, b: u16, bit_index: u16) -> (u16, bool) {
-    let bit_index = (16 - bit_index).clamp(0, 16);
-
-    // Extract the original bit
-    let original_bit = ((x >> bit_index) & 1) as u8;
-    let mut bit = original_bit;
-
-    // Invert the bit if a > b
-    if a > b {
-        bit ^= 1;
-    }
-
-    // Shift x right by 1
-    let shifted = x >> 1;
-
-    // Insert the (possibly inverted) bit into the MSB
-    let result = shifted | ((bit as u16) << 15);
-
-    // Return the new value and whether the bit was flipped
-    let flipped = bit != original_bit;
-    (result, flipped)
-}
-
-fn scale_to_12bit(input: u16, x: u8) -> u16 {
-    let x = x.clamp(1, 16);
-
-    // Shift to keep the top `x` bits
-    let top_x_bits = input >> (16 - x);
-
-    // Scale to 12-bit
-    let max_x_val = (1 << x) - 1;
-    ((top_x_bits as u32 * 4095) / max_x_val as u32) as u16
-}
@@ -11,6 +11,7 @@ use static_cell::StaticCell;
 use libfp::{InnerLayout, Layout, GLOBAL_CHANNELS};
 
 use crate::apps::spawn_app_by_id;
+use crate::storage::load_layout_slot;
 
 // Receivers: layout spawn loop, configure
 const LAYOUT_WATCH_SUBSCRIBERS: usize = 2;
@@ -23,6 +24,14 @@ pub static FORCE_RESPAWN_SIGNAL: Signal<CriticalSectionRawMutex, ()> = Signal::n
 
 pub static LAYOUT_MANAGER: StaticCell<LayoutManager> = StaticCell::new();
 
+/// Swap the running layout for the one saved to `slot`. The layout manager spawns it and
+/// stores it as the active layout, like any other layout change.
+pub async fn load_layout_from_slot(slot: u8) -> Option<Layout> {
+    let layout = load_layout_slot(slot).await?;
+    LAYOUT_WATCH.sender().send(layout.clone());
+    Some(layout)
+}
+
 pub struct LayoutManager {
     exit_signals: [Signal<NoopRawMutex, bool>; GLOBAL_CHANNELS],
     layout: Mutex<NoopRawMutex, InnerLayout>,
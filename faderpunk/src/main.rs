@@ -30,6 +30,7 @@ use embassy_sync::lazy_lock::LazyLock;
 use embassy_sync::mutex::Mutex;
 use fm24v10::{Address, Fm24v10};
 use libfp::quantizer::Quantizer;
+use libfp::self_test::SelfTestMode;
 use libfp::I2cMode;
 use static_cell::StaticCell;
 use {defmt_rtt as _, panic_probe as _};
@@ -39,7 +40,7 @@ use crate::storage::{factory_reset, store_layout};
 use layout::{LayoutManager, FORCE_RESPAWN_SIGNAL, LAYOUT_MANAGER, LAYOUT_WATCH};
 use storage::{load_calibration_data, load_global_config, load_layout};
 use tasks::{
-    buttons::{is_channel_button_pressed, is_scene_button_pressed},
+    buttons::{is_channel_button_pressed, is_scene_button_pressed, is_shift_button_pressed},
     fram::MAX_DATA_LEN,
     global_config::GLOBAL_CONFIG_WATCH,
     i2c::I2C_LEADER_CHANNEL,
@@ -190,6 +191,16 @@ async fn main(spawner: Spawner) {
         return factory_reset().await;
     }
 
+    // Run the hardware self-test when the first and last buttons are pressed during startup,
+    // one channel at a time when shift is held as well
+    let self_test = (is_channel_button_pressed(0) && is_channel_button_pressed(15)).then(|| {
+        if is_shift_button_pressed() {
+            SelfTestMode::PerChannel
+        } else {
+            SelfTestMode::AllChannels
+        }
+    });
+
     // Enter calibration mode if there is no calibration data or
     // when scene is pressed during startup; otherwise preserve the saved mode
     if calibration_data.is_none() || is_scene_button_pressed() {
@@ -208,6 +219,10 @@ async fn main(spawner: Spawner) {
 
     tasks::max::start_max(&spawner, spi0, p.PIO0, mux_pins, p.PIN_17, calibration_data).await;
 
+    if let Some(mode) = self_test {
+        return tasks::self_test::run_self_test(mode).await;
+    }
+
     tasks::i2c::start_i2c(&spawner, p.I2C0, p.PIN_21, p.PIN_20).await;
 
     tasks::transport::start_transports(&spawner, usb_driver, uart0, uart1, chip_id).await;
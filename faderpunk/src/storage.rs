@@ -2,22 +2,29 @@ use core::{cell::RefCell, ops::Range};
 
 use embassy_futures::select::{select, Either};
 use embassy_sync::{blocking_mutex::raw::NoopRawMutex, signal::Signal};
-use embassy_time::Timer;
+use embassy_time::{Instant, Timer};
 use heapless::Vec;
 use postcard::{from_bytes, to_slice};
-use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
+use serde::{Deserialize, Serialize};
 
 use libfp::{
-    types::{CalibFile, MaxCalibration, MaxCalibrationV1},
-    GlobalConfig, Layout, Value, APP_MAX_PARAMS, CALIB_FILE_MAGIC,
+    coalesce::{fingerprint, SaveCoalescer},
+    from_app_bytes,
+    lerp::Lerp,
+    midi_channels_left,
+    types::{CalibFile, CalibFileV2, MaxCalibration, MaxCalibrationV1, MaxCalibrationV2},
+    GlobalConfig, GlobalConfigFile, Layout, Value, APP_MAX_PARAMS, CALIBRATION_VERSION_LATEST,
+    CALIB_FILE_MAGIC, LAYOUT_SLOTS,
 };
 
 use crate::{
+    app::AppError,
     apps::get_channels,
     state::RuntimeState,
     tasks::{
         configure::{AppParamCmd, APP_PARAM_CHANNEL, APP_PARAM_SIGNALS},
-        fram::{erase_with, read_data, write_with},
+        fram::{erase_with, read_data, write_with, FramError},
+        midi::send_all_notes_off,
     },
 };
 
@@ -25,16 +32,19 @@ const GLOBAL_CONFIG_RANGE: Range<u32> = 0..320;
 const RUNTIME_STATE_RANGE: Range<u32> = GLOBAL_CONFIG_RANGE.end..384;
 const LAYOUT_RANGE: Range<u32> = RUNTIME_STATE_RANGE.end..512;
 const CALIBRATION_RANGE: Range<u32> = LAYOUT_RANGE.end..1024;
-const APP_STORAGE_RANGE: Range<u32> = CALIBRATION_RANGE.end..122_880;
-const APP_PARAM_RANGE: Range<u32> = APP_STORAGE_RANGE.end..131_072;
+const APP_STORAGE_RANGE: Range<u32> = CALIBRATION_RANGE.end..121_856;
+const LAYOUT_SLOTS_RANGE: Range<u32> = APP_STORAGE_RANGE.end..122_880;
+const APP_PARAM_RANGE: Range<u32> = LAYOUT_SLOTS_RANGE.end..131_072;
 
 const APP_STORAGE_MAX_BYTES: u32 = 400;
 const APP_PARAMS_MAX_BYTES: u32 = 128;
 const SCENES_PER_APP: u32 = 16;
+const LAYOUT_SLOT_MAX_BYTES: u32 = 128;
 
 pub async fn store_global_config(config: &GlobalConfig) {
+    let file = GlobalConfigFile::new(config.clone());
     let res = write_with(GLOBAL_CONFIG_RANGE.start, |buf| {
-        Ok(to_slice(&config, &mut *buf)?.len())
+        Ok(to_slice(&file, &mut *buf)?.len())
     })
     .await;
 
@@ -47,8 +57,12 @@ pub async fn load_global_config() -> GlobalConfig {
     if let Ok(guard) = read_data(GLOBAL_CONFIG_RANGE.start).await {
         let data = guard.data();
         if !data.is_empty() {
-            if let Ok(mut config) = from_bytes::<GlobalConfig>(data) {
+            if let Some((mut config, converted)) = GlobalConfigFile::decode(data) {
                 config.validate();
+                if converted {
+                    defmt::info!("Old global config found, converting to new format.");
+                    store_global_config(&config).await;
+                }
                 return config;
             }
         }
@@ -111,6 +125,36 @@ pub async fn load_layout() -> Layout {
     layout
 }
 
+fn layout_slot_address(slot: u8) -> Option<u32> {
+    ((slot as usize) < LAYOUT_SLOTS)
+        .then(|| LAYOUT_SLOTS_RANGE.start + slot as u32 * LAYOUT_SLOT_MAX_BYTES)
+}
+
+pub async fn store_layout_slot(slot: u8, layout: &Layout) {
+    let Some(address) = layout_slot_address(slot) else {
+        return;
+    };
+    let res = write_with(address, |buf| Ok(to_slice(&layout, &mut *buf)?.len())).await;
+
+    if res.is_err() {
+        defmt::error!("Could not save Layout to slot {}", slot);
+    }
+}
+
+pub async fn load_layout_slot(slot: u8) -> Option<Layout> {
+    let guard = read_data(layout_slot_address(slot)?).await.ok()?;
+    Layout::from_slot_bytes(guard.data(), get_channels)
+}
+
+/// Which of the layout slots hold a layout
+pub async fn get_layout_slots() -> [bool; LAYOUT_SLOTS] {
+    let mut slots = [false; LAYOUT_SLOTS];
+    for (slot, used) in slots.iter_mut().enumerate() {
+        *used = load_layout_slot(slot as u8).await.is_some();
+    }
+    slots
+}
+
 pub async fn store_calibration_data(data: &MaxCalibration) {
     let file_to_save = CalibFile::new(*data);
 
@@ -133,18 +177,31 @@ pub async fn load_calibration_data() -> Option<MaxCalibration> {
         }
 
         if data[0..4] == CALIB_FILE_MAGIC {
-            if let Ok(file) = from_bytes::<CalibFile>(data) {
-                if file.version == 2 {
-                    return Some(file.data);
-                } else {
-                    defmt::warn!("Unsupported calibration file version: {}", file.version);
+            match data.get(4) {
+                Some(&CALIBRATION_VERSION_LATEST) => {
+                    if let Ok(file) = from_bytes::<CalibFile>(data) {
+                        return Some(file.data);
+                    }
+                }
+                Some(2) => {
+                    if let Ok(file) = from_bytes::<CalibFileV2>(data) {
+                        defmt::info!("Old V2 calibration data found, converting to new format.");
+                        let new_data = MaxCalibration::from(file.data);
+                        // Re-save the data in the new V3 format for next time
+                        store_calibration_data(&new_data).await;
+                        return Some(new_data);
+                    }
+                }
+                Some(&version) => {
+                    defmt::warn!("Unsupported calibration file version: {}", version);
                     return None;
                 }
+                None => {}
             }
         } else if let Ok(old_data) = from_bytes::<MaxCalibrationV1>(data) {
             defmt::info!("Old V1 calibration data found, converting to new format.");
-            let new_data = MaxCalibration::from(old_data);
-            // Re-save the data in the new V2 format for next time
+            let new_data = MaxCalibration::from(MaxCalibrationV2::from(old_data));
+            // Re-save the data in the new V3 format for next time
             store_calibration_data(&new_data).await;
             return Some(new_data);
         }
@@ -154,6 +211,29 @@ pub async fn load_calibration_data() -> Option<MaxCalibration> {
     None
 }
 
+/// Copy the scenes of one app instance to another. Scenes that are empty in the source are
+/// cleared in the target.
+pub async fn copy_app_scenes(from_layout_id: u8, to_layout_id: u8) {
+    for scene in 0..SCENES_PER_APP as u8 {
+        let from = AppStorageAddress::new(from_layout_id, Some(scene)).into();
+        let to: u32 = AppStorageAddress::new(to_layout_id, Some(scene)).into();
+        match read_data(from).await {
+            Ok(guard) => {
+                let data = guard.data();
+                let res = write_with(to, |buf| {
+                    buf[..data.len()].copy_from_slice(data);
+                    Ok(data.len())
+                })
+                .await;
+                if res.is_err() {
+                    defmt::error!("Could not copy scene {} to app {}", scene, to_layout_id);
+                }
+            }
+            Err(_) => erase_range(to..to + APP_STORAGE_MAX_BYTES).await,
+        }
+    }
+}
+
 async fn erase_range(range: Range<u32>) {
     // Prevent erasing the calibration range
     if range.start < CALIBRATION_RANGE.end && range.end > CALIBRATION_RANGE.start {
@@ -209,6 +289,7 @@ pub async fn factory_reset() {
     erase_range(RUNTIME_STATE_RANGE).await;
     erase_range(LAYOUT_RANGE).await;
     erase_range(APP_STORAGE_RANGE).await;
+    erase_range(LAYOUT_SLOTS_RANGE).await;
     erase_range(APP_PARAM_RANGE).await;
     // Wait a bit
     Timer::after_millis(100).await;
@@ -216,83 +297,6 @@ pub async fn factory_reset() {
     cortex_m::peripheral::SCB::sys_reset();
 }
 
-#[derive(Clone, Copy)]
-pub struct Arr<T: Sized + Copy + Default, const N: usize>([T; N]);
-
-impl<T: Sized + Copy + Default, const N: usize> Default for Arr<T, N> {
-    fn default() -> Self {
-        Self([T::default(); N])
-    }
-}
-
-impl<T: Sized + Copy + Default, const N: usize> Arr<T, N> {
-    pub fn new(initial: [T; N]) -> Self {
-        Self(initial)
-    }
-
-    #[inline(always)]
-    #[allow(dead_code)]
-    pub fn at(&self, idx: usize) -> T {
-        self.0[idx]
-    }
-
-    #[inline(always)]
-    #[allow(dead_code)]
-    pub fn set_at(&mut self, idx: usize, value: T) {
-        self.0[idx] = value;
-    }
-
-    #[inline(always)]
-    pub fn get(&self) -> [T; N] {
-        self.0
-    }
-
-    #[inline(always)]
-    pub fn set(&mut self, value: [T; N]) {
-        self.0 = value;
-    }
-}
-
-impl<T, const N: usize> Serialize for Arr<T, N>
-where
-    T: Serialize + Sized + Copy + Default,
-{
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        let vec = Vec::<T, N>::from_slice(&self.0).unwrap();
-        vec.serialize(serializer)
-    }
-}
-
-impl<'de, T, const N: usize> Deserialize<'de> for Arr<T, N>
-where
-    T: Deserialize<'de> + Sized + Copy + Default,
-{
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let vec = Vec::<T, N>::deserialize(deserializer)?;
-        if vec.len() != N {
-            return Err(D::Error::invalid_length(
-                vec.len(),
-                &"an array of exact length N",
-            ));
-        }
-        let mut arr = [T::default(); N];
-        arr.copy_from_slice(vec.as_slice()); // Safe due to length check above
-        Ok(Arr(arr))
-    }
-}
-
-impl<T: Sized + Copy + PartialEq + Default, const N: usize> PartialEq for Arr<T, N> {
-    fn eq(&self, other: &Self) -> bool {
-        self.0 == other.0
-    }
-}
-
 #[derive(Clone, Copy)]
 pub struct AppStorageAddress {
     pub layout_id: u8,
@@ -373,6 +377,8 @@ pub trait AppParams: Sized + Send + Sync + 'static {
 
 pub struct ParamStore<P: AppParams> {
     app_id: u8,
+    // The initial params, to go back to on a reset
+    defaults: Vec<Value, APP_MAX_PARAMS>,
     inner: RefCell<P>,
     layout_id: u8,
 }
@@ -381,20 +387,20 @@ impl<P: AppParams> ParamStore<P> {
     pub fn new(app_id: u8, layout_id: u8, initial: P) -> Self {
         Self {
             app_id,
+            defaults: initial.to_values(),
             inner: RefCell::new(initial),
             layout_id,
         }
     }
 
-    fn des(&self, data: &[u8]) -> Option<P> {
-        // First byte is app id
-        if data[0] != self.app_id {
-            return None;
-        }
-        if let Ok(val) = from_bytes::<Vec<Value, APP_MAX_PARAMS>>(&data[1..]) {
-            return P::from_values(&val);
+    fn des(&self, data: &[u8]) -> Result<Option<P>, AppError> {
+        match from_app_bytes::<Vec<Value, APP_MAX_PARAMS>>(self.app_id, data) {
+            Ok(Some(values)) => P::from_values(&values)
+                .map(Some)
+                .ok_or(AppError::DeserializeFailed),
+            Ok(None) => Ok(None),
+            Err(_) => Err(AppError::DeserializeFailed),
         }
-        None
     }
 
     async fn send_values(&self) {
@@ -425,13 +431,21 @@ impl<P: AppParams> ParamStore<P> {
 
     pub async fn load(&self) {
         let address = AppParamsAddress::new(self.layout_id);
-        if let Ok(guard) = read_data(address.into()).await {
-            let data = guard.data();
-            if !data.is_empty() {
-                if let Some(val) = self.des(data) {
-                    drop(guard);
-                    let mut inner = self.inner.borrow_mut();
-                    *inner = val;
+        let loaded = match read_data(address.into()).await {
+            Ok(guard) => self.des(guard.data()),
+            Err(FramError::CrcMismatch) => Err(AppError::DeserializeFailed),
+            Err(_) => Ok(None),
+        };
+        match loaded {
+            Ok(Some(val)) => *self.inner.borrow_mut() = val,
+            Ok(None) => {}
+            Err(_) => {
+                defmt::error!(
+                    "Could not load params on app {}, using defaults",
+                    self.app_id
+                );
+                if let Some(defaults) = P::from_values(&self.defaults) {
+                    *self.inner.borrow_mut() = defaults;
                 }
             }
         }
@@ -450,7 +464,8 @@ impl<P: AppParams> ParamStore<P> {
         loop {
             match APP_PARAM_SIGNALS[self.layout_id as usize].wait().await {
                 AppParamCmd::SetAppParams { values } => {
-                    let mut current_values = self.inner.borrow().to_values();
+                    let old_values = self.inner.borrow().to_values();
+                    let mut current_values = old_values.clone();
                     let mut changed = false;
 
                     for (index, &value) in values.iter().enumerate() {
@@ -471,6 +486,7 @@ impl<P: AppParams> ParamStore<P> {
                         };
 
                         if updated {
+                            silence_midi_channels_left(&old_values, &current_values).await;
                             self.save().await;
                             self.send_values().await;
                             // Re-spawn app
@@ -479,6 +495,18 @@ impl<P: AppParams> ParamStore<P> {
                     }
                     self.send_values().await;
                 }
+                AppParamCmd::ResetAppParams => {
+                    if let Some(defaults) = P::from_values(&self.defaults) {
+                        let old_values = self.inner.borrow().to_values();
+                        *self.inner.borrow_mut() = defaults;
+                        silence_midi_channels_left(&old_values, &self.defaults).await;
+                        self.save().await;
+                        self.send_values().await;
+                        // Re-spawn app
+                        break;
+                    }
+                    self.send_values().await;
+                }
                 AppParamCmd::RequestParamValues => {
                     self.send_values().await;
                 }
@@ -487,9 +515,22 @@ impl<P: AppParams> ParamStore<P> {
     }
 }
 
+/// Notes sent on a MIDI channel the app has just switched away from would hang, the re-spawned
+/// app only knows about the new one
+async fn silence_midi_channels_left(old: &[Value], new: &[Value]) {
+    for (channel, target) in midi_channels_left(old, new) {
+        send_all_notes_off(channel, target).await;
+    }
+}
+
 pub trait AppStorage:
     Serialize + for<'de> Deserialize<'de> + Default + Send + Sync + 'static
 {
+    /// Storage saved in an older layout, converted to the current one. Tried when the data
+    /// doesn't decode as the current layout.
+    fn decode_legacy(_data: &[u8]) -> Option<Self> {
+        None
+    }
 }
 
 pub struct ManagedStorage<S: AppStorage> {
@@ -497,6 +538,7 @@ pub struct ManagedStorage<S: AppStorage> {
     inner: RefCell<S>,
     layout_id: u8,
     save_signal: Signal<NoopRawMutex, ()>,
+    coalescer: RefCell<SaveCoalescer>,
 }
 
 impl<S: AppStorage> ManagedStorage<S> {
@@ -506,23 +548,47 @@ impl<S: AppStorage> ManagedStorage<S> {
             inner: RefCell::new(S::default()),
             layout_id,
             save_signal: Signal::new(),
+            coalescer: RefCell::new(SaveCoalescer::default()),
         }
     }
 
-    async fn load_inner(&self, scene: Option<u8>) {
+    async fn read_inner(&self, scene: Option<u8>) -> Result<Option<S>, AppError> {
         let address = AppStorageAddress::new(self.layout_id, scene).into();
-        if let Ok(guard) = read_data(address).await {
-            let data = guard.data();
-            if !data.is_empty() && data[0] == self.app_id {
-                if let Ok(val) = from_bytes::<S>(&data[1..]) {
-                    let mut inner = self.inner.borrow_mut();
-                    *inner = val;
+        let guard = match read_data(address).await {
+            Ok(guard) => guard,
+            // Partially written, e.g. the power went out while saving
+            Err(FramError::CrcMismatch) => return Err(AppError::DeserializeFailed),
+            Err(_) => return Ok(None),
+        };
+        match from_app_bytes(self.app_id, guard.data()) {
+            Ok(val) => Ok(val),
+            // The app id matched, but the data may be in an older layout
+            Err(_) => S::decode_legacy(&guard.data()[1..])
+                .map(Some)
+                .ok_or(AppError::DeserializeFailed),
+        }
+    }
+
+    async fn load_inner(&self, scene: Option<u8>) {
+        match self.read_inner(scene).await {
+            Ok(Some(val)) => {
+                if scene.is_none() {
+                    self.coalescer.borrow_mut().stored(fingerprint(&val));
                 }
+                *self.inner.borrow_mut() = val;
+            }
+            Ok(None) => {}
+            Err(_) => {
+                defmt::error!(
+                    "Could not load storage on app {}, using defaults",
+                    self.app_id
+                );
+                *self.inner.borrow_mut() = S::default();
             }
         }
     }
 
-    async fn save_inner(&self, scene: Option<u8>) {
+    async fn save_inner(&self, scene: Option<u8>) -> bool {
         let address = AppStorageAddress::new(self.layout_id, scene).into();
 
         let res = write_with(address, |buf| {
@@ -536,10 +602,20 @@ impl<S: AppStorage> ManagedStorage<S> {
         if res.is_err() {
             defmt::error!("Could not save ManagedStorage");
         }
+        res.is_ok()
     }
 
-    pub async fn save(&self) {
-        self.save_inner(None).await;
+    /// Write the storage right away, instead of after the changes settle. Skipped if nothing
+    /// changed since it was last stored.
+    pub async fn save_now(&self) {
+        self.coalescer.borrow_mut().cancel();
+        let stored = fingerprint(&*self.inner.borrow());
+        if self.coalescer.borrow().is_stored(stored) {
+            return;
+        }
+        if self.save_inner(None).await {
+            self.coalescer.borrow_mut().stored(stored);
+        }
     }
 
     pub async fn save_to_scene(&self, scene: u8) {
@@ -554,6 +630,39 @@ impl<S: AppStorage> ManagedStorage<S> {
         self.load_inner(Some(scene)).await;
     }
 
+    /// Load a scene and run `on_loaded` once with the loaded values, before any other task of
+    /// the app gets to run. Use it to re-derive globals and LEDs from the storage.
+    pub async fn load_from_scene_with<F, R>(&self, scene: u8, on_loaded: F) -> R
+    where
+        F: FnOnce(&S) -> R,
+    {
+        self.load_inner(Some(scene)).await;
+        self.query(on_loaded)
+    }
+
+    /// Read a stored scene without loading it. `None` if nothing was saved to it for this app,
+    /// or if it can't be read.
+    #[allow(dead_code)]
+    pub async fn read_scene(&self, scene: u8) -> Option<S> {
+        self.read_inner(Some(scene)).await.ok().flatten()
+    }
+
+    /// Blend of two stored scenes, `t` goes from `0` (`scene_a`) to `LERP_MAX` (`scene_b`).
+    /// Scenes that weren't saved use the current values. This reads both scenes from flash,
+    /// for a continuous morph read them once with `read_scene` and lerp them directly.
+    #[allow(dead_code)]
+    pub async fn blend(&self, scene_a: u8, scene_b: u8, t: u16) -> S
+    where
+        S: Lerp,
+    {
+        let a = self.read_scene(scene_a).await;
+        let b = self.read_scene(scene_b).await;
+        let current = self.inner.borrow();
+        a.as_ref()
+            .unwrap_or(&*current)
+            .lerp(b.as_ref().unwrap_or(&*current), t)
+    }
+
     #[allow(dead_code)]
     pub fn reset(&self) {
         let mut guard = self.inner.borrow_mut();
@@ -580,29 +689,41 @@ impl<S: AppStorage> ManagedStorage<S> {
     where
         F: FnOnce(&mut S) -> R,
     {
+        let before = fingerprint(&*self.inner.borrow());
         let result = self.modify(modifier);
-        self.save_signal.signal(());
+        let after = fingerprint(&*self.inner.borrow());
+        if self
+            .coalescer
+            .borrow_mut()
+            .modified(before, after, Instant::now())
+        {
+            self.save_signal.signal(());
+        }
         result
     }
 
+    /// Whether the storage differs from what was last saved or loaded
+    #[allow(dead_code)]
+    pub fn is_dirty(&self) -> bool {
+        let current = fingerprint(&*self.inner.borrow());
+        self.coalescer.borrow().is_dirty(current)
+    }
+
+    /// Writes the changes made with `modify_and_save` once they settle, see `SaveCoalescer`
     pub async fn saver_task(&self) {
         loop {
-            self.save_signal.wait().await;
-
-            loop {
-                let timer = Timer::after_millis(500);
-                match select(self.save_signal.wait(), timer).await {
-                    // Another signal arrived before the timer finished.
-                    // Loop again to restart the timer
-                    Either::First(_) => continue,
-                    // The timer finished without being interrupted.
-                    // Break the inner loop to proceed with saving
-                    Either::Second(_) => break,
-                }
+            let due = self.coalescer.borrow().due();
+            let Some(due) = due else {
+                self.save_signal.wait().await;
+                continue;
+            };
+            // Another change moves the due time, check it again
+            if let Either::First(_) = select(self.save_signal.wait(), Timer::at(due)).await {
+                continue;
+            }
+            if self.coalescer.borrow_mut().poll(Instant::now()) {
+                self.save_now().await;
             }
-
-            self.save_signal.reset();
-            self.save_inner(None).await;
         }
     }
 }
@@ -7,11 +7,11 @@ use embassy_rp::peripherals::{
     PIN_37, PIN_38, PIN_4, PIN_5, PIN_6, PIN_7,
 };
 use embassy_rp::Peri;
-use embassy_time::Timer;
+use embassy_time::{Instant, Timer};
 use portable_atomic::{AtomicBool, Ordering};
 
 use crate::events::{EventPubSubPublisher, InputEvent, EVENT_PUBSUB};
-use crate::tasks::clock::{TransportCmd, TRANSPORT_CMD_CHANNEL};
+use crate::tasks::clock::{TransportCmd, TAP_TEMPO_CHANNEL, TRANSPORT_CMD_CHANNEL};
 
 const LONG_PRESS_DURATION_MS: u64 = 500;
 
@@ -172,6 +172,9 @@ async fn process_modifier_button(
         // Start clock if shift is pressed while scene is held
         if i == 17 && BUTTON_PRESSED[16].load(Ordering::Relaxed) {
             TRANSPORT_CMD_CHANNEL.send(TransportCmd::Toggle).await;
+        } else if i == 16 && BUTTON_PRESSED[17].load(Ordering::Relaxed) {
+            // Tap tempo if scene is tapped while shift is held
+            TAP_TEMPO_CHANNEL.send(Instant::now()).await;
         } else {
             BUTTON_PRESSED[i].store(true, Ordering::Relaxed);
             event_publisher.publish(down_event.clone()).await;
@@ -6,7 +6,7 @@ use max11300::config::{ConfigMode5, ConfigMode7, Mode, Port, ADCRANGE, AVR, DACR
 use portable_atomic::Ordering;
 
 use libfp::{
-    types::{MaxCalibration, RegressionValuesInput, RegressionValuesOutput},
+    types::{MaxCalibration, OutputCalibration, OutputCalibrations, RegressionValuesInput},
     Brightness, Color, CALIBRATION_SCALE_FACTOR,
 };
 
@@ -142,8 +142,8 @@ async fn run_manual_input_calibration() -> RegressionValuesInput {
     input_results
 }
 
-async fn run_manual_output_calibration() -> RegressionValuesOutput {
-    let mut output_results = RegressionValuesOutput::default();
+async fn run_manual_output_calibration() -> OutputCalibrations {
+    let mut output_results = OutputCalibrations::default();
 
     for i in 0..CHANNELS {
         set_led_color(i, Led::Button, Color::Red);
@@ -257,17 +257,15 @@ async fn run_manual_output_calibration() -> RegressionValuesOutput {
                 info!("------------------");
             }
 
-            if let Ok(results) = linear_regression::<f32, f32, f32>(
-                &set_values.map(|v| v as f32),
-                &target_values.map(|v| v as f32),
-            ) {
-                // Convert f32 results to i64 fixed-point format
-                let slope = (results.0 * CALIBRATION_SCALE_FACTOR as f32) as i64;
-                let intercept = (results.1 * CALIBRATION_SCALE_FACTOR as f32) as i64;
-                output_results[chan][range_idx] = (slope, intercept);
+            let points: [(u16, u16); 3] =
+                core::array::from_fn(|j| (target_values[j], set_values[j]));
+            if let Some(results) = OutputCalibration::from_points(&points) {
+                output_results[chan][range_idx] = results;
                 info!(
-                    "Linear regression results for outputs channel {} range {}: ({}, {})",
-                    chan, range_idx, slope, intercept
+                    "Calibration breakpoints for outputs channel {} range {}: {}",
+                    chan,
+                    range_idx,
+                    results.breakpoints()
                 );
             } else {
                 // Blink LED red if calibration didn't succeeed
@@ -296,7 +294,7 @@ async fn run_manual_output_calibration() -> RegressionValuesOutput {
 
 async fn run_automatic_calibration(
     receiver: &mut I2cFollowerReceiver,
-) -> (RegressionValuesInput, RegressionValuesOutput) {
+) -> (RegressionValuesInput, OutputCalibrations) {
     for i in 0..CHANNELS {
         set_led_color(i, Led::Button, Color::Yellow);
     }
@@ -312,7 +310,9 @@ async fn run_automatic_calibration(
             receiver.receive().await
         {
             info!("Received calibration data.");
-            return (input_values, output_values);
+            let outputs =
+                output_values.map(|ranges| ranges.map(OutputCalibration::from_regression));
+            return (input_values, outputs);
         }
     }
 }
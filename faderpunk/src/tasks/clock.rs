@@ -18,7 +18,12 @@ use midly::live::SystemRealtime;
 use portable_atomic::{AtomicBool, AtomicU64, Ordering};
 
 use libfp::{
-    utils::bpm_to_clock_duration, AuxJackMode, ClockSrc, GlobalConfig, MidiOut, MidiOutConfig,
+    utils::{
+        bpm_to_clock_duration, clock_duration_to_bpm, restart_swing_window, swung_offset,
+        SWING_HALF_INTERVAL,
+    },
+    AuxJackMode, ClockDivider, ClockSrc, GlobalConfig, MidiOut, MidiOutConfig, PulseIntervals,
+    TapTempo, TransportEvent, TransportState,
 };
 
 use max11300::config::Port;
@@ -46,6 +51,10 @@ const METRONOME_HIGH_MS: u64 = 25;
 
 pub static TICK_COUNTER: AtomicU64 = AtomicU64::new(0);
 pub static METRONOME_HIGH: AtomicBool = AtomicBool::new(true);
+/// Whether the clock is running, as published by the gatekeeper
+static CLOCK_RUNNING: AtomicBool = AtomicBool::new(false);
+/// Measured interval between external clock pulses in embassy ticks, `0` when there is none
+static EXT_TICK_DURATION: AtomicU64 = AtomicU64::new(0);
 
 type AuxInputs = (
     Peri<'static, PIN_1>,
@@ -71,6 +80,8 @@ pub static CLOCK_PUBSUB: PubSubChannel<
 
 pub static CLOCK_IN_CHANNEL: Channel<ThreadModeRawMutex, ClockInEvent, 16> = Channel::new();
 pub static TRANSPORT_CMD_CHANNEL: Channel<ThreadModeRawMutex, TransportCmd, 8> = Channel::new();
+/// Times of the taps setting the internal BPM
+pub static TAP_TEMPO_CHANNEL: Channel<ThreadModeRawMutex, Instant, 8> = Channel::new();
 
 #[derive(Clone, Copy)]
 pub enum ClockInEvent {
@@ -116,11 +127,16 @@ pub enum ClockEvent {
     /// Clock pulse triggering at the set PPQN division.
     /// Tick counter reports the number of 24ppqn ticks since the last reset.
     Tick,
-    /// The clock has started or resumed playback (no phase reset).
+    /// The clock has started playback. Always follows a `Reset`.
     Start,
+    /// The clock has resumed playback where it stopped. No phase reset, counters and sequences
+    /// carry on from where they were.
+    Continue,
     /// The clock has stopped. No phase reset; notes/gates should be silenced.
     Stop,
-    /// A full phase reset. The next tick counter value will be `0`.
+    /// A full phase reset. The next tick counter value will be `0`. Can arrive while the clock is
+    /// running (e.g. from the reset input), apps should go back to their first step and play it
+    /// on the next `Tick`.
     Reset,
 }
 
@@ -156,54 +172,51 @@ const WATCHDOG_MULTIPLIER: u32 = 8;
 /// Raise this to support slower clocks; lower it for faster Stop detection.
 const WATCHDOG_FLOOR: Duration = Duration::from_millis(2000);
 
-/// Half of the swing window, in 24-PPQN ticks. With `H = 6`, the swing window
-/// is one 8th note (12 ticks) and swing is applied at the 16th-note level.
-const SWING_HALF_INTERVAL: u32 = 6;
-
 /// Capacity of the external-clock pending-emission queue. One swing window of
 /// 2H = 12 ticks is scheduled up-front on the window-start pulse; capacity 32
 /// leaves ample headroom for transient jitter.
 const PENDING_EMISSIONS_CAPACITY: usize = 32;
 
-/// Swung absolute offset of tick `i` (in `[0, 2H]`) from the start of the swing
-/// window. Used by both the internal clock (to schedule the next tick directly)
-/// and the external clock (to schedule the whole window on its anchor pulse).
-///
-/// The result is clamped to 500µs before the window boundary. Without this,
-/// heavy positive swing pushes the last ticks of the window past the boundary,
-/// causing the engine to fire tick 0 of the next window as an immediate
-/// catch-up. That catch-up creates two ticks in rapid succession: the
-/// gatekeeper processes both before any subscriber runs, incrementing
-/// TICK_COUNTER twice, so subscribers read the same stale counter for both
-/// events and double-fire notes on beat boundaries.
-fn swung_offset(i: u32, t: Duration, swing: i8) -> Duration {
-    let h = SWING_HALF_INTERVAL as i64;
-    let t_ticks = t.as_ticks() as i64;
-    let s = swing as i64;
-    let i = i as i64;
-
-    let raw = if i < h {
-        // First 16th note: normal spacing
-        i * t_ticks
-    } else {
-        // Second 16th note: shifted start, normal spacing within
-        let boundary = h * t_ticks * (50 + s) / 50;
-        boundary + (i - h) * t_ticks
-    };
-
-    // Clamp to 500µs before the window end. A 1µs margin was insufficient:
-    // by the time the next loop iteration polls `Timer::at(next_tick_at)`,
-    // several µs of code execution have elapsed, consuming the gap and causing
-    // the timer to fire immediately — no executor yield, same race. 500µs is
-    // larger than any plausible Arm 4 round-trip.
-    let window_end = 2 * h * t_ticks - 500;
-    Duration::from_ticks((raw.max(0) as u64).min(window_end as u64))
+/// Tempo of the external clock, derived from the interval between its pulses. External clocks
+/// tick at 24 PPQN. `None` until enough pulses came in to measure it.
+pub fn get_external_bpm() -> Option<f32> {
+    match EXT_TICK_DURATION.load(Ordering::Relaxed) {
+        0 => None,
+        ticks => clock_duration_to_bpm(Duration::from_ticks(ticks), INTERNAL_PPQN),
+    }
+}
+
+/// Whether the clock is running. Analog clocks have no transport and tick while it's stopped.
+pub fn is_transport_running() -> bool {
+    CLOCK_RUNNING.load(Ordering::Relaxed)
 }
 
 pub async fn start_clock(spawner: &Spawner, aux_inputs: AuxInputs) {
     spawner.spawn(run_clock_sources(aux_inputs)).unwrap();
     spawner.spawn(run_clock_gatekeeper()).unwrap();
     spawner.spawn(metronome()).unwrap();
+    spawner.spawn(run_tap_tempo()).unwrap();
+}
+
+#[embassy_executor::task]
+async fn run_tap_tempo() {
+    let config_sender = GLOBAL_CONFIG_WATCH.sender();
+    let mut tap_tempo = TapTempo::default();
+    loop {
+        let tap = TAP_TEMPO_CHANNEL.receive().await;
+        if let Some(bpm) = tap_tempo.tap(tap) {
+            // The config storer persists the new BPM and the clock engine picks it up
+            config_sender.send_if_modified(|c| {
+                if let Some(config) = c {
+                    if config.clock.internal_bpm != bpm {
+                        config.clock.internal_bpm = bpm;
+                        return true;
+                    }
+                }
+                false
+            });
+        }
+    }
 }
 
 async fn make_ext_clock_loop(mut pin: Input<'_>, clock_src: ClockSrc) {
@@ -220,18 +233,17 @@ async fn make_ext_clock_loop(mut pin: Input<'_>, clock_src: ClockSrc) {
     }
 }
 
-async fn send_analog_ticks(spawner: &Spawner, config: &GlobalConfig, counters: &mut [u16; 3]) {
+async fn send_analog_ticks(
+    spawner: &Spawner,
+    config: &GlobalConfig,
+    dividers: &mut [ClockDivider; 3],
+) {
     let mut ports: heapless::Vec<Port, 4> = heapless::Vec::new();
     for (i, aux) in config.aux.iter().enumerate() {
         if let AuxJackMode::ClockOut(div) = aux {
-            if counters[i] == 0 {
+            if dividers[i].tick(*div) {
                 let _ = ports.push(Port::try_from(17 + i).unwrap());
             }
-
-            counters[i] += 1;
-            if counters[i] >= *div as u16 {
-                counters[i] = 0;
-            }
         }
     }
     if !ports.is_empty() {
@@ -291,10 +303,19 @@ async fn metronome() {
             ClockEvent::Stop => {
                 METRONOME_HIGH.store(false, Ordering::Relaxed);
             }
+            ClockEvent::Continue => {}
         }
     }
 }
 
+/// Apply a transport message in the gatekeeper. The running state and the tick counter are
+/// published before the matching clock event, so apps see them as soon as they get the event.
+fn set_transport(transport: &mut TransportState, event: TransportEvent) {
+    transport.apply(event);
+    CLOCK_RUNNING.store(transport.is_running(), Ordering::Relaxed);
+    TICK_COUNTER.store(transport.ticks(), Ordering::Relaxed);
+}
+
 #[embassy_executor::task]
 async fn run_clock_gatekeeper() {
     let clock_publisher = CLOCK_PUBSUB.publisher().unwrap();
@@ -305,8 +326,8 @@ async fn run_clock_gatekeeper() {
     let spawner = Spawner::for_current_executor().await;
 
     let mut config = config_receiver.get().await;
-    let mut is_running = false;
-    let mut analog_tick_counters: [u16; 3] = [0; 3];
+    let mut transport = TransportState::default();
+    let mut analog_dividers = [ClockDivider::default(); 3];
 
     loop {
         match select(clock_in_receiver.receive(), config_receiver.changed()).await {
@@ -355,18 +376,18 @@ async fn run_clock_gatekeeper() {
                 match event {
                     // Clock tick. Only process if clock is running
                     ClockInEvent::Tick(source) => {
-                        if is_running
+                        if transport.is_running()
                             || matches!(source, ClockSrc::Atom | ClockSrc::Meteor | ClockSrc::Cube)
                         {
-                            // Relies on AtomicU64 wrapping on overflow MAX + 1 to ensure first reported TICK_COUNTER after a Clock::Start is always 0
-                            TICK_COUNTER.fetch_add(1, Ordering::Relaxed);
+                            // The first reported TICK_COUNTER after a Clock::Start is always 0
+                            TICK_COUNTER.store(transport.tick(), Ordering::Relaxed);
                             clock_publisher.publish(ClockEvent::Tick).await;
-                            send_analog_ticks(&spawner, &config, &mut analog_tick_counters).await;
+                            send_analog_ticks(&spawner, &config, &mut analog_dividers).await;
                         }
                     }
                     // Unswung MIDI clock tick — forwarded to MIDI outputs at the straight rate
                     ClockInEvent::MidiTick(source) => {
-                        if is_running
+                        if transport.is_running()
                             || matches!(source, ClockSrc::Atom | ClockSrc::Meteor | ClockSrc::Cube)
                         {
                             midi_rt_event = Some(SystemRealtime::TimingClock);
@@ -374,31 +395,30 @@ async fn run_clock_gatekeeper() {
                     }
                     // Start the clock without resetting the phase
                     ClockInEvent::Continue(_) => {
-                        is_running = true;
-                        clock_publisher.publish(ClockEvent::Start).await;
+                        set_transport(&mut transport, TransportEvent::Continue);
+                        clock_publisher.publish(ClockEvent::Continue).await;
                         midi_rt_event = Some(SystemRealtime::Continue);
                     }
                     // (Re-)start the clock. Full phase reset
                     ClockInEvent::Start(_) => {
-                        TICK_COUNTER.store(u64::MAX, Ordering::Relaxed);
-                        is_running = true;
+                        set_transport(&mut transport, TransportEvent::Start);
                         clock_publisher.publish(ClockEvent::Reset).await;
                         clock_publisher.publish(ClockEvent::Start).await;
-                        analog_tick_counters = [0; 3];
+                        analog_dividers = [ClockDivider::default(); 3];
                         send_analog_reset(&spawner, &config).await;
                         midi_rt_event = Some(SystemRealtime::Start);
                     }
                     // Stop the clock. No phase reset
                     ClockInEvent::Stop(_) => {
-                        is_running = false;
+                        set_transport(&mut transport, TransportEvent::Stop);
                         clock_publisher.publish(ClockEvent::Stop).await;
                         midi_rt_event = Some(SystemRealtime::Stop);
                     }
                     // Reset the phase without affecting the run state
                     ClockInEvent::Reset(_) => {
-                        TICK_COUNTER.store(u64::MAX, Ordering::Relaxed);
+                        set_transport(&mut transport, TransportEvent::Reset);
                         clock_publisher.publish(ClockEvent::Reset).await;
-                        analog_tick_counters = [0; 3];
+                        analog_dividers = [ClockDivider::default(); 3];
                         send_analog_reset(&spawner, &config).await;
                         midi_rt_event = Some(SystemRealtime::Reset);
                     }
@@ -414,8 +434,8 @@ async fn run_clock_gatekeeper() {
             Either::Second(new_config) => {
                 // If the clock source has been changed, reset the running state.
                 if config.clock.clock_src != new_config.clock.clock_src {
-                    is_running = false;
-                    analog_tick_counters = [0; 3];
+                    set_transport(&mut transport, TransportEvent::Stop);
+                    analog_dividers = [ClockDivider::default(); 3];
                 }
                 config = new_config;
             }
@@ -456,8 +476,7 @@ async fn run_unified_clock_engine() {
     // to compute a rolling average, which gates the external watchdog so it can't
     // fire based on a stale internal-BPM-derived duration.
     let mut measured_ext_period: Option<Duration> = None;
-    let mut delta_history: [Duration; HISTORY_SIZE] = [Duration::from_ticks(0); HISTORY_SIZE];
-    let mut history_idx: usize = 0;
+    let mut pulse_intervals = PulseIntervals::<HISTORY_SIZE>::new();
     // Queued swung emissions for the external clock path. Each entry is the
     // absolute emission time for the front-most unpublished tick. Empty in the
     // internal or straight-passthrough (`swing == 0`) case.
@@ -522,8 +541,8 @@ async fn run_unified_clock_engine() {
                     // Source changed: reset external tracking state
                     last_pulse = None;
                     measured_ext_period = None;
-                    delta_history = [Duration::from_ticks(0); HISTORY_SIZE];
-                    history_idx = 0;
+                    EXT_TICK_DURATION.store(0, Ordering::Relaxed);
+                    pulse_intervals.reset();
                     pending_emissions.clear();
                     tick_in_window = 0;
 
@@ -580,8 +599,10 @@ async fn run_unified_clock_engine() {
 
                 if is_running != next_is_running {
                     if next_is_running {
-                        window_start_at =
-                            Instant::now() + Duration::from_millis(TICK_RESET_DELAY as u64);
+                        window_start_at = restart_swing_window(
+                            Instant::now(),
+                            Duration::from_millis(TICK_RESET_DELAY as u64),
+                        );
                         tick_in_window = 0;
                         next_tick_at = window_start_at;
                         next_midi_tick_at = window_start_at;
@@ -635,6 +656,17 @@ async fn run_unified_clock_engine() {
                         clock_in_sender.send(ClockInEvent::Reset(source)).await;
                         pending_emissions.clear();
                         tick_in_window = 0;
+                        if config.clock.clock_src == ClockSrc::Internal && is_running {
+                            // Re-anchor the swing window on the reset pulse. Keeping the old
+                            // anchor would schedule the rest of the window in the past and
+                            // burst out ticks right after the reset.
+                            window_start_at = restart_swing_window(
+                                Instant::now(),
+                                Duration::from_millis(TICK_RESET_DELAY as u64),
+                            );
+                            next_tick_at = window_start_at;
+                            next_midi_tick_at = window_start_at;
+                        }
                         continue;
                     }
 
@@ -731,21 +763,10 @@ async fn run_unified_clock_engine() {
                     // Frequency tracking: compute rolling average of pulse intervals
                     if let Some(last) = last_pulse {
                         let delta = timestamp.duration_since(last);
-                        delta_history[history_idx] = delta;
-                        history_idx = (history_idx + 1) % HISTORY_SIZE;
-
-                        let mut sum: u64 = 0;
-                        let mut count: u32 = 0;
-                        for d in &delta_history {
-                            if d.as_ticks() > 0 {
-                                sum += d.as_ticks();
-                                count += 1;
-                            }
-                        }
-                        if count > 0 {
-                            let avg = Duration::from_ticks(sum / count as u64);
+                        if let Some(avg) = pulse_intervals.push(delta) {
                             current_tick_duration = avg;
                             measured_ext_period = Some(avg);
+                            EXT_TICK_DURATION.store(avg.as_ticks(), Ordering::Relaxed);
                         }
                     }
 
@@ -813,6 +834,7 @@ async fn run_unified_clock_engine() {
                             .send(ClockInEvent::Stop(config.clock.clock_src))
                             .await;
                         last_pulse = None;
+                        EXT_TICK_DURATION.store(0, Ordering::Relaxed);
                         is_running = false;
                         pending_emissions.clear();
                         tick_in_window = 0;
@@ -9,11 +9,14 @@ use embassy_usb::driver::{Endpoint, EndpointIn, EndpointOut};
 use heapless::Vec;
 use postcard::{from_bytes, to_vec};
 
-use libfp::{ConfigMsgIn, ConfigMsgOut, Value, APP_MAX_PARAMS, GLOBAL_CHANNELS};
+use libfp::{
+    clamp_param_values, AppStateBatch, ConfigMsgIn, ConfigMsgOut, Value, APP_MAX_PARAMS,
+    GLOBAL_CHANNELS,
+};
 
 use crate::apps::{get_channels, get_config, REGISTERED_APP_IDS};
-use crate::layout::LAYOUT_WATCH;
-use crate::storage::factory_reset;
+use crate::layout::{load_layout_from_slot, LAYOUT_WATCH};
+use crate::storage::{copy_app_scenes, factory_reset, get_layout_slots, store_layout_slot};
 use crate::tasks::global_config::{get_global_config, GLOBAL_CONFIG_WATCH};
 
 use super::transport::{WebEndpoints, USB_MAX_PACKET_SIZE};
@@ -33,6 +36,7 @@ pub enum AppParamCmd {
     SetAppParams {
         values: [Option<Value>; APP_MAX_PARAMS],
     },
+    ResetAppParams,
     RequestParamValues,
 }
 
@@ -103,7 +107,17 @@ pub async fn start_webusb_loop<'a>(webusb: WebEndpoints<'a, Driver<'a, USB>>) {
                         .unwrap();
                 }
             }
-            ConfigMsgIn::SetAppParams { layout_id, values } => {
+            ConfigMsgIn::SetAppParams {
+                layout_id,
+                mut values,
+            } => {
+                let layout = layout_receiver.get().await;
+                // Keep malformed messages from pushing params out of their declared bounds
+                if let Some((_, _, (_, _, _, _, _, params))) =
+                    layout.get_app_id(layout_id).and_then(get_config)
+                {
+                    clamp_param_values(params, &mut values);
+                }
                 APP_PARAM_SIGNALS[layout_id as usize].signal(AppParamCmd::SetAppParams { values });
                 if let Ok((res_layout_id, values)) =
                     with_timeout(Duration::from_secs(1), APP_PARAM_CHANNEL.receive()).await
@@ -114,34 +128,40 @@ pub async fn start_webusb_loop<'a>(webusb: WebEndpoints<'a, Driver<'a, USB>>) {
                         .unwrap();
                 }
             }
+            ConfigMsgIn::ResetAppParams { layout_id } => {
+                let layout = layout_receiver.get().await;
+                if layout.get_app_id(layout_id).is_none() {
+                    continue;
+                }
+                APP_PARAM_SIGNALS[layout_id as usize].signal(AppParamCmd::ResetAppParams);
+                if let Ok((res_layout_id, values)) =
+                    with_timeout(Duration::from_secs(1), APP_PARAM_CHANNEL.receive()).await
+                {
+                    proto
+                        .send_msg(ConfigMsgOut::AppState(res_layout_id, &values))
+                        .await
+                        .unwrap();
+                }
+            }
             ConfigMsgIn::GetAllAppParams => {
                 let layout = layout_receiver.get().await;
-                let layout_ids = layout.get_layout_ids();
-                let app_count = layout_ids.len();
-
-                proto
-                    .send_msg(ConfigMsgOut::BatchMsgStart(app_count))
-                    .await
-                    .unwrap();
-
-                if app_count > 0 {
-                    for id in layout_ids {
-                        APP_PARAM_SIGNALS[id as usize].signal(AppParamCmd::RequestParamValues);
+                let mut batch = AppStateBatch::new(&layout);
+                for &id in batch.layout_ids() {
+                    APP_PARAM_SIGNALS[id as usize].signal(AppParamCmd::RequestParamValues);
+                }
+                // Collect all replies first, so the batch announces exactly what it holds, even
+                // if an app doesn't reply in time
+                let receiver = async {
+                    while !batch.is_complete() {
+                        let (res_layout_id, values) = APP_PARAM_CHANNEL.receive().await;
+                        batch.add(res_layout_id, values);
                     }
-                    let receiver = async {
-                        for _ in 0..app_count {
-                            let (res_layout_id, values) = APP_PARAM_CHANNEL.receive().await;
-                            proto
-                                .send_msg(ConfigMsgOut::AppState(res_layout_id, &values))
-                                .await
-                                .unwrap();
-                        }
-                    };
+                };
+                with_timeout(Duration::from_secs(1), receiver).await.ok();
 
-                    with_timeout(Duration::from_secs(1), receiver).await.ok();
+                for msg in batch.messages() {
+                    proto.send_msg(msg).await.unwrap();
                 }
-
-                proto.send_msg(ConfigMsgOut::BatchMsgEnd).await.unwrap();
             }
             ConfigMsgIn::SetGlobalConfig(mut global_config) => {
                 global_config.validate();
@@ -160,6 +180,66 @@ pub async fn start_webusb_loop<'a>(webusb: WebEndpoints<'a, Driver<'a, USB>>) {
             ConfigMsgIn::FactoryReset => {
                 factory_reset().await;
             }
+            ConfigMsgIn::SaveLayoutSlot(slot) => {
+                let layout = layout_receiver.get().await;
+                store_layout_slot(slot, &layout).await;
+                let slots = get_layout_slots().await;
+                proto
+                    .send_msg(ConfigMsgOut::LayoutSlots(slots))
+                    .await
+                    .unwrap();
+            }
+            ConfigMsgIn::LoadLayoutSlot(slot) => {
+                // An empty slot leaves the running layout alone
+                let layout = match load_layout_from_slot(slot).await {
+                    Some(layout) => layout,
+                    None => layout_receiver.get().await,
+                };
+                proto.send_msg(ConfigMsgOut::Layout(layout)).await.unwrap();
+            }
+            ConfigMsgIn::GetLayoutSlots => {
+                let slots = get_layout_slots().await;
+                proto
+                    .send_msg(ConfigMsgOut::LayoutSlots(slots))
+                    .await
+                    .unwrap();
+            }
+            ConfigMsgIn::CopyAppParams {
+                from_layout_id,
+                to_layout_id,
+                scenes,
+            } => {
+                let layout = layout_receiver.get().await;
+                if layout.copy_app_id(from_layout_id, to_layout_id).is_none() {
+                    proto.send_msg(ConfigMsgOut::CopyRejected).await.unwrap();
+                    continue;
+                }
+                APP_PARAM_SIGNALS[from_layout_id as usize].signal(AppParamCmd::RequestParamValues);
+                let Ok((_, from_values)) =
+                    with_timeout(Duration::from_secs(1), APP_PARAM_CHANNEL.receive()).await
+                else {
+                    proto.send_msg(ConfigMsgOut::CopyRejected).await.unwrap();
+                    continue;
+                };
+                if scenes {
+                    copy_app_scenes(from_layout_id, to_layout_id).await;
+                }
+                let mut values = [None; APP_MAX_PARAMS];
+                for (value, &from_value) in values.iter_mut().zip(from_values.iter()) {
+                    *value = Some(from_value);
+                }
+                // The target app respawns with the new params and reports them back
+                APP_PARAM_SIGNALS[to_layout_id as usize]
+                    .signal(AppParamCmd::SetAppParams { values });
+                if let Ok((res_layout_id, values)) =
+                    with_timeout(Duration::from_secs(1), APP_PARAM_CHANNEL.receive()).await
+                {
+                    proto
+                        .send_msg(ConfigMsgOut::AppState(res_layout_id, &values))
+                        .await
+                        .unwrap();
+                }
+            }
         }
     }
 }
@@ -11,7 +11,10 @@ use embassy_sync::{
 use embassy_time::{with_timeout, Duration};
 use fm24v10::Fm24v10;
 use heapless::Vec;
-use libfp::Color;
+use libfp::{
+    blob::{BlobHeader, BLOB_HEADER_LEN},
+    Color,
+};
 
 use crate::{
     app::Led,
@@ -208,15 +211,19 @@ where
 {
     WRITE_BUFFER_TOKEN.receive().await;
 
-    let len = {
+    let res = {
         let mut buffer = WRITE_BUFFER.lock().await;
-        writer(&mut *buffer).map_err(|_| FramError::BufferOverflow)?
+        writer(&mut *buffer)
     };
 
-    if len > MAX_DATA_LEN {
-        WRITE_BUFFER_TOKEN.try_send(()).unwrap();
-        return Err(FramError::BufferOverflow);
-    }
+    // Nothing is sent to `run_fram` on failure, so the token has to be released here
+    let len = match res {
+        Ok(len) if len <= MAX_DATA_LEN => len,
+        _ => {
+            WRITE_BUFFER_TOKEN.try_send(()).unwrap();
+            return Err(FramError::BufferOverflow);
+        }
+    };
 
     let op = WriteRequest::Store { address, len };
     WRITE_CHANNEL.send(op).await;
@@ -232,15 +239,19 @@ where
 {
     WRITE_BUFFER_TOKEN.receive().await;
 
-    let len = {
+    let res = {
         let mut buffer = WRITE_BUFFER.lock().await;
-        writer(&mut *buffer).map_err(|_| FramError::BufferOverflow)?
+        writer(&mut *buffer)
     };
 
-    if len > MAX_DATA_LEN {
-        WRITE_BUFFER_TOKEN.try_send(()).unwrap();
-        return Err(FramError::BufferOverflow);
-    }
+    // Nothing is sent to `run_fram` on failure, so the token has to be released here
+    let len = match res {
+        Ok(len) if len <= MAX_DATA_LEN => len,
+        _ => {
+            WRITE_BUFFER_TOKEN.try_send(()).unwrap();
+            return Err(FramError::BufferOverflow);
+        }
+    };
 
     let op = WriteRequest::Erase { address, len };
     WRITE_CHANNEL.send(op).await;
@@ -248,13 +259,9 @@ where
     Ok(())
 }
 
-fn calculate_checksum(data: &[u8]) -> u8 {
-    data.iter().fold(0, |acc, &byte| acc.wrapping_add(byte))
-}
-
 struct Storage {
     fram: Fram,
-    write_buf: Vec<u8, { MAX_DATA_LEN + 2 }>,
+    write_buf: Vec<u8, { MAX_DATA_LEN + BLOB_HEADER_LEN }>,
 }
 
 impl Storage {
@@ -265,25 +272,20 @@ impl Storage {
         }
     }
 
-    /// Writes data to FRAM, prefixing it with a 2-byte length and a 1-byte checksum.
+    /// Writes data to FRAM, prefixing it with a header holding a magic, its length and a CRC16.
     pub async fn store(&mut self, address: u32, data: &[u8]) -> Result<(), FramError> {
         if data.len() > MAX_DATA_LEN {
             return Err(FramError::BufferOverflow);
         }
         self.write_buf.clear();
 
-        let checksum = calculate_checksum(data);
-
-        // Resize buffer to hold header (2 bytes length + 1 byte checksum) + data
-        if self.write_buf.resize(3 + data.len(), 0).is_err() {
+        // Write the header and then the data
+        let header = BlobHeader::new(data).to_bytes();
+        if self.write_buf.extend_from_slice(&header).is_err()
+            || self.write_buf.extend_from_slice(data).is_err()
+        {
             return Err(FramError::BufferOverflow);
         }
-        // Write the header and then the data
-        let len_bytes = (data.len() as u16).to_le_bytes();
-        self.write_buf[0] = len_bytes[0];
-        self.write_buf[1] = len_bytes[1];
-        self.write_buf[2] = checksum;
-        self.write_buf[3..].copy_from_slice(data);
 
         self.fram
             .write(address, &self.write_buf)
@@ -298,19 +300,18 @@ impl Storage {
             .map_err(|_| FramError::I2c)
     }
 
-    /// Reads length-prefixed and checksummed data from FRAM into the provided buffer.
+    /// Reads length-prefixed and checksummed data from FRAM into the provided buffer. Data stored
+    /// by older firmware, with a shorter header and an 8-bit checksum, is still accepted.
     pub async fn read(&mut self, address: u32, data_buf: &mut [u8]) -> FramReadResult {
-        const HEADER_SIZE: u32 = 3;
-        let mut header: [u8; HEADER_SIZE as usize] = [0; HEADER_SIZE as usize];
+        let mut header = [0; BLOB_HEADER_LEN];
 
-        // Read the 3-byte header first.
+        // Read the header first. A legacy header is shorter, the rest of the bytes are ignored.
         self.fram
             .read(address, &mut header)
             .await
             .map_err(|_| FramError::I2c)?;
-        let len_bytes = [header[0], header[1]];
-        let stored_checksum = header[2];
-        let data_length = u16::from_le_bytes(len_bytes) as usize;
+        let header = BlobHeader::parse(&header);
+        let data_length = header.data_len();
 
         if data_length == 0 {
             // No data to read.
@@ -323,17 +324,12 @@ impl Storage {
         // Read the actual data into the provided buffer.
         let read_slice = &mut data_buf[..data_length];
         self.fram
-            .read(address + HEADER_SIZE, read_slice)
+            .read(address + header.header_len() as u32, read_slice)
             .await
             .map_err(|_| FramError::I2c)?;
 
-        let calculated_checksum = calculate_checksum(read_slice);
-        if stored_checksum != calculated_checksum {
-            defmt::error!(
-                "FRAM checksum mismatch! Stored: {}, calculated: {}",
-                stored_checksum,
-                calculated_checksum
-            );
+        if !header.verify(read_slice) {
+            defmt::error!("FRAM checksum mismatch at address {}", address);
             return Err(FramError::CrcMismatch);
         }
 
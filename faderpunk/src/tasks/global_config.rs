@@ -11,7 +11,7 @@ use crate::storage::store_global_config;
 use crate::tasks::buttons::is_scene_button_pressed;
 use crate::tasks::input_handlers::{show_config_top_leds, show_scale_keyboard};
 use crate::tasks::leds::LED_BRIGHTNESS;
-use crate::tasks::max::{MaxCmd, MAX_CHANNEL};
+use crate::tasks::max::{MaxCmd, MAX_CHANNEL, OUTPUT_SLEW};
 use crate::QUANTIZER;
 
 // Receivers: unified clock engine (1), clock gatekeeper (1)
@@ -195,6 +195,9 @@ async fn global_config_change() {
     // Initialize leds with loaded config
     LED_BRIGHTNESS.store(old.led_brightness, Ordering::Relaxed);
 
+    // Initialize the DAC writes with the loaded output slew
+    OUTPUT_SLEW.store(old.output_slew, Ordering::Relaxed);
+
     // Initialize quantizer with loaded config
     let mut quantizer = QUANTIZER.get().lock().await;
     quantizer.set_scale(old.quantizer.key, old.quantizer.tonic);
@@ -227,6 +230,9 @@ async fn global_config_change() {
         if config.led_brightness != old.led_brightness {
             LED_BRIGHTNESS.store(config.led_brightness, Ordering::Relaxed);
         }
+        if config.output_slew != old.output_slew {
+            OUTPUT_SLEW.store(config.output_slew, Ordering::Relaxed);
+        }
 
         for (i, (new_aux, old_aux)) in config.aux.iter().zip(old.aux.iter()).enumerate() {
             if new_aux != old_aux {
@@ -8,7 +8,9 @@ use embassy_sync::blocking_mutex::raw::{CriticalSectionRawMutex, ThreadModeRawMu
 use embassy_sync::channel::{Channel, Receiver, Sender};
 use embassy_time::Timer;
 use embedded_hal_async::i2c::I2c;
-use max11300::config::{ConfigMode5, ConfigMode7, Mode, Port, AVR, NSAMPLES};
+use max11300::config::{
+    ConfigMode3, ConfigMode5, ConfigMode7, Mode, Port, AVR, DACRANGE, NSAMPLES,
+};
 use mii::{
     devices::{ansible, er301, telexo},
     Command as MiiCommand,
@@ -17,10 +19,15 @@ use portable_atomic::Ordering;
 
 use libfp::{
     i2c_proto::{
-        DeviceStatus, ErrorCode, Response, WriteCommand, WriteReadCommand, MAX_MESSAGE_SIZE,
+        DeviceStatus, ErrorCode, ExpanderAction, ExpanderJack, Response, WriteCommand,
+        WriteReadCommand, EXPANDER_CHANNELS, MAX_MESSAGE_SIZE,
+    },
+    teletype::{
+        ansible_device_port, fader_to_tt_cv, gate_to_fader, gate_to_tt_cv, note_to_fader,
+        note_to_tt_cv, txo_port,
     },
     types::{RegressionValuesInput, RegressionValuesOutput},
-    I2cMode, Range, I2C_ADDRESS_CALIBRATION,
+    I2cMode, MidiNote, Range, I2C_ADDRESS, I2C_ADDRESS_CALIBRATION,
 };
 use postcard::{from_bytes, to_slice};
 
@@ -41,6 +48,10 @@ pub enum I2cFollowerMessage {
 
 pub enum I2cLeaderMessage {
     FaderValue(usize, u16, Range),
+    /// (channel, note) as 1V/oct
+    Note(usize, MidiNote),
+    /// (channel, high)
+    Gate(usize, bool),
 }
 
 const I2C_LEADER_CHANNEL_SIZE: usize = 16;
@@ -84,15 +95,14 @@ pub async fn start_i2c(
             run_calibration(msg_receiver).await;
         }
         I2cMode::Follower => {
-            // Currently unimplemented
-            // let msg_receiver = I2C_FOLLOWER_CHANNEL.receiver();
-            // let msg_sender = I2C_FOLLOWER_CHANNEL.sender();
-            // let mut i2c0_config = i2c_slave::Config::default();
-            // i2c0_config.addr = I2C_ADDRESS;
-            // let i2c_device = i2c_slave::I2cSlave::new(i2c0, scl, sda, Irqs, i2c0_config);
-            // spawner
-            //     .spawn(run_i2c_follower(i2c_device, msg_sender, false))
-            //     .unwrap();
+            // Act as an expander for a leader Faderpunk
+            let msg_sender = I2C_FOLLOWER_CHANNEL.sender();
+            let mut i2c0_config = i2c_slave::Config::default();
+            i2c0_config.addr = I2C_ADDRESS;
+            let i2c_device = i2c_slave::I2cSlave::new(i2c0, scl, sda, Irqs, i2c0_config);
+            spawner
+                .spawn(run_i2c_follower(i2c_device, msg_sender, false))
+                .unwrap();
         }
         I2cMode::Leader => {
             let mut i2c0_config = i2c::Config::default();
@@ -107,6 +117,7 @@ pub async fn start_i2c(
 struct DiscoveredDevices {
     ansible: bool,
     er301: bool,
+    faderpunk: bool,
     txo: bool,
 }
 
@@ -131,6 +142,9 @@ impl<'a> Compat16N<'a> {
                     er301::ADDRESS => {
                         devices.er301 = true;
                     }
+                    a if a == I2C_ADDRESS as u8 => {
+                        devices.faderpunk = true;
+                    }
                     a if (telexo::BASE_ADDRESS..telexo::BASE_ADDRESS + 8).contains(&a) => {
                         devices.txo = true;
                     }
@@ -147,8 +161,43 @@ impl<'a> Compat16N<'a> {
     }
 
     async fn handle_fader_update(&mut self, chan: usize, value: u16, _range: Range) {
-        let scaled: i16 = (value as u32 * 16383 / 4095) as i16;
+        self.set_cv(chan, fader_to_tt_cv(value), value).await;
+        self.send_to_expander(WriteCommand::ExpanderCv(chan, value))
+            .await;
+    }
+
+    /// Notes are sent as 1V/oct CV on the channel's output
+    async fn handle_note(&mut self, chan: usize, note: MidiNote) {
+        self.set_cv(chan, note_to_tt_cv(note), note_to_fader(note))
+            .await;
+        self.send_to_expander(WriteCommand::ExpanderNote(chan, note))
+            .await;
+    }
+
+    /// Gates are sent as 0V/5V CV on the channel's output, a Faderpunk expander uses a gate jack
+    async fn handle_gate(&mut self, chan: usize, high: bool) {
+        self.set_cv(chan, gate_to_tt_cv(high), gate_to_fader(high))
+            .await;
+        self.send_to_expander(WriteCommand::ExpanderGate(chan, high))
+            .await;
+    }
+
+    /// Send a command to a Faderpunk in follower mode
+    async fn send_to_expander(&mut self, command: WriteCommand) {
+        if !self.devices.faderpunk {
+            return;
+        }
+        let mut buf = [0u8; 16];
+        if let Ok(msg) = to_slice(&command, &mut buf) {
+            if self.i2c.write(I2C_ADDRESS as u8, msg).await.is_err() {
+                error!("I2C write to Faderpunk expander failed");
+            }
+        }
+    }
 
+    /// Set the CV of a channel on all discovered devices. Ansible takes a 12-bit fader value
+    /// instead of the teletype CV value.
+    async fn set_cv(&mut self, chan: usize, scaled: i16, value: u16) {
         if self.devices.er301 {
             let cmd = er301::Commands::SetCv {
                 port: chan as u8,
@@ -163,8 +212,7 @@ impl<'a> Compat16N<'a> {
 
         // Send to TXo if present
         if self.devices.txo {
-            let device_index = (chan / 4) as u8;
-            let port = (chan % 4) as u8;
+            let (device_index, port) = txo_port(chan);
             let address = telexo::BASE_ADDRESS + device_index;
             let cmd = telexo::Commands::SetCv {
                 port,
@@ -180,7 +228,7 @@ impl<'a> Compat16N<'a> {
 
         // Send to Ansible if present
         if self.devices.ansible {
-            let device_port = ((chan / 4) << 1) as u8;
+            let device_port = ansible_device_port(chan);
             let cmd = ansible::Commands::SetCvFromFader { device_port, value };
 
             if let Ok(msg) = cmd.to_bytes(&mut self.buffer) {
@@ -221,7 +269,11 @@ async fn process_write_read(command: WriteReadCommand) -> Response {
     }
 }
 
-async fn process_write(command: WriteCommand, sender: &mut I2cFollowerSender) {
+async fn process_write(
+    command: WriteCommand,
+    sender: &mut I2cFollowerSender,
+    jacks: &mut [Option<ExpanderJack>; EXPANDER_CHANNELS],
+) {
     match command {
         WriteCommand::CalibStart => {
             // Send command to i2c follower channel
@@ -249,6 +301,47 @@ async fn process_write(command: WriteCommand, sender: &mut I2cFollowerSender) {
         WriteCommand::SysReset => {
             cortex_m::peripheral::SCB::sys_reset();
         }
+        WriteCommand::ExpanderCv(..)
+        | WriteCommand::ExpanderNote(..)
+        | WriteCommand::ExpanderGate(..) => {
+            if let Some(action) = command.expander_action() {
+                run_expander_action(action, jacks).await;
+            }
+        }
+    }
+}
+
+/// Write an expander action to the jacks, configuring the jack first if it isn't set up for it
+async fn run_expander_action(
+    action: ExpanderAction,
+    jacks: &mut [Option<ExpanderJack>; EXPANDER_CHANNELS],
+) {
+    let chan = action.channel();
+    let port = Port::try_from(chan).unwrap();
+    if jacks[chan] != Some(action.jack()) {
+        let (mode, gpo_level) = match action.jack() {
+            ExpanderJack::Cv => (Mode::Mode5(ConfigMode5(DACRANGE::Rg0_10v)), None),
+            ExpanderJack::Gate => (Mode::Mode3(ConfigMode3), Some(4095)),
+        };
+        MAX_CHANNEL
+            .send(MaxCmd::ConfigurePort {
+                port,
+                mode,
+                gpo_level,
+            })
+            .await;
+        jacks[chan] = Some(action.jack());
+    }
+    match action {
+        ExpanderAction::SetCv(_, value) => {
+            MAX_VALUES_DAC[chan].store(value, Ordering::Relaxed);
+        }
+        ExpanderAction::SetGate(_, true) => {
+            MAX_CHANNEL.send(MaxCmd::GpoSetHigh { port }).await;
+        }
+        ExpanderAction::SetGate(_, false) => {
+            MAX_CHANNEL.send(MaxCmd::GpoSetLow { port }).await;
+        }
     }
 }
 
@@ -260,6 +353,8 @@ async fn run_i2c_follower(
     _calibrating: bool,
 ) {
     let mut buf = [0u8; MAX_MESSAGE_SIZE];
+    // How the jacks are configured when acting as an expander
+    let mut expander_jacks = [None; EXPANDER_CHANNELS];
     loop {
         match i2c_device.listen(&mut buf).await {
             Ok(Command::WriteRead(len)) => {
@@ -290,7 +385,9 @@ async fn run_i2c_follower(
 
             Ok(Command::Write(len)) => {
                 match from_bytes::<WriteCommand>(&buf[..len]) {
-                    Ok(command) => process_write(command, &mut msg_sender).await,
+                    Ok(command) => {
+                        process_write(command, &mut msg_sender, &mut expander_jacks).await
+                    }
                     Err(_) => {
                         error!("Failed to deserialize write command from master");
                     }
@@ -319,7 +416,16 @@ async fn run_i2c_leader(mut i2c: i2c::I2c<'static, I2C0, Async>) {
     let mut compat16n = Compat16N::new(&mut i2c).await;
 
     loop {
-        let I2cLeaderMessage::FaderValue(chan, value, range) = I2C_LEADER_CHANNEL.receive().await;
-        compat16n.handle_fader_update(chan, value, range).await;
+        match I2C_LEADER_CHANNEL.receive().await {
+            I2cLeaderMessage::FaderValue(chan, value, range) => {
+                compat16n.handle_fader_update(chan, value, range).await;
+            }
+            I2cLeaderMessage::Note(chan, note) => {
+                compat16n.handle_note(chan, note).await;
+            }
+            I2cLeaderMessage::Gate(chan, high) => {
+                compat16n.handle_gate(chan, high).await;
+            }
+        }
     }
 }
@@ -11,10 +11,12 @@ use embassy_sync::{
     channel::{Channel, Sender},
     mutex::Mutex,
 };
-use embassy_time::Timer;
+use embassy_time::{Instant, Timer};
 use libfp::{
+    gate_pulse_due,
     latch::{AnalogLatch, LatchLayer},
     types::MaxCalibration,
+    utils::output_slew,
     CALIBRATION_SCALE_FACTOR,
 };
 use max11300::{
@@ -24,7 +26,7 @@ use max11300::{
     },
     ConfigurePort, IntoConfiguredPort, Max11300, Mode0Port, Ports,
 };
-use portable_atomic::{AtomicBool, AtomicU16, Ordering};
+use portable_atomic::{AtomicBool, AtomicU16, AtomicU64, Ordering};
 use static_cell::StaticCell;
 
 use crate::{
@@ -56,6 +58,12 @@ pub static MAX_VALUES_FADER: [AtomicU16; 16] = [const { AtomicU16::new(0) }; 16]
 pub static MAX_VALUES_DAC: [AtomicU16; 20] = [const { AtomicU16::new(0) }; 20];
 pub static MAX_VALUES_ADC: [AtomicU16; 20] = [const { AtomicU16::new(0) }; 20];
 pub static CALIBRATING: AtomicBool = AtomicBool::new(false);
+/// Global slew applied to all DAC writes, see `GlobalConfig::output_slew`
+pub static OUTPUT_SLEW: AtomicU16 = AtomicU16::new(0);
+/// When the pulse of a gate jack in auto mode ends, in embassy ticks. `0` when none is pending
+pub static GATE_PULSE_ENDS: [AtomicU64; 20] = [const { AtomicU64::new(0) }; 20];
+/// Gate jacks with an inverted polarity, they are off when the GPO is high
+pub static GATE_ACTIVE_LOW: [AtomicBool; 20] = [const { AtomicBool::new(false) }; 20];
 
 #[derive(Clone)]
 #[allow(dead_code)]
@@ -255,17 +263,35 @@ async fn process_channel_values(
     max_driver: &'static SharedMax,
     calibration_data: Option<MaxCalibration>,
 ) {
+    // Values last written to the DAC ports, before calibration
+    let mut dac_values = [0_u16; 20];
+    let mut last_write = Instant::now();
     loop {
         // Hopefully we can write it at about 2kHz
         Timer::after_micros(500).await;
 
+        let now = Instant::now();
+        let elapsed_us = (now - last_write).as_micros() as u32;
+        last_write = now;
+        let slew_ms = OUTPUT_SLEW.load(Ordering::Relaxed);
+
         // Do not process channel 16 (faders)
         for i in (0..16).chain(17..20) {
             let port = Port::try_from(i).unwrap();
             let mut max = max_driver.lock().await;
             match max.get_mode(port) {
                 Mode::Mode5(config) => {
-                    let target_dac_value = MAX_VALUES_DAC[i].load(Ordering::Relaxed);
+                    let target_dac_value = if CALIBRATING.load(Ordering::Relaxed) {
+                        MAX_VALUES_DAC[i].load(Ordering::Relaxed)
+                    } else {
+                        output_slew(
+                            dac_values[i],
+                            MAX_VALUES_DAC[i].load(Ordering::Relaxed),
+                            slew_ms,
+                            elapsed_us,
+                        )
+                    };
+                    dac_values[i] = target_dac_value;
                     let calibrated_value = if target_dac_value == 0 {
                         // If the target is 0, the output MUST be 0
                         0
@@ -278,13 +304,7 @@ async fn process_channel_values(
                             max11300::config::DACRANGE::RgNeg5_5v => 1,
                             _ => 0, // Default to 0-10V range for other ranges
                         };
-                        let (slope, intercept) = data.outputs[i][range_idx];
-
-                        (((target_dac_value as i64 * slope)
-                            + intercept
-                            + (CALIBRATION_SCALE_FACTOR / 2))
-                            >> 16)
-                            .clamp(0, 4095) as u16
+                        data.outputs[i][range_idx].apply(target_dac_value)
                     } else {
                         target_dac_value
                     };
@@ -315,7 +335,25 @@ async fn process_channel_values(
                     };
                     MAX_VALUES_ADC[i].store(calibrated_value, Ordering::Relaxed);
                 }
-                _ => {}
+                Mode::Mode3(_) => {
+                    // End auto gate pulses
+                    let end = GATE_PULSE_ENDS[i].load(Ordering::Relaxed);
+                    if gate_pulse_due(end, now)
+                        && GATE_PULSE_ENDS[i]
+                            .compare_exchange(end, 0, Ordering::Relaxed, Ordering::Relaxed)
+                            .is_ok()
+                    {
+                        if GATE_ACTIVE_LOW[i].load(Ordering::Relaxed) {
+                            max.gpo_set_high(port).await.unwrap();
+                        } else {
+                            max.gpo_set_low(port).await.unwrap();
+                        }
+                    }
+                }
+                _ => {
+                    // The jack isn't a gate (anymore), drop any pending pulse
+                    GATE_PULSE_ENDS[i].store(0, Ordering::Relaxed);
+                }
             }
         }
     }
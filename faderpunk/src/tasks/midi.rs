@@ -13,7 +13,7 @@ use embassy_sync::{
     channel::{Channel, Sender},
     pubsub::{PubSubChannel, Publisher, Subscriber},
 };
-use embassy_time::{with_timeout, Duration, Instant, Ticker, TimeoutError};
+use embassy_time::{with_timeout, Duration, Instant, Ticker, TimeoutError, Timer};
 use embassy_usb::class::midi::{Receiver as UsbReceiver, Sender as UsbSender};
 use embedded_io_async::{Read, Write};
 use heapless::{Deque, Vec};
@@ -21,11 +21,14 @@ use midly::{
     io::Cursor,
     live::{LiveEvent, SystemCommon, SystemRealtime},
     num::{u4, u7},
-    stream::MidiStream,
     MidiMessage,
 };
 
-use libfp::{ClockSrc, MidiIn, MidiOut, MidiOutConfig, MidiOutMode, GLOBAL_CHANNELS};
+use libfp::{
+    midi_stream::{ActiveSensing, MidiDinParser},
+    ClockSrc, MidiChannel, MidiConfig, MidiInPort, MidiOut, MidiOutConfig, MidiOutMode,
+    GLOBAL_CHANNELS,
+};
 
 use crate::{
     events::{EventPubSubPublisher, InputEvent, EVENT_PUBSUB},
@@ -35,14 +38,11 @@ use crate::{
     },
 };
 
-midly::stack_buffer! {
-    struct MidiStreamBuffer([u8; 64]);
-}
-
 const MIDI_CHANNEL_SIZE: usize = 16;
 const MIDI_APP_QUEUE_SIZE: usize = 16;
 const MIDI_PUBSUB_SIZE: usize = 64;
 const MIDI_BURST_PER_TICK: usize = 8;
+const UART_RX_BUFFER_SIZE: usize = 64;
 // Max apps
 const MIDI_PUBSUB_SUBS: usize = GLOBAL_CHANNELS;
 // Only one, from here
@@ -51,7 +51,8 @@ const MIDI_PUBSUB_SENDERS: usize = 1;
 #[derive(Clone, Copy)]
 pub enum MidiEventSource {
     Local,
-    Passthrough,
+    /// Passed through from a MIDI input, never sent back out of the same connection
+    Passthrough(MidiInPort),
 }
 
 #[derive(Clone, Copy)]
@@ -295,21 +296,43 @@ pub async fn midi_out_task<'a>(
             }
         )
     });
+    let mut active_sensing_enabled = config.midi.active_sensing;
+    let mut active_sensing_targets = active_sensing_outs(&config.midi);
+    let mut active_sensing = ActiveSensing::default();
 
     loop {
-        match select(midi_receiver.receive(), config_receiver.changed()).await {
-            Either::First(midi_out_msg) => {
+        let sensing_due = active_sensing_enabled.then(|| active_sensing.next_due(Instant::now()));
+        let sensing_fut = async {
+            match sensing_due {
+                Some(due) => Timer::at(due).await,
+                None => core::future::pending().await,
+            }
+        };
+
+        match select3(
+            midi_receiver.receive(),
+            config_receiver.changed(),
+            sensing_fut,
+        )
+        .await
+        {
+            Either3::First(midi_out_msg) => {
                 match midi_out_msg {
                     MidiOutEvent::Event(MidiMsg::Live {
                         event,
                         mut target,
                         source,
                     }) => {
-                        // Disable targets where we have a strict THRU port or no output.
-                        // Only for local events; passthrough and clock are handled elsewhere.
-                        if let MidiEventSource::Local = source {
-                            for (i, disabled) in disabled_outs_for_local.iter().enumerate() {
-                                target.0[i] = target.0[i] && !disabled;
+                        match source {
+                            // Disable targets where we have a strict THRU port or no output
+                            MidiEventSource::Local => {
+                                for (i, disabled) in disabled_outs_for_local.iter().enumerate() {
+                                    target.0[i] = target.0[i] && !disabled;
+                                }
+                            }
+                            // Don't echo messages back to where they came from
+                            MidiEventSource::Passthrough(port) => {
+                                target = target.without_return_to(port);
                             }
                         }
 
@@ -404,7 +427,12 @@ pub async fn midi_out_task<'a>(
                     }
                 }
             }
-            Either::Second(new_config) => {
+            Either3::Second(new_config) => {
+                if !new_config.midi.active_sensing {
+                    active_sensing.reset();
+                }
+                active_sensing_enabled = new_config.midi.active_sensing;
+                active_sensing_targets = active_sensing_outs(&new_config.midi);
                 disabled_outs_for_local = new_config.midi.outs.map(|c| {
                     matches!(
                         c,
@@ -418,6 +446,19 @@ pub async fn midi_out_task<'a>(
                     )
                 });
             }
+            Either3::Third(_) => {
+                if !active_sensing.poll(Instant::now()) {
+                    continue;
+                }
+                // DIN outputs only, USB doesn't need it
+                let event = LiveEvent::Realtime(SystemRealtime::ActiveSensing);
+                if let MidiOut([_, true, _]) = active_sensing_targets {
+                    let _ = write_msg_to_uart1(&mut uart1_tx, event).await;
+                }
+                if let MidiOut([_, _, true]) = active_sensing_targets {
+                    let _ = write_msg_to_uart0(&mut uart0_tx, event).await;
+                }
+            }
         }
     }
 }
@@ -435,53 +476,17 @@ pub async fn midi_in_task<'a>(
     let event_publisher = EVENT_PUBSUB.publisher().unwrap();
 
     let mut usb_rx_buf = [0; 64];
-    let mut uart_rx_buffer = [0u8; 64];
-    let mut midi_stream = MidiStream::<MidiStreamBuffer>::default();
-    let mut uart_events = Vec::<LiveEvent<'static>, 64>::new();
+    let mut uart_rx_buffer = [0u8; UART_RX_BUFFER_SIZE];
+    let mut din_parser = MidiDinParser::default();
+    // Every byte completes at most one event, so a full read always fits
+    let mut uart_events = Vec::<LiveEvent<'static>, UART_RX_BUFFER_SIZE>::new();
     let mut usb_nrpn_trackers: [NrpnTracker; 16] = Default::default();
     let mut din_nrpn_trackers: [NrpnTracker; 16] = Default::default();
 
     let config = config_receiver.get().await;
 
-    // Get outputs that forward from MIDI DIN
-    let mut midi_passthru_from_din = config.midi.outs.map(|c| {
-        matches!(
-            c,
-            MidiOutConfig {
-                mode: MidiOutMode::MidiThru {
-                    sources: MidiIn([_, true]),
-                    ..
-                },
-                ..
-            } | MidiOutConfig {
-                mode: MidiOutMode::MidiMerge {
-                    sources: MidiIn([_, true]),
-                    ..
-                },
-                ..
-            }
-        )
-    });
-
-    // Get outputs that forward from MIDI USB
-    let mut midi_passthru_from_usb = config.midi.outs.map(|c| {
-        matches!(
-            c,
-            MidiOutConfig {
-                mode: MidiOutMode::MidiThru {
-                    sources: MidiIn([true, _]),
-                    ..
-                },
-                ..
-            } | MidiOutConfig {
-                mode: MidiOutMode::MidiMerge {
-                    sources: MidiIn([_, true]),
-                    ..
-                },
-                ..
-            }
-        )
-    });
+    let mut midi_passthru_from_din = config.midi.passthrough_targets(MidiInPort::Din);
+    let mut midi_passthru_from_usb = config.midi.passthrough_targets(MidiInPort::Usb);
 
     loop {
         match select3(
@@ -513,7 +518,7 @@ pub async fn midi_in_task<'a>(
                                     &usb_publisher,
                                     &mut usb_nrpn_trackers,
                                     midi_passthru_from_usb,
-                                    ClockSrc::MidiUsb,
+                                    MidiInPort::Usb,
                                     &sync_engine_sender,
                                     &midi_sender,
                                     &event_publisher,
@@ -535,8 +540,8 @@ pub async fn midi_in_task<'a>(
                     }
 
                     uart_events.clear();
-                    midi_stream.feed(&uart_rx_buffer[..bytes_read], |event| {
-                        let _ = uart_events.push(event.to_static());
+                    din_parser.feed(&uart_rx_buffer[..bytes_read], |event| {
+                        let _ = uart_events.push(event);
                     });
 
                     for event in uart_events.iter() {
@@ -545,7 +550,7 @@ pub async fn midi_in_task<'a>(
                             &din_publisher,
                             &mut din_nrpn_trackers,
                             midi_passthru_from_din,
-                            ClockSrc::MidiIn,
+                            MidiInPort::Din,
                             &sync_engine_sender,
                             &midi_sender,
                             &event_publisher,
@@ -555,45 +560,8 @@ pub async fn midi_in_task<'a>(
                 }
             }
             Either3::Third(new_config) => {
-                // Get outputs that forward from MIDI DIN
-                midi_passthru_from_din = new_config.midi.outs.map(|c| {
-                    matches!(
-                        c,
-                        MidiOutConfig {
-                            mode: MidiOutMode::MidiThru {
-                                sources: MidiIn([_, true]),
-                                ..
-                            },
-                            ..
-                        } | MidiOutConfig {
-                            mode: MidiOutMode::MidiMerge {
-                                sources: MidiIn([_, true]),
-                                ..
-                            },
-                            ..
-                        }
-                    )
-                });
-
-                // Get outputs that forward from MIDI USB
-                midi_passthru_from_usb = new_config.midi.outs.map(|c| {
-                    matches!(
-                        c,
-                        MidiOutConfig {
-                            mode: MidiOutMode::MidiThru {
-                                sources: MidiIn([true, _]),
-                                ..
-                            },
-                            ..
-                        } | MidiOutConfig {
-                            mode: MidiOutMode::MidiMerge {
-                                sources: MidiIn([_, true]),
-                                ..
-                            },
-                            ..
-                        }
-                    )
-                });
+                midi_passthru_from_din = new_config.midi.passthrough_targets(MidiInPort::Din);
+                midi_passthru_from_usb = new_config.midi.passthrough_targets(MidiInPort::Usb);
             }
         }
     }
@@ -667,12 +635,13 @@ async fn process_midi_event(
     event: &LiveEvent<'_>,
     publisher: &MidiPubSubPublisher,
     nrpn_trackers: &mut [NrpnTracker; 16],
-    thru_targets: [bool; 3],
-    clock_src: ClockSrc,
+    thru_targets: MidiOut,
+    port: MidiInPort,
     sync_engine_sender: &Sender<'static, ThreadModeRawMutex, SyncEngineEvent, 16>,
     midi_sender: &Sender<'static, CriticalSectionRawMutex, MidiOutEvent, 16>,
     event_publisher: &EventPubSubPublisher,
 ) {
+    let clock_src: ClockSrc = port.into();
     match event {
         LiveEvent::Realtime(msg) => match msg {
             SystemRealtime::TimingClock => {
@@ -705,6 +674,8 @@ async fn process_midi_event(
                     .send(SyncEngineEvent::Transport(ClockInEvent::Reset(clock_src)))
                     .await;
             }
+            // Incoming Active Sensing is dropped, a sender going quiet is not acted on
+            SystemRealtime::ActiveSensing => {}
             _ => {}
         },
         LiveEvent::Midi { channel, message } => {
@@ -721,8 +692,8 @@ async fn process_midi_event(
             midi_sender
                 .send(MidiOutEvent::Event(MidiMsg::new(
                     ev,
-                    MidiOut(thru_targets),
-                    MidiEventSource::Passthrough,
+                    thru_targets,
+                    MidiEventSource::Passthrough(port),
                 )))
                 .await;
 
@@ -742,14 +713,39 @@ async fn process_midi_event(
             midi_sender
                 .send(MidiOutEvent::Event(MidiMsg::new(
                     ev,
-                    MidiOut(thru_targets),
-                    MidiEventSource::Passthrough,
+                    thru_targets,
+                    MidiEventSource::Passthrough(port),
                 )))
                 .await;
         }
     }
 }
 
+/// Turn off all notes on `channel`, bypassing the app queues
+pub async fn send_all_notes_off(channel: MidiChannel, target: MidiOut) {
+    let event = LiveEvent::Midi {
+        channel: channel.into(),
+        message: MidiMessage::Controller {
+            controller: u7::new(123),
+            value: u7::new(0),
+        },
+    };
+    MIDI_CHANNEL
+        .send(MidiOutEvent::Event(MidiMsg::new(
+            event,
+            target,
+            MidiEventSource::Local,
+        )))
+        .await;
+}
+
+/// DIN outputs that aren't turned off
+fn active_sensing_outs(config: &MidiConfig) -> MidiOut {
+    let mut outs = MidiOut(config.outs.map(|c| !matches!(c.mode, MidiOutMode::None)));
+    outs.0[0] = false;
+    outs
+}
+
 fn cin_from_live_event(midi_ev: &LiveEvent) -> CodeIndexNumber {
     match midi_ev {
         LiveEvent::Realtime(..) => CodeIndexNumber::SingleByte,
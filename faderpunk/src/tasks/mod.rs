@@ -9,5 +9,6 @@ pub mod input_handlers;
 pub mod leds;
 pub mod max;
 pub mod midi;
+pub mod self_test;
 pub mod transport;
 pub mod web_usb;
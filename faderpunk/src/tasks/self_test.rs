@@ -0,0 +1,183 @@
+use defmt::info;
+use embassy_time::Timer;
+use max11300::config::{ConfigMode3, ConfigMode5, Mode, Port, DACRANGE};
+use portable_atomic::Ordering;
+
+use libfp::{
+    self_test::{self_test_steps, SelfTestMode, SelfTestPhase, SelfTestStep},
+    Brightness, Color,
+};
+
+use crate::app::Led;
+use crate::tasks::leds::{set_led_mode, LedMode, LedMsg};
+use crate::tasks::max::{MaxCmd, MAX_CHANNEL, MAX_VALUES_DAC};
+
+const CHANNELS: usize = 16;
+const LED_POS: [Led; 3] = [Led::Button, Led::Bottom, Led::Top];
+/// In the order of `CHAN_LED_MAP`, as indexed by `SelfTestStep::ChannelLed`
+const CHANNEL_LEDS: [Led; 3] = [Led::Top, Led::Bottom, Led::Button];
+
+async fn configure_jacks(mode: Mode) {
+    for chan in 0..CHANNELS {
+        MAX_CHANNEL
+            .send(MaxCmd::ConfigurePort {
+                port: Port::try_from(chan).unwrap(),
+                mode,
+                gpo_level: Some(4095),
+            })
+            .await;
+    }
+}
+
+async fn set_gates(high: bool) {
+    for chan in 0..CHANNELS {
+        let port = Port::try_from(chan).unwrap();
+        let cmd = if high {
+            MaxCmd::GpoSetHigh { port }
+        } else {
+            MaxCmd::GpoSetLow { port }
+        };
+        MAX_CHANNEL.send(cmd).await;
+    }
+}
+
+fn set_all_leds(mode: LedMode) {
+    for chan in 0..CHANNELS {
+        for &pos in LED_POS.iter() {
+            set_led_mode(chan, pos, LedMsg::Set(mode));
+        }
+    }
+}
+
+/// Run the hardware self-test in `mode`: the LEDs light up, the jacks pulse as gates and then
+/// go through DAC values as 0-10V outputs, on all channels at once or one channel at a time.
+/// Every phase and, per channel, every step is logged, so a fault can be pinned on a single LED
+/// or jack. Restarts the device when done.
+pub async fn run_self_test(mode: SelfTestMode) {
+    info!("Starting self-test...");
+
+    let mut phase = None;
+    let mut last_channel = None;
+    for step in self_test_steps(mode) {
+        // Leave the previous channel dark once the next one takes over
+        if let Some(last) = last_channel.filter(|&last| Some(last) != step.channel()) {
+            set_led_mode(last, Led::Top, LedMsg::Reset);
+        }
+        last_channel = step.channel();
+
+        if phase != Some(step.phase()) {
+            // Only the channel under test is lit in the per-channel self-test
+            let leds = match mode {
+                SelfTestMode::AllChannels => LedMode::Static(Color::Yellow, Brightness::Low),
+                SelfTestMode::PerChannel => LedMode::Static(Color::White, Brightness::Off),
+            };
+            match step.phase() {
+                SelfTestPhase::Leds => {
+                    info!("Self-test: LEDs");
+                }
+                SelfTestPhase::Gates => {
+                    info!("Self-test: gate outputs");
+                    set_all_leds(leds);
+                    configure_jacks(Mode::Mode3(ConfigMode3)).await;
+                    set_gates(false).await;
+                }
+                SelfTestPhase::Dac => {
+                    info!("Self-test: DAC outputs");
+                    set_all_leds(leds);
+                    set_gates(false).await;
+                    configure_jacks(Mode::Mode5(ConfigMode5(DACRANGE::Rg0_10v))).await;
+                }
+            }
+            phase = Some(step.phase());
+        }
+
+        match step {
+            SelfTestStep::Leds(color) => {
+                set_all_leds(LedMode::Static(color, Brightness::High));
+            }
+            SelfTestStep::Gates(high) => {
+                set_gates(high).await;
+                for chan in 0..CHANNELS {
+                    if high {
+                        set_led_mode(
+                            chan,
+                            Led::Top,
+                            LedMsg::Set(LedMode::Static(Color::Yellow, Brightness::High)),
+                        );
+                    } else {
+                        set_led_mode(chan, Led::Top, LedMsg::Reset);
+                    }
+                }
+            }
+            SelfTestStep::Dac(value) => {
+                for (chan, dac_value) in MAX_VALUES_DAC.iter().enumerate().take(CHANNELS) {
+                    dac_value.store(value, Ordering::Relaxed);
+                    set_led_mode(
+                        chan,
+                        Led::Top,
+                        LedMsg::Set(LedMode::Static(
+                            Color::Cyan,
+                            Brightness::Custom((value / 16) as u8),
+                        )),
+                    );
+                }
+            }
+            SelfTestStep::ChannelLed { channel, led } => {
+                info!("Self-test: channel {} LED {}", channel, led);
+                set_all_leds(LedMode::Static(Color::White, Brightness::Off));
+                set_led_mode(
+                    channel,
+                    CHANNEL_LEDS[led],
+                    LedMsg::Set(LedMode::Static(Color::White, Brightness::High)),
+                );
+            }
+            SelfTestStep::ChannelGate { channel, high } => {
+                info!("Self-test: jack {} gate {}", channel, high);
+                let port = Port::try_from(channel).unwrap();
+                let (cmd, brightness) = if high {
+                    (MaxCmd::GpoSetHigh { port }, Brightness::High)
+                } else {
+                    (MaxCmd::GpoSetLow { port }, Brightness::Low)
+                };
+                MAX_CHANNEL.send(cmd).await;
+                set_led_mode(
+                    channel,
+                    Led::Top,
+                    LedMsg::Set(LedMode::Static(Color::Yellow, brightness)),
+                );
+            }
+            SelfTestStep::ChannelDac { channel, value } => {
+                info!(
+                    "Self-test: jack {} DAC {} ({} mV)",
+                    channel,
+                    value,
+                    value as u32 * 10_000 / 4095
+                );
+                for (chan, dac_value) in MAX_VALUES_DAC.iter().enumerate().take(CHANNELS) {
+                    dac_value.store(if chan == channel { value } else { 0 }, Ordering::Relaxed);
+                }
+                set_led_mode(
+                    channel,
+                    Led::Top,
+                    LedMsg::Set(LedMode::Static(
+                        Color::Cyan,
+                        Brightness::Custom((value / 16) as u8),
+                    )),
+                );
+            }
+        }
+
+        Timer::after_millis(step.duration_ms()).await;
+    }
+
+    for dac_value in MAX_VALUES_DAC.iter().take(CHANNELS) {
+        dac_value.store(0, Ordering::Relaxed);
+    }
+    set_all_leds(LedMode::Flash(Color::Green, Some(5)));
+
+    info!("Self-test done. Restarting...");
+
+    // Wait for 2 seconds, then restart the device
+    Timer::after_secs(2).await;
+    cortex_m::peripheral::SCB::sys_reset();
+}
@@ -0,0 +1,195 @@
+/// Marks a blob stored with a CRC. As a little endian length, as written first by older
+/// firmware, it would be far beyond the largest blob, so the two headers can't be mixed up.
+pub const BLOB_MAGIC: [u8; 2] = *b"FP";
+/// Magic, length and CRC
+pub const BLOB_HEADER_LEN: usize = 6;
+/// Length and sum of the bytes, written by older firmware
+pub const LEGACY_BLOB_HEADER_LEN: usize = 3;
+
+/// CRC-16/CCITT-FALSE
+pub fn crc16(data: &[u8]) -> u16 {
+    data.iter().fold(0xFFFF, |crc, &byte| {
+        let mut crc = crc ^ ((byte as u16) << 8);
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+        }
+        crc
+    })
+}
+
+fn legacy_checksum(data: &[u8]) -> u8 {
+    data.iter().fold(0, |acc, &byte| acc.wrapping_add(byte))
+}
+
+/// Header in front of every blob stored in FRAM, to tell a complete blob from one that was
+/// only partially written, e.g. after a power loss.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlobHeader {
+    Crc {
+        len: usize,
+        crc: u16,
+    },
+    /// Blobs stored by older firmware are still read, they get a CRC the next time they're saved
+    Legacy {
+        len: usize,
+        checksum: u8,
+    },
+}
+
+impl BlobHeader {
+    /// Header for storing `data`
+    pub fn new(data: &[u8]) -> Self {
+        Self::Crc {
+            len: data.len(),
+            crc: crc16(data),
+        }
+    }
+
+    /// Parse the start of a stored blob. Legacy headers are shorter, the bytes after them are
+    /// data and are ignored.
+    pub fn parse(bytes: &[u8; BLOB_HEADER_LEN]) -> Self {
+        if bytes[..2] == BLOB_MAGIC {
+            Self::Crc {
+                len: u16::from_le_bytes([bytes[2], bytes[3]]) as usize,
+                crc: u16::from_le_bytes([bytes[4], bytes[5]]),
+            }
+        } else {
+            Self::Legacy {
+                len: u16::from_le_bytes([bytes[0], bytes[1]]) as usize,
+                checksum: bytes[2],
+            }
+        }
+    }
+
+    /// Bytes to write in front of the data
+    pub fn to_bytes(&self) -> [u8; BLOB_HEADER_LEN] {
+        let (len, crc) = match *self {
+            Self::Crc { len, crc } => (len, crc),
+            Self::Legacy { len, checksum } => (len, checksum as u16),
+        };
+        let len = (len as u16).to_le_bytes();
+        let crc = crc.to_le_bytes();
+        [BLOB_MAGIC[0], BLOB_MAGIC[1], len[0], len[1], crc[0], crc[1]]
+    }
+
+    /// Offset of the data from the start of the blob
+    pub fn header_len(&self) -> usize {
+        match self {
+            Self::Crc { .. } => BLOB_HEADER_LEN,
+            Self::Legacy { .. } => LEGACY_BLOB_HEADER_LEN,
+        }
+    }
+
+    /// Length of the data, 0 if nothing is stored
+    pub fn data_len(&self) -> usize {
+        match *self {
+            Self::Crc { len, .. } | Self::Legacy { len, .. } => len,
+        }
+    }
+
+    /// Whether `data` is what was stored with this header
+    pub fn verify(&self, data: &[u8]) -> bool {
+        data.len() == self.data_len()
+            && match *self {
+                Self::Crc { crc, .. } => crc16(data) == crc,
+                Self::Legacy { checksum, .. } => legacy_checksum(data) == checksum,
+            }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::Vec;
+
+    fn store(data: &[u8]) -> Vec<u8, 64> {
+        let mut blob = Vec::new();
+        blob.extend_from_slice(&BlobHeader::new(data).to_bytes())
+            .unwrap();
+        blob.extend_from_slice(data).unwrap();
+        blob
+    }
+
+    // Read like the FRAM task: the header first, then the data behind it
+    fn read(blob: &[u8]) -> Option<&[u8]> {
+        let mut header = [0; BLOB_HEADER_LEN];
+        header.copy_from_slice(&blob[..BLOB_HEADER_LEN]);
+        let header = BlobHeader::parse(&header);
+        let start = header.header_len();
+        let data = blob.get(start..start + header.data_len())?;
+        header.verify(data).then_some(data)
+    }
+
+    #[test]
+    fn test_crc16_check_value() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+        assert_eq!(crc16(&[]), 0xFFFF);
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let data = [7, 0, 1, 1, 24, 0x80, 0x20, 150, 2, 0, 0, 0];
+        let blob = store(&data);
+        assert_eq!(blob.len(), BLOB_HEADER_LEN + data.len());
+        assert_eq!(read(&blob), Some(&data[..]));
+    }
+
+    #[test]
+    fn test_every_corrupted_byte_is_detected() {
+        let data = [7, 0, 1, 1, 24, 0x80, 0x20, 150, 2, 0, 0, 0];
+        let blob = store(&data);
+        for i in 0..blob.len() {
+            for flip in [0x01, 0x80, 0xFF] {
+                let mut corrupted = blob.clone();
+                corrupted[i] ^= flip;
+                assert_eq!(read(&corrupted), None, "byte {i} ^ {flip:#x}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_partial_write_is_detected() {
+        let old = store(&[1; 20]);
+        let new = store(&[2; 20]);
+        // Power lost while the new blob was being written over the old one. Bytes that are the
+        // same in both leave the old blob intact, but a mix of the two is never read.
+        for written in 1..new.len() {
+            let mut blob = old.clone();
+            blob[..written].copy_from_slice(&new[..written]);
+            let data = read(&blob);
+            assert!(
+                data.is_none() || data == Some(&[1; 20][..]),
+                "{written} bytes written"
+            );
+            if written > BLOB_HEADER_LEN {
+                assert_eq!(data, None, "{written} bytes written");
+            }
+        }
+    }
+
+    #[test]
+    fn test_legacy_blobs_are_read() {
+        let data = [3, 1, 4, 1, 5, 9, 2, 6];
+        let mut blob: Vec<u8, 64> = Vec::new();
+        blob.extend_from_slice(&(data.len() as u16).to_le_bytes())
+            .unwrap();
+        blob.push(legacy_checksum(&data)).unwrap();
+        blob.extend_from_slice(&data).unwrap();
+        assert_eq!(read(&blob), Some(&data[..]));
+        blob[5] ^= 1;
+        assert_eq!(read(&blob), None);
+        // Erased storage reads as an empty legacy blob
+        let header = BlobHeader::parse(&[0; BLOB_HEADER_LEN]);
+        assert_eq!(header.data_len(), 0);
+    }
+
+    #[test]
+    fn test_magic_is_no_legacy_length() {
+        let len = u16::from_le_bytes(BLOB_MAGIC) as usize;
+        assert!(len > 1024);
+    }
+}
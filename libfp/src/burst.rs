@@ -0,0 +1,156 @@
+use crate::utils::attenuate;
+
+/// Schedules a burst of evenly spaced triggers, counted in clock ticks.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Burst {
+    remaining: u8,
+    spacing: u32,
+    countdown: u32,
+    index: u8,
+}
+
+impl Burst {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start a new burst of `count` triggers, `spacing` ticks apart. Restarts a running burst.
+    /// Returns `true` if the first trigger should fire right away.
+    pub fn start(&mut self, count: u8, spacing: u32) -> bool {
+        if count == 0 {
+            self.stop();
+            return false;
+        }
+        self.remaining = count - 1;
+        self.spacing = spacing.max(1);
+        self.countdown = self.spacing;
+        self.index = 0;
+        true
+    }
+
+    /// Cancel the remaining triggers
+    pub fn stop(&mut self) {
+        self.remaining = 0;
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.remaining > 0
+    }
+
+    /// Advance by one clock tick. Returns the index of the trigger (1 for the first repeat)
+    /// if one is due on this tick.
+    pub fn tick(&mut self) -> Option<u8> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.countdown -= 1;
+        if self.countdown > 0 {
+            return None;
+        }
+        self.countdown = self.spacing;
+        self.remaining -= 1;
+        self.index += 1;
+        Some(self.index)
+    }
+}
+
+/// Probability (0 - 4095) for the echo `repeat` to fire, where each repeat is `decay` (0 - 4095)
+/// times as likely as the one before.
+pub fn echo_probability(decay: u16, repeat: u8) -> u16 {
+    let decay = decay.min(4095);
+    (0..repeat).fold(4095, |p, _| attenuate(p, decay))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fire_ticks(burst: &mut Burst, ticks: u32) -> heapless::Vec<u32, 32> {
+        let mut fired = heapless::Vec::new();
+        for t in 1..=ticks {
+            if burst.tick().is_some() {
+                fired.push(t).unwrap();
+            }
+        }
+        fired
+    }
+
+    #[test]
+    fn test_idle_burst_never_fires() {
+        let mut burst = Burst::new();
+        assert!(!burst.is_active());
+        assert!(fire_ticks(&mut burst, 100).is_empty());
+    }
+
+    #[test]
+    fn test_burst_is_evenly_spaced() {
+        let mut burst = Burst::new();
+        assert!(burst.start(4, 6));
+        assert_eq!(fire_ticks(&mut burst, 100).as_slice(), &[6, 12, 18]);
+        assert!(!burst.is_active());
+    }
+
+    #[test]
+    fn test_single_trigger_burst() {
+        let mut burst = Burst::new();
+        assert!(burst.start(1, 6));
+        assert!(!burst.is_active());
+        assert!(fire_ticks(&mut burst, 100).is_empty());
+    }
+
+    #[test]
+    fn test_zero_count_does_not_fire() {
+        let mut burst = Burst::new();
+        burst.start(4, 6);
+        assert!(!burst.start(0, 6));
+        assert!(fire_ticks(&mut burst, 100).is_empty());
+    }
+
+    #[test]
+    fn test_repeat_indices() {
+        let mut burst = Burst::new();
+        burst.start(3, 1);
+        assert_eq!(burst.tick(), Some(1));
+        assert_eq!(burst.tick(), Some(2));
+        assert_eq!(burst.tick(), None);
+    }
+
+    #[test]
+    fn test_restart_resets_schedule() {
+        let mut burst = Burst::new();
+        burst.start(8, 4);
+        fire_ticks(&mut burst, 6);
+        burst.start(2, 3);
+        assert_eq!(fire_ticks(&mut burst, 100).as_slice(), &[3]);
+    }
+
+    #[test]
+    fn test_zero_spacing_fires_every_tick() {
+        let mut burst = Burst::new();
+        burst.start(3, 0);
+        assert_eq!(fire_ticks(&mut burst, 10).as_slice(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_stop_cancels_burst() {
+        let mut burst = Burst::new();
+        burst.start(8, 2);
+        burst.tick();
+        burst.stop();
+        assert!(!burst.is_active());
+        assert!(fire_ticks(&mut burst, 100).is_empty());
+    }
+
+    #[test]
+    fn test_echo_probability_decays() {
+        assert_eq!(echo_probability(2048, 0), 4095);
+        let mut prev = 4095;
+        for repeat in 1..8 {
+            let p = echo_probability(2048, repeat);
+            assert!(p < prev);
+            prev = p;
+        }
+        assert_eq!(echo_probability(4095, 10), 4095);
+        assert_eq!(echo_probability(0, 1), 0);
+    }
+}
@@ -0,0 +1,271 @@
+use embassy_time::{Duration, Instant};
+use postcard::ser_flavors::Flavor;
+use serde::Serialize;
+
+/// Time without changes before they are written
+pub const SAVE_DEBOUNCE: Duration = Duration::from_millis(100);
+/// Longest a change waits to be written while more changes keep coming in
+pub const SAVE_MAX_DELAY: Duration = Duration::from_secs(1);
+
+/// Fingerprint of the serialized `value`, to tell whether it changed since it was last stored.
+/// `None` if it can't be serialized.
+pub fn fingerprint<T: Serialize>(value: &T) -> Option<u32> {
+    postcard::serialize_with_flavor(value, Fnv1a(0x811C_9DC5)).ok()
+}
+
+/// FNV-1a hash of the serialized bytes, so nothing needs to be buffered
+struct Fnv1a(u32);
+
+impl Flavor for Fnv1a {
+    type Output = u32;
+
+    fn try_push(&mut self, data: u8) -> postcard::Result<()> {
+        self.0 = (self.0 ^ data as u32).wrapping_mul(0x0100_0193);
+        Ok(())
+    }
+
+    fn finalize(self) -> postcard::Result<u32> {
+        Ok(self.0)
+    }
+}
+
+/// Coalesces the saves of frequently changing data, like app storage updated on every fader
+/// move. A flush is due `SAVE_DEBOUNCE` after the last change, but no later than
+/// `SAVE_MAX_DELAY` after the first one. Data identical to what was stored last is not
+/// written again.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SaveCoalescer {
+    first_change: Option<Instant>,
+    last_change: Option<Instant>,
+    stored: Option<u32>,
+}
+
+impl SaveCoalescer {
+    /// Record a modification made at `now`, given the fingerprints from before and after it.
+    /// Returns whether there is something to flush: a modification that changed nothing is
+    /// ignored, and one going back to what was stored drops the pending flush.
+    pub fn modified(&mut self, before: Option<u32>, after: Option<u32>, now: Instant) -> bool {
+        if before.is_some() && before == after {
+            return self.due().is_some();
+        }
+        if self.is_stored(after) {
+            self.cancel();
+            return false;
+        }
+        self.changed(now);
+        true
+    }
+
+    /// Record a change made at `now`
+    pub fn changed(&mut self, now: Instant) {
+        self.first_change.get_or_insert(now);
+        self.last_change = Some(now);
+    }
+
+    /// When the pending changes are due to be flushed, `None` if there are none
+    pub fn due(&self) -> Option<Instant> {
+        let first = self.first_change?;
+        let last = self.last_change.unwrap_or(first);
+        Some((last + SAVE_DEBOUNCE).min(first + SAVE_MAX_DELAY))
+    }
+
+    /// Whether a flush is due at `now`, the pending changes are then counted as flushed
+    pub fn poll(&mut self, now: Instant) -> bool {
+        match self.due() {
+            Some(due) if now >= due => {
+                self.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Drop the pending changes, e.g. because they were saved right away
+    pub fn cancel(&mut self) {
+        self.first_change = None;
+        self.last_change = None;
+    }
+
+    /// Whether data with this fingerprint is what was stored last
+    pub fn is_stored(&self, fingerprint: Option<u32>) -> bool {
+        fingerprint.is_some() && fingerprint == self.stored
+    }
+
+    /// Whether data with this fingerprint differs from what was stored last
+    pub fn is_dirty(&self, fingerprint: Option<u32>) -> bool {
+        !self.is_stored(fingerprint)
+    }
+
+    /// Record the fingerprint of the data that was stored or loaded
+    pub fn stored(&mut self, fingerprint: Option<u32>) {
+        self.stored = fingerprint;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Storage {
+        values: [u16; 8],
+        muted: bool,
+    }
+
+    fn ms(ms: u64) -> Instant {
+        Instant::from_millis(ms)
+    }
+
+    // Polls every millisecond like the saver task would wake, returns the flush times
+    fn flushes(
+        coalescer: &mut SaveCoalescer,
+        changes: &[u64],
+        until: u64,
+    ) -> heapless::Vec<u64, 16> {
+        let mut flushed = heapless::Vec::new();
+        for now in 0..until {
+            if changes.contains(&now) {
+                coalescer.changed(ms(now));
+            }
+            if coalescer.poll(ms(now)) {
+                flushed.push(now).unwrap();
+            }
+        }
+        flushed
+    }
+
+    #[test]
+    fn test_rapid_changes_flush_once() {
+        let mut coalescer = SaveCoalescer::default();
+        // A fader sweep, one change every 5ms
+        let changes: heapless::Vec<u64, 128> = (100..500).step_by(5).collect();
+        let flushed = flushes(&mut coalescer, &changes, 2000);
+        assert_eq!(flushed, [495 + 100]);
+        assert_eq!(coalescer.due(), None);
+    }
+
+    #[test]
+    fn test_nothing_to_flush() {
+        let mut coalescer = SaveCoalescer::default();
+        assert!(flushes(&mut coalescer, &[], 1000).is_empty());
+    }
+
+    #[test]
+    fn test_separate_changes_flush_separately() {
+        let mut coalescer = SaveCoalescer::default();
+        let flushed = flushes(&mut coalescer, &[10, 50, 300, 700, 750], 1000);
+        assert_eq!(flushed, [150, 400, 850]);
+    }
+
+    #[test]
+    fn test_continuous_changes_flush_after_max_delay() {
+        let mut coalescer = SaveCoalescer::default();
+        let changes: heapless::Vec<u64, 128> = (0..2500).step_by(50).collect();
+        let flushed = flushes(&mut coalescer, &changes, 3000);
+        assert_eq!(flushed, [1000, 2050, 2550]);
+    }
+
+    #[test]
+    fn test_cancel_drops_pending_flush() {
+        let mut coalescer = SaveCoalescer::default();
+        coalescer.changed(ms(0));
+        assert_eq!(coalescer.due(), Some(ms(100)));
+        coalescer.cancel();
+        assert_eq!(coalescer.due(), None);
+        assert!(!coalescer.poll(ms(1000)));
+    }
+
+    // Runs `modify` like ManagedStorage::modify_and_save and counts the flushes that follow
+    fn modify_and_flush(
+        coalescer: &mut SaveCoalescer,
+        storage: &mut Storage,
+        modify: impl FnOnce(&mut Storage),
+    ) -> usize {
+        let before = fingerprint(storage);
+        modify(storage);
+        coalescer.modified(before, fingerprint(storage), ms(0));
+        let flushes = (0..1000).filter(|&now| coalescer.poll(ms(now))).count();
+        if flushes > 0 {
+            coalescer.stored(fingerprint(storage));
+        }
+        flushes
+    }
+
+    #[test]
+    fn test_unchanged_modify_is_not_written() {
+        let mut coalescer = SaveCoalescer::default();
+        let mut storage = Storage {
+            values: [0; 8],
+            muted: false,
+        };
+        assert!(coalescer.is_dirty(fingerprint(&storage)));
+        assert_eq!(
+            modify_and_flush(&mut coalescer, &mut storage, |s| s.muted = true),
+            1
+        );
+        assert!(!coalescer.is_dirty(fingerprint(&storage)));
+
+        // Writing the same values again, like an LED-rate loop would
+        assert_eq!(
+            modify_and_flush(&mut coalescer, &mut storage, |s| s.muted = true),
+            0
+        );
+        assert_eq!(
+            modify_and_flush(&mut coalescer, &mut storage, |s| s.values[0] = 0),
+            0
+        );
+        assert_eq!(
+            modify_and_flush(&mut coalescer, &mut storage, |s| s.values[0] = 4095),
+            1
+        );
+        assert!(!coalescer.is_dirty(fingerprint(&storage)));
+    }
+
+    #[test]
+    fn test_changing_back_drops_pending_flush() {
+        let mut coalescer = SaveCoalescer::default();
+        let mut storage = Storage {
+            values: [0; 8],
+            muted: false,
+        };
+        coalescer.stored(fingerprint(&storage));
+
+        let before = fingerprint(&storage);
+        storage.values[2] = 100;
+        assert!(coalescer.modified(before, fingerprint(&storage), ms(0)));
+        assert!(coalescer.is_dirty(fingerprint(&storage)));
+        // An unchanged modify keeps the pending flush
+        let unchanged = fingerprint(&storage);
+        assert!(coalescer.modified(unchanged, unchanged, ms(10)));
+        assert_eq!(coalescer.due(), Some(ms(100)));
+
+        let before = fingerprint(&storage);
+        storage.values[2] = 0;
+        assert!(!coalescer.modified(before, fingerprint(&storage), ms(20)));
+        assert!(!coalescer.is_dirty(fingerprint(&storage)));
+        assert_eq!(coalescer.due(), None);
+    }
+
+    #[test]
+    fn test_identical_data_is_not_stored_again() {
+        let mut coalescer = SaveCoalescer::default();
+        let mut storage = Storage {
+            values: [1000; 8],
+            muted: false,
+        };
+        let stored = fingerprint(&storage);
+        assert!(!coalescer.is_stored(stored));
+        coalescer.stored(stored);
+        assert!(coalescer.is_stored(fingerprint(&storage)));
+
+        // Moved away and back again
+        storage.values[3] = 1001;
+        assert!(!coalescer.is_stored(fingerprint(&storage)));
+        storage.values[3] = 1000;
+        assert!(coalescer.is_stored(fingerprint(&storage)));
+
+        storage.muted = true;
+        assert!(!coalescer.is_stored(fingerprint(&storage)));
+        assert!(!coalescer.is_stored(None));
+    }
+}
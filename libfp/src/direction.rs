@@ -0,0 +1,160 @@
+/// Order in which a sequencer track plays its steps
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Direction {
+    #[default]
+    Forward,
+    Reverse,
+    PingPong,
+    Random,
+}
+
+impl Direction {
+    /// Split the fader travel into four equal zones, one per direction
+    pub fn from_fader(value: u16) -> Self {
+        match value.min(4095) / 1024 {
+            0 => Direction::Forward,
+            1 => Direction::Reverse,
+            2 => Direction::PingPong,
+            _ => Direction::Random,
+        }
+    }
+
+    /// Step to play for the `count`-th step of a track that is `length` steps long.
+    /// `prev` is the previously played step and `roll` a 12-bit die roll, both only used by
+    /// `Random`.
+    pub fn step_index(&self, count: usize, length: usize, prev: usize, roll: u16) -> usize {
+        let length = length.max(1);
+        match self {
+            Direction::Forward => count % length,
+            Direction::Reverse => length - 1 - count % length,
+            Direction::PingPong => {
+                if length == 1 {
+                    return 0;
+                }
+                // The end steps are only played once per cycle
+                let period = 2 * (length - 1);
+                let pos = count % period;
+                if pos < length {
+                    pos
+                } else {
+                    period - pos
+                }
+            }
+            Direction::Random => random_step(prev, length, roll),
+        }
+    }
+}
+
+/// Random step within `length` that is never `prev`, unless the track has a single step
+pub fn random_step(prev: usize, length: usize, roll: u16) -> usize {
+    if length <= 1 {
+        return 0;
+    }
+    let step = roll.min(4095) as usize * (length - 1) / 4096;
+    if step >= prev.min(length - 1) {
+        step + 1
+    } else {
+        step
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle<const N: usize>(direction: Direction, length: usize) -> [usize; N] {
+        core::array::from_fn(|count| direction.step_index(count, length, 0, 0))
+    }
+
+    #[test]
+    fn test_from_fader() {
+        assert_eq!(Direction::from_fader(0), Direction::Forward);
+        assert_eq!(Direction::from_fader(1023), Direction::Forward);
+        assert_eq!(Direction::from_fader(1024), Direction::Reverse);
+        assert_eq!(Direction::from_fader(2048), Direction::PingPong);
+        assert_eq!(Direction::from_fader(3072), Direction::Random);
+        assert_eq!(Direction::from_fader(4095), Direction::Random);
+        assert_eq!(Direction::from_fader(u16::MAX), Direction::Random);
+    }
+
+    #[test]
+    fn test_forward_cycle() {
+        assert_eq!(cycle::<5>(Direction::Forward, 5), [0, 1, 2, 3, 4]);
+        assert_eq!(cycle::<8>(Direction::Forward, 4), [0, 1, 2, 3, 0, 1, 2, 3]);
+        assert_eq!(cycle::<3>(Direction::Forward, 1), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_reverse_cycle() {
+        assert_eq!(cycle::<5>(Direction::Reverse, 5), [4, 3, 2, 1, 0]);
+        assert_eq!(cycle::<8>(Direction::Reverse, 4), [3, 2, 1, 0, 3, 2, 1, 0]);
+        assert_eq!(cycle::<3>(Direction::Reverse, 1), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_ping_pong_cycle() {
+        assert_eq!(cycle::<8>(Direction::PingPong, 5), [0, 1, 2, 3, 4, 3, 2, 1]);
+        // The cycle repeats without doubling the end steps
+        assert_eq!(
+            cycle::<10>(Direction::PingPong, 4),
+            [0, 1, 2, 3, 2, 1, 0, 1, 2, 3]
+        );
+        assert_eq!(cycle::<4>(Direction::PingPong, 2), [0, 1, 0, 1]);
+        assert_eq!(cycle::<3>(Direction::PingPong, 1), [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_every_direction_visits_all_steps() {
+        for length in 1..=16 {
+            for direction in [Direction::Forward, Direction::Reverse, Direction::PingPong] {
+                let mut seen = [false; 16];
+                for count in 0..2 * length {
+                    let step = direction.step_index(count, length, 0, 0);
+                    assert!(step < length);
+                    seen[step] = true;
+                }
+                assert!(seen[..length].iter().all(|&s| s), "{direction:?} {length}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_random_never_repeats() {
+        let mut seed: u16 = 4321;
+        for length in 2..=16 {
+            let mut prev = 0;
+            let mut seen = [false; 16];
+            for count in 0..200 {
+                seed = seed.wrapping_mul(25173).wrapping_add(13849);
+                let step = Direction::Random.step_index(count, length, prev, seed % 4096);
+                assert!(step < length);
+                assert_ne!(step, prev, "length {length}");
+                seen[step] = true;
+                prev = step;
+            }
+            assert!(seen[..length].iter().all(|&s| s), "length {length}");
+        }
+    }
+
+    #[test]
+    fn test_random_step_covers_other_steps_evenly() {
+        let length = 8;
+        for prev in 0..length {
+            let mut counts = [0; 8];
+            for roll in 0..4096 {
+                counts[random_step(prev, length, roll)] += 1;
+            }
+            assert_eq!(counts[prev], 0);
+            for (step, &count) in counts.iter().enumerate() {
+                if step != prev {
+                    assert!((580..=590).contains(&count), "{step}: {count}");
+                }
+            }
+        }
+        // Single step tracks can only repeat
+        assert_eq!(random_step(0, 1, 4095), 0);
+        assert_eq!(random_step(0, 0, 100), 0);
+        // A stale previous step past the length still gives a valid step
+        assert!(random_step(12, 4, 4095) < 4);
+    }
+}
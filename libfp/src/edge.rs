@@ -0,0 +1,182 @@
+/// Gate and trigger threshold on the input jacks (~1V)
+pub const GATE_THRESHOLD: u16 = 406;
+/// How far a gate needs to drop below the threshold to go low again (~0.1V)
+pub const GATE_HYSTERESIS: u16 = 40;
+/// Samples a gate needs to stay high or low for before it counts
+pub const GATE_MIN_WIDTH: u16 = 2;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Edge {
+    Rising,
+    Falling,
+}
+
+/// Finds rising and falling edges in sampled gate or CV values. The input goes high once it
+/// reaches `threshold` and only goes low again when it drops more than `hysteresis` below it,
+/// so noise around the threshold doesn't retrigger. With a minimum width set, the input has to
+/// stay past the threshold for that many samples before the edge counts, which filters out spikes.
+#[derive(Clone, Copy, Debug)]
+pub struct EdgeDetector {
+    threshold: u16,
+    hysteresis: u16,
+    min_width: u16,
+    pending: u16,
+    last: bool,
+}
+
+impl EdgeDetector {
+    pub const fn new(threshold: u16, hysteresis: u16) -> Self {
+        Self {
+            threshold,
+            hysteresis,
+            min_width: 0,
+            pending: 0,
+            last: false,
+        }
+    }
+
+    /// Ignore pulses and gaps shorter than `samples`. Edges are reported once the input has
+    /// been past the threshold for that long, so they come `samples - 1` samples late.
+    pub const fn with_min_width(mut self, samples: u16) -> Self {
+        self.min_width = samples;
+        self
+    }
+
+    /// Feed the next sample, returns the edge if the input changed state
+    pub fn update(&mut self, value: u16) -> Option<Edge> {
+        let crossed = if self.last {
+            value < self.threshold.saturating_sub(self.hysteresis)
+        } else {
+            value >= self.threshold
+        };
+        if !crossed {
+            self.pending = 0;
+            return None;
+        }
+        self.pending = self.pending.saturating_add(1);
+        if self.pending < self.min_width {
+            return None;
+        }
+        self.pending = 0;
+        self.last = !self.last;
+        if self.last {
+            Some(Edge::Rising)
+        } else {
+            Some(Edge::Falling)
+        }
+    }
+
+    /// Whether the input is currently high
+    pub fn is_high(&self) -> bool {
+        self.last
+    }
+}
+
+impl Default for EdgeDetector {
+    fn default() -> Self {
+        Self::new(GATE_THRESHOLD, GATE_HYSTERESIS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rising_and_falling() {
+        let mut edge = EdgeDetector::new(1000, 0);
+        assert_eq!(edge.update(0), None);
+        assert_eq!(edge.update(999), None);
+        assert_eq!(edge.update(1000), Some(Edge::Rising));
+        assert!(edge.is_high());
+        assert_eq!(edge.update(4095), None);
+        assert_eq!(edge.update(1000), None);
+        assert_eq!(edge.update(999), Some(Edge::Falling));
+        assert!(!edge.is_high());
+        assert_eq!(edge.update(0), None);
+    }
+
+    #[test]
+    fn test_starts_low() {
+        // A gate that is already high when the detector starts is a rising edge
+        let mut edge = EdgeDetector::default();
+        assert!(!edge.is_high());
+        assert_eq!(edge.update(4095), Some(Edge::Rising));
+    }
+
+    #[test]
+    fn test_hysteresis() {
+        let mut edge = EdgeDetector::new(1000, 100);
+        assert_eq!(edge.update(1000), Some(Edge::Rising));
+        assert_eq!(edge.update(950), None);
+        assert_eq!(edge.update(900), None);
+        assert_eq!(edge.update(899), Some(Edge::Falling));
+        // Going low doesn't lower the rising threshold
+        assert_eq!(edge.update(950), None);
+        assert_eq!(edge.update(1000), Some(Edge::Rising));
+    }
+
+    #[test]
+    fn test_noise_around_threshold() {
+        let mut edge = EdgeDetector::new(GATE_THRESHOLD, GATE_HYSTERESIS);
+        let noise = [-30, 12, -5, 25, -38, 0, 17, -20, 39, -1];
+        let mut edges = 0;
+        for n in noise.iter().cycle().take(100) {
+            if edge.update((GATE_THRESHOLD as i32 + n) as u16).is_some() {
+                edges += 1;
+            }
+        }
+        // Only the first crossing counts
+        assert_eq!(edges, 1);
+        assert_eq!(edge.update(0), Some(Edge::Falling));
+    }
+
+    #[test]
+    fn test_min_width_rejects_spikes() {
+        let mut edge = EdgeDetector::new(1000, 0).with_min_width(3);
+        // Spikes of one and two samples
+        for value in [0, 4095, 0, 4095, 4095, 0, 0] {
+            assert_eq!(edge.update(value), None);
+        }
+        assert!(!edge.is_high());
+        // A short dip doesn't end a gate either
+        let edges: [Option<Edge>; 8] =
+            core::array::from_fn(|i| edge.update([4095, 4095, 4095, 0, 0, 4095, 4095, 4095][i]));
+        assert_eq!(
+            edges,
+            [None, None, Some(Edge::Rising), None, None, None, None, None]
+        );
+        assert!(edge.is_high());
+    }
+
+    #[test]
+    fn test_min_width_passes_gates() {
+        let mut edge = EdgeDetector::new(1000, 0).with_min_width(3);
+        let mut edges = [None; 20];
+        for (i, out) in edges.iter_mut().enumerate() {
+            // 10 samples high, 10 samples low
+            *out = edge.update(if i < 10 { 4095 } else { 0 });
+        }
+        assert_eq!(edges[2], Some(Edge::Rising));
+        assert_eq!(edges[12], Some(Edge::Falling));
+        assert_eq!(edges.iter().flatten().count(), 2);
+    }
+
+    #[test]
+    fn test_min_width_of_one_is_immediate() {
+        for min_width in [0, 1] {
+            let mut edge = EdgeDetector::new(1000, 0).with_min_width(min_width);
+            assert_eq!(edge.update(1000), Some(Edge::Rising));
+            assert_eq!(edge.update(0), Some(Edge::Falling));
+        }
+    }
+
+    #[test]
+    fn test_low_threshold_saturates() {
+        let mut edge = EdgeDetector::new(10, 100);
+        assert_eq!(edge.update(10), Some(Edge::Rising));
+        // Can't drop below zero, so it stays high
+        assert_eq!(edge.update(0), None);
+        assert!(edge.is_high());
+    }
+}
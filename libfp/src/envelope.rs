@@ -0,0 +1,262 @@
+use crate::Curve;
+
+/// Stage of an [`Envelope`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum EnvelopeStage {
+    #[default]
+    Idle,
+    Attack,
+    Decay,
+    Sustain,
+    Release,
+}
+
+/// Stage times (in update ticks) and sustain level of an [`Envelope`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EnvelopeSettings {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: u16,
+    pub release: f32,
+}
+
+impl EnvelopeSettings {
+    /// Settings of an AD envelope: no sustain, so the gate length is ignored
+    pub fn ad(attack: f32, decay: f32) -> Self {
+        Self {
+            attack,
+            decay,
+            sustain: 0,
+            release: decay,
+        }
+    }
+}
+
+/// Gate driven ADSR envelope with linear segments.
+/// With a sustain level of `0` it behaves like an AD envelope.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Envelope {
+    stage: EnvelopeStage,
+    level: f32,
+    gate: bool,
+}
+
+impl Envelope {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn stage(&self) -> EnvelopeStage {
+        self.stage
+    }
+
+    /// The current (linear) level, 0 - 4095
+    pub fn level(&self) -> u16 {
+        self.level as u16
+    }
+
+    /// The current level shaped by `curve`
+    pub fn output(&self, curve: Curve) -> u16 {
+        curve.at(self.level())
+    }
+
+    /// Feed the current gate state. A rising edge (re)starts the attack from the current level,
+    /// a falling edge moves a running envelope into its release stage.
+    pub fn gate(&mut self, on: bool) {
+        if on && !self.gate {
+            self.stage = EnvelopeStage::Attack;
+        } else if !on && self.gate && self.stage != EnvelopeStage::Idle {
+            self.stage = EnvelopeStage::Release;
+        }
+        self.gate = on;
+    }
+
+    /// Advance the envelope by one tick and return the new linear level
+    pub fn update(&mut self, settings: &EnvelopeSettings) -> u16 {
+        let sustain = settings.sustain.min(4095) as f32;
+        match self.stage {
+            EnvelopeStage::Idle => {
+                self.level = 0.0;
+            }
+            EnvelopeStage::Attack => {
+                self.level += 4095.0 / settings.attack.max(1.0);
+                if self.level >= 4095.0 {
+                    self.level = 4095.0;
+                    self.stage = EnvelopeStage::Decay;
+                }
+            }
+            EnvelopeStage::Decay => {
+                self.level -= 4095.0 / settings.decay.max(1.0);
+                if self.level <= sustain {
+                    self.level = sustain;
+                    self.stage = if sustain > 0.0 {
+                        EnvelopeStage::Sustain
+                    } else {
+                        EnvelopeStage::Idle
+                    };
+                }
+            }
+            EnvelopeStage::Sustain => {
+                self.level = sustain;
+            }
+            EnvelopeStage::Release => {
+                self.level -= 4095.0 / settings.release.max(1.0);
+                if self.level <= 0.0 {
+                    self.level = 0.0;
+                    self.stage = EnvelopeStage::Idle;
+                }
+            }
+        }
+        self.level()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADSR: EnvelopeSettings = EnvelopeSettings {
+        attack: 10.0,
+        decay: 10.0,
+        sustain: 2048,
+        release: 10.0,
+    };
+
+    fn run(env: &mut Envelope, settings: &EnvelopeSettings, ticks: usize) {
+        for _ in 0..ticks {
+            env.update(settings);
+        }
+    }
+
+    #[test]
+    fn test_idle_without_gate() {
+        let mut env = Envelope::new();
+        run(&mut env, &ADSR, 100);
+        assert_eq!(env.stage(), EnvelopeStage::Idle);
+        assert_eq!(env.level(), 0);
+    }
+
+    #[test]
+    fn test_full_adsr_cycle() {
+        let mut env = Envelope::new();
+        env.gate(true);
+        assert_eq!(env.stage(), EnvelopeStage::Attack);
+
+        run(&mut env, &ADSR, 10);
+        assert_eq!(env.stage(), EnvelopeStage::Decay);
+        assert_eq!(env.level(), 4095);
+
+        run(&mut env, &ADSR, 10);
+        assert_eq!(env.stage(), EnvelopeStage::Sustain);
+        assert_eq!(env.level(), 2048);
+
+        run(&mut env, &ADSR, 1000);
+        assert_eq!(env.stage(), EnvelopeStage::Sustain);
+        assert_eq!(env.level(), 2048);
+
+        env.gate(false);
+        assert_eq!(env.stage(), EnvelopeStage::Release);
+        run(&mut env, &ADSR, 10);
+        assert_eq!(env.stage(), EnvelopeStage::Idle);
+        assert_eq!(env.level(), 0);
+    }
+
+    #[test]
+    fn test_attack_rises_monotonically() {
+        let mut env = Envelope::new();
+        env.gate(true);
+        let mut prev = env.level();
+        while env.stage() == EnvelopeStage::Attack {
+            let level = env.update(&ADSR);
+            assert!(level >= prev);
+            prev = level;
+        }
+        assert_eq!(prev, 4095);
+    }
+
+    #[test]
+    fn test_release_during_attack() {
+        let mut env = Envelope::new();
+        env.gate(true);
+        run(&mut env, &ADSR, 5);
+        let level = env.level();
+        assert!(level > 0 && level < 4095);
+
+        env.gate(false);
+        assert_eq!(env.stage(), EnvelopeStage::Release);
+        assert!(env.update(&ADSR) < level);
+    }
+
+    #[test]
+    fn test_retrigger_starts_from_current_level() {
+        let mut env = Envelope::new();
+        env.gate(true);
+        run(&mut env, &ADSR, 20);
+        env.gate(false);
+        run(&mut env, &ADSR, 3);
+        let level = env.level();
+
+        env.gate(true);
+        assert_eq!(env.stage(), EnvelopeStage::Attack);
+        assert!(env.update(&ADSR) > level);
+    }
+
+    #[test]
+    fn test_held_gate_does_not_retrigger() {
+        let mut env = Envelope::new();
+        env.gate(true);
+        run(&mut env, &ADSR, 20);
+        env.gate(true);
+        assert_eq!(env.stage(), EnvelopeStage::Sustain);
+    }
+
+    #[test]
+    fn test_ad_ignores_gate_length() {
+        let settings = EnvelopeSettings::ad(10.0, 10.0);
+        let mut env = Envelope::new();
+        env.gate(true);
+        run(&mut env, &settings, 20);
+        assert_eq!(env.stage(), EnvelopeStage::Idle);
+        assert_eq!(env.level(), 0);
+
+        // Gate is still high, a new rising edge is needed
+        run(&mut env, &settings, 10);
+        assert_eq!(env.level(), 0);
+        env.gate(false);
+        assert_eq!(env.stage(), EnvelopeStage::Idle);
+    }
+
+    #[test]
+    fn test_zero_times_are_instant() {
+        let settings = EnvelopeSettings {
+            attack: 0.0,
+            decay: 0.0,
+            sustain: 1000,
+            release: 0.0,
+        };
+        let mut env = Envelope::new();
+        env.gate(true);
+        assert_eq!(env.update(&settings), 4095);
+        assert_eq!(env.update(&settings), 1000);
+        env.gate(false);
+        assert_eq!(env.update(&settings), 0);
+    }
+
+    #[test]
+    fn test_output_curve() {
+        let mut env = Envelope::new();
+        env.gate(true);
+        run(&mut env, &ADSR, 5);
+        let level = env.level();
+
+        assert_eq!(env.output(Curve::Linear), level);
+        assert_eq!(env.output(Curve::Exponential), Curve::Exponential.at(level));
+        assert!(env.output(Curve::Exponential) < level);
+        assert!(env.output(Curve::Logarithmic) > level);
+
+        run(&mut env, &ADSR, 5);
+        for curve in [Curve::Linear, Curve::Exponential, Curve::Logarithmic] {
+            assert_eq!(env.output(curve), 4095);
+        }
+    }
+}
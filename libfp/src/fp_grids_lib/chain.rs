@@ -0,0 +1,183 @@
+use serde::{Deserialize, Serialize};
+
+use super::resources::K_NUM_PARTS;
+
+/// Maximum number of snapshots in a `PatternChain`
+pub const CHAIN_MAX_LENGTH: usize = 8;
+
+/// Drum map position and part densities, one step of a `PatternChain`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChainSnapshot {
+    pub x: u8,
+    pub y: u8,
+    pub density: [u8; K_NUM_PARTS],
+}
+
+/// Song mode for Drums mode: steps through stored snapshots, moving on every few bars
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PatternChain {
+    snapshots: [ChainSnapshot; CHAIN_MAX_LENGTH],
+    length: u8,
+    position: u8,
+    bars_played: u8,
+    restarted: bool,
+}
+
+impl PatternChain {
+    /// Append a snapshot to the end of the chain. Returns false if the chain is full.
+    pub fn push(&mut self, snapshot: ChainSnapshot) -> bool {
+        if self.len() >= CHAIN_MAX_LENGTH {
+            return false;
+        }
+        self.snapshots[self.len()] = snapshot;
+        self.length += 1;
+        true
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn len(&self) -> usize {
+        self.length as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Index of the snapshot that is playing
+    pub fn position(&self) -> usize {
+        self.position as usize
+    }
+
+    pub fn current(&self) -> Option<ChainSnapshot> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.snapshots[self.position()])
+        }
+    }
+
+    /// Go back to the first snapshot, it is played from the next bar
+    pub fn restart(&mut self) {
+        self.position = 0;
+        self.bars_played = 0;
+        self.restarted = true;
+    }
+
+    /// Call at the start of every bar. Returns the snapshot to switch to when the chain moves
+    /// on, which happens after `bars_per_step` bars and on the first bar after a restart.
+    pub fn next_bar(&mut self, bars_per_step: u8) -> Option<ChainSnapshot> {
+        if self.is_empty() {
+            return None;
+        }
+        if self.restarted {
+            self.restarted = false;
+            self.bars_played = 1;
+            return self.current();
+        }
+        if self.bars_played < bars_per_step.max(1) {
+            self.bars_played += 1;
+            return None;
+        }
+        self.position = (self.position + 1) % self.length;
+        self.bars_played = 1;
+        self.current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(n: u8) -> ChainSnapshot {
+        ChainSnapshot {
+            x: n,
+            y: 255 - n,
+            density: [n; K_NUM_PARTS],
+        }
+    }
+
+    fn chain(length: u8) -> PatternChain {
+        let mut chain = PatternChain::default();
+        for n in 0..length {
+            assert!(chain.push(snapshot(n)));
+        }
+        chain
+    }
+
+    #[test]
+    fn test_empty_chain_never_advances() {
+        let mut chain = PatternChain::default();
+        chain.restart();
+        for _ in 0..16 {
+            assert_eq!(chain.next_bar(1), None);
+        }
+        assert_eq!(chain.current(), None);
+    }
+
+    #[test]
+    fn test_advances_every_n_bars() {
+        for bars_per_step in 1..=4u8 {
+            let mut chain = chain(3);
+            chain.restart();
+            let mut switches = [None; 24];
+            for switch in switches.iter_mut() {
+                *switch = chain.next_bar(bars_per_step).map(|s| s.x);
+            }
+            for (bar, switch) in switches.iter().enumerate() {
+                // Each snapshot plays for bars_per_step bars, in order, and wraps around
+                let expected = if bar % bars_per_step as usize == 0 {
+                    Some((bar / bars_per_step as usize % 3) as u8)
+                } else {
+                    None
+                };
+                assert_eq!(
+                    *switch, expected,
+                    "{bars_per_step} bars per step, bar {bar}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_zero_bars_is_one_bar() {
+        let mut chain = chain(2);
+        assert_eq!(chain.next_bar(0), None);
+        assert_eq!(chain.next_bar(0), Some(snapshot(1)));
+        assert_eq!(chain.next_bar(0), Some(snapshot(0)));
+    }
+
+    #[test]
+    fn test_restart_goes_back_to_the_first_snapshot() {
+        let mut chain = chain(4);
+        for _ in 0..6 {
+            chain.next_bar(1);
+        }
+        assert_eq!(chain.position(), 1);
+        chain.restart();
+        assert_eq!(chain.next_bar(2), Some(snapshot(0)));
+        assert_eq!(chain.next_bar(2), None);
+        assert_eq!(chain.next_bar(2), Some(snapshot(1)));
+    }
+
+    #[test]
+    fn test_single_snapshot_repeats() {
+        let mut chain = chain(1);
+        chain.restart();
+        assert_eq!(chain.next_bar(2), Some(snapshot(0)));
+        assert_eq!(chain.next_bar(2), None);
+        assert_eq!(chain.next_bar(2), Some(snapshot(0)));
+    }
+
+    #[test]
+    fn test_chain_is_bounded() {
+        let mut chain = chain(CHAIN_MAX_LENGTH as u8);
+        assert!(!chain.push(snapshot(100)));
+        assert_eq!(chain.len(), CHAIN_MAX_LENGTH);
+        chain.clear();
+        assert!(chain.is_empty());
+        assert_eq!(chain.next_bar(1), None);
+    }
+}
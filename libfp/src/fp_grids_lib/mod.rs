@@ -1,11 +1,15 @@
+mod chain;
 mod pattern_generator;
 mod resources;
+mod storage;
 mod utils;
 
 // Re-export public module members
+pub use chain::{ChainSnapshot, PatternChain, CHAIN_MAX_LENGTH};
 pub use pattern_generator::{
     Options, OutputBits, OutputMode, PatternGenerator, PatternGeneratorSettings,
     PatternModeSettings, SequencerState, DNB_NUM_PATTERNS,
 };
 
 pub use resources::{K_NUM_PARTS, K_NUM_STEPS_PER_PATTERN, LUT_RES_EUCLIDEAN};
+pub use storage::{LegacyStorage, Storage};
@@ -20,7 +20,9 @@
 use enum_ordinalize::Ordinalize;
 use serde::{Deserialize, Serialize};
 
-use crate::fp_grids_lib::resources::{DRUM_MAP, K_NUM_PARTS, K_NUM_STEPS_PER_PATTERN};
+use crate::fp_grids_lib::resources::{
+    DRUM_MAP, K_NUM_PARTS, K_NUM_STEPS_PER_PATTERN, NODE_DATA_SIZE,
+};
 use crate::fp_grids_lib::utils::{u8_mix, u8_u8_mul_shift8, Random};
 use crate::utils::euclidean_pattern;
 
@@ -35,6 +37,44 @@ use crate::utils::euclidean_pattern;
 
 const K_PULSE_DURATION: u8 = 8; // 8 ticks of the main 24 ppqn clock
 
+/// The four drum map nodes around a map position and the interpolation weights between them.
+/// Fetched once per step and shared by all parts, instead of per part in `read_drum_map`.
+struct DrumMapCell {
+    // Top-left, top-right, bottom-left, bottom-right
+    nodes: [&'static [u8; NODE_DATA_SIZE]; 4],
+    x_weight: u8,
+    y_weight: u8,
+}
+
+impl DrumMapCell {
+    /// x and y are 0-255 coordinates for map interpolation
+    fn new(x: u8, y: u8) -> Self {
+        static MAP: [[[u8; NODE_DATA_SIZE]; 5]; 5] = DRUM_MAP;
+        let i = (x >> 6) as usize;
+        let j = (y >> 6) as usize;
+        Self {
+            nodes: [
+                &MAP[j][i],
+                &MAP[j][i + 1],
+                &MAP[j + 1][i],
+                &MAP[j + 1][i + 1],
+            ],
+            x_weight: (x % 64) << 2,
+            y_weight: (y % 64) << 2,
+        }
+    }
+
+    fn level(&self, step: u8, instrument: u8) -> u8 {
+        let offset = (instrument as usize) * K_NUM_STEPS_PER_PATTERN as usize + step as usize;
+        let [a, b, c, d] = self.nodes.map(|node| node[offset]);
+        u8_mix(
+            u8_mix(a, b, self.x_weight),
+            u8_mix(c, d, self.x_weight),
+            self.y_weight,
+        )
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum PatternModeSettings {
     Drums { x: u8, y: u8, randomness: u8 },
@@ -139,11 +179,14 @@ pub struct PatternGenerator {
     pub options_: Options,
 
     chaos_globally_enabled_: bool, // Master switch for chaos effects
+    live_chaos: bool,              // Drum mode chaos also jitters the map position every step
 
     // Internal state variables
     current_euclidean_length: [u8; K_NUM_PARTS], // Active length for each Euclidean part
     fill: [u8; K_NUM_PARTS], // Calculated number of active steps for Euclidean parts, based on density
     part_perturbation: [u8; K_NUM_PARTS], // Randomness value applied per part in Drum mode
+    last_level: [u8; K_NUM_PARTS], // Drum map level of each part at the last evaluated step
+    map_position: (u8, u8),  // Drum map x/y used for the last evaluated step
     euclidean_step: [u8; K_NUM_PARTS], // Current step for each Euclidean generator (0 to length-1)
     euclidean_offset: [u8; K_NUM_PARTS], // Per-part Euclidean rotation offset (0 to length-1)
 
@@ -209,6 +252,22 @@ impl PatternGenerator {
     pub fn set_global_chaos(&mut self, enabled: bool) {
         self.chaos_globally_enabled_ = enabled;
     }
+    /// In Drums mode, let chaos also move the map position at random on every step, by up to
+    /// half the randomness setting in each direction. Off by default, when chaos only perturbs
+    /// the part levels once per pattern.
+    pub fn set_live_chaos(&mut self, enabled: bool) {
+        self.live_chaos = enabled;
+    }
+    /// Drum map x/y the current step was read from, including live chaos
+    pub fn get_map_position(&self) -> (u8, u8) {
+        self.map_position
+    }
+    /// Drum map level (0-255) of a part at the current step, including the chaos perturbation.
+    /// A part triggers when its level is above the inverted density. Only Drums mode reports
+    /// levels, other modes and out of range parts return 0.
+    pub fn get_last_level(&self, part: usize) -> u8 {
+        self.last_level.get(part).copied().unwrap_or(0)
+    }
     /// Provides the current trigger state for all parts (and accent).
     /// Bit 0: Part 1 (BD/EUC1), Bit 1: Part 2 (SD/EUC2), Bit 2: Part 3 (HH/EUC3)
     /// Bit 3: Accent (in Drum mode or chaotic Euclidean)
@@ -253,6 +312,7 @@ impl PatternGenerator {
         self.pulse_ = 0;
         self.euclidean_step = [0; K_NUM_PARTS];
         self.part_perturbation = [0; K_NUM_PARTS];
+        self.last_level = [0; K_NUM_PARTS];
         self.first_beat_ = true;
         self.beat_ = true;
         self.state_ = 0;
@@ -385,12 +445,15 @@ impl Default for PatternGenerator {
     fn default() -> Self {
         Self {
             part_perturbation: [0; K_NUM_PARTS],
+            last_level: [0; K_NUM_PARTS],
             euclidean_step: [0; K_NUM_PARTS],
             options_: Options {
                 output_mode: OutputMode::OutputModeDrums,
                 gate_mode: false,
             },
             chaos_globally_enabled_: false,
+            live_chaos: false,
+            map_position: (0, 0),
             state_: 0,
             step_: 0, // Ensure step_ (if different from sequence_step_) is also init
             // beat_counter_: 0,
@@ -446,6 +509,8 @@ impl PatternGenerator {
 
     /// Reads the drum map, interpolating between 4 points in the map.
     /// x and y are 0-255 coordinates for map interpolation.
+    /// Reference for `DrumMapCell`, which `evaluate_drums` uses.
+    #[cfg(test)]
     fn read_drum_map(&self, step: u8, instrument: u8, x: u8, y: u8) -> u8 {
         let i = x >> 6; // Determines a 2x2 cell in the 5x5 map based on X (quantized to 0-3 for cell index)
         let j = y >> 6; // Determines a 2x2 cell in the 5x5 map based on Y (quantized to 0-3 for cell index)
@@ -516,10 +581,16 @@ impl PatternGenerator {
         let current_step_in_pattern = self.step_;
         let mut new_state_for_tick = 0u8; // Accumulates trigger and accent bits for the current tick
 
-        let (x, y) = match self.settings_[OutputMode::OutputModeDrums.ordinal() as usize].options {
-            PatternModeSettings::Drums { x, y, .. } => (x, y),
-            _ => (0, 0), // Default to 0 if not in Drum mode, though this should never happen
-        };
+        let (mut x, mut y, randomness) =
+            match self.settings_[OutputMode::OutputModeDrums.ordinal() as usize].options {
+                PatternModeSettings::Drums { x, y, randomness } => (x, y, randomness),
+                _ => (0, 0, 0), // Default to 0 if not in Drum mode, though this should never happen
+            };
+        if self.live_chaos && self.chaos_globally_enabled_ && randomness > 0 {
+            x = self.jitter(x, randomness);
+            y = self.jitter(y, randomness);
+        }
+        self.map_position = (x, y);
         let mut density_thresholds = [0u8; K_NUM_PARTS];
 
         for (part, density) in self.settings_[OutputMode::OutputModeDrums.ordinal() as usize]
@@ -532,14 +603,16 @@ impl PatternGenerator {
             density_thresholds[part] = !*density;
         }
 
+        let cell = DrumMapCell::new(x, y);
         let mut accent_bits_for_parts: u8 = 0; // Accumulates trigger and accent bits for the current tick
         for (part, threshold) in density_thresholds.iter().enumerate().take(K_NUM_PARTS) {
-            let mut level: u8 = self.read_drum_map(current_step_in_pattern, part as u8, x, y);
+            let mut level: u8 = cell.level(current_step_in_pattern, part as u8);
             if level < 255 - self.part_perturbation[part] {
                 level += self.part_perturbation[part];
             } else {
                 level = 255;
             }
+            self.last_level[part] = level;
 
             if level > *threshold {
                 if level > 192 {
@@ -564,8 +637,15 @@ impl PatternGenerator {
         self.state_ = new_state_for_tick; // Update the main trigger/accent state for the current tick
     }
 
+    // Moves a map coordinate by a random amount of up to +/- randomness / 2
+    fn jitter(&mut self, value: u8, randomness: u8) -> u8 {
+        let offset = (self.random.get_byte() as i16 - 128) * randomness as i16 / 256;
+        (value as i16 + offset).clamp(0, 255) as u8
+    }
+
     fn evaluate_euclidean(&mut self) {
         self.state_ = 0;
+        self.last_level = [0; K_NUM_PARTS];
 
         let chaos = match self.settings_[OutputMode::OutputModeEuclidean.ordinal() as usize].options
         {
@@ -602,6 +682,7 @@ impl PatternGenerator {
     }
 
     fn evaluate_dnb(&mut self) {
+        self.last_level = [0; K_NUM_PARTS];
         if self.first_beat_ && self.pulse_ == 0 {
             // At start of pattern sequence
             if self.pattern_change_queued && self.queued_pattern_id >= 0 {
@@ -1004,6 +1085,7 @@ impl PatternGenerator {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::ManualTicks;
     use env_logger::Env;
 
     fn init_logger() {
@@ -1036,6 +1118,184 @@ mod tests {
         assert_eq!(value, 217);
     }
 
+    #[test]
+    fn test_drum_map_cell_matches_read_drum_map() {
+        let generator: PatternGenerator = PatternGenerator::default();
+        for (x, y) in [
+            (0, 0),
+            (32, 32),
+            (63, 64),
+            (128, 200),
+            (191, 17),
+            (255, 255),
+            (255, 0),
+            (0, 255),
+        ] {
+            let cell = DrumMapCell::new(x, y);
+            for step in 0..K_NUM_STEPS_PER_PATTERN {
+                for part in 0..K_NUM_PARTS as u8 {
+                    assert_eq!(
+                        cell.level(step, part),
+                        generator.read_drum_map(step, part, x, y)
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_last_level_matches_drum_map() {
+        let mut generator = PatternGenerator::default();
+        generator.set_output_mode(OutputMode::OutputModeDrums);
+        generator.settings_[OutputMode::OutputModeDrums.ordinal() as usize] =
+            PatternGeneratorSettings {
+                options: PatternModeSettings::Drums {
+                    x: 100,
+                    y: 30,
+                    randomness: 0,
+                },
+                density: [128, 200, 64],
+            };
+        generator.reset();
+        for clkn in 0..K_NUM_STEPS_PER_PATTERN as u32 {
+            generator.tick(clkn, 1);
+            let step = generator.get_step();
+            let state = generator.get_trigger_state();
+            for part in 0..K_NUM_PARTS {
+                let level = generator.get_last_level(part);
+                assert_eq!(level, generator.read_drum_map(step, part as u8, 100, 30));
+                // The level is what decides the trigger
+                let threshold = !generator.settings_[1].density[part];
+                assert_eq!(state & (1 << part) > 0, level > threshold);
+            }
+        }
+        assert_eq!(generator.get_last_level(K_NUM_PARTS), 0);
+    }
+
+    #[test]
+    fn test_last_level_includes_chaos() {
+        let mut generator = PatternGenerator::default();
+        generator.set_seed(0x1234);
+        generator.set_global_chaos(true);
+        generator.settings_[OutputMode::OutputModeDrums.ordinal() as usize].options =
+            PatternModeSettings::Drums {
+                x: 200,
+                y: 180,
+                randomness: 255,
+            };
+        generator.reset();
+        for clkn in 0..K_NUM_STEPS_PER_PATTERN as u32 {
+            generator.tick(clkn, 1);
+            for part in 0..K_NUM_PARTS {
+                let map_level = generator.read_drum_map(generator.get_step(), part as u8, 200, 180);
+                let expected = map_level.saturating_add(generator.part_perturbation[part]);
+                assert_eq!(generator.get_last_level(part), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn test_last_level_only_in_drums_mode() {
+        let mut generator = PatternGenerator::default();
+        generator.reset();
+        generator.tick(0, 1);
+        assert!((0..K_NUM_PARTS).any(|part| generator.get_last_level(part) > 0));
+        generator.set_output_mode(OutputMode::OutputModeEuclidean);
+        generator.tick(1, 1);
+        for part in 0..K_NUM_PARTS {
+            assert_eq!(generator.get_last_level(part), 0);
+        }
+    }
+
+    // Trigger states and levels of two patterns' worth of steps
+    fn drum_run(live_chaos: bool, randomness: u8) -> [(u8, [u8; K_NUM_PARTS]); 64] {
+        let mut generator = PatternGenerator::default();
+        generator.set_seed(0xBEEF);
+        generator.set_global_chaos(true);
+        generator.set_live_chaos(live_chaos);
+        generator.settings_[OutputMode::OutputModeDrums.ordinal() as usize] =
+            PatternGeneratorSettings {
+                options: PatternModeSettings::Drums {
+                    x: 60,
+                    y: 190,
+                    randomness,
+                },
+                density: [150, 100, 200],
+            };
+        generator.reset();
+        core::array::from_fn(|clkn| {
+            generator.tick(clkn as u32, 1);
+            let levels = core::array::from_fn(|part| generator.get_last_level(part));
+            (generator.get_trigger_state(), levels)
+        })
+    }
+
+    #[test]
+    fn test_live_chaos_off_at_zero_randomness() {
+        assert_eq!(drum_run(true, 0), drum_run(false, 0));
+        let mut generator = PatternGenerator::default();
+        generator.set_global_chaos(true);
+        generator.set_live_chaos(true);
+        generator.reset();
+        // The default map position at the center, unmoved
+        assert_eq!(generator.get_map_position(), (128, 128));
+    }
+
+    #[test]
+    fn test_live_chaos_variation_is_bounded() {
+        assert_ne!(drum_run(true, 255), drum_run(false, 255));
+        for randomness in [64, 128, 255] {
+            let mut generator = PatternGenerator::default();
+            generator.set_seed(0x1234);
+            generator.set_global_chaos(true);
+            generator.set_live_chaos(true);
+            for (x, y) in [(0, 0), (128, 128), (255, 255), (10, 240)] {
+                generator.settings_[OutputMode::OutputModeDrums.ordinal() as usize].options =
+                    PatternModeSettings::Drums { x, y, randomness };
+                generator.reset();
+                let mut moved = false;
+                for clkn in 0..64 {
+                    generator.tick(clkn, 1);
+                    let (jx, jy) = generator.get_map_position();
+                    let bound = randomness as i16 / 2;
+                    assert!((jx as i16 - x as i16).abs() <= bound);
+                    assert!((jy as i16 - y as i16).abs() <= bound);
+                    moved |= (jx, jy) != (x, y);
+                }
+                assert!(moved);
+            }
+        }
+    }
+
+    #[test]
+    fn test_manual_ticks_advance_one_step() {
+        for (mode, div) in [
+            (OutputMode::OutputModeDrums, 3),
+            (OutputMode::OutputModeEuclidean, 6),
+            (OutputMode::OutputModeEuclidean, 1),
+            (OutputMode::OutputModeDnB, 4),
+        ] {
+            let mut generator = PatternGenerator::default();
+            generator.set_output_mode(mode);
+            generator.reset();
+            let steps = if mode == OutputMode::OutputModeDnB {
+                generator.current_dnb_pattern.steps
+            } else {
+                K_NUM_STEPS_PER_PATTERN
+            };
+            let mut manual = ManualTicks::default();
+            for n in 0..100u32 {
+                generator.tick(manual.step(div), div);
+                assert_eq!(generator.get_step() as u32, n % steps as u32);
+            }
+            // Starting over plays the first step again
+            manual.reset();
+            generator.tick(manual.step(div), div);
+            assert_eq!(generator.get_step(), 0);
+            assert!(generator.is_on_first_beat());
+        }
+    }
+
     #[test]
     fn test_evaluate_drums() {
         init_logger(); // Logs will now be visible
@@ -1121,6 +1381,59 @@ mod tests {
         assert_eq!(0, generator.get_trigger_state()); // step 7: no fire
     }
 
+    #[test]
+    fn test_euclidean_parts_are_independent() {
+        let mut generator: PatternGenerator = PatternGenerator::default();
+        generator.options_.output_mode = OutputMode::OutputModeEuclidean;
+        generator.options_.gate_mode = true;
+        generator.settings_[OutputMode::OutputModeEuclidean.ordinal() as usize].options =
+            PatternModeSettings::Euclidean { chaos_amount: 0 };
+
+        // (length, beats, rotation) per part
+        let rhythms: [(u8, u8, u8); K_NUM_PARTS] = [(8, 3, 0), (5, 2, 1), (16, 4, 3)];
+        for (part, &(length, beats, rotation)) in rhythms.iter().enumerate() {
+            generator.set_length(part, length);
+            generator.set_offset(part, rotation);
+            generator.settings_[OutputMode::OutputModeEuclidean.ordinal() as usize].density[part] =
+                beats;
+        }
+
+        for clkn in 0..80 {
+            generator.tick(clkn, 1);
+            for (part, &(length, beats, rotation)) in rhythms.iter().enumerate() {
+                let pattern = euclidean_pattern(length, beats, rotation, 0);
+                let expected = (pattern >> (clkn % length as u32)) & 1 == 1;
+                let fired = generator.get_trigger_state() & (1 << part) != 0;
+                assert_eq!(expected, fired, "part {part} at step {clkn}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_euclidean_beat_count_per_cycle() {
+        let mut generator: PatternGenerator = PatternGenerator::default();
+        generator.options_.output_mode = OutputMode::OutputModeEuclidean;
+        generator.options_.gate_mode = true;
+        generator.settings_[OutputMode::OutputModeEuclidean.ordinal() as usize].options =
+            PatternModeSettings::Euclidean { chaos_amount: 0 };
+
+        for length in 2..=32u8 {
+            for beats in [0, 1, length / 2, length] {
+                generator.set_length(0, length);
+                generator.set_offset(0, length / 3);
+                generator.settings_[OutputMode::OutputModeEuclidean.ordinal() as usize].density
+                    [0] = beats;
+                let hits = (0..length as u32)
+                    .filter(|&clkn| {
+                        generator.tick(clkn, 1);
+                        generator.get_trigger_state() & 1 != 0
+                    })
+                    .count();
+                assert_eq!(hits, beats as usize, "E({beats},{length})");
+            }
+        }
+    }
+
     #[test]
     fn test_evaluate_dnb() {
         init_logger();
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+
+use super::{chain::PatternChain, pattern_generator::SequencerState, resources::K_NUM_PARTS};
+
+/// Stored state of the FP Grids app
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    pub fader_saved: [u16; K_NUM_PARTS + 1],
+    pub shift_fader_saved: [u16; K_NUM_PARTS],
+    #[serde(default)]
+    pub euclidean_offset_saved: [u8; K_NUM_PARTS],
+    pub div_fader_saved: u16, // 0 - 4095 range, maps to index into 'resolution' clock div array (same as euclid.rs)
+    pub mute_saved: [bool; K_NUM_PARTS + 1], // 3 triggers + accent
+    pub drum_mode: u8,        // 0 = Drums Mode, 1 = Euclidean mode, 2 = DnB Mode
+    pub generator_state: SequencerState, // Internal generator state, use to restore after app re-spawn
+    pub note_on: [bool; K_NUM_PARTS],
+    pub accent_on: bool,
+    pub chain: PatternChain, // Song mode snapshots
+}
+
+impl Storage {
+    /// Decode storage saved in the layout of `LegacyStorage`. The whole data has to be used up,
+    /// so a different layout isn't misread as this one.
+    pub fn decode_legacy(data: &[u8]) -> Option<Self> {
+        match postcard::take_from_bytes::<LegacyStorage>(data) {
+            Ok((legacy, [])) => Some(legacy.into()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            fader_saved: [2047, 2047, 2047, 0 /* zero chaos */],
+            shift_fader_saved: [2047; K_NUM_PARTS],
+            euclidean_offset_saved: [0; K_NUM_PARTS],
+            div_fader_saved: 3000,
+            mute_saved: [false; K_NUM_PARTS + 1],
+            drum_mode: 0,
+            generator_state: SequencerState::default(),
+            note_on: [false; K_NUM_PARTS],
+            accent_on: false,
+            chain: PatternChain::default(),
+        }
+    }
+}
+
+/// Storage as it was saved before the song mode chain
+#[derive(Deserialize)]
+pub struct LegacyStorage {
+    pub fader_saved: [u16; K_NUM_PARTS + 1],
+    pub shift_fader_saved: [u16; K_NUM_PARTS],
+    pub euclidean_offset_saved: [u8; K_NUM_PARTS],
+    pub div_fader_saved: u16,
+    pub mute_saved: [bool; K_NUM_PARTS + 1],
+    pub drum_mode: u8,
+    pub generator_state: SequencerState,
+    pub note_on: [bool; K_NUM_PARTS],
+    pub accent_on: bool,
+}
+
+impl From<LegacyStorage> for Storage {
+    fn from(old: LegacyStorage) -> Self {
+        Self {
+            fader_saved: old.fader_saved,
+            shift_fader_saved: old.shift_fader_saved,
+            euclidean_offset_saved: old.euclidean_offset_saved,
+            div_fader_saved: old.div_fader_saved,
+            mute_saved: old.mute_saved,
+            drum_mode: old.drum_mode,
+            generator_state: old.generator_state,
+            note_on: old.note_on,
+            accent_on: old.accent_on,
+            chain: PatternChain::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::pattern_generator::DNB_MAX_STEPS;
+    use super::*;
+
+    /// Storage as the firmware before the song mode chain saved it: faders at 100, Euclidean
+    /// offsets of 1, 2 and 3, the accent muted, DnB mode and a DnB pattern with a kick on every
+    /// fourth step
+    fn legacy_blob() -> heapless::Vec<u8, 512> {
+        let mut data = heapless::Vec::new();
+        // Faders, shift faders, Euclidean offsets and clock division
+        data.extend_from_slice(&[100; K_NUM_PARTS + 1 + K_NUM_PARTS])
+            .unwrap();
+        data.extend_from_slice(&[1, 2, 3]).unwrap();
+        data.push(100).unwrap();
+        data.extend_from_slice(&[0, 0, 0, 1]).unwrap();
+        data.push(2).unwrap();
+        // Generator state: steps, pulse and pulse duration, then the current and base pattern
+        data.extend_from_slice(&[5, 1, 2, 3, 4, 6]).unwrap();
+        for _ in 0..2 {
+            for step in 0..DNB_MAX_STEPS {
+                data.push((step % 4 == 0) as u8).unwrap();
+            }
+            data.extend_from_slice(&[0; 2 * DNB_MAX_STEPS + 1 + DNB_MAX_STEPS])
+                .unwrap();
+            data.push(16).unwrap();
+        }
+        // Notes and accent on
+        data.extend_from_slice(&[1, 0, 0, 1]).unwrap();
+        data
+    }
+
+    #[test]
+    fn legacy_storage_is_converted() {
+        let data = legacy_blob();
+        assert!(postcard::from_bytes::<Storage>(&data).is_err());
+
+        let storage = Storage::decode_legacy(&data).unwrap();
+        assert_eq!(storage.fader_saved, [100; K_NUM_PARTS + 1]);
+        assert_eq!(storage.shift_fader_saved, [100; K_NUM_PARTS]);
+        assert_eq!(storage.euclidean_offset_saved, [1, 2, 3]);
+        assert_eq!(storage.div_fader_saved, 100);
+        assert_eq!(storage.mute_saved, [false, false, false, true]);
+        assert_eq!(storage.drum_mode, 2);
+        let state = storage.generator_state;
+        assert_eq!(state.sequence_step, 5);
+        assert_eq!(state.euclidean_step, [1, 2, 3]);
+        assert_eq!(state.pulse, 4);
+        assert_eq!(state.pulse_duration_counter, 6);
+        for pattern in [state.current_dnb_pattern, state.base_dnb_pattern] {
+            assert!((0..DNB_MAX_STEPS).all(|step| pattern.kick[step] == (step % 4 == 0)));
+            assert_eq!(pattern.snare, [false; DNB_MAX_STEPS]);
+            assert_eq!(pattern.steps, 16);
+        }
+        assert_eq!(storage.note_on, [true, false, false]);
+        assert!(storage.accent_on);
+        assert_eq!(storage.chain, PatternChain::default());
+    }
+
+    #[test]
+    fn legacy_storage_has_to_match_exactly() {
+        let data = legacy_blob();
+        assert!(Storage::decode_legacy(&data[..data.len() - 1]).is_none());
+        let mut longer = data.clone();
+        longer.push(0).unwrap();
+        assert!(Storage::decode_legacy(&longer).is_none());
+
+        let mut buf = [0u8; 512];
+        let current = postcard::to_slice(&Storage::default(), &mut buf).unwrap();
+        assert!(Storage::decode_legacy(current).is_none());
+    }
+
+    #[test]
+    fn largest_storage_fits_in_one_blob() {
+        let mut storage = Storage {
+            fader_saved: [4095; K_NUM_PARTS + 1],
+            shift_fader_saved: [4095; K_NUM_PARTS],
+            div_fader_saved: 4095,
+            ..Storage::default()
+        };
+        storage.generator_state.pulse_duration_counter = u16::MAX;
+        let mut buf = [0u8; 512];
+        let len = postcard::to_slice(&storage, &mut buf).unwrap().len();
+        // The FRAM task stores up to 384 bytes, one of them is the app id
+        assert!(len <= 383, "{len} bytes");
+    }
+}
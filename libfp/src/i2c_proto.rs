@@ -1,8 +1,9 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
+    teletype::note_to_fader,
     types::{RegressionValuesInput, RegressionValuesOutput},
-    Range,
+    MidiNote, Range,
 };
 
 /// Maximum size of a serialized message in bytes.
@@ -34,6 +35,65 @@ pub enum WriteCommand {
     DacSetVoltage(usize, Range, u16),
     /// Reset the device
     SysReset,
+    /// Expander: set a 0-10V output (channel, value)
+    ExpanderCv(usize, u16),
+    /// Expander: set a 0-10V output to a 1V/oct note (channel, note)
+    ExpanderNote(usize, MidiNote),
+    /// Expander: set a gate output (channel, high)
+    ExpanderGate(usize, bool),
+}
+
+/// Number of jacks an expander drives
+pub const EXPANDER_CHANNELS: usize = 16;
+
+/// How an expander jack has to be configured for an action
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExpanderJack {
+    /// 0-10V DAC output
+    Cv,
+    /// GPO gate output
+    Gate,
+}
+
+/// What an expander does with a command from the leader
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExpanderAction {
+    /// Write a value to the DAC of a channel
+    SetCv(usize, u16),
+    /// Set the gate of a channel high or low
+    SetGate(usize, bool),
+}
+
+impl ExpanderAction {
+    pub fn channel(&self) -> usize {
+        match *self {
+            ExpanderAction::SetCv(chan, _) | ExpanderAction::SetGate(chan, _) => chan,
+        }
+    }
+
+    /// The jack configuration the action needs
+    pub fn jack(&self) -> ExpanderJack {
+        match self {
+            ExpanderAction::SetCv(..) => ExpanderJack::Cv,
+            ExpanderAction::SetGate(..) => ExpanderJack::Gate,
+        }
+    }
+}
+
+impl WriteCommand {
+    /// The expander action for a command. `None` for commands that aren't meant for an expander
+    /// and for channels the expander doesn't have.
+    pub fn expander_action(&self) -> Option<ExpanderAction> {
+        let action = match *self {
+            WriteCommand::ExpanderCv(chan, value) => ExpanderAction::SetCv(chan, value.min(4095)),
+            WriteCommand::ExpanderNote(chan, note) => {
+                ExpanderAction::SetCv(chan, note_to_fader(note))
+            }
+            WriteCommand::ExpanderGate(chan, high) => ExpanderAction::SetGate(chan, high),
+            _ => return None,
+        };
+        (action.channel() < EXPANDER_CHANNELS).then_some(action)
+    }
 }
 
 /// Responses sent from the device to the leader
@@ -67,3 +127,68 @@ pub enum ErrorCode {
     InvalidChannel,
     MeasurementFailed,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expander_actions() {
+        assert_eq!(
+            WriteCommand::ExpanderCv(3, 2000).expander_action(),
+            Some(ExpanderAction::SetCv(3, 2000))
+        );
+        assert_eq!(
+            WriteCommand::ExpanderNote(0, MidiNote::from(60u8)).expander_action(),
+            Some(ExpanderAction::SetCv(0, 2048))
+        );
+        assert_eq!(
+            WriteCommand::ExpanderGate(15, true).expander_action(),
+            Some(ExpanderAction::SetGate(15, true))
+        );
+        // Values are clamped to 12 bits
+        assert_eq!(
+            WriteCommand::ExpanderCv(1, u16::MAX).expander_action(),
+            Some(ExpanderAction::SetCv(1, 4095))
+        );
+    }
+
+    #[test]
+    fn test_expander_ignores_other_commands_and_channels() {
+        assert_eq!(WriteCommand::CalibStart.expander_action(), None);
+        assert_eq!(WriteCommand::SysReset.expander_action(), None);
+        assert_eq!(
+            WriteCommand::DacSetVoltage(0, Range::_0_10V, 100).expander_action(),
+            None
+        );
+        assert_eq!(
+            WriteCommand::ExpanderCv(EXPANDER_CHANNELS, 100).expander_action(),
+            None
+        );
+        assert_eq!(
+            WriteCommand::ExpanderGate(EXPANDER_CHANNELS, true).expander_action(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_expander_jacks() {
+        assert_eq!(ExpanderAction::SetCv(2, 0).jack(), ExpanderJack::Cv);
+        assert_eq!(ExpanderAction::SetGate(2, false).jack(), ExpanderJack::Gate);
+        assert_eq!(ExpanderAction::SetGate(7, false).channel(), 7);
+    }
+
+    #[test]
+    fn test_expander_commands_roundtrip() {
+        let mut buf = [0u8; MAX_MESSAGE_SIZE];
+        for command in [
+            WriteCommand::ExpanderCv(5, 4095),
+            WriteCommand::ExpanderNote(6, MidiNote::from(48u8)),
+            WriteCommand::ExpanderGate(7, true),
+        ] {
+            let bytes = postcard::to_slice(&command, &mut buf).unwrap();
+            let decoded: WriteCommand = postcard::from_bytes(bytes).unwrap();
+            assert_eq!(decoded, command);
+        }
+    }
+}
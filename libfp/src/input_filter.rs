@@ -0,0 +1,162 @@
+use heapless::Deque;
+
+/// Largest number of samples an `InputFilter` averages over
+pub const INPUT_FILTER_MAX_WINDOW: usize = 16;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FilterMode {
+    /// Mean of the window, smooths out noise
+    #[default]
+    MovingAverage,
+    /// Median of the window, drops single spikes but keeps steps sharp
+    Median,
+}
+
+/// Filter for noisy CV inputs over the last `window` samples
+#[derive(Clone, Debug)]
+pub struct InputFilter {
+    mode: FilterMode,
+    window: usize,
+    samples: Deque<u16, INPUT_FILTER_MAX_WINDOW>,
+}
+
+impl InputFilter {
+    /// The window is clamped to `1..=INPUT_FILTER_MAX_WINDOW`, a window of 1 passes the input
+    /// through unchanged
+    pub fn new(mode: FilterMode, window: usize) -> Self {
+        Self {
+            mode,
+            window: window.clamp(1, INPUT_FILTER_MAX_WINDOW),
+            samples: Deque::new(),
+        }
+    }
+
+    /// Add a sample and return the filtered value. Until the window is full the filter works on
+    /// the samples it has.
+    pub fn push(&mut self, value: u16) -> u16 {
+        while self.samples.len() >= self.window {
+            self.samples.pop_front();
+        }
+        let _ = self.samples.push_back(value);
+
+        match self.mode {
+            FilterMode::MovingAverage => {
+                let sum: u32 = self.samples.iter().map(|&s| s as u32).sum();
+                (sum / self.samples.len() as u32) as u16
+            }
+            FilterMode::Median => {
+                let mut sorted = [0u16; INPUT_FILTER_MAX_WINDOW];
+                let len = self.samples.len();
+                for (dst, &src) in sorted.iter_mut().zip(self.samples.iter()) {
+                    *dst = src;
+                }
+                let sorted = &mut sorted[..len];
+                sorted.sort_unstable();
+                sorted[len / 2]
+            }
+        }
+    }
+
+    /// Forget all samples, e.g. when the input source changes
+    pub fn reset(&mut self) {
+        self.samples.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Deterministic noise of +/- 40 around a center value
+    fn noisy(center: u16, i: usize) -> u16 {
+        const NOISE: [i16; 7] = [40, -25, 10, -40, 30, -5, -10];
+        (center as i16 + NOISE[i % NOISE.len()]) as u16
+    }
+
+    fn variance(values: &[u16]) -> f32 {
+        let mean = values.iter().map(|&v| v as f32).sum::<f32>() / values.len() as f32;
+        values
+            .iter()
+            .map(|&v| (v as f32 - mean) * (v as f32 - mean))
+            .sum::<f32>()
+            / values.len() as f32
+    }
+
+    #[test]
+    fn test_reduces_noise() {
+        for mode in [FilterMode::MovingAverage, FilterMode::Median] {
+            let mut filter = InputFilter::new(mode, 8);
+            let mut raw = [0u16; 64];
+            let mut filtered = [0u16; 64];
+            for i in 0..64 {
+                raw[i] = noisy(2000, i);
+                filtered[i] = filter.push(raw[i]);
+            }
+            // Skip the samples where the window fills up
+            assert!(variance(&filtered[8..]) < variance(&raw[8..]) / 4.0);
+            assert!(filtered[8..].iter().all(|&v| v.abs_diff(2000) <= 20));
+        }
+    }
+
+    #[test]
+    fn test_window_of_one_passes_through() {
+        for mode in [FilterMode::MovingAverage, FilterMode::Median] {
+            let mut filter = InputFilter::new(mode, 0);
+            for i in 0..16 {
+                assert_eq!(filter.push(noisy(1000, i)), noisy(1000, i));
+            }
+        }
+    }
+
+    #[test]
+    fn test_moving_average() {
+        let mut filter = InputFilter::new(FilterMode::MovingAverage, 4);
+        assert_eq!(filter.push(100), 100);
+        assert_eq!(filter.push(200), 150);
+        assert_eq!(filter.push(300), 200);
+        assert_eq!(filter.push(400), 250);
+        // The oldest sample drops out
+        assert_eq!(filter.push(500), 350);
+        assert_eq!(filter.push(4095), 1323);
+    }
+
+    #[test]
+    fn test_median_drops_spikes() {
+        let mut filter = InputFilter::new(FilterMode::Median, 5);
+        for _ in 0..5 {
+            filter.push(1000);
+        }
+        assert_eq!(filter.push(4095), 1000);
+        assert_eq!(filter.push(1000), 1000);
+        assert_eq!(filter.push(0), 1000);
+        // A step comes through once it fills half the window
+        for _ in 0..5 {
+            filter.push(1000);
+        }
+        assert_eq!(filter.push(3000), 1000);
+        assert_eq!(filter.push(3000), 1000);
+        assert_eq!(filter.push(3000), 3000);
+    }
+
+    #[test]
+    fn test_window_is_bounded() {
+        let mut filter = InputFilter::new(FilterMode::MovingAverage, 1000);
+        for _ in 0..INPUT_FILTER_MAX_WINDOW {
+            filter.push(0);
+        }
+        // Only the last INPUT_FILTER_MAX_WINDOW samples count
+        for _ in 0..INPUT_FILTER_MAX_WINDOW {
+            filter.push(1600);
+        }
+        assert_eq!(filter.push(1600), 1600);
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut filter = InputFilter::new(FilterMode::MovingAverage, 4);
+        filter.push(4000);
+        filter.push(4000);
+        filter.reset();
+        assert_eq!(filter.push(100), 100);
+    }
+}
@@ -1,9 +1,10 @@
 use postcard_bindgen::PostcardBindings;
 use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[repr(usize)]
 pub enum LatchLayer {
+    #[default]
     Main,
     Alt,
     Third,
@@ -19,6 +20,18 @@ impl From<bool> for LatchLayer {
     }
 }
 
+impl LatchLayer {
+    /// Layer to edit for apps that remember the layer they were left on. Without shift the
+    /// stored layer is edited, holding shift switches to the other one of Main and Alt.
+    pub fn from_stored(stored: LatchLayer, is_alternate_layer: bool) -> Self {
+        match (stored, is_alternate_layer) {
+            (layer, false) => layer,
+            (Self::Alt, true) => Self::Main,
+            (_, true) => Self::Alt,
+        }
+    }
+}
+
 /// Defines how a fader should take control of a value when switching layers or starting a session.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize, PostcardBindings)]
 pub enum TakeoverMode {
@@ -277,6 +290,40 @@ impl AnalogLatch {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_layer_from_stored() {
+        use LatchLayer::*;
+        let cases = [
+            (Main, false, Main),
+            (Main, true, Alt),
+            (Alt, false, Alt),
+            (Alt, true, Main),
+            (Third, false, Third),
+            (Third, true, Alt),
+        ];
+        for (stored, shift, layer) in cases {
+            assert_eq!(LatchLayer::from_stored(stored, shift), layer);
+        }
+        // Without a stored layer it's the same as deriving it from shift
+        for shift in [false, true] {
+            assert_eq!(
+                LatchLayer::from_stored(LatchLayer::default(), shift),
+                LatchLayer::from(shift)
+            );
+        }
+    }
+
+    #[test]
+    fn test_stored_layer_restores() {
+        let mut buf = [0u8; 8];
+        for layer in [LatchLayer::Main, LatchLayer::Alt, LatchLayer::Third] {
+            let bytes = postcard::to_slice(&layer, &mut buf).unwrap();
+            assert_eq!(postcard::from_bytes::<LatchLayer>(bytes).unwrap(), layer);
+        }
+        // A layer that doesn't exist isn't restored
+        assert!(postcard::from_bytes::<LatchLayer>(&[7]).is_err());
+    }
+
     #[test]
     fn test_new_creates_latched_state() {
         let latch = AnalogLatch::new(100, TakeoverMode::Pickup);
@@ -0,0 +1,148 @@
+/// Position of a blend at `other`, blends take a 12-bit position like the faders
+pub const LERP_MAX: u16 = 4095;
+
+/// Interpolation between two values, e.g. to morph between two scenes of an app's storage.
+/// `t` goes from `0` (all `self`) to `LERP_MAX` (all `other`), larger values are clamped.
+pub trait Lerp {
+    fn lerp(&self, other: &Self, t: u16) -> Self;
+}
+
+impl Lerp for u16 {
+    fn lerp(&self, other: &Self, t: u16) -> Self {
+        let t = t.min(LERP_MAX) as i32;
+        let a = *self as i32;
+        let b = *other as i32;
+        (a + (b - a) * t / LERP_MAX as i32) as u16
+    }
+}
+
+impl Lerp for u8 {
+    fn lerp(&self, other: &Self, t: u16) -> Self {
+        (*self as u16).lerp(&(*other as u16), t) as u8
+    }
+}
+
+impl Lerp for i16 {
+    fn lerp(&self, other: &Self, t: u16) -> Self {
+        let t = t.min(LERP_MAX) as i32;
+        let a = *self as i32;
+        let b = *other as i32;
+        (a + (b - a) * t / LERP_MAX as i32) as i16
+    }
+}
+
+impl Lerp for bool {
+    fn lerp(&self, other: &Self, t: u16) -> Self {
+        *lerp_step(self, other, t)
+    }
+}
+
+impl<T: Lerp, const N: usize> Lerp for [T; N] {
+    fn lerp(&self, other: &Self, t: u16) -> Self {
+        core::array::from_fn(|i| self[i].lerp(&other[i], t))
+    }
+}
+
+/// Blend for values that can't be interpolated, like enums: switches from `a` to `b` halfway
+pub fn lerp_step<'a, T>(a: &'a T, b: &'a T, t: u16) -> &'a T {
+    if t < LERP_MAX / 2 + 1 {
+        a
+    } else {
+        b
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Waveform;
+
+    // Storage like the panner app's
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Storage {
+        muted: bool,
+        att: u16,
+        pan: u16,
+        offsets: [i16; 2],
+        wave: Waveform,
+    }
+
+    impl Lerp for Storage {
+        fn lerp(&self, other: &Self, t: u16) -> Self {
+            Self {
+                muted: self.muted.lerp(&other.muted, t),
+                att: self.att.lerp(&other.att, t),
+                pan: self.pan.lerp(&other.pan, t),
+                offsets: self.offsets.lerp(&other.offsets, t),
+                wave: *lerp_step(&self.wave, &other.wave, t),
+            }
+        }
+    }
+
+    const A: Storage = Storage {
+        muted: false,
+        att: 4095,
+        pan: 0,
+        offsets: [-2048, 100],
+        wave: Waveform::Sine,
+    };
+    const B: Storage = Storage {
+        muted: true,
+        att: 1000,
+        pan: 4095,
+        offsets: [2047, 100],
+        wave: Waveform::Square,
+    };
+
+    #[test]
+    fn test_ends_are_the_scenes() {
+        assert_eq!(A.lerp(&B, 0), A);
+        assert_eq!(A.lerp(&B, LERP_MAX), B);
+        // Past the end stays at the end
+        assert_eq!(A.lerp(&B, u16::MAX), B);
+    }
+
+    #[test]
+    fn test_halfway() {
+        let mid = A.lerp(&B, 2048);
+        assert_eq!(mid.att, 2548);
+        assert_eq!(mid.pan, 2048);
+        assert_eq!(mid.offsets, [0, 100]);
+        // Discrete values switch halfway
+        assert!(mid.muted);
+        assert_eq!(mid.wave, Waveform::Square);
+        let before = A.lerp(&B, 2047);
+        assert!(!before.muted);
+        assert_eq!(before.wave, Waveform::Sine);
+    }
+
+    #[test]
+    fn test_numbers_move_monotonically() {
+        let mut prev = A;
+        for t in (0..=LERP_MAX).step_by(64) {
+            let blend = A.lerp(&B, t);
+            assert!(blend.att <= prev.att);
+            assert!(blend.pan >= prev.pan);
+            assert!(blend.offsets[0] >= prev.offsets[0]);
+            assert!((1000..=4095).contains(&blend.att));
+            prev = blend;
+        }
+    }
+
+    #[test]
+    fn test_blending_is_symmetric() {
+        for t in [0, 1000, 2048, 3000, LERP_MAX] {
+            let ab = A.lerp(&B, t);
+            let ba = B.lerp(&A, LERP_MAX - t);
+            assert!(ab.att.abs_diff(ba.att) <= 1);
+            assert!(ab.pan.abs_diff(ba.pan) <= 1);
+        }
+    }
+
+    #[test]
+    fn test_small_types() {
+        assert_eq!(0u8.lerp(&255, 2048), 127);
+        assert_eq!(255u8.lerp(&0, LERP_MAX), 0);
+        assert_eq!(i16::MIN.lerp(&i16::MAX, LERP_MAX), i16::MAX);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::Curve;
+
+/// Number of positions in one LFO cycle, matching the waveform tables
+pub const LFO_CYCLE: f32 = 4096.0;
+
+/// Phase increment per millisecond of a free running LFO for a 12-bit rate value
+pub fn lfo_free_speed(value: u16) -> f32 {
+    Curve::Exponential.at(value) as f32 * 0.015 + 0.0682
+}
+
+/// Phase increment per millisecond of an LFO that completes one cycle every `div` clock ticks
+/// (24 ppqn), given the measured length of a quarter note in milliseconds
+pub fn lfo_synced_speed(beat_millis: u32, div: u32) -> f32 {
+    4095. / ((beat_millis.max(1) as f32 * div as f32) / 24.)
+}
+
+/// Advance an LFO position by `speed`, wrapping around at the end of the cycle
+pub fn lfo_advance(pos: f32, speed: f32) -> f32 {
+    (pos + speed) % LFO_CYCLE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Waveform;
+
+    fn cycle_millis(speed: f32) -> u32 {
+        let mut pos = 0.0;
+        let mut millis = 0;
+        loop {
+            let next = lfo_advance(pos, speed);
+            millis += 1;
+            if next < pos {
+                return millis;
+            }
+            pos = next;
+        }
+    }
+
+    #[test]
+    fn test_free_speed_increases_with_value() {
+        let mut prev = lfo_free_speed(0);
+        assert!(prev > 0.0);
+        for value in (1..=4095).step_by(64) {
+            let speed = lfo_free_speed(value);
+            assert!(speed >= prev);
+            prev = speed;
+        }
+    }
+
+    #[test]
+    fn test_free_speed_range() {
+        // Slowest is one cycle in roughly a minute, fastest is about 15Hz
+        let slowest = cycle_millis(lfo_free_speed(0));
+        assert!((59_000..61_000).contains(&slowest));
+        let fastest = cycle_millis(lfo_free_speed(4095));
+        assert!((60..70).contains(&fastest));
+    }
+
+    #[test]
+    fn test_synced_speed_follows_clock() {
+        // 120 BPM: 500ms per quarter note
+        let quarter = lfo_synced_speed(500, 24);
+        assert!((cycle_millis(quarter) as i32 - 500).abs() <= 1);
+        let bar = lfo_synced_speed(500, 96);
+        assert!((cycle_millis(bar) as i32 - 2000).abs() <= 2);
+        let eighth = lfo_synced_speed(500, 12);
+        assert!((cycle_millis(eighth) as i32 - 250).abs() <= 1);
+    }
+
+    #[test]
+    fn test_synced_speed_handles_zero_beat() {
+        assert!(lfo_synced_speed(0, 24).is_finite());
+    }
+
+    #[test]
+    fn test_advance_wraps() {
+        assert_eq!(lfo_advance(100.0, 10.0), 110.0);
+        assert_eq!(lfo_advance(4090.0, 10.0), 4.0);
+        assert!(lfo_advance(4095.9, 0.2) < 1.0);
+    }
+
+    #[test]
+    fn test_waveform_output_over_cycle() {
+        for wave in [
+            Waveform::Sine,
+            Waveform::Triangle,
+            Waveform::Saw,
+            Waveform::SawInv,
+            Waveform::Square,
+        ] {
+            let mut pos = 0.0;
+            let mut min = u16::MAX;
+            let mut max = 0;
+            for _ in 0..4096 {
+                let value = wave.at(pos as usize);
+                assert!(value <= 4095);
+                min = min.min(value);
+                max = max.max(value);
+                pos = lfo_advance(pos, 1.0);
+            }
+            assert!(min < 50, "{wave:?} min {min}");
+            assert!(max > 4045, "{wave:?} max {max}");
+        }
+    }
+
+    #[test]
+    fn test_saw_waveforms_mirror() {
+        for index in (0..4096).step_by(128) {
+            let saw = Waveform::Saw.at(index) as i32;
+            let inv = Waveform::SawInv.at(index) as i32;
+            assert!((saw + inv - 4095).abs() <= 2);
+        }
+    }
+}
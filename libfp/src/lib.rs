@@ -1,21 +1,45 @@
 #![no_std]
 
-use core::ops::Add;
+use core::ops::{Add, Sub};
 
-use embassy_time::Duration;
+use embassy_time::{Duration, Instant};
 use heapless::Vec;
 use max11300::config::{ADCRANGE, DACRANGE};
 use midly::num::{u4, u7};
 use postcard_bindgen::PostcardBindings;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 
+pub mod blob;
+pub mod burst;
+pub mod coalesce;
 pub mod colors;
 pub mod constants;
+pub mod direction;
+pub mod edge;
+pub mod envelope;
 pub mod ext;
 pub mod fp_grids_lib;
 pub mod i2c_proto;
+pub mod input_filter;
 pub mod latch;
+pub mod lerp;
+pub mod lfo;
+pub mod midi_stream;
+pub mod mpe;
+pub mod note_repeat;
+pub mod poly;
+pub mod portamento;
 pub mod quantizer;
+pub mod sample_hold;
+pub mod self_test;
+pub mod seq8;
+pub mod soft_random;
+pub mod square_seq;
+pub mod stereo;
+pub mod stutter;
+pub mod teletype;
+pub mod trigger_grid;
+pub mod turing;
 pub mod types;
 pub mod utils;
 
@@ -50,12 +74,20 @@ pub const STARTUP_ANIMATION_DURATION: Duration = Duration::from_secs(2);
 /// Range in which the LED brightness is scaled
 pub const LED_BRIGHTNESS_RANGE: core::ops::Range<u8> = 100..255;
 
+/// Longest global output slew in milliseconds
+pub const OUTPUT_SLEW_MAX_MS: u16 = 1000;
+
 pub const CALIBRATION_SCALE_FACTOR: i64 = 1 << 16;
-pub const CALIBRATION_VERSION_LATEST: u8 = 2;
+pub const CALIBRATION_VERSION_LATEST: u8 = 3;
 pub const CALIB_FILE_MAGIC: [u8; 4] = *b"FPBC";
+pub const GLOBAL_CONFIG_VERSION_LATEST: u8 = 2;
+pub const GLOBAL_CONFIG_FILE_MAGIC: [u8; 4] = *b"FPGC";
 
 pub type ConfigMeta<'a> = (usize, &'a str, &'a str, Color, AppIcon, &'a [Param]);
 
+/// Number of layout slots to save performance setups to
+pub const LAYOUT_SLOTS: usize = 8;
+
 /// The config layout is a layout with all the apps in the appropriate spots
 // (app_id, channels, layout_id)
 pub type InnerLayout = [Option<(u8, usize, u8)>; GLOBAL_CHANNELS];
@@ -109,6 +141,32 @@ impl Layout {
         changed
     }
 
+    /// Move the apps towards channel 0 in their current order, closing the gaps between them.
+    /// The layout is validated first and the layout ids are kept. Returns whether anything moved.
+    pub fn compact(&mut self, get_channels: fn(u8) -> Option<usize>) -> bool {
+        let original = self.0;
+        self.validate(get_channels);
+
+        let mut compacted: InnerLayout = [None; GLOBAL_CHANNELS];
+        let mut start_channel = 0;
+        for (app_id, _, channels, layout_id) in self.iter() {
+            // Validated apps don't overlap, so they always fit
+            compacted[start_channel] = Some((app_id, channels, layout_id));
+            start_channel += channels;
+        }
+
+        self.0 = compacted;
+        self.0 != original
+    }
+
+    /// Read a layout saved to a slot. It is validated like the active layout, as the apps may
+    /// have changed since it was saved.
+    pub fn from_slot_bytes(data: &[u8], get_channels: fn(u8) -> Option<usize>) -> Option<Self> {
+        let mut layout = postcard::from_bytes::<Layout>(data).ok()?;
+        layout.validate(get_channels);
+        Some(layout)
+    }
+
     pub fn iter(&self) -> LayoutIter<'_> {
         self.into_iter()
     }
@@ -120,6 +178,22 @@ impl Layout {
     pub fn get_layout_ids(&self) -> Vec<u8, { GLOBAL_CHANNELS }> {
         self.iter().map(|(_, _, _, layout_id)| layout_id).collect()
     }
+
+    pub fn get_app_id(&self, layout_id: u8) -> Option<u8> {
+        self.iter()
+            .find(|&(_, _, _, id)| id == layout_id)
+            .map(|(app_id, _, _, _)| app_id)
+    }
+
+    /// The app params and scenes can be copied from one instance to another. Returns the app id
+    /// if both layout ids are different instances of the same app.
+    pub fn copy_app_id(&self, from_layout_id: u8, to_layout_id: u8) -> Option<u8> {
+        if from_layout_id == to_layout_id {
+            return None;
+        }
+        let app_id = self.get_app_id(from_layout_id)?;
+        (self.get_app_id(to_layout_id)? == app_id).then_some(app_id)
+    }
 }
 
 impl Default for Layout {
@@ -242,6 +316,36 @@ impl From<u8> for Note {
     }
 }
 
+impl Note {
+    /// Name of the note, spelled with sharps
+    pub fn name(&self) -> &'static str {
+        self.name_with_pref(false)
+    }
+
+    /// Name of the note, spelling the black keys with flats ("Db") or sharps ("C#")
+    pub fn name_with_pref(&self, flats: bool) -> &'static str {
+        match (self, flats) {
+            (Note::C, _) => "C",
+            (Note::CSharp, false) => "C#",
+            (Note::CSharp, true) => "Db",
+            (Note::D, _) => "D",
+            (Note::DSharp, false) => "D#",
+            (Note::DSharp, true) => "Eb",
+            (Note::E, _) => "E",
+            (Note::F, _) => "F",
+            (Note::FSharp, false) => "F#",
+            (Note::FSharp, true) => "Gb",
+            (Note::G, _) => "G",
+            (Note::GSharp, false) => "G#",
+            (Note::GSharp, true) => "Ab",
+            (Note::A, _) => "A",
+            (Note::ASharp, false) => "A#",
+            (Note::ASharp, true) => "Bb",
+            (Note::B, _) => "B",
+        }
+    }
+}
+
 impl FromValue for Note {
     fn from_value(value: Value) -> Self {
         match value {
@@ -295,6 +399,62 @@ impl Key {
             Key::HungarianMin => 0b101100111001,
         }
     }
+
+    /// Notes of the scale from C up, e.g. for LED layouts or logging
+    pub fn degrees(&self) -> Vec<Note, 12> {
+        let mask = self.as_u16_key();
+        (0..12u8)
+            .filter(|i| (mask >> (11 - i)) & 1 != 0) // Read from MSB (C) to LSB (B)
+            .map(Note::from)
+            .collect()
+    }
+
+    /// Number of notes in the scale
+    pub fn note_count(&self) -> u8 {
+        self.as_u16_key().count_ones() as u8
+    }
+}
+
+/// Custom scale as a 12-bit note mask, read from the MSB (tonic) to the LSB
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScaleMask(u16);
+
+impl ScaleMask {
+    /// Get the u16 bitmask
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+}
+
+impl Default for ScaleMask {
+    fn default() -> Self {
+        Key::Chromatic.into()
+    }
+}
+
+impl From<u16> for ScaleMask {
+    fn from(value: u16) -> Self {
+        match value & 0b111111111111 {
+            // A scale without notes can't be quantized to
+            0 => Self::default(),
+            mask => Self(mask),
+        }
+    }
+}
+
+impl From<Key> for ScaleMask {
+    fn from(value: Key) -> Self {
+        Self(value.as_u16_key())
+    }
+}
+
+impl FromValue for ScaleMask {
+    fn from_value(value: Value) -> Self {
+        match value {
+            Value::ScaleMask(m) => m.into(),
+            _ => Self::default(),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Serialize, Deserialize, PostcardBindings, PartialEq)]
@@ -305,6 +465,41 @@ pub enum MidiOutMode {
     MidiMerge { sources: MidiIn },
 }
 
+/// MIDI input a message came in on
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MidiInPort {
+    Usb,
+    Din,
+}
+
+impl MidiInPort {
+    /// Index of the port in `MidiIn`
+    pub fn index(&self) -> usize {
+        match self {
+            MidiInPort::Usb => 0,
+            MidiInPort::Din => 1,
+        }
+    }
+
+    /// Index in `MidiOut` of the output on the same connection. USB is a single two-way
+    /// connection to the host, MIDI In has no output of its own.
+    pub fn return_out(&self) -> Option<usize> {
+        match self {
+            MidiInPort::Usb => Some(0),
+            MidiInPort::Din => None,
+        }
+    }
+}
+
+impl From<MidiInPort> for ClockSrc {
+    fn from(value: MidiInPort) -> Self {
+        match value {
+            MidiInPort::Usb => ClockSrc::MidiUsb,
+            MidiInPort::Din => ClockSrc::MidiIn,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Serialize, Deserialize, PostcardBindings, PartialEq)]
 pub struct MidiOutConfig {
     pub send_clock: bool,
@@ -321,12 +516,37 @@ impl MidiOutConfig {
             mode: MidiOutMode::Local,
         }
     }
+
+    /// Whether messages coming in on `port` are passed through to this output
+    pub fn passes_through(&self, port: MidiInPort) -> bool {
+        match self.mode {
+            MidiOutMode::MidiThru { sources } | MidiOutMode::MidiMerge { sources } => {
+                sources.0[port.index()]
+            }
+            MidiOutMode::None | MidiOutMode::Local => false,
+        }
+    }
 }
 
+/// Routing of the MIDI outputs, each one picks what it sends with its mode:
+///
+/// | Mode      | From apps | From USB in      | From MIDI In     |
+/// |-----------|-----------|------------------|------------------|
+/// | None      | no        | no               | no               |
+/// | Local     | yes       | no               | no               |
+/// | MidiThru  | no        | if in `sources`  | if in `sources`  |
+/// | MidiMerge | yes       | if in `sources`  | if in `sources`  |
+///
+/// A message is never passed back out of the connection it came in on, so merging USB into the
+/// USB output can't echo the host's messages back to it. MIDI In to Out 1 or Out 2 is a regular
+/// thru. Incoming clock and transport are not passed through, the outputs send the internal
+/// clock according to `send_clock` and `send_transport`.
 #[derive(Clone, Serialize, Deserialize, PostcardBindings, PartialEq)]
 pub struct MidiConfig {
     // [usb, out1, out2]
     pub outs: [MidiOutConfig; 3],
+    /// Send Active Sensing on Out 1 and Out 2
+    pub active_sensing: bool,
 }
 
 #[allow(clippy::new_without_default)]
@@ -334,8 +554,14 @@ impl MidiConfig {
     pub const fn new() -> Self {
         Self {
             outs: [MidiOutConfig::new(); 3],
+            active_sensing: false,
         }
     }
+
+    /// Outputs that pass through messages coming in on `port`
+    pub fn passthrough_targets(&self, port: MidiInPort) -> MidiOut {
+        MidiOut(self.outs.map(|out| out.passes_through(port))).without_return_to(port)
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize, PostcardBindings, PartialEq)]
@@ -396,6 +622,175 @@ pub enum ClockDivision {
     _384 = 384,
 }
 
+impl ClockDivision {
+    /// Pulses per quarter note of a clock output at this division, `None` if it pulses less
+    /// than once per quarter note
+    pub fn ppqn(self) -> Option<u8> {
+        let div = self as u16;
+        (div <= 24).then_some((24 / div) as u8)
+    }
+}
+
+/// Counts the 24 PPQN ticks of a clock output running at a division of the clock
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ClockDivider {
+    count: u16,
+}
+
+impl ClockDivider {
+    /// Count a tick, returns whether the output pulses on it. The first tick after a reset
+    /// always pulses.
+    pub fn tick(&mut self, division: ClockDivision) -> bool {
+        let pulse = self.count == 0;
+        self.count += 1;
+        if self.count >= division as u16 {
+            self.count = 0;
+        }
+        pulse
+    }
+
+    pub fn reset(&mut self) {
+        self.count = 0;
+    }
+}
+
+/// Transport messages of the clock
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TransportEvent {
+    /// Start playing from the top, resets the phase
+    Start,
+    /// Resume playing where the clock stopped
+    Continue,
+    Stop,
+    /// Reset the phase without starting or stopping
+    Reset,
+}
+
+/// Whether the clock is running and how many ticks it counted since the last phase reset,
+/// following its transport messages
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TransportState {
+    running: bool,
+    ticks: u64,
+}
+
+impl TransportState {
+    pub const fn new(running: bool) -> Self {
+        Self { running, ticks: 0 }
+    }
+
+    /// Apply a transport message, returns whether the running state changed
+    pub fn apply(&mut self, event: TransportEvent) -> bool {
+        let running = match event {
+            TransportEvent::Start | TransportEvent::Continue => true,
+            TransportEvent::Stop => false,
+            TransportEvent::Reset => self.running,
+        };
+        if matches!(event, TransportEvent::Start | TransportEvent::Reset) {
+            // Wraps around, so the first tick after a reset is 0
+            self.ticks = u64::MAX;
+        }
+        let changed = running != self.running;
+        self.running = running;
+        changed
+    }
+
+    /// Count a tick, returns the tick counter
+    pub fn tick(&mut self) -> u64 {
+        self.ticks = self.ticks.wrapping_add(1);
+        self.ticks
+    }
+
+    /// Ticks since the last phase reset, `u64::MAX` until the first tick after it
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+}
+
+/// Rolling average over the last `N` intervals between external clock pulses, to measure the
+/// tempo of the clock
+#[derive(Clone, Copy, Debug)]
+pub struct PulseIntervals<const N: usize> {
+    history: [Duration; N],
+    idx: usize,
+}
+
+impl<const N: usize> PulseIntervals<N> {
+    pub const fn new() -> Self {
+        Self {
+            history: [Duration::from_ticks(0); N],
+            idx: 0,
+        }
+    }
+
+    /// Add the interval since the previous pulse, returns the new average
+    pub fn push(&mut self, interval: Duration) -> Option<Duration> {
+        if N == 0 {
+            return None;
+        }
+        self.history[self.idx] = interval;
+        self.idx = (self.idx + 1) % N;
+        self.average()
+    }
+
+    /// Average of the intervals so far, `None` before the first one
+    pub fn average(&self) -> Option<Duration> {
+        let (sum, count) = self
+            .history
+            .iter()
+            .filter(|d| d.as_ticks() > 0)
+            .fold((0_u64, 0_u64), |(sum, count), d| {
+                (sum + d.as_ticks(), count + 1)
+            });
+        (count > 0).then(|| Duration::from_ticks(sum / count))
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl<const N: usize> Default for PulseIntervals<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Taps further apart than this start a new tap tempo sequence
+pub const TAP_TEMPO_TIMEOUT: Duration = Duration::from_secs(2);
+/// Range of the BPM set by tap tempo
+pub const TAP_TEMPO_BPM_RANGE: core::ops::RangeInclusive<f32> = 30.0..=300.0;
+
+/// Tap tempo for the internal clock, averaging the intervals of the last few taps. Every tap
+/// is a quarter note.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TapTempo {
+    last_tap: Option<Instant>,
+    intervals: PulseIntervals<4>,
+}
+
+impl TapTempo {
+    /// Register a tap, returns the new BPM from the second tap of a sequence on
+    pub fn tap(&mut self, now: Instant) -> Option<f32> {
+        let interval = self
+            .last_tap
+            .map(|last| now.saturating_duration_since(last))
+            .filter(|&interval| interval <= TAP_TEMPO_TIMEOUT);
+        self.last_tap = Some(now);
+        let Some(interval) = interval else {
+            self.intervals.reset();
+            return None;
+        };
+        let average = self.intervals.push(interval)?;
+        utils::clock_duration_to_bpm(average, 1)
+            .map(|bpm| bpm.clamp(*TAP_TEMPO_BPM_RANGE.start(), *TAP_TEMPO_BPM_RANGE.end()))
+    }
+}
+
 #[derive(Clone, Serialize, PartialEq, Deserialize, PostcardBindings)]
 #[repr(u8)]
 pub enum AuxJackMode {
@@ -413,6 +808,8 @@ pub struct GlobalConfig {
     pub midi: MidiConfig,
     pub quantizer: QuantizerConfig,
     pub takeover_mode: TakeoverMode,
+    /// Time in milliseconds a full scale jump takes on the CV outputs, 0 turns the slew off
+    pub output_slew: u16,
 }
 
 #[allow(clippy::new_without_default)]
@@ -430,10 +827,14 @@ impl GlobalConfig {
             midi: MidiConfig::new(),
             quantizer: QuantizerConfig::new(),
             takeover_mode: TakeoverMode::Pickup,
+            output_slew: 0,
         }
     }
 
     pub const fn validate(&mut self) {
+        if self.output_slew > OUTPUT_SLEW_MAX_MS {
+            self.output_slew = OUTPUT_SLEW_MAX_MS;
+        }
         match self.clock.clock_src {
             ClockSrc::Atom => {
                 self.aux[0] = AuxJackMode::None;
@@ -461,6 +862,88 @@ impl GlobalConfig {
     }
 }
 
+/// The global config as it is stored, tagged with a version so older layouts can be converted
+#[derive(Serialize, Deserialize)]
+pub struct GlobalConfigFile {
+    pub magic: [u8; 4],
+    pub version: u8,
+    pub config: GlobalConfig,
+}
+
+impl GlobalConfigFile {
+    pub fn new(config: GlobalConfig) -> Self {
+        Self {
+            magic: GLOBAL_CONFIG_FILE_MAGIC,
+            version: GLOBAL_CONFIG_VERSION_LATEST,
+            config,
+        }
+    }
+
+    /// Decode a stored global config. A config in an older layout is converted, which is
+    /// returned as `true` so it can be stored again in the latest one.
+    pub fn decode(data: &[u8]) -> Option<(GlobalConfig, bool)> {
+        if data.starts_with(&GLOBAL_CONFIG_FILE_MAGIC) {
+            return match data.get(4) {
+                Some(&GLOBAL_CONFIG_VERSION_LATEST) => postcard::from_bytes::<Self>(data)
+                    .ok()
+                    .map(|file| (file.config, false)),
+                _ => None,
+            };
+        }
+        // Configs were stored without a version before. Firmware with the output slew stored it
+        // after the other settings, but had no Active Sensing in the MIDI config yet. The whole
+        // data has to be used up, so a different layout isn't misread as this one.
+        let (old, rest) = postcard::take_from_bytes::<GlobalConfigV1>(data).ok()?;
+        let output_slew = match rest {
+            [] => 0,
+            rest => match postcard::take_from_bytes::<u16>(rest) {
+                Ok((output_slew, [])) => output_slew,
+                _ => return None,
+            },
+        };
+        let mut config = GlobalConfig::from(old);
+        config.output_slew = output_slew;
+        Some((config, true))
+    }
+}
+
+/// MidiConfig of the unversioned global config
+#[derive(Deserialize)]
+pub struct MidiConfigV1 {
+    pub outs: [MidiOutConfig; 3],
+}
+
+/// The global config as it was stored before it had a version, without Active Sensing. The
+/// output slew may follow it.
+#[derive(Deserialize)]
+pub struct GlobalConfigV1 {
+    pub aux: [AuxJackMode; 3],
+    pub clock: ClockConfig,
+    pub i2c_mode: I2cMode,
+    pub led_brightness: u8,
+    pub midi: MidiConfigV1,
+    pub quantizer: QuantizerConfig,
+    pub takeover_mode: TakeoverMode,
+}
+
+impl From<GlobalConfigV1> for GlobalConfig {
+    fn from(old: GlobalConfigV1) -> Self {
+        Self {
+            aux: old.aux,
+            clock: old.clock,
+            i2c_mode: old.i2c_mode,
+            led_brightness: old.led_brightness,
+            midi: MidiConfig {
+                outs: old.midi.outs,
+                active_sensing: false,
+            },
+            quantizer: old.quantizer,
+            takeover_mode: old.takeover_mode,
+            output_slew: 0,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, PostcardBindings)]
 pub enum Curve {
     #[default]
@@ -497,6 +980,28 @@ impl FromValue for Curve {
     }
 }
 
+/// Smallest 12-bit velocity that is still a note on (MIDI velocity 1)
+const MIN_NOTE_ON_VELOCITY: u16 = 33;
+
+/// Curve for the velocity of outgoing MIDI notes, to make up for gear with a non-linear velocity
+/// response
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct VelocityCurve(pub Curve);
+
+impl VelocityCurve {
+    /// Shape a 12-bit velocity. A note on never gets curved down to velocity 0, which would turn
+    /// it into a note off.
+    pub fn apply(&self, velocity: u16) -> u16 {
+        let velocity = velocity.min(4095);
+        let curved = self.0.at(velocity);
+        if velocity >= MIN_NOTE_ON_VELOCITY {
+            curved.max(MIN_NOTE_ON_VELOCITY)
+        } else {
+            curved
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, PostcardBindings)]
 pub enum Waveform {
     #[default]
@@ -586,6 +1091,29 @@ impl From<usize> for Color {
     }
 }
 
+// Once around the color wheel, starting with red for C
+const PITCH_CLASS_COLORS: [Color; 12] = [
+    Color::Red,
+    Color::Orange,
+    Color::Yellow,
+    Color::Lime,
+    Color::Green,
+    Color::PaleGreen,
+    Color::Cyan,
+    Color::SkyBlue,
+    Color::LightBlue,
+    Color::Blue,
+    Color::Violet,
+    Color::Pink,
+];
+
+impl Color {
+    /// Color of a pitch class, 0 being C
+    pub fn from_pitch_class(pitch_class: u8) -> Self {
+        PITCH_CLASS_COLORS[pitch_class as usize % 12]
+    }
+}
+
 impl From<Color> for RGB8 {
     fn from(value: Color) -> Self {
         match value {
@@ -625,6 +1153,7 @@ pub enum Brightness {
     Low,
     Mid,
     High,
+    /// Linear in perceived brightness, the LED task gamma corrects every frame on output
     Custom(u8),
 }
 
@@ -666,15 +1195,19 @@ pub enum AppIcon {
 #[derive(Clone, Copy, Serialize, PostcardBindings)]
 pub enum Param {
     None,
+    /// A `step` of `0` leaves the granularity to the configurator (`1`)
     i32 {
         name: &'static str,
         min: i32,
         max: i32,
+        step: i32,
     },
+    /// A `step` of `0.0` leaves the granularity to the configurator (any value)
     f32 {
         name: &'static str,
         min: f32,
         max: f32,
+        step: f32,
     },
     bool {
         name: &'static str,
@@ -716,6 +1249,9 @@ pub enum Param {
     },
     MidiOut,
     MidiNrpn,
+    ScaleMask {
+        name: &'static str,
+    },
 }
 
 #[allow(non_camel_case_types)]
@@ -737,6 +1273,7 @@ pub enum Value {
     MidiNote(MidiNote),
     MidiOut(MidiOut),
     MidiNrpn(bool),
+    ScaleMask(u16),
 }
 
 impl From<Curve> for Value {
@@ -805,6 +1342,12 @@ impl From<MidiOut> for Value {
     }
 }
 
+impl From<ScaleMask> for Value {
+    fn from(value: ScaleMask) -> Self {
+        Value::ScaleMask(value.0)
+    }
+}
+
 impl From<i32> for Value {
     fn from(value: i32) -> Self {
         Value::i32(value)
@@ -846,8 +1389,22 @@ pub enum ConfigMsgIn {
         values: [Option<Value>; APP_MAX_PARAMS],
     },
     FactoryReset,
+    SaveLayoutSlot(u8),
+    LoadLayoutSlot(u8),
+    GetLayoutSlots,
+    CopyAppParams {
+        from_layout_id: u8,
+        to_layout_id: u8,
+        // Also copy the scenes saved for the app
+        scenes: bool,
+    },
+    ResetAppParams {
+        layout_id: u8,
+    },
 }
 
+/// Replies to `ConfigMsgIn`. The configurator reads exactly one reply (or one batch) per request,
+/// so nothing may be sent without being asked for.
 #[derive(Clone, Serialize, PostcardBindings)]
 #[allow(clippy::large_enum_variant)]
 pub enum ConfigMsgOut<'a> {
@@ -858,6 +1415,66 @@ pub enum ConfigMsgOut<'a> {
     Layout(Layout),
     AppConfig(u8, usize, ConfigMeta<'a>),
     AppState(u8, &'a [Value]),
+    // Which of the layout slots hold a layout
+    LayoutSlots([bool; LAYOUT_SLOTS]),
+    // The params could not be copied, the layout ids don't hold the same app
+    CopyRejected,
+}
+
+/// The params of all apps in a layout, collected from the apps to be sent as one batch
+pub struct AppStateBatch {
+    layout_ids: Vec<u8, GLOBAL_CHANNELS>,
+    // Indexed like `layout_ids`
+    states: [Option<Vec<Value, APP_MAX_PARAMS>>; GLOBAL_CHANNELS],
+}
+
+impl AppStateBatch {
+    pub fn new(layout: &Layout) -> Self {
+        Self {
+            layout_ids: layout.get_layout_ids(),
+            states: [const { None }; GLOBAL_CHANNELS],
+        }
+    }
+
+    /// The apps to ask for their params
+    pub fn layout_ids(&self) -> &[u8] {
+        &self.layout_ids
+    }
+
+    /// Add the params an app reported. Replies from apps that are not in the layout, or that
+    /// already replied, are ignored.
+    pub fn add(&mut self, layout_id: u8, values: Vec<Value, APP_MAX_PARAMS>) -> bool {
+        let Some(index) = self.layout_ids.iter().position(|&id| id == layout_id) else {
+            return false;
+        };
+        if self.states[index].is_some() {
+            return false;
+        }
+        self.states[index] = Some(values);
+        true
+    }
+
+    /// Whether every app in the layout replied
+    pub fn is_complete(&self) -> bool {
+        self.states[..self.layout_ids.len()]
+            .iter()
+            .all(Option::is_some)
+    }
+
+    /// The messages to send: `BatchMsgStart` with the number of apps that replied, their
+    /// `AppState`s in layout order and `BatchMsgEnd`. An app that didn't reply is left out,
+    /// the count always matches what follows.
+    pub fn messages(&self) -> impl Iterator<Item = ConfigMsgOut<'_>> {
+        let states = self
+            .layout_ids
+            .iter()
+            .zip(self.states.iter())
+            .filter_map(|(&id, values)| values.as_ref().map(|values| (id, values)));
+        let count = states.clone().count();
+        core::iter::once(ConfigMsgOut::BatchMsgStart(count))
+            .chain(states.map(|(id, values)| ConfigMsgOut::AppState(id, values)))
+            .chain(core::iter::once(ConfigMsgOut::BatchMsgEnd))
+    }
 }
 
 pub struct Config<const N: usize> {
@@ -900,6 +1517,28 @@ impl<const N: usize> Config<N> {
         }
     }
 
+    /// Check the finished config: the added params have to fill all `N` and the params
+    /// configuring the app's MIDI ports can only be added once. Used on a `static`, a mistake
+    /// fails the build.
+    pub const fn finalize(self) -> Self {
+        assert!(self.len == N, "Number of added params doesn't match N");
+        let (mut midi_in, mut midi_out, mut midi_mode) = (0, 0, 0);
+        let mut i = 0;
+        while i < N {
+            match self.params[i] {
+                Param::MidiIn => midi_in += 1,
+                Param::MidiOut => midi_out += 1,
+                Param::MidiMode => midi_mode += 1,
+                _ => {}
+            }
+            i += 1;
+        }
+        assert!(midi_in <= 1, "Duplicate MidiIn param");
+        assert!(midi_out <= 1, "Duplicate MidiOut param");
+        assert!(midi_mode <= 1, "Duplicate MidiMode param");
+        self
+    }
+
     pub fn get_meta(&self) -> ConfigMeta<'_> {
         (
             N,
@@ -910,6 +1549,73 @@ impl<const N: usize> Config<N> {
             &self.params,
         )
     }
+
+    /// Bring values sent by the host within the bounds of the declared params
+    pub fn clamp_values(&self, values: &mut [Option<Value>]) {
+        clamp_param_values(&self.params, values);
+    }
+}
+
+/// Clamp each value to the bounds of the param at the same index. Numbers are held within
+/// their min and max and enum indices within their variants.
+pub fn clamp_param_values(params: &[Param], values: &mut [Option<Value>]) {
+    for (param, value) in params.iter().zip(values.iter_mut()) {
+        match (param, value) {
+            (Param::i32 { min, max, .. }, Some(Value::i32(v))) => {
+                *v = (*v).clamp(*min, (*max).max(*min));
+            }
+            (Param::f32 { min, max, .. }, Some(Value::f32(v))) => {
+                *v = if v.is_nan() {
+                    *min
+                } else {
+                    v.clamp(*min, max.max(*min))
+                };
+            }
+            (Param::Enum { variants, .. }, Some(Value::Enum(v))) => {
+                *v = (*v).min(variants.len().saturating_sub(1));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Deserialize app data stored behind the app id in the first byte. Returns `Ok(None)` when
+/// nothing is stored for this app and an error when the data is corrupt, e.g. after a write was
+/// cut short. Never panics, whatever the bytes.
+pub fn from_app_bytes<T: DeserializeOwned>(
+    app_id: u8,
+    data: &[u8],
+) -> Result<Option<T>, postcard::Error> {
+    match data.split_first() {
+        Some((&id, data)) if id == app_id => postcard::from_bytes(data).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// MIDI channels an app sent on with its `old` param values that the `new` ones don't use
+/// anymore, with the outputs they went to. Notes still sounding there would hang otherwise. The
+/// channels of apps without a MIDI output param are input channels and are left alone.
+pub fn midi_channels_left(
+    old: &[Value],
+    new: &[Value],
+) -> Vec<(MidiChannel, MidiOut), APP_MAX_PARAMS> {
+    let mut left: Vec<(MidiChannel, MidiOut), APP_MAX_PARAMS> = Vec::new();
+    let Some(midi_out) = old.iter().find_map(|value| match value {
+        Value::MidiOut(midi_out) if midi_out.is_some() => Some(*midi_out),
+        _ => None,
+    }) else {
+        return left;
+    };
+    for value in old {
+        if let Value::MidiChannel(channel) = value {
+            let still_used = new.contains(value);
+            if !still_used && !left.iter().any(|(c, _)| c == channel) {
+                // Can't fail, there are no more channels than params
+                let _ = left.push((*channel, midi_out));
+            }
+        }
+    }
+    left
 }
 
 /// Supported DAC ranges
@@ -960,6 +1666,52 @@ impl FromValue for Range {
     }
 }
 
+/// Polarity of a gate output
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GatePolarity {
+    /// High while the gate is on
+    #[default]
+    ActiveHigh,
+    /// Low while the gate is on, for gear that expects inverted gates
+    ActiveLow,
+}
+
+impl GatePolarity {
+    /// Whether the GPO has to be driven high for a gate state
+    pub fn gpo_high(&self, gate: bool) -> bool {
+        match self {
+            GatePolarity::ActiveHigh => gate,
+            GatePolarity::ActiveLow => !gate,
+        }
+    }
+}
+
+/// How a gate output goes back to off
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GateMode {
+    /// The gate stays on until the app turns it off
+    #[default]
+    Manual,
+    /// Every gate is a trigger that turns itself off after this many milliseconds
+    Auto(u16),
+}
+
+impl GateMode {
+    /// When a gate turned on at `now` turns itself off, `None` in manual mode. Triggers are at
+    /// least 1ms long.
+    pub fn pulse_end(&self, now: Instant) -> Option<Instant> {
+        match *self {
+            GateMode::Manual => None,
+            GateMode::Auto(ms) => Some(now + Duration::from_millis(ms.max(1) as u64)),
+        }
+    }
+}
+
+/// Whether a gate pulse ending at `end` (in embassy ticks, `0` when there is none) is over
+pub fn gate_pulse_due(end: u64, now: Instant) -> bool {
+    end != 0 && now.as_ticks() >= end
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, PostcardBindings)]
 pub struct MidiCc(u16);
 
@@ -1099,11 +1851,66 @@ impl Add<MidiNote> for MidiNote {
     }
 }
 
+impl Sub<MidiNote> for MidiNote {
+    type Output = Self;
+
+    fn sub(self, rhs: MidiNote) -> Self::Output {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+}
+
 impl MidiNote {
     /// Transpose a MidiNote by +/- semitones
     pub fn transpose(&mut self, semitones: i8) -> Self {
         Self((self.0 as i8 + semitones).clamp(0, 127) as u8)
     }
+
+    /// Pitch class of the note, 0 for C up to 11 for B
+    pub fn pitch_class(&self) -> u8 {
+        self.0 % 12
+    }
+
+    /// Note name and octave, with note 60 as C4 (middle C) like `Pitch::from_midi`
+    pub fn name(&self) -> (Note, i8) {
+        (Note::from(self.pitch_class()), (self.0 / 12) as i8 - 1)
+    }
+
+    pub fn as_u8(&self) -> u8 {
+        self.0
+    }
+
+    /// Semitones from this note up to `other`, negative when `other` is lower
+    pub fn interval_to(&self, other: MidiNote) -> i8 {
+        other.0 as i8 - self.0 as i8
+    }
+}
+
+/// A note starting or ending, with its 12-bit (release) velocity
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoteEvent {
+    On { note: MidiNote, vel: u16 },
+    Off { note: MidiNote, vel: u16 },
+}
+
+impl NoteEvent {
+    /// Note ons with velocity 0 are note offs, as sent by a lot of gear with running status
+    pub fn from_message(message: &midly::MidiMessage) -> Option<Self> {
+        match *message {
+            midly::MidiMessage::NoteOn { key, vel } if vel > 0 => Some(Self::On {
+                note: MidiNote::from(key.as_int()),
+                vel: utils::scale_bits_7_12(vel),
+            }),
+            midly::MidiMessage::NoteOn { key, .. } => Some(Self::Off {
+                note: MidiNote::from(key.as_int()),
+                vel: 0,
+            }),
+            midly::MidiMessage::NoteOff { key, vel } => Some(Self::Off {
+                note: MidiNote::from(key.as_int()),
+                vel: utils::scale_bits_7_12(vel),
+            }),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize, PostcardBindings)]
@@ -1150,11 +1957,31 @@ impl MidiOut {
     pub fn is_none(&self) -> bool {
         !self.is_some()
     }
+
+    /// Drop the output leading back to where a message came in
+    pub fn without_return_to(mut self, port: MidiInPort) -> Self {
+        if let Some(out) = port.return_out() {
+            self.0[out] = false;
+        }
+        self
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Layout, GLOBAL_CHANNELS};
+    use super::{
+        clamp_param_values, from_app_bytes, gate_pulse_due, midi_channels_left, AppIcon,
+        AppStateBatch, AuxJackMode, ClockDivider, ClockDivision, ClockSrc, Color, Config,
+        ConfigMsgOut, Curve, GateMode, GatePolarity, GlobalConfig, GlobalConfigFile, I2cMode, Key,
+        Layout, MidiChannel, MidiConfig, MidiIn, MidiInPort, MidiNote, MidiOut, MidiOutConfig,
+        MidiOutMode, Note, NoteEvent, Param, PulseIntervals, QuantizerConfig, ScaleMask, TapTempo,
+        TransportEvent, TransportState, Value, VelocityCurve, APP_MAX_PARAMS, GLOBAL_CHANNELS,
+        GLOBAL_CONFIG_FILE_MAGIC, GLOBAL_CONFIG_VERSION_LATEST,
+    };
+    use crate::ext::FromValue;
+    use crate::latch::TakeoverMode;
+    use crate::utils::{bpm_to_clock_duration, clock_duration_to_bpm, scale_bits_12_7};
+    use embassy_time::{Duration, Instant};
     use heapless::Vec;
 
     fn mock_get_channels(app_id: u8) -> Option<usize> {
@@ -1266,4 +2093,1230 @@ mod tests {
             final_ids.push(layout_id).unwrap();
         }
     }
+
+    #[test]
+    fn compact_closes_gaps() {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[2] = Some((1, 1, 4));
+        layout.0[5] = Some((2, 4, 0));
+        layout.0[11] = Some((3, 3, 7));
+        layout.0[15] = Some((1, 1, 2));
+
+        assert!(layout.compact(mock_get_channels));
+
+        assert_eq!(layout.0[0], Some((1, 1, 4)));
+        assert_eq!(layout.0[1], Some((2, 4, 0)));
+        assert_eq!(layout.0[5], Some((3, 3, 7)));
+        assert_eq!(layout.0[8], Some((1, 1, 2)));
+        assert!(layout.0[9..].iter().all(|app| app.is_none()));
+        assert_eq!(layout.count(), 4);
+    }
+
+    #[test]
+    fn compact_keeps_compact_layouts() {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[0] = Some((2, 4, 1));
+        layout.0[4] = Some((1, 1, 0));
+        let original = layout.0;
+
+        assert!(!layout.compact(mock_get_channels));
+        assert_eq!(layout.0, original);
+
+        let mut empty = Layout([None; GLOBAL_CHANNELS]);
+        assert!(!empty.compact(mock_get_channels));
+    }
+
+    #[test]
+    fn compact_validates_first() {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        // Invalid app, gets dropped
+        layout.0[1] = Some((99, 1, 0));
+        layout.0[3] = Some((2, 4, 1));
+        // Overlaps with app 2, gets dropped
+        layout.0[5] = Some((1, 1, 2));
+        // Stale channel count and duplicate layout id
+        layout.0[10] = Some((3, 1, 1));
+
+        assert!(layout.compact(mock_get_channels));
+
+        assert_eq!(layout.0[0], Some((2, 4, 1)));
+        assert_eq!(layout.0[4], Some((3, 3, 3)));
+        assert_eq!(layout.count(), 2);
+    }
+
+    fn note_on(key: u8, vel: u8) -> midly::MidiMessage {
+        midly::MidiMessage::NoteOn {
+            key: key.into(),
+            vel: vel.into(),
+        }
+    }
+
+    fn note_off(key: u8, vel: u8) -> midly::MidiMessage {
+        midly::MidiMessage::NoteOff {
+            key: key.into(),
+            vel: vel.into(),
+        }
+    }
+
+    #[test]
+    fn note_event_from_note_on_and_off() {
+        assert_eq!(
+            NoteEvent::from_message(&note_on(60, 127)),
+            Some(NoteEvent::On {
+                note: MidiNote::from(60_u8),
+                vel: 4095
+            })
+        );
+        // Explicit note off keeps its release velocity
+        assert_eq!(
+            NoteEvent::from_message(&note_off(60, 64)),
+            Some(NoteEvent::Off {
+                note: MidiNote::from(60_u8),
+                vel: 2063
+            })
+        );
+        assert_eq!(
+            NoteEvent::from_message(&note_off(61, 0)),
+            Some(NoteEvent::Off {
+                note: MidiNote::from(61_u8),
+                vel: 0
+            })
+        );
+    }
+
+    #[test]
+    fn note_event_velocity_zero_is_note_off() {
+        assert_eq!(
+            NoteEvent::from_message(&note_on(72, 0)),
+            Some(NoteEvent::Off {
+                note: MidiNote::from(72_u8),
+                vel: 0
+            })
+        );
+        // Same as an explicit note off without release velocity
+        assert_eq!(
+            NoteEvent::from_message(&note_on(72, 0)),
+            NoteEvent::from_message(&note_off(72, 0))
+        );
+        assert_eq!(
+            NoteEvent::from_message(&note_on(72, 1)),
+            Some(NoteEvent::On {
+                note: MidiNote::from(72_u8),
+                vel: 32
+            })
+        );
+    }
+
+    #[test]
+    fn note_event_ignores_other_messages() {
+        let cc = midly::MidiMessage::Controller {
+            controller: 1.into(),
+            value: 64.into(),
+        };
+        assert_eq!(NoteEvent::from_message(&cc), None);
+        let aftertouch = midly::MidiMessage::Aftertouch {
+            key: 60.into(),
+            vel: 64.into(),
+        };
+        assert_eq!(NoteEvent::from_message(&aftertouch), None);
+    }
+
+    const DIVISIONS: [ClockDivision; 10] = [
+        ClockDivision::_1,
+        ClockDivision::_2,
+        ClockDivision::_4,
+        ClockDivision::_6,
+        ClockDivision::_8,
+        ClockDivision::_12,
+        ClockDivision::_24,
+        ClockDivision::_96,
+        ClockDivision::_192,
+        ClockDivision::_384,
+    ];
+
+    #[test]
+    fn clock_divider_pulse_rate() {
+        for division in DIVISIONS {
+            let mut divider = ClockDivider::default();
+            // Four bars of 24 PPQN ticks
+            let pulses: Vec<usize, 384> = (0..384).filter(|_| divider.tick(division)).collect();
+            assert_eq!(pulses.len(), 384 / division as usize);
+            // Evenly spaced, starting on the first tick
+            for (i, &tick) in pulses.iter().enumerate() {
+                assert_eq!(tick, i * division as usize);
+            }
+            if let Some(ppqn) = division.ppqn() {
+                assert_eq!(
+                    pulses.iter().filter(|&&tick| tick < 24).count(),
+                    ppqn as usize
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn clock_division_ppqn() {
+        assert_eq!(ClockDivision::_1.ppqn(), Some(24));
+        assert_eq!(ClockDivision::_6.ppqn(), Some(4));
+        assert_eq!(ClockDivision::_8.ppqn(), Some(3));
+        assert_eq!(ClockDivision::_24.ppqn(), Some(1));
+        assert_eq!(ClockDivision::_96.ppqn(), None);
+    }
+
+    #[test]
+    fn clock_divider_reset_pulses_next_tick() {
+        let mut divider = ClockDivider::default();
+        assert!(divider.tick(ClockDivision::_6));
+        assert!(!divider.tick(ClockDivision::_6));
+        assert!(!divider.tick(ClockDivision::_6));
+        divider.reset();
+        assert!(divider.tick(ClockDivision::_6));
+        assert!(!divider.tick(ClockDivision::_6));
+    }
+
+    #[test]
+    fn transport_state_follows_start_and_stop() {
+        let mut state = TransportState::default();
+        assert!(!state.is_running());
+        assert!(state.apply(TransportEvent::Start));
+        assert!(state.is_running());
+        assert!(state.apply(TransportEvent::Stop));
+        assert!(!state.is_running());
+        assert!(state.apply(TransportEvent::Continue));
+        assert!(state.is_running());
+    }
+
+    #[test]
+    fn transport_state_repeated_events_change_nothing() {
+        let mut state = TransportState::new(true);
+        assert!(!state.apply(TransportEvent::Start));
+        assert!(!state.apply(TransportEvent::Continue));
+        assert!(state.is_running());
+        state.apply(TransportEvent::Stop);
+        assert!(!state.apply(TransportEvent::Stop));
+        assert!(!state.is_running());
+    }
+
+    #[test]
+    fn transport_state_reset_keeps_running_state() {
+        let mut state = TransportState::default();
+        assert!(!state.apply(TransportEvent::Reset));
+        assert!(!state.is_running());
+        state.apply(TransportEvent::Start);
+        assert!(!state.apply(TransportEvent::Reset));
+        assert!(state.is_running());
+    }
+
+    #[test]
+    fn transport_state_continue_keeps_ticks() {
+        let mut state = TransportState::default();
+        state.apply(TransportEvent::Start);
+        for expected in 0..10 {
+            assert_eq!(state.tick(), expected);
+        }
+        state.apply(TransportEvent::Stop);
+        assert_eq!(state.ticks(), 9);
+        state.apply(TransportEvent::Continue);
+        assert_eq!(state.ticks(), 9);
+        assert_eq!(state.tick(), 10);
+    }
+
+    #[test]
+    fn transport_state_start_and_reset_clear_ticks() {
+        let mut state = TransportState::new(true);
+        for _ in 0..30 {
+            state.tick();
+        }
+        state.apply(TransportEvent::Reset);
+        assert!(state.is_running());
+        assert_eq!(state.tick(), 0);
+        assert_eq!(state.tick(), 1);
+        state.apply(TransportEvent::Stop);
+        state.apply(TransportEvent::Start);
+        assert_eq!(state.tick(), 0);
+    }
+
+    /// Feed pulses at the given timestamps in microseconds, returns the measured BPM at 24 PPQN
+    fn measure_bpm(intervals: &mut PulseIntervals<4>, timestamps: &[u64]) -> Option<f32> {
+        let mut bpm = None;
+        for pair in timestamps.windows(2) {
+            let delta = Instant::from_micros(pair[1]).duration_since(Instant::from_micros(pair[0]));
+            bpm = intervals
+                .push(delta)
+                .and_then(|avg| clock_duration_to_bpm(avg, 24));
+        }
+        bpm
+    }
+
+    fn pulses<const N: usize>(start: u64, bpm: f32) -> [u64; N] {
+        let tick = bpm_to_clock_duration(bpm, 24).as_micros();
+        core::array::from_fn(|i| start + i as u64 * tick)
+    }
+
+    #[test]
+    fn pulse_intervals_measure_steady_clock() {
+        for bpm in [60.0, 120.0, 174.0, 240.0] {
+            let mut intervals = PulseIntervals::<4>::new();
+            let measured = measure_bpm(&mut intervals, &pulses::<12>(1000, bpm)).unwrap();
+            assert!((measured - bpm).abs() < 0.1, "{bpm} {measured}");
+        }
+    }
+
+    #[test]
+    fn pulse_intervals_need_two_pulses() {
+        let mut intervals = PulseIntervals::<4>::new();
+        assert_eq!(intervals.average(), None);
+        assert_eq!(measure_bpm(&mut intervals, &[5000]), None);
+        assert!(measure_bpm(&mut intervals, &[5000, 25_833]).is_some());
+    }
+
+    #[test]
+    fn pulse_intervals_average_jitter() {
+        let mut intervals = PulseIntervals::<4>::new();
+        // 120 BPM is 20833us per tick, the pulses come in 1ms early and late
+        let timestamps = [0, 19_833, 41_666, 61_499, 83_332];
+        let measured = measure_bpm(&mut intervals, &timestamps).unwrap();
+        assert!((measured - 120.0).abs() < 0.5, "{measured}");
+    }
+
+    #[test]
+    fn pulse_intervals_follow_tempo_change() {
+        let mut intervals = PulseIntervals::<4>::new();
+        let slow = pulses::<8>(0, 100.0);
+        measure_bpm(&mut intervals, &slow);
+        let fast = pulses::<6>(slow[7], 150.0);
+        // Halfway through the history the average is between both tempos
+        let halfway = measure_bpm(&mut intervals, &fast[..3]).unwrap();
+        assert!(halfway > 100.0 && halfway < 150.0, "{halfway}");
+        // After a full history only the new tempo is left
+        let measured = measure_bpm(&mut intervals, &fast[2..]).unwrap();
+        assert!((measured - 150.0).abs() < 0.1, "{measured}");
+    }
+
+    /// Tap at the given intervals in milliseconds, returns the BPM after the last tap
+    fn tap_intervals(tap_tempo: &mut TapTempo, start: u64, intervals: &[u64]) -> Option<f32> {
+        let mut now = start;
+        let mut bpm = tap_tempo.tap(Instant::from_millis(now));
+        for interval in intervals {
+            now += interval;
+            bpm = tap_tempo.tap(Instant::from_millis(now));
+        }
+        bpm
+    }
+
+    #[test]
+    fn tap_tempo_from_steady_taps() {
+        for (interval, bpm) in [(500, 120.0), (1000, 60.0), (400, 150.0), (750, 80.0)] {
+            let mut tap_tempo = TapTempo::default();
+            let measured = tap_intervals(&mut tap_tempo, 0, &[interval; 4]).unwrap();
+            assert!((measured - bpm).abs() < 0.1, "{interval} {measured}");
+        }
+    }
+
+    #[test]
+    fn tap_tempo_averages_uneven_taps() {
+        let mut tap_tempo = TapTempo::default();
+        let measured = tap_intervals(&mut tap_tempo, 0, &[480, 520, 510, 490]).unwrap();
+        assert!((measured - 120.0).abs() < 0.5, "{measured}");
+        // Only the last four intervals count
+        let measured = tap_intervals(&mut tap_tempo, 2600, &[600, 600, 600]).unwrap();
+        assert!((measured - 100.0).abs() < 0.1, "{measured}");
+    }
+
+    #[test]
+    fn tap_tempo_needs_two_taps() {
+        let mut tap_tempo = TapTempo::default();
+        assert_eq!(tap_tempo.tap(Instant::from_millis(1000)), None);
+        assert!(tap_tempo.tap(Instant::from_millis(1500)).is_some());
+    }
+
+    #[test]
+    fn tap_tempo_restarts_after_pause() {
+        let mut tap_tempo = TapTempo::default();
+        tap_intervals(&mut tap_tempo, 0, &[300, 300, 300]);
+        // A long pause starts over, the old taps don't count anymore
+        assert_eq!(tap_tempo.tap(Instant::from_millis(10_000)), None);
+        let measured = tap_intervals(&mut tap_tempo, 10_000, &[1000]).unwrap();
+        assert!((measured - 60.0).abs() < 0.1, "{measured}");
+    }
+
+    #[test]
+    fn tap_tempo_clamps_bpm() {
+        let mut tap_tempo = TapTempo::default();
+        let measured = tap_intervals(&mut tap_tempo, 0, &[50, 50]).unwrap();
+        assert_eq!(measured, 300.0);
+    }
+
+    #[test]
+    fn pulse_intervals_reset() {
+        let mut intervals = PulseIntervals::<4>::new();
+        measure_bpm(&mut intervals, &pulses::<6>(0, 90.0));
+        intervals.reset();
+        assert_eq!(intervals.average(), None);
+        let measured = measure_bpm(&mut intervals, &pulses::<2>(0, 130.0)).unwrap();
+        assert!((measured - 130.0).abs() < 0.1, "{measured}");
+    }
+
+    #[test]
+    fn layout_slot_round_trips() {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[0] = Some((2, 4, 3));
+        layout.0[4] = Some((1, 1, 0));
+        layout.0[9] = Some((3, 3, 7));
+
+        let mut buf = [0u8; 128];
+        let bytes = postcard::to_slice(&layout, &mut buf).unwrap();
+        let loaded = Layout::from_slot_bytes(bytes, mock_get_channels).unwrap();
+        assert_eq!(loaded.0, layout.0);
+    }
+
+    #[test]
+    fn layout_slot_is_validated_on_load() {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[0] = Some((2, 4, 0));
+        // Overlaps with app 2
+        layout.0[2] = Some((3, 3, 1));
+        // Not an app of this firmware (anymore)
+        layout.0[6] = Some((42, 1, 2));
+        // Saved with a stale channel count
+        layout.0[8] = Some((3, 1, 0));
+
+        let mut buf = [0u8; 128];
+        let bytes = postcard::to_slice(&layout, &mut buf).unwrap();
+        let loaded = Layout::from_slot_bytes(bytes, mock_get_channels).unwrap();
+
+        let mut expected = layout.clone();
+        expected.validate(mock_get_channels);
+        assert_eq!(loaded.0, expected.0);
+        assert_eq!(loaded.0[0], Some((2, 4, 0)));
+        assert_eq!(loaded.0[2], None);
+        assert_eq!(loaded.0[6], None);
+        assert_eq!(loaded.0[8], Some((3, 3, 3)));
+    }
+
+    #[test]
+    fn layout_slot_rejects_garbage() {
+        assert!(Layout::from_slot_bytes(&[], mock_get_channels).is_none());
+        assert!(Layout::from_slot_bytes(&[0xff, 0xff, 0xff], mock_get_channels).is_none());
+    }
+
+    #[test]
+    fn copy_app_params_between_same_apps() {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[0] = Some((3, 3, 4));
+        layout.0[3] = Some((3, 3, 1));
+        layout.0[6] = Some((1, 1, 2));
+        layout.0[7] = Some((1, 1, 9));
+
+        assert_eq!(layout.get_app_id(1), Some(3));
+        assert_eq!(layout.get_app_id(5), None);
+        assert_eq!(layout.copy_app_id(4, 1), Some(3));
+        assert_eq!(layout.copy_app_id(1, 4), Some(3));
+        assert_eq!(layout.copy_app_id(9, 2), Some(1));
+    }
+
+    #[test]
+    fn copy_app_params_rejects_mismatched_apps() {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[0] = Some((3, 3, 4));
+        layout.0[3] = Some((3, 3, 1));
+        layout.0[6] = Some((1, 1, 2));
+
+        // Different apps
+        assert_eq!(layout.copy_app_id(4, 2), None);
+        assert_eq!(layout.copy_app_id(2, 1), None);
+        // Onto itself
+        assert_eq!(layout.copy_app_id(4, 4), None);
+        // Layout ids that aren't in the layout
+        assert_eq!(layout.copy_app_id(4, 7), None);
+        assert_eq!(layout.copy_app_id(7, 4), None);
+        assert_eq!(layout.copy_app_id(200, 4), None);
+    }
+
+    #[test]
+    fn get_app_id_only_finds_its_layout_id() {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[0] = Some((2, 4, 3));
+        layout.0[4] = Some((2, 4, 0));
+        layout.0[8] = Some((1, 1, 5));
+
+        assert_eq!(layout.get_app_id(3), Some(2));
+        assert_eq!(layout.get_app_id(0), Some(2));
+        assert_eq!(layout.get_app_id(5), Some(1));
+        // Layout ids are not channels
+        assert_eq!(layout.get_app_id(4), None);
+        assert_eq!(layout.get_app_id(8), None);
+        assert_eq!(layout.get_app_id(GLOBAL_CHANNELS as u8), None);
+    }
+
+    // Wire format of the first `Param` variants, to decode what the firmware sends
+    #[allow(non_camel_case_types)]
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    enum ParamWire<'a> {
+        None,
+        i32 {
+            name: &'a str,
+            min: i32,
+            max: i32,
+            step: i32,
+        },
+        f32 {
+            name: &'a str,
+            min: f32,
+            max: f32,
+            step: f32,
+        },
+    }
+
+    #[test]
+    fn param_step_round_trips() {
+        let params = [
+            Param::f32 {
+                name: "BPM",
+                min: 30.0,
+                max: 300.0,
+                step: 0.5,
+            },
+            Param::i32 {
+                name: "Steps",
+                min: 1,
+                max: 64,
+                step: 4,
+            },
+        ];
+        let expected = [
+            ParamWire::f32 {
+                name: "BPM",
+                min: 30.0,
+                max: 300.0,
+                step: 0.5,
+            },
+            ParamWire::i32 {
+                name: "Steps",
+                min: 1,
+                max: 64,
+                step: 4,
+            },
+        ];
+        for (param, expected) in params.iter().zip(expected) {
+            let mut buf = [0u8; 64];
+            let bytes = postcard::to_slice(param, &mut buf).unwrap();
+            let decoded: ParamWire = postcard::from_bytes(bytes).unwrap();
+            assert_eq!(decoded, expected);
+        }
+    }
+
+    #[test]
+    fn param_step_is_part_of_the_config_meta() {
+        static CONFIG: Config<2> = Config::new("Test", "Test app", Color::Blue, AppIcon::Fader)
+            .add_param(Param::f32 {
+                name: "BPM",
+                min: 30.0,
+                max: 300.0,
+                step: 0.5,
+            })
+            .add_param(Param::i32 {
+                name: "Length",
+                min: 1,
+                max: 16,
+                step: 0,
+            });
+        let (len, _, _, _, _, params) = CONFIG.get_meta();
+        assert_eq!(len, 2);
+        assert!(matches!(params[0], Param::f32 { step, .. } if step == 0.5));
+        assert!(matches!(params[1], Param::i32 { step: 0, .. }));
+    }
+
+    fn test_config<const N: usize>() -> Config<N> {
+        Config::new("Test", "Test app", Color::Blue, AppIcon::Fader)
+    }
+
+    #[test]
+    fn finalize_accepts_complete_config() {
+        static CONFIG: Config<3> = Config::new("Test", "Test app", Color::Blue, AppIcon::Fader)
+            .add_param(Param::MidiIn)
+            .add_param(Param::MidiChannel { name: "Channel" })
+            .add_param(Param::MidiOut)
+            .finalize();
+        assert_eq!(CONFIG.get_meta().0, 3);
+        // Several params of the same kind are fine, only the port params are unique
+        test_config::<2>()
+            .add_param(Param::MidiChannel { name: "In" })
+            .add_param(Param::MidiChannel { name: "Out" })
+            .finalize();
+        test_config::<0>().finalize();
+    }
+
+    #[test]
+    #[should_panic(expected = "Number of added params doesn't match N")]
+    fn finalize_detects_missing_params() {
+        test_config::<3>()
+            .add_param(Param::MidiIn)
+            .add_param(Param::MidiOut)
+            .finalize();
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate MidiOut param")]
+    fn finalize_detects_duplicate_midi_out() {
+        test_config::<3>()
+            .add_param(Param::MidiOut)
+            .add_param(Param::MidiChannel { name: "Channel" })
+            .add_param(Param::MidiOut)
+            .finalize();
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate MidiMode param")]
+    fn finalize_detects_duplicate_midi_mode() {
+        test_config::<2>()
+            .add_param(Param::MidiMode)
+            .add_param(Param::MidiMode)
+            .finalize();
+    }
+
+    #[test]
+    fn note_names_with_sharps_and_flats() {
+        let sharps = [
+            "C", "C#", "D", "D#", "E", "F", "F#", "G", "G#", "A", "A#", "B",
+        ];
+        let flats = [
+            "C", "Db", "D", "Eb", "E", "F", "Gb", "G", "Ab", "A", "Bb", "B",
+        ];
+        for i in 0..12 {
+            let note = Note::from(i as u8);
+            assert_eq!(note.name_with_pref(false), sharps[i]);
+            assert_eq!(note.name_with_pref(true), flats[i]);
+            assert_eq!(note.name(), sharps[i]);
+        }
+    }
+
+    #[test]
+    fn clamp_values_enforces_declared_bounds() {
+        static CONFIG: Config<4> = Config::new("Test", "Test app", Color::Blue, AppIcon::Fader)
+            .add_param(Param::i32 {
+                name: "Length",
+                min: 1,
+                max: 16,
+                step: 1,
+            })
+            .add_param(Param::f32 {
+                name: "BPM",
+                min: 30.0,
+                max: 300.0,
+                step: 0.5,
+            })
+            .add_param(Param::Enum {
+                name: "Mode",
+                variants: &["A", "B", "C"],
+            })
+            .add_param(Param::bool { name: "Invert" });
+
+        let mut values = [
+            Some(Value::i32(1000)),
+            Some(Value::f32(-20.0)),
+            Some(Value::Enum(7)),
+            Some(Value::bool(true)),
+        ];
+        CONFIG.clamp_values(&mut values);
+        assert_eq!(
+            values,
+            [
+                Some(Value::i32(16)),
+                Some(Value::f32(30.0)),
+                Some(Value::Enum(2)),
+                Some(Value::bool(true)),
+            ]
+        );
+
+        let mut values = [
+            Some(Value::i32(i32::MIN)),
+            Some(Value::f32(f32::INFINITY)),
+            None,
+            None,
+        ];
+        CONFIG.clamp_values(&mut values);
+        assert_eq!(
+            values,
+            [Some(Value::i32(1)), Some(Value::f32(300.0)), None, None]
+        );
+
+        // Values within bounds are left alone, NaN falls back to the minimum
+        let mut values = [Some(Value::i32(8)), Some(Value::f32(f32::NAN))];
+        CONFIG.clamp_values(&mut values);
+        assert_eq!(values, [Some(Value::i32(8)), Some(Value::f32(30.0))]);
+    }
+
+    #[test]
+    fn clamp_values_ignores_mismatched_and_extra_values() {
+        let params = [Param::i32 {
+            name: "Length",
+            min: 1,
+            max: 16,
+            step: 1,
+        }];
+        // A value of another type is left for `FromValue` to handle
+        let mut values = [Some(Value::f32(99.0)), Some(Value::i32(99))];
+        clamp_param_values(&params, &mut values);
+        assert_eq!(values, [Some(Value::f32(99.0)), Some(Value::i32(99))]);
+    }
+
+    #[test]
+    fn scale_mask_value_round_trips() {
+        let mask = ScaleMask::from(0b101101011010);
+        let value = Value::from(mask);
+        assert_eq!(value, Value::ScaleMask(0b101101011010));
+
+        let mut buf = [0u8; 8];
+        let bytes = postcard::to_slice(&value, &mut buf).unwrap();
+        let decoded: Value = postcard::from_bytes(bytes).unwrap();
+        assert_eq!(decoded, value);
+        assert_eq!(ScaleMask::from_value(decoded), mask);
+        assert_eq!(mask, ScaleMask::from(Key::Aeolian));
+    }
+
+    #[test]
+    fn key_degrees_are_the_scale_notes() {
+        use Note::*;
+        assert_eq!(Key::Ionian.degrees(), [C, D, E, F, G, A, B]);
+        assert_eq!(Key::Ionian.note_count(), 7);
+        assert_eq!(Key::PentatonicMin.degrees(), [C, DSharp, F, G, ASharp]);
+        assert_eq!(Key::Chromatic.note_count(), 12);
+        for key in crate::quantizer::SCALE_KEYS {
+            assert_eq!(key.degrees().len(), key.note_count() as usize);
+        }
+    }
+
+    #[test]
+    fn scale_mask_from_value_defaults_to_chromatic() {
+        let chromatic = Key::Chromatic.as_u16_key();
+        assert_eq!(ScaleMask::from_value(Value::i32(0b101)).as_u16(), chromatic);
+        assert_eq!(ScaleMask::from_value(Value::Enum(3)).as_u16(), chromatic);
+        // Bits above the 12 notes are dropped, an empty scale is chromatic
+        assert_eq!(
+            ScaleMask::from_value(Value::ScaleMask(0xf000 | 0b101011010101)).as_u16(),
+            Key::Ionian.as_u16_key()
+        );
+        assert_eq!(
+            ScaleMask::from_value(Value::ScaleMask(0)).as_u16(),
+            chromatic
+        );
+        assert_eq!(
+            ScaleMask::from_value(Value::ScaleMask(0xf000)).as_u16(),
+            chromatic
+        );
+    }
+
+    #[test]
+    fn test_pitch_class() {
+        assert_eq!(MidiNote::from(0_u8).pitch_class(), 0);
+        assert_eq!(MidiNote::from(60_u8).pitch_class(), 0);
+        assert_eq!(MidiNote::from(61_u8).pitch_class(), 1);
+        assert_eq!(MidiNote::from(71_u8).pitch_class(), 11);
+        assert_eq!(MidiNote::from(127_u8).pitch_class(), 7);
+    }
+
+    #[test]
+    fn test_midi_note_name() {
+        assert_eq!(MidiNote::from(60_u8).name(), (Note::C, 4));
+        assert_eq!(MidiNote::from(69_u8).name(), (Note::A, 4));
+        assert_eq!(MidiNote::from(0_u8).name(), (Note::C, -1));
+        assert_eq!(MidiNote::from(127_u8).name(), (Note::G, 9));
+        assert_eq!(MidiNote::from(59_u8).name(), (Note::B, 3));
+        for n in 0..=127_u8 {
+            let note = MidiNote::from(n);
+            assert_eq!(note.as_u8(), n);
+            let pitch = crate::quantizer::Pitch::from_midi(n);
+            assert_eq!(note.name(), (pitch.note, pitch.octave));
+        }
+    }
+
+    #[test]
+    fn test_midi_note_subtraction_saturates() {
+        let note = |n: u8| MidiNote::from(n);
+        assert_eq!(note(64) - note(4), note(60));
+        assert_eq!(note(4) - note(64), note(0));
+        assert_eq!(note(0) - note(127), note(0));
+        assert_eq!(note(127) - note(0), note(127));
+        // Adding clamps at the top
+        assert_eq!(note(120) + note(12), note(127));
+    }
+
+    #[test]
+    fn test_midi_note_interval() {
+        let note = |n: u8| MidiNote::from(n);
+        assert_eq!(note(60).interval_to(note(67)), 7);
+        assert_eq!(note(67).interval_to(note(60)), -7);
+        assert_eq!(note(60).interval_to(note(60)), 0);
+        assert_eq!(note(0).interval_to(note(127)), 127);
+        assert_eq!(note(127).interval_to(note(0)), -127);
+        // Moving by the interval lands on the other note
+        let mut from = note(50);
+        assert_eq!(from.transpose(from.interval_to(note(38))), note(38));
+    }
+
+    #[test]
+    fn test_linear_velocity_curve_passes_through() {
+        let curve = VelocityCurve::default();
+        for velocity in [0, 1, 32, 33, 1000, 2048, 3500, 4095] {
+            assert_eq!(curve.apply(velocity), velocity);
+        }
+        assert_eq!(curve.apply(u16::MAX), 4095);
+    }
+
+    #[test]
+    fn test_exponential_velocity_curve() {
+        let curve = VelocityCurve(Curve::Exponential);
+        // Soft notes get softer, the loudest stay the loudest
+        assert_eq!(curve.apply(0), 0);
+        assert_eq!(curve.apply(1024), 64);
+        assert_eq!(curve.apply(2048), 513);
+        assert_eq!(curve.apply(3072), 1730);
+        assert_eq!(curve.apply(4095), 4095);
+        // Soft note ons stay note ons
+        for velocity in 33..1000 {
+            assert!(scale_bits_12_7(curve.apply(velocity)).as_int() >= 1);
+        }
+        let mut prev = 0;
+        for velocity in 0..=4095 {
+            let curved = curve.apply(velocity);
+            assert!(curved >= prev);
+            prev = curved;
+        }
+        // Logarithmic goes the other way
+        assert!(VelocityCurve(Curve::Logarithmic).apply(2048) > 2048);
+    }
+
+    #[test]
+    fn test_pitch_class_colors() {
+        assert_eq!(Color::from_pitch_class(0), Color::Red);
+        assert_eq!(Color::from_pitch_class(7), Color::SkyBlue);
+        assert_eq!(Color::from_pitch_class(11), Color::Pink);
+        // Octaves share a color
+        assert_eq!(
+            Color::from_pitch_class(MidiNote::from(36_u8).pitch_class()),
+            Color::from_pitch_class(MidiNote::from(96_u8).pitch_class())
+        );
+        assert_eq!(Color::from_pitch_class(12), Color::Red);
+        // Every pitch class gets its own color
+        for a in 0..12 {
+            for b in a + 1..12 {
+                assert_ne!(Color::from_pitch_class(a), Color::from_pitch_class(b));
+            }
+        }
+    }
+
+    #[test]
+    fn test_gate_polarity() {
+        assert_eq!(GatePolarity::default(), GatePolarity::ActiveHigh);
+        assert!(GatePolarity::ActiveHigh.gpo_high(true));
+        assert!(!GatePolarity::ActiveHigh.gpo_high(false));
+        // Inverted gates drive the opposite level
+        assert!(!GatePolarity::ActiveLow.gpo_high(true));
+        assert!(GatePolarity::ActiveLow.gpo_high(false));
+    }
+
+    #[test]
+    fn test_gate_mode_pulse_end() {
+        let now = Instant::from_millis(1000);
+        assert_eq!(GateMode::default(), GateMode::Manual);
+        assert_eq!(GateMode::Manual.pulse_end(now), None);
+        assert_eq!(
+            GateMode::Auto(10).pulse_end(now),
+            Some(now + Duration::from_millis(10))
+        );
+        // Zero length triggers still go high
+        assert_eq!(
+            GateMode::Auto(0).pulse_end(now),
+            Some(now + Duration::from_millis(1))
+        );
+    }
+
+    #[test]
+    fn test_gate_pulse_auto_low() {
+        let start = Instant::from_millis(1000);
+        let end = GateMode::Auto(10).pulse_end(start).unwrap().as_ticks();
+        assert!(!gate_pulse_due(end, start));
+        assert!(!gate_pulse_due(end, start + Duration::from_millis(9)));
+        assert!(gate_pulse_due(end, start + Duration::from_millis(10)));
+        assert!(gate_pulse_due(end, start + Duration::from_millis(50)));
+        // No pending pulse
+        assert!(!gate_pulse_due(0, start + Duration::from_millis(50)));
+        // Retriggering pushes the end back
+        let retrigger = start + Duration::from_millis(5);
+        let end = GateMode::Auto(10).pulse_end(retrigger).unwrap().as_ticks();
+        assert!(!gate_pulse_due(end, start + Duration::from_millis(10)));
+        assert!(gate_pulse_due(end, start + Duration::from_millis(15)));
+    }
+
+    fn midi_config(modes: [MidiOutMode; 3]) -> MidiConfig {
+        let mut config = MidiConfig::new();
+        for (out, mode) in config.outs.iter_mut().zip(modes) {
+            out.mode = mode;
+        }
+        config
+    }
+
+    #[test]
+    fn test_cross_merge_does_not_echo() {
+        // USB and MIDI In both merged into every output
+        let both = MidiIn([true, true]);
+        let config = midi_config([MidiOutMode::MidiMerge { sources: both }; 3]);
+        assert_eq!(
+            config.passthrough_targets(MidiInPort::Usb),
+            MidiOut([false, true, true])
+        );
+        assert_eq!(
+            config.passthrough_targets(MidiInPort::Din),
+            MidiOut([true, true, true])
+        );
+    }
+
+    #[test]
+    fn test_passthrough_follows_sources() {
+        let config = midi_config([
+            MidiOutMode::MidiMerge {
+                sources: MidiIn([false, true]),
+            },
+            MidiOutMode::MidiThru {
+                sources: MidiIn([true, false]),
+            },
+            MidiOutMode::Local,
+        ]);
+        assert_eq!(
+            config.passthrough_targets(MidiInPort::Usb),
+            MidiOut([false, true, false])
+        );
+        assert_eq!(
+            config.passthrough_targets(MidiInPort::Din),
+            MidiOut([true, false, false])
+        );
+    }
+
+    #[test]
+    fn test_no_message_returns_to_its_port() {
+        let sources = [
+            MidiIn([false, false]),
+            MidiIn([true, false]),
+            MidiIn([false, true]),
+            MidiIn([true, true]),
+        ];
+        let mut modes = [MidiOutMode::None; 10];
+        modes[1] = MidiOutMode::Local;
+        for (i, sources) in sources.into_iter().enumerate() {
+            modes[2 + 2 * i] = MidiOutMode::MidiThru { sources };
+            modes[3 + 2 * i] = MidiOutMode::MidiMerge { sources };
+        }
+        for usb in modes {
+            for out1 in modes {
+                let config = midi_config([usb, out1, MidiOutMode::Local]);
+                for port in [MidiInPort::Usb, MidiInPort::Din] {
+                    let targets = config.passthrough_targets(port);
+                    if let Some(out) = port.return_out() {
+                        assert!(!targets.0[out]);
+                    }
+                    // Local and disabled outputs never pass anything through
+                    assert!(!targets.0[2]);
+                }
+            }
+        }
+        // Targets tagged with their origin are cleaned up too
+        assert_eq!(
+            MidiOut::default().without_return_to(MidiInPort::Usb),
+            MidiOut([false, true, true])
+        );
+        assert_eq!(
+            MidiOut::default().without_return_to(MidiInPort::Din),
+            MidiOut::default()
+        );
+        assert!(!MidiOutConfig::new().passes_through(MidiInPort::Din));
+    }
+
+    #[test]
+    fn test_midi_channel_change_silences_the_old_channel_once() {
+        let out = MidiOut([false, true, false]);
+        let params = |channel| {
+            [
+                Value::MidiOut(out),
+                Value::MidiChannel(MidiChannel(channel)),
+                Value::Color(Color::Blue),
+            ]
+        };
+        assert_eq!(
+            midi_channels_left(&params(1), &params(2)).as_slice(),
+            &[(MidiChannel(1), out)]
+        );
+        // Another change on the new channel leaves it alone
+        let mut recolored = params(2);
+        recolored[2] = Value::Color(Color::Red);
+        assert!(midi_channels_left(&params(2), &recolored).is_empty());
+        assert!(midi_channels_left(&params(2), &params(2)).is_empty());
+    }
+
+    #[test]
+    fn test_midi_channels_left_with_several_channels() {
+        let out = MidiOut::default();
+        let params = |channels: [u8; 4]| {
+            let mut values: Vec<Value, 5> = Vec::new();
+            values.push(Value::MidiOut(out)).unwrap();
+            for channel in channels {
+                values
+                    .push(Value::MidiChannel(MidiChannel(channel)))
+                    .unwrap();
+            }
+            values
+        };
+        // Two tracks move away from channel 1 together, it is only silenced once
+        assert_eq!(
+            midi_channels_left(&params([1, 1, 3, 4]), &params([2, 2, 3, 4])).as_slice(),
+            &[(MidiChannel(1), out)]
+        );
+        // Swapped channels are both still in use
+        assert!(midi_channels_left(&params([1, 2, 3, 4]), &params([2, 1, 3, 4])).is_empty());
+    }
+
+    #[test]
+    fn test_midi_input_channels_are_left_alone() {
+        let input = |channel| {
+            [
+                Value::MidiIn(MidiIn::default()),
+                Value::MidiChannel(MidiChannel(channel)),
+            ]
+        };
+        assert!(midi_channels_left(&input(1), &input(2)).is_empty());
+        let disabled = |channel| {
+            [
+                Value::MidiOut(MidiOut([false; 3])),
+                Value::MidiChannel(MidiChannel(channel)),
+            ]
+        };
+        assert!(midi_channels_left(&disabled(1), &disabled(2)).is_empty());
+    }
+
+    // Storage like an app's
+    #[derive(Debug, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct AppData {
+        muted: bool,
+        att: u16,
+        values: [i16; 4],
+        name: heapless::String<8>,
+    }
+
+    // Load like ManagedStorage, falling back to the default for corrupt data
+    fn load(app_id: u8, data: &[u8]) -> AppData {
+        from_app_bytes(app_id, data)
+            .unwrap_or_default()
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_from_app_bytes_roundtrip() {
+        let stored = AppData {
+            muted: true,
+            att: 4095,
+            values: [-2048, 0, 7, 2047],
+            name: heapless::String::from("seq"),
+        };
+        let mut buf = [0u8; 64];
+        buf[0] = 7;
+        postcard::to_slice(&stored, &mut buf[1..]).unwrap();
+        // Unused space after the data is ignored
+        assert_eq!(from_app_bytes::<AppData>(7, &buf).unwrap(), Some(stored));
+        // Data of another app isn't read
+        assert_eq!(from_app_bytes::<AppData>(8, &buf).unwrap(), None);
+        assert_eq!(from_app_bytes::<AppData>(7, &[]).unwrap(), None);
+    }
+
+    #[test]
+    fn test_from_app_bytes_garbage_falls_back_to_default() {
+        // Deterministic noise, including truncated and overlong blobs
+        let mut seed = 0x2545_f491_u32;
+        let mut garbage = [0u8; 128];
+        for byte in garbage.iter_mut() {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            *byte = seed as u8;
+        }
+        garbage[0] = 7;
+        for len in 1..garbage.len() {
+            // Whatever the bytes, it never panics, failures load the default
+            let _ = from_app_bytes::<AppData>(7, &garbage[..len]);
+        }
+        assert!(from_app_bytes::<AppData>(7, &[7, 1, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]).is_err());
+        assert_eq!(
+            load(7, &[7, 1, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF]),
+            AppData::default()
+        );
+        // A bool that isn't 0 or 1
+        assert!(from_app_bytes::<AppData>(7, &[7, 2]).is_err());
+        // Cut short
+        assert!(from_app_bytes::<AppData>(7, &[7, 1]).is_err());
+        assert_eq!(load(7, &[7, 1]), AppData::default());
+        // Stored param values too
+        let values = from_app_bytes::<Vec<Value, { super::APP_MAX_PARAMS }>>(7, &[7, 3, 42, 0]);
+        assert!(values.is_err());
+    }
+
+    fn app_state_batch(layout: &Layout, replies: &[u8]) -> AppStateBatch {
+        let mut batch = AppStateBatch::new(layout);
+        for &layout_id in replies {
+            let mut values: Vec<Value, APP_MAX_PARAMS> = Vec::new();
+            values.push(Value::i32(layout_id as i32 * 10)).unwrap();
+            batch.add(layout_id, values);
+        }
+        batch
+    }
+
+    // The layout id and first value of every AppState, after checking the framing
+    fn batch_states(batch: &AppStateBatch) -> Vec<(u8, i32), GLOBAL_CHANNELS> {
+        let mut messages = batch.messages();
+        let Some(ConfigMsgOut::BatchMsgStart(count)) = messages.next() else {
+            panic!("batch has to start with BatchMsgStart");
+        };
+        let mut states = Vec::new();
+        for message in messages.by_ref().take(count) {
+            let ConfigMsgOut::AppState(layout_id, values) = message else {
+                panic!("expected {count} AppStates");
+            };
+            let Value::i32(value) = values[0] else {
+                panic!("unexpected value");
+            };
+            states.push((layout_id, value)).unwrap();
+        }
+        assert_eq!(states.len(), count);
+        assert!(matches!(messages.next(), Some(ConfigMsgOut::BatchMsgEnd)));
+        assert!(messages.next().is_none());
+        states
+    }
+
+    #[test]
+    fn test_app_state_batch_has_one_state_per_app() {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[0] = Some((1, 1, 4));
+        layout.0[3] = Some((2, 4, 9));
+        layout.0[10] = Some((3, 3, 2));
+        // Replies come in any order, with a duplicate and one from an app that's gone
+        let batch = app_state_batch(&layout, &[2, 9, 9, 7, 4]);
+        assert!(batch.is_complete());
+        assert_eq!(batch_states(&batch), [(4, 40), (9, 90), (2, 20)]);
+    }
+
+    #[test]
+    fn test_app_state_batch_leaves_out_missing_replies() {
+        let mut layout = Layout([None; GLOBAL_CHANNELS]);
+        layout.0[0] = Some((1, 1, 0));
+        layout.0[1] = Some((1, 1, 1));
+        layout.0[2] = Some((1, 1, 2));
+        let batch = app_state_batch(&layout, &[2, 0]);
+        assert!(!batch.is_complete());
+        assert_eq!(batch.layout_ids(), [0, 1, 2]);
+        assert_eq!(batch_states(&batch), [(0, 0), (2, 20)]);
+    }
+
+    #[test]
+    fn test_app_state_batch_of_empty_layout() {
+        let layout = Layout([None; GLOBAL_CHANNELS]);
+        let batch = app_state_batch(&layout, &[3]);
+        assert!(batch.is_complete());
+        assert!(batch_states(&batch).is_empty());
+    }
+
+    // A config stored before it had a version: clocked from MIDI In at 133 BPM with swing, aux
+    // jacks sending quarter notes and resets, I2C follower, MIDI In passed through to Out 1
+    // without transport, D Dorian and jump takeover
+    const GLOBAL_CONFIG_V1: [u8; 28] = [
+        1, 2, 2, 0, 5, 24, 0, 0, 0, 5, 67, 244, 2, 200, 1, 1, 1, 1, 0, 2, 1, 0, 1, 1, 1, 2, 2, 1,
+    ];
+
+    fn assert_v1_settings(config: &GlobalConfig) {
+        assert!(
+            config.aux
+                == [
+                    AuxJackMode::ClockOut(ClockDivision::_4),
+                    AuxJackMode::ResetOut,
+                    AuxJackMode::None
+                ]
+        );
+        assert!(config.clock.clock_src == ClockSrc::MidiIn);
+        assert_eq!(config.clock.ext_ppqn, 24);
+        assert_eq!(config.clock.internal_bpm, 133.0);
+        assert_eq!(config.clock.swing_amount, -12);
+        assert!(matches!(config.i2c_mode, I2cMode::Follower));
+        assert_eq!(config.led_brightness, 200);
+        assert!(config.midi.outs[0] == MidiOutConfig::new());
+        assert!(!config.midi.outs[1].send_transport);
+        assert!(
+            config.midi.outs[1].mode
+                == MidiOutMode::MidiThru {
+                    sources: MidiIn([true, false])
+                }
+        );
+        assert!(
+            config.quantizer
+                == QuantizerConfig {
+                    key: Key::Dorian,
+                    tonic: Note::D
+                }
+        );
+        assert_eq!(config.takeover_mode, TakeoverMode::Jump);
+    }
+
+    #[test]
+    fn test_global_config_v1_is_converted() {
+        let (config, converted) = GlobalConfigFile::decode(&GLOBAL_CONFIG_V1).unwrap();
+        assert!(converted);
+        assert_v1_settings(&config);
+        // Settings that didn't exist yet are off
+        assert_eq!(config.output_slew, 0);
+        assert!(!config.midi.active_sensing);
+    }
+
+    #[test]
+    fn test_global_config_before_active_sensing_is_converted() {
+        // Stored with an output slew of 250ms, before MidiConfig had Active Sensing
+        let mut data = [0; 30];
+        data[..28].copy_from_slice(&GLOBAL_CONFIG_V1);
+        data[28..].copy_from_slice(&[250, 1]);
+        let (config, converted) = GlobalConfigFile::decode(&data).unwrap();
+        assert!(converted);
+        // The settings after the MIDI config are read from where they were stored
+        assert_v1_settings(&config);
+        assert_eq!(config.output_slew, 250);
+        assert!(!config.midi.active_sensing);
+    }
+
+    #[test]
+    fn test_global_config_file_round_trip() {
+        let (mut config, _) = GlobalConfigFile::decode(&GLOBAL_CONFIG_V1).unwrap();
+        config.output_slew = 300;
+        config.midi.active_sensing = true;
+        let mut buf = [0; 128];
+        let bytes = postcard::to_slice(&GlobalConfigFile::new(config), &mut buf).unwrap();
+        assert_eq!(bytes[..4], GLOBAL_CONFIG_FILE_MAGIC);
+        assert_eq!(bytes[4], GLOBAL_CONFIG_VERSION_LATEST);
+
+        let (config, converted) = GlobalConfigFile::decode(bytes).unwrap();
+        assert!(!converted);
+        assert_v1_settings(&config);
+        assert_eq!(config.output_slew, 300);
+        assert!(config.midi.active_sensing);
+    }
+
+    #[test]
+    fn test_global_config_unknown_data_is_rejected() {
+        let mut buf = [0; 128];
+        let bytes =
+            postcard::to_slice(&GlobalConfigFile::new(GlobalConfig::new()), &mut buf).unwrap();
+        let len = bytes.len();
+        // A version this firmware doesn't know
+        buf[4] = GLOBAL_CONFIG_VERSION_LATEST + 1;
+        assert!(GlobalConfigFile::decode(&buf[..len]).is_none());
+        // Unversioned data has to be used up by the old layouts
+        let mut longer = [0; 31];
+        longer[..28].copy_from_slice(&GLOBAL_CONFIG_V1);
+        longer[28..].copy_from_slice(&[250, 1, 0]);
+        assert!(GlobalConfigFile::decode(&longer).is_none());
+        assert!(GlobalConfigFile::decode(&GLOBAL_CONFIG_V1[..27]).is_none());
+    }
 }
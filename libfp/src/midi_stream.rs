@@ -0,0 +1,343 @@
+use embassy_time::{Duration, Instant};
+use midly::live::{LiveEvent, SystemCommon, SystemRealtime};
+
+const SYSEX_START: u8 = 0xF0;
+const SYSEX_END: u8 = 0xF7;
+
+/// Time between Active Sensing messages. Receivers give up after 300ms without any message, so
+/// this leaves some room for a busy output.
+pub const ACTIVE_SENSING_INTERVAL: Duration = Duration::from_millis(270);
+
+/// Streaming parser for the raw bytes coming in on MIDI DIN. Realtime bytes are passed on as soon
+/// as they arrive, even in between the data bytes of another message, without disturbing it.
+/// Channel messages keep their status for running status, System Common messages cancel it.
+/// SysEx payloads are skipped instead of buffered, so long dumps can't overflow anything and only
+/// their end is reported.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MidiDinParser {
+    status: Option<u8>,
+    data: [u8; 2],
+    len: usize,
+}
+
+impl MidiDinParser {
+    /// Parse `bytes`, calling `handle_ev` for every completed event. Feeding a stream in chunks
+    /// gives the same events as feeding it all at once.
+    pub fn feed(&mut self, bytes: &[u8], mut handle_ev: impl FnMut(LiveEvent<'static>)) {
+        for &byte in bytes {
+            if let Some(event) = self.feed_byte(byte) {
+                handle_ev(event);
+            }
+        }
+    }
+
+    /// Parse a single byte, returns the event it completes
+    pub fn feed_byte(&mut self, byte: u8) -> Option<LiveEvent<'static>> {
+        match byte {
+            0xF8..=0xFF => Some(LiveEvent::Realtime(SystemRealtime::new(byte))),
+            SYSEX_END => {
+                let sysex = self.status == Some(SYSEX_START);
+                self.status = None;
+                sysex.then_some(LiveEvent::Common(SystemCommon::SysEx(&[])))
+            }
+            SYSEX_START => {
+                self.status = Some(byte);
+                None
+            }
+            0x80..=0xF6 => {
+                self.status = Some(byte);
+                self.len = 0;
+                // Messages without data bytes are complete right away
+                if data_len(byte) == 0 {
+                    self.complete(byte)
+                } else {
+                    None
+                }
+            }
+            _ => {
+                // Data bytes without a status are dropped until the next status byte
+                let status = self.status?;
+                if status == SYSEX_START {
+                    return None;
+                }
+                self.data[self.len] = byte;
+                self.len += 1;
+                if self.len < data_len(status) {
+                    return None;
+                }
+                self.complete(status)
+            }
+        }
+    }
+
+    fn complete(&mut self, status: u8) -> Option<LiveEvent<'static>> {
+        let len = data_len(status);
+        self.len = 0;
+        // Only channel messages can be followed by running status
+        if status >= SYSEX_START {
+            self.status = None;
+        }
+        let raw = [status, self.data[0], self.data[1]];
+        LiveEvent::parse(&raw[..1 + len])
+            .ok()
+            .map(|event| event.to_static())
+    }
+}
+
+/// Generates Active Sensing (0xFE) for gear that expects it. The first message goes out right
+/// away, then one every `ACTIVE_SENSING_INTERVAL`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ActiveSensing {
+    next: Option<Instant>,
+}
+
+impl ActiveSensing {
+    /// When the next message is due
+    pub fn next_due(&self, now: Instant) -> Instant {
+        self.next.unwrap_or(now)
+    }
+
+    /// Whether a message is due at `now`, it is counted as sent
+    pub fn poll(&mut self, now: Instant) -> bool {
+        let due = self.next_due(now);
+        if now < due {
+            return false;
+        }
+        // Keep the cadence, but don't send a burst to catch up after a stall
+        let next = due + ACTIVE_SENSING_INTERVAL;
+        self.next = Some(if next > now {
+            next
+        } else {
+            now + ACTIVE_SENSING_INTERVAL
+        });
+        true
+    }
+
+    /// Start over, e.g. after sensing was turned off
+    pub fn reset(&mut self) {
+        self.next = None;
+    }
+}
+
+/// Number of data bytes following a (non-SysEx) status byte
+fn data_len(status: u8) -> usize {
+    match status {
+        0xC0..=0xDF | 0xF1 | 0xF3 => 1,
+        0x80..=0xEF | 0xF2 => 2,
+        _ => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use heapless::Vec;
+    use midly::{
+        num::{u14, u4, u7},
+        MidiMessage,
+    };
+
+    const CLOCK: u8 = 0xF8;
+
+    fn parse(parser: &mut MidiDinParser, bytes: &[u8]) -> Vec<LiveEvent<'static>, 256> {
+        let mut events = Vec::new();
+        parser.feed(bytes, |event| events.push(event).unwrap());
+        events
+    }
+
+    fn clock() -> LiveEvent<'static> {
+        LiveEvent::Realtime(SystemRealtime::TimingClock)
+    }
+
+    fn note_on(channel: u8, key: u8, vel: u8) -> LiveEvent<'static> {
+        LiveEvent::Midi {
+            channel: u4::new(channel),
+            message: MidiMessage::NoteOn {
+                key: u7::new(key),
+                vel: u7::new(vel),
+            },
+        }
+    }
+
+    fn cc(channel: u8, controller: u8, value: u8) -> LiveEvent<'static> {
+        LiveEvent::Midi {
+            channel: u4::new(channel),
+            message: MidiMessage::Controller {
+                controller: u7::new(controller),
+                value: u7::new(value),
+            },
+        }
+    }
+
+    #[test]
+    fn test_clock_between_data_bytes() {
+        let mut parser = MidiDinParser::default();
+        let events = parse(&mut parser, &[0x92, CLOCK, 60, CLOCK, CLOCK, 100]);
+        assert_eq!(events, [clock(), clock(), clock(), note_on(2, 60, 100)]);
+    }
+
+    #[test]
+    fn test_running_status_survives_realtime() {
+        let mut parser = MidiDinParser::default();
+        let events = parse(
+            &mut parser,
+            &[0x90, 60, 100, 64, CLOCK, 90, 67, CLOCK, 0xFA, 80],
+        );
+        assert_eq!(
+            events,
+            [
+                note_on(0, 60, 100),
+                clock(),
+                note_on(0, 64, 90),
+                clock(),
+                LiveEvent::Realtime(SystemRealtime::Start),
+                note_on(0, 67, 80),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_running_status_single_data_byte() {
+        let mut parser = MidiDinParser::default();
+        let events = parse(&mut parser, &[0xC1, 5, CLOCK, 6]);
+        let program = |program| LiveEvent::Midi {
+            channel: u4::new(1),
+            message: MidiMessage::ProgramChange {
+                program: u7::new(program),
+            },
+        };
+        assert_eq!(events, [program(5), clock(), program(6)]);
+    }
+
+    #[test]
+    fn test_system_common_is_not_delayed() {
+        let mut parser = MidiDinParser::default();
+        // Song position followed by continue, a stray data byte is not another song position
+        let events = parse(&mut parser, &[0xF2, 0x10, CLOCK, 0x01, 0xFB, 0x10]);
+        assert_eq!(
+            events,
+            [
+                clock(),
+                LiveEvent::Common(SystemCommon::SongPosition(u14::new(0x90))),
+                LiveEvent::Realtime(SystemRealtime::Continue),
+            ]
+        );
+        let events = parse(&mut parser, &[0xF6]);
+        assert_eq!(events, [LiveEvent::Common(SystemCommon::TuneRequest)]);
+    }
+
+    #[test]
+    fn test_long_sysex_does_not_corrupt_parsing() {
+        let mut parser = MidiDinParser::default();
+        let mut sysex = [0x42u8; 1000];
+        sysex[0] = SYSEX_START;
+        sysex[500] = CLOCK;
+        sysex[999] = SYSEX_END;
+        let events = parse(&mut parser, &sysex);
+        assert_eq!(
+            events,
+            [clock(), LiveEvent::Common(SystemCommon::SysEx(&[]))]
+        );
+        // No running status after a SysEx
+        assert!(parse(&mut parser, &[60, 100]).is_empty());
+        let events = parse(&mut parser, &[0x90, 60, 100]);
+        assert_eq!(events, [note_on(0, 60, 100)]);
+    }
+
+    #[test]
+    fn test_active_sensing_is_ignored() {
+        let mut parser = MidiDinParser::default();
+        let sensing = LiveEvent::Realtime(SystemRealtime::ActiveSensing);
+        let events = parse(&mut parser, &[0xFE, 0xB3, 0xFE, 7, 0xFE, 100, 8, 0xFE, 50]);
+        assert_eq!(
+            events,
+            [
+                sensing,
+                sensing,
+                sensing,
+                cc(3, 7, 100),
+                sensing,
+                cc(3, 8, 50)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_active_sensing_cadence() {
+        let start = Instant::from_millis(1000);
+        let mut sensing = ActiveSensing::default();
+        let mut sent: Vec<u64, 16> = Vec::new();
+        // Polled every millisecond for two seconds
+        for ms in 0..2000 {
+            if sensing.poll(start + Duration::from_millis(ms)) {
+                sent.push(ms).unwrap();
+            }
+        }
+        assert_eq!(sent, [0, 270, 540, 810, 1080, 1350, 1620, 1890]);
+        // Waiting for the due time hits it exactly
+        let now = start + Duration::from_millis(1900);
+        assert_eq!(sensing.next_due(now), start + Duration::from_millis(2160));
+    }
+
+    #[test]
+    fn test_active_sensing_after_a_stall() {
+        let start = Instant::from_millis(0);
+        let mut sensing = ActiveSensing::default();
+        assert!(sensing.poll(start));
+        assert!(!sensing.poll(start + Duration::from_millis(269)));
+        // A late poll sends once and the cadence continues from there
+        let late = start + Duration::from_millis(1000);
+        assert!(sensing.poll(late));
+        assert!(!sensing.poll(late));
+        assert_eq!(sensing.next_due(late), late + ACTIVE_SENSING_INTERVAL);
+        sensing.reset();
+        assert!(sensing.poll(late));
+    }
+
+    #[test]
+    fn test_dense_sweep_in_chunks() {
+        // A controller sweep using running status with a clock every 24 bytes, and note ons on
+        // another channel interrupting it
+        let mut stream: Vec<u8, 1024> = Vec::new();
+        let mut expected: Vec<LiveEvent<'static>, 256> = Vec::new();
+        let push = |stream: &mut Vec<u8, 1024>, byte| {
+            if stream.len() % 24 == 23 {
+                stream.push(CLOCK).unwrap();
+            }
+            stream.push(byte).unwrap();
+        };
+        for value in 0..128u8 {
+            if value % 32 == 0 {
+                for byte in [0x95, value / 2, 100] {
+                    push(&mut stream, byte);
+                }
+                expected.push(note_on(5, value / 2, 100)).unwrap();
+                push(&mut stream, 0xB0);
+            }
+            push(&mut stream, 74);
+            push(&mut stream, value);
+            expected.push(cc(0, 74, value)).unwrap();
+        }
+
+        let clocks = stream.iter().filter(|&&byte| byte == CLOCK).count();
+        assert!(clocks > 10);
+
+        // Chunks the size of the UART reads, and a couple of odd sizes
+        for chunk_size in [1, 7, 64] {
+            let mut parser = MidiDinParser::default();
+            let mut events: Vec<LiveEvent<'static>, 256> = Vec::new();
+            for chunk in stream.chunks(chunk_size) {
+                for event in parse(&mut parser, chunk) {
+                    events.push(event).unwrap();
+                }
+            }
+            let realtime = events
+                .iter()
+                .filter(|event| matches!(event, LiveEvent::Realtime(_)))
+                .count();
+            assert_eq!(realtime, clocks, "chunks of {chunk_size}");
+            events.retain(|event| !matches!(event, LiveEvent::Realtime(_)));
+            assert_eq!(events, expected, "chunks of {chunk_size}");
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use midly::{num::u4, MidiMessage};
+
+use crate::{
+    utils::{scale_bits_14_12, scale_bits_7_12},
+    MidiNote, NoteEvent,
+};
+
+/// Pitch bend of a voice that isn't bent, in 12 bits
+pub const MPE_BEND_CENTER: u16 = 2047;
+
+/// A note playing on one MPE member channel, with that channel's expression. Bend is
+/// 12-bit and centered on `MPE_BEND_CENTER`, velocity and pressure are 12-bit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MpeVoice {
+    pub note: MidiNote,
+    pub vel: u16,
+    pub bend: u16,
+    pub pressure: u16,
+}
+
+/// Keeps track of the voices of an MPE controller, which plays every note on its own member
+/// channel. The `N` member channels start at `first_channel`, so voice `i` is always played on
+/// `first_channel + i`. Bend and pressure are kept per channel, even while no note is playing,
+/// as MPE controllers send them right before the note on.
+#[derive(Clone, Copy, Debug)]
+pub struct MpeTracker<const N: usize> {
+    first_channel: u8,
+    voices: [Option<MpeVoice>; N],
+    bend: [u16; N],
+    pressure: [u16; N],
+}
+
+impl<const N: usize> MpeTracker<N> {
+    pub fn new(first_channel: u4) -> Self {
+        Self {
+            first_channel: first_channel.as_int(),
+            voices: [None; N],
+            bend: [MPE_BEND_CENTER; N],
+            pressure: [0; N],
+        }
+    }
+
+    fn voice_index(&self, channel: u4) -> Option<usize> {
+        let idx = channel.as_int().checked_sub(self.first_channel)? as usize;
+        (idx < N).then_some(idx)
+    }
+
+    /// Feed a message received on `channel`. Returns the voice it changed, messages on
+    /// channels outside of the member channels and other messages are ignored.
+    pub fn update(&mut self, channel: u4, message: &MidiMessage) -> Option<usize> {
+        let idx = self.voice_index(channel)?;
+        match *message {
+            MidiMessage::PitchBend { bend } => {
+                self.bend[idx] = scale_bits_14_12(bend.0.as_int());
+            }
+            MidiMessage::ChannelAftertouch { vel } => {
+                self.pressure[idx] = scale_bits_7_12(vel);
+            }
+            _ => match NoteEvent::from_message(message)? {
+                NoteEvent::On { note, vel } => {
+                    // A new note on a busy channel takes over the voice
+                    self.voices[idx] = Some(MpeVoice {
+                        note,
+                        vel,
+                        bend: self.bend[idx],
+                        pressure: self.pressure[idx],
+                    });
+                }
+                NoteEvent::Off { note, .. } => {
+                    if self.voices[idx].is_some_and(|v| v.note == note) {
+                        self.voices[idx] = None;
+                    } else {
+                        return None;
+                    }
+                }
+            },
+        }
+        if let Some(voice) = self.voices[idx].as_mut() {
+            voice.bend = self.bend[idx];
+            voice.pressure = self.pressure[idx];
+        }
+        Some(idx)
+    }
+
+    /// The voices on each member channel, `None` for channels without a note
+    pub fn voices(&self) -> [Option<MpeVoice>; N] {
+        self.voices
+    }
+
+    /// Release all voices and center the bends, like on an all notes off
+    pub fn reset(&mut self) {
+        *self = Self::new(u4::from_int_lossy(self.first_channel));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use midly::{
+        num::{u14, u7},
+        PitchBend,
+    };
+
+    use super::*;
+
+    fn note_on(key: u8, vel: u8) -> MidiMessage {
+        MidiMessage::NoteOn {
+            key: u7::new(key),
+            vel: u7::new(vel),
+        }
+    }
+
+    fn note_off(key: u8) -> MidiMessage {
+        MidiMessage::NoteOff {
+            key: u7::new(key),
+            vel: u7::new(64),
+        }
+    }
+
+    fn bend(value: u16) -> MidiMessage {
+        MidiMessage::PitchBend {
+            bend: PitchBend(u14::new(value)),
+        }
+    }
+
+    fn pressure(value: u8) -> MidiMessage {
+        MidiMessage::ChannelAftertouch {
+            vel: u7::new(value),
+        }
+    }
+
+    #[test]
+    fn test_voice_per_member_channel() {
+        // Lower zone, member channels 2 to 5
+        let mut mpe = MpeTracker::<4>::new(u4::new(1));
+        assert_eq!(mpe.update(u4::new(1), &note_on(60, 127)), Some(0));
+        assert_eq!(mpe.update(u4::new(3), &note_on(64, 127)), Some(2));
+        let voices = mpe.voices();
+        assert_eq!(voices[0].map(|v| v.note), Some(MidiNote::from(60)));
+        assert_eq!(voices[1], None);
+        assert_eq!(voices[2].map(|v| v.note), Some(MidiNote::from(64)));
+        assert_eq!(voices[2].map(|v| v.vel), Some(4095));
+        assert_eq!(voices[3], None);
+    }
+
+    #[test]
+    fn test_channels_outside_the_zone_are_ignored() {
+        let mut mpe = MpeTracker::<4>::new(u4::new(1));
+        // The master channel and channels past the zone
+        assert_eq!(mpe.update(u4::new(0), &note_on(60, 100)), None);
+        assert_eq!(mpe.update(u4::new(5), &note_on(60, 100)), None);
+        assert_eq!(mpe.update(u4::new(15), &bend(16383)), None);
+        assert_eq!(mpe.voices(), [None; 4]);
+    }
+
+    #[test]
+    fn test_note_off_releases_voice() {
+        let mut mpe = MpeTracker::<2>::new(u4::new(1));
+        mpe.update(u4::new(1), &note_on(60, 100));
+        mpe.update(u4::new(2), &note_on(62, 100));
+        // Another note's off doesn't release the voice
+        assert_eq!(mpe.update(u4::new(1), &note_off(61)), None);
+        assert!(mpe.voices()[0].is_some());
+        assert_eq!(mpe.update(u4::new(1), &note_off(60)), Some(0));
+        assert_eq!(mpe.voices()[0], None);
+        // Velocity 0 is a note off too
+        assert_eq!(mpe.update(u4::new(2), &note_on(62, 0)), Some(1));
+        assert_eq!(mpe.voices(), [None; 2]);
+    }
+
+    #[test]
+    fn test_new_note_takes_over_channel() {
+        let mut mpe = MpeTracker::<2>::new(u4::new(1));
+        mpe.update(u4::new(1), &note_on(60, 100));
+        mpe.update(u4::new(1), &note_on(67, 100));
+        assert_eq!(mpe.voices()[0].map(|v| v.note), Some(MidiNote::from(67)));
+        // The first note's off is stale by now
+        mpe.update(u4::new(1), &note_off(60));
+        assert!(mpe.voices()[0].is_some());
+    }
+
+    #[test]
+    fn test_bend_per_voice() {
+        let mut mpe = MpeTracker::<3>::new(u4::new(1));
+        mpe.update(u4::new(1), &note_on(60, 100));
+        mpe.update(u4::new(2), &note_on(64, 100));
+        assert_eq!(mpe.voices()[0].map(|v| v.bend), Some(MPE_BEND_CENTER));
+
+        assert_eq!(mpe.update(u4::new(1), &bend(16383)), Some(0));
+        assert_eq!(mpe.update(u4::new(2), &bend(0)), Some(1));
+        let voices = mpe.voices();
+        assert_eq!(voices[0].map(|v| v.bend), Some(4095));
+        assert_eq!(voices[1].map(|v| v.bend), Some(0));
+
+        mpe.update(u4::new(1), &bend(8192));
+        assert_eq!(mpe.voices()[0].map(|v| v.bend), Some(MPE_BEND_CENTER));
+        assert_eq!(mpe.voices()[1].map(|v| v.bend), Some(0));
+    }
+
+    #[test]
+    fn test_expression_before_note_on() {
+        // Controllers send the initial bend and pressure ahead of the note
+        let mut mpe = MpeTracker::<2>::new(u4::new(1));
+        assert_eq!(mpe.update(u4::new(2), &bend(12288)), Some(1));
+        assert_eq!(mpe.update(u4::new(2), &pressure(127)), Some(1));
+        assert_eq!(mpe.voices()[1], None);
+        mpe.update(u4::new(2), &note_on(48, 64));
+        assert_eq!(
+            mpe.voices()[1],
+            Some(MpeVoice {
+                note: MidiNote::from(48),
+                vel: 2063,
+                bend: 3071,
+                pressure: 4095,
+            })
+        );
+    }
+
+    #[test]
+    fn test_pressure_per_voice() {
+        let mut mpe = MpeTracker::<2>::new(u4::new(1));
+        mpe.update(u4::new(1), &note_on(60, 100));
+        mpe.update(u4::new(2), &note_on(64, 100));
+        mpe.update(u4::new(2), &pressure(127));
+        assert_eq!(mpe.voices()[0].map(|v| v.pressure), Some(0));
+        assert_eq!(mpe.voices()[1].map(|v| v.pressure), Some(4095));
+    }
+
+    #[test]
+    fn test_reset() {
+        let mut mpe = MpeTracker::<2>::new(u4::new(1));
+        mpe.update(u4::new(1), &bend(0));
+        mpe.update(u4::new(1), &note_on(60, 100));
+        mpe.reset();
+        assert_eq!(mpe.voices(), [None; 2]);
+        mpe.update(u4::new(1), &note_on(60, 100));
+        assert_eq!(mpe.voices()[0].map(|v| v.bend), Some(MPE_BEND_CENTER));
+        // Still listening on the same channels
+        assert_eq!(mpe.update(u4::new(0), &note_on(60, 100)), None);
+    }
+}
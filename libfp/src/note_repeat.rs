@@ -0,0 +1,205 @@
+use heapless::Vec;
+
+use crate::{MidiNote, NoteEvent};
+
+/// Notes kept while held, the oldest is dropped when more are pressed
+pub const NOTE_REPEAT_HELD: usize = 8;
+
+/// Retriggers the held note at a clock division, like the note repeat of a groovebox. The last
+/// pressed note is repeated, releasing it goes back to the note held before it. A press plays
+/// right away and the repeats are counted from there.
+#[derive(Clone, Debug, Default)]
+pub struct NoteRepeat {
+    held: Vec<(MidiNote, u16), NOTE_REPEAT_HELD>,
+    sounding: Option<MidiNote>,
+    count: u32,
+}
+
+impl NoteRepeat {
+    /// A note was pressed, returns the note on to play right away
+    pub fn press(&mut self, note: MidiNote, vel: u16) -> NoteEvent {
+        self.held.retain(|&(held, _)| held != note);
+        if self.held.is_full() {
+            self.held.remove(0);
+        }
+        // Can't fail, there is room after the removal above
+        let _ = self.held.push((note, vel));
+        self.count = 0;
+        self.sounding = Some(note);
+        NoteEvent::On { note, vel }
+    }
+
+    /// A note was released, returns the note off once no notes are held anymore
+    pub fn release(&mut self, note: MidiNote) -> Option<NoteEvent> {
+        self.held.retain(|&(held, _)| held != note);
+        if !self.held.is_empty() {
+            return None;
+        }
+        self.sounding
+            .take()
+            .map(|note| NoteEvent::Off { note, vel: 0 })
+    }
+
+    /// Advance by one clock tick. Retriggers the held note every `division` ticks and ends it
+    /// `gate_ticks` after each hit.
+    pub fn tick(&mut self, division: u32, gate_ticks: u32) -> Option<NoteEvent> {
+        let &(note, vel) = self.held.last()?;
+        self.count += 1;
+        if self.count >= division {
+            self.count = 0;
+            self.sounding = Some(note);
+            return Some(NoteEvent::On { note, vel });
+        }
+        if self.count == gate_ticks {
+            return self
+                .sounding
+                .take()
+                .map(|note| NoteEvent::Off { note, vel: 0 });
+        }
+        None
+    }
+
+    /// End the sounding note, like when the clock stops. Held notes stay held.
+    pub fn stop(&mut self) -> Option<NoteEvent> {
+        self.count = 0;
+        self.sounding
+            .take()
+            .map(|note| NoteEvent::Off { note, vel: 0 })
+    }
+
+    /// The note that is repeated, if any is held
+    pub fn held(&self) -> Option<MidiNote> {
+        self.held.last().map(|&(note, _)| note)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn on(note: u8, vel: u16) -> Option<NoteEvent> {
+        Some(NoteEvent::On {
+            note: MidiNote::from(note),
+            vel,
+        })
+    }
+
+    fn off(note: u8) -> Option<NoteEvent> {
+        Some(NoteEvent::Off {
+            note: MidiNote::from(note),
+            vel: 0,
+        })
+    }
+
+    #[test]
+    fn test_press_plays_right_away() {
+        let mut repeat = NoteRepeat::default();
+        assert_eq!(repeat.tick(6, 3), None);
+        assert_eq!(Some(repeat.press(MidiNote::from(60), 4095)), on(60, 4095));
+        assert_eq!(repeat.held(), Some(MidiNote::from(60)));
+    }
+
+    #[test]
+    fn test_repeats_at_division_while_held() {
+        let mut repeat = NoteRepeat::default();
+        repeat.press(MidiNote::from(60), 3000);
+        let events: [Option<NoteEvent>; 18] = core::array::from_fn(|_| repeat.tick(6, 3));
+        for (i, event) in events.iter().enumerate() {
+            let tick = i as u32 + 1;
+            let expected = match tick % 6 {
+                0 => on(60, 3000),
+                3 => off(60),
+                _ => None,
+            };
+            assert_eq!(*event, expected, "tick {tick}");
+        }
+    }
+
+    #[test]
+    fn test_rate_follows_division() {
+        for division in [3, 4, 6, 8, 12, 24] {
+            let mut repeat = NoteRepeat::default();
+            repeat.press(MidiNote::from(36), 4095);
+            let hits = (0..96)
+                .filter(|_| matches!(repeat.tick(division, 1), Some(NoteEvent::On { .. })))
+                .count();
+            assert_eq!(hits as u32, 96 / division);
+        }
+    }
+
+    #[test]
+    fn test_release_stops_repeating() {
+        let mut repeat = NoteRepeat::default();
+        repeat.press(MidiNote::from(60), 4095);
+        assert_eq!(repeat.release(MidiNote::from(60)), off(60));
+        assert_eq!(repeat.held(), None);
+        for _ in 0..48 {
+            assert_eq!(repeat.tick(6, 3), None);
+        }
+    }
+
+    #[test]
+    fn test_release_after_gate_ended() {
+        let mut repeat = NoteRepeat::default();
+        repeat.press(MidiNote::from(60), 4095);
+        repeat.tick(6, 1);
+        // The gate already ended, there is nothing left to turn off
+        assert_eq!(repeat.release(MidiNote::from(60)), None);
+    }
+
+    #[test]
+    fn test_last_pressed_note_repeats() {
+        let mut repeat = NoteRepeat::default();
+        repeat.press(MidiNote::from(60), 1000);
+        repeat.press(MidiNote::from(64), 2000);
+        let hit = (0..6).find_map(|_| repeat.tick(6, 3));
+        assert_eq!(hit, off(64));
+        let hit = (0..6).find_map(|_| repeat.tick(6, 3));
+        assert_eq!(hit, on(64, 2000));
+
+        // Releasing the last note goes back to the one held before
+        assert_eq!(repeat.release(MidiNote::from(64)), None);
+        let hits: [Option<NoteEvent>; 6] = core::array::from_fn(|_| repeat.tick(6, 3));
+        assert_eq!(hits.iter().flatten().count(), 2);
+        assert_eq!(hits[2], off(64));
+        assert_eq!(hits[5], on(60, 1000));
+        assert_eq!(repeat.release(MidiNote::from(60)), off(60));
+    }
+
+    #[test]
+    fn test_press_restarts_count() {
+        let mut repeat = NoteRepeat::default();
+        repeat.press(MidiNote::from(60), 4095);
+        for _ in 0..4 {
+            repeat.tick(6, 3);
+        }
+        repeat.press(MidiNote::from(62), 4095);
+        let events: [Option<NoteEvent>; 6] = core::array::from_fn(|_| repeat.tick(6, 3));
+        assert_eq!(events[2], off(62));
+        assert_eq!(events[5], on(62, 4095));
+    }
+
+    #[test]
+    fn test_oldest_held_note_is_dropped() {
+        let mut repeat = NoteRepeat::default();
+        for note in 0..NOTE_REPEAT_HELD as u8 + 1 {
+            repeat.press(MidiNote::from(60 + note), 4095);
+        }
+        for note in (1..NOTE_REPEAT_HELD as u8 + 1).rev() {
+            assert_eq!(repeat.held(), Some(MidiNote::from(60 + note)));
+            repeat.release(MidiNote::from(60 + note));
+        }
+        assert_eq!(repeat.held(), None);
+    }
+
+    #[test]
+    fn test_stop() {
+        let mut repeat = NoteRepeat::default();
+        repeat.press(MidiNote::from(60), 4095);
+        assert_eq!(repeat.stop(), off(60));
+        assert_eq!(repeat.stop(), None);
+        // Still held, repeats go on when the clock starts again
+        let hit = (0..6).find_map(|_| repeat.tick(6, 3));
+        assert_eq!(hit, on(60, 4095));
+    }
+}
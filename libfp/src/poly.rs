@@ -0,0 +1,225 @@
+use crate::MidiNote;
+
+/// A note given to a voice. The note stays with the voice after its release, so the pitch
+/// doesn't jump while an envelope is still closing.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PolyVoice {
+    pub note: MidiNote,
+    pub vel: u16,
+    pub held: bool,
+    // Order the notes were played in, to find the oldest one
+    order: u32,
+}
+
+/// Hands out `N` voices to incoming notes. Free voices are used round-robin, so a release
+/// can ring out while the next notes play on other voices. When every voice is held, the
+/// oldest note is stolen.
+#[derive(Clone, Copy, Debug)]
+pub struct VoiceAllocator<const N: usize> {
+    voices: [Option<PolyVoice>; N],
+    next: usize,
+    counter: u32,
+}
+
+impl<const N: usize> VoiceAllocator<N> {
+    pub fn new() -> Self {
+        Self {
+            voices: [None; N],
+            next: 0,
+            counter: 0,
+        }
+    }
+
+    /// Give a voice to a new note and return it. A note that is already held is retriggered
+    /// on its voice.
+    pub fn note_on(&mut self, note: MidiNote, vel: u16) -> usize {
+        let idx = self
+            .find_held(note)
+            .or_else(|| {
+                (0..N)
+                    .map(|i| (self.next + i) % N)
+                    .find(|&i| !self.voices[i].is_some_and(|v| v.held))
+            })
+            .unwrap_or_else(|| self.oldest());
+        self.counter = self.counter.wrapping_add(1);
+        self.voices[idx] = Some(PolyVoice {
+            note,
+            vel,
+            held: true,
+            order: self.counter,
+        });
+        self.next = (idx + 1) % N;
+        idx
+    }
+
+    /// Release the voice playing `note`, if any
+    pub fn note_off(&mut self, note: MidiNote) -> Option<usize> {
+        let idx = self.find_held(note)?;
+        if let Some(voice) = self.voices[idx].as_mut() {
+            voice.held = false;
+        }
+        Some(idx)
+    }
+
+    /// The note of a voice, `None` if it hasn't played yet
+    pub fn voice(&self, idx: usize) -> Option<PolyVoice> {
+        self.voices.get(idx).copied().flatten()
+    }
+
+    /// Changes every time the voice plays a note, also when the same note is retriggered
+    pub fn triggered(&self, idx: usize) -> u32 {
+        self.voice(idx).map_or(0, |v| v.order)
+    }
+
+    /// Release all voices, keeping their notes
+    pub fn release_all(&mut self) {
+        for voice in self.voices.iter_mut().flatten() {
+            voice.held = false;
+        }
+    }
+
+    fn find_held(&self, note: MidiNote) -> Option<usize> {
+        self.voices
+            .iter()
+            .position(|v| v.is_some_and(|v| v.held && v.note == note))
+    }
+
+    fn oldest(&self) -> usize {
+        (0..N)
+            .max_by_key(|&i| {
+                self.voices[i].map_or(u32::MAX, |v| self.counter.wrapping_sub(v.order))
+            })
+            .unwrap_or(0)
+    }
+}
+
+impl<const N: usize> Default for VoiceAllocator<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note(n: u8) -> MidiNote {
+        MidiNote::from(n)
+    }
+
+    fn notes<const N: usize>(alloc: &VoiceAllocator<N>) -> [Option<MidiNote>; N] {
+        core::array::from_fn(|i| alloc.voice(i).map(|v| v.note))
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let mut alloc = VoiceAllocator::<4>::new();
+        assert_eq!(alloc.note_on(note(60), 4095), 0);
+        assert_eq!(alloc.note_on(note(64), 4095), 1);
+        assert_eq!(alloc.note_on(note(67), 4095), 2);
+        assert_eq!(
+            notes(&alloc),
+            [Some(note(60)), Some(note(64)), Some(note(67)), None]
+        );
+        // Released voices are reused only once it's their turn again
+        assert_eq!(alloc.note_off(note(60)), Some(0));
+        assert_eq!(alloc.note_on(note(72), 4095), 3);
+        assert_eq!(alloc.note_on(note(74), 4095), 0);
+    }
+
+    #[test]
+    fn test_release_keeps_note() {
+        let mut alloc = VoiceAllocator::<2>::new();
+        alloc.note_on(note(60), 2000);
+        alloc.note_off(note(60));
+        assert_eq!(
+            alloc.voice(0),
+            Some(PolyVoice {
+                note: note(60),
+                vel: 2000,
+                held: false,
+                order: 1,
+            })
+        );
+        // Not held anymore, a second note off does nothing
+        assert_eq!(alloc.note_off(note(60)), None);
+        assert_eq!(alloc.note_off(note(61)), None);
+    }
+
+    #[test]
+    fn test_skips_held_voices() {
+        let mut alloc = VoiceAllocator::<4>::new();
+        for n in [60, 62, 64, 65] {
+            alloc.note_on(note(n), 4095);
+        }
+        alloc.note_off(note(62));
+        // Voice 0 is next in turn but still held, so the free voice 1 is used
+        assert_eq!(alloc.note_on(note(67), 4095), 1);
+        assert_eq!(alloc.note_off(note(64)), Some(2));
+        assert_eq!(alloc.note_on(note(69), 4095), 2);
+    }
+
+    #[test]
+    fn test_steals_oldest_note() {
+        let mut alloc = VoiceAllocator::<3>::new();
+        for n in [60, 62, 64] {
+            alloc.note_on(note(n), 4095);
+        }
+        assert_eq!(alloc.note_on(note(65), 4095), 0);
+        assert_eq!(
+            notes(&alloc),
+            [Some(note(65)), Some(note(62)), Some(note(64))]
+        );
+        assert_eq!(alloc.note_on(note(67), 4095), 1);
+        assert_eq!(alloc.note_on(note(69), 4095), 2);
+        assert_eq!(alloc.note_on(note(71), 4095), 0);
+        // The stolen note's off doesn't release the voice that took over
+        assert_eq!(alloc.note_off(note(60)), None);
+        assert!(alloc.voice(0).is_some_and(|v| v.held));
+    }
+
+    #[test]
+    fn test_steals_oldest_after_releases() {
+        let mut alloc = VoiceAllocator::<3>::new();
+        for n in [60, 62, 64] {
+            alloc.note_on(note(n), 4095);
+        }
+        alloc.note_off(note(62));
+        alloc.note_on(note(66), 4095);
+        // Voice 1 got the newest note, voice 0 still has the oldest
+        assert_eq!(alloc.note_on(note(67), 4095), 0);
+        assert_eq!(alloc.note_on(note(69), 4095), 2);
+    }
+
+    #[test]
+    fn test_held_note_retriggers_its_voice() {
+        let mut alloc = VoiceAllocator::<4>::new();
+        alloc.note_on(note(60), 1000);
+        alloc.note_on(note(64), 1000);
+        let triggered = alloc.triggered(0);
+        assert_eq!(alloc.note_on(note(60), 3000), 0);
+        assert_ne!(alloc.triggered(0), triggered);
+        assert_eq!(alloc.voice(0).map(|v| v.vel), Some(3000));
+        assert_eq!(alloc.note_off(note(60)), Some(0));
+        assert_eq!(alloc.note_off(note(60)), None);
+    }
+
+    #[test]
+    fn test_release_all() {
+        let mut alloc = VoiceAllocator::<2>::new();
+        alloc.note_on(note(60), 4095);
+        alloc.note_on(note(62), 4095);
+        alloc.release_all();
+        assert!((0..2).all(|i| alloc.voice(i).is_some_and(|v| !v.held)));
+        assert_eq!(notes(&alloc), [Some(note(60)), Some(note(62))]);
+    }
+
+    #[test]
+    fn test_single_voice() {
+        let mut alloc = VoiceAllocator::<1>::new();
+        assert_eq!(alloc.note_on(note(60), 4095), 0);
+        assert_eq!(alloc.note_on(note(62), 4095), 0);
+        assert_eq!(notes(&alloc), [Some(note(62))]);
+        assert_eq!(alloc.voice(1), None);
+    }
+}
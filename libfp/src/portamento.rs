@@ -0,0 +1,163 @@
+use libm::expf;
+
+/// Number of time constants in a glide, after that the output is within 1% of the target
+const GLIDE_TIME_CONSTANTS: f32 = 5.0;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum GlideMode {
+    /// Every new note glides from the previous one
+    Always,
+    /// Only notes played legato (while another note is held) glide
+    #[default]
+    Fingered,
+}
+
+/// RC filter glide between pitches, shared by apps that play notes
+#[derive(Clone, Copy, Debug)]
+pub struct Portamento {
+    glide_ms: u16,
+    mode: GlideMode,
+    legato: bool,
+    gliding: bool,
+    current: f32,
+    target: f32,
+}
+
+impl Portamento {
+    /// `glide_ms` is the time it takes to (nearly) reach a new note, 0 jumps right to it
+    pub fn new(glide_ms: u16, mode: GlideMode) -> Self {
+        Self {
+            glide_ms,
+            mode,
+            legato: false,
+            gliding: false,
+            current: 0.0,
+            target: 0.0,
+        }
+    }
+
+    pub fn set_glide_ms(&mut self, glide_ms: u16) {
+        self.glide_ms = glide_ms;
+    }
+
+    pub fn set_mode(&mut self, mode: GlideMode) {
+        self.mode = mode;
+    }
+
+    /// Whether a note is still held when the next one comes in. Set this before the new target
+    /// reaches `process`.
+    pub fn set_legato(&mut self, legato: bool) {
+        self.legato = legato;
+    }
+
+    /// Move towards `target` for `dt_ms` and return the new pitch. A new target starts a glide
+    /// when the mode allows it, otherwise the output jumps.
+    pub fn process(&mut self, target: f32, dt_ms: f32) -> f32 {
+        if target != self.target {
+            self.target = target;
+            self.gliding = match self.mode {
+                GlideMode::Always => true,
+                GlideMode::Fingered => self.legato,
+            };
+        }
+
+        if !self.gliding || self.glide_ms == 0 {
+            self.current = target;
+        } else {
+            let tau = self.glide_ms as f32 / GLIDE_TIME_CONSTANTS;
+            let coeff = 1.0 - expf(-dt_ms / tau);
+            self.current += (target - self.current) * coeff;
+        }
+
+        self.current
+    }
+
+    /// Current pitch without advancing the glide
+    pub fn value(&self) -> f32 {
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Run a glide from 0 to 1000 in 1ms steps, returns the output after each step
+    fn glide(portamento: &mut Portamento, steps: usize) -> f32 {
+        let mut out = 0.0;
+        for _ in 0..steps {
+            out = portamento.process(1000.0, 1.0);
+        }
+        out
+    }
+
+    #[test]
+    fn test_settles_within_glide_time() {
+        for glide_ms in [10, 50, 150, 500, 2000] {
+            let mut portamento = Portamento::new(glide_ms, GlideMode::Always);
+            portamento.process(0.0, 1.0);
+            // Halfway through it is still noticeably off
+            let half = glide(&mut portamento, glide_ms as usize / 2);
+            assert!(half < 950.0, "{glide_ms}ms: {half}");
+            // At the glide time it is within 1%
+            let done = glide(&mut portamento, glide_ms as usize - glide_ms as usize / 2);
+            assert!(done > 990.0 && done <= 1000.0, "{glide_ms}ms: {done}");
+        }
+    }
+
+    #[test]
+    fn test_longer_glides_are_slower() {
+        let mut prev = 1000.0;
+        for glide_ms in [10, 50, 150, 500, 2000] {
+            let mut portamento = Portamento::new(glide_ms, GlideMode::Always);
+            portamento.process(0.0, 1.0);
+            let out = glide(&mut portamento, 10);
+            assert!(out < prev);
+            prev = out;
+        }
+    }
+
+    #[test]
+    fn test_step_size_does_not_matter() {
+        let mut fine = Portamento::new(100, GlideMode::Always);
+        let mut coarse = Portamento::new(100, GlideMode::Always);
+        fine.process(0.0, 1.0);
+        coarse.process(0.0, 1.0);
+        glide(&mut fine, 40);
+        coarse.process(1000.0, 40.0);
+        assert!((fine.value() - coarse.value()).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_zero_glide_is_instant() {
+        let mut portamento = Portamento::new(0, GlideMode::Always);
+        portamento.process(0.0, 1.0);
+        assert_eq!(portamento.process(1000.0, 1.0), 1000.0);
+    }
+
+    #[test]
+    fn test_fingered_only_glides_legato() {
+        let mut portamento = Portamento::new(100, GlideMode::Fingered);
+        // Detached note jumps
+        assert_eq!(portamento.process(500.0, 1.0), 500.0);
+        // Legato note glides
+        portamento.set_legato(true);
+        let out = portamento.process(1000.0, 1.0);
+        assert!(out > 500.0 && out < 600.0);
+        // Releasing the keys doesn't cut a glide short
+        portamento.set_legato(false);
+        assert!(portamento.process(1000.0, 1.0) < 1000.0);
+        // The next detached note jumps again
+        assert_eq!(portamento.process(200.0, 1.0), 200.0);
+    }
+
+    #[test]
+    fn test_always_glides() {
+        let mut portamento = Portamento::new(100, GlideMode::Always);
+        portamento.process(500.0, 100.0);
+        let out = portamento.process(0.0, 1.0);
+        assert!(out > 400.0);
+        portamento.set_mode(GlideMode::Fingered);
+        assert_eq!(portamento.process(1000.0, 1.0), 1000.0);
+    }
+}
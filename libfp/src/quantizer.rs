@@ -1,11 +1,18 @@
 // V/oct quantizer, based on the ideas in
 // https://github.com/pichenettes/eurorack/blob/master/braids/quantizer_scales.h
 
-use crate::{Key, MidiNote, Note, Range};
+use crate::{
+    portamento::{GlideMode, Portamento},
+    Key, MidiNote, Note, Range,
+};
 use heapless::Vec;
 use libm::roundf;
+use midly::num::u7;
 
 const CODEBOOK_SIZE: usize = 216;
+/// How far (in 1/128 semitones) the input has to move back past the chosen tone before an `Up`
+/// or `Down` quantizer lets go of it
+const DIRECTED_HYSTERESIS: i32 = 8;
 
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct Pitch {
@@ -14,6 +21,34 @@ pub struct Pitch {
 }
 
 impl Pitch {
+    /// Pitch of a MIDI note, MIDI note 60 is C4
+    pub fn from_midi(midi: u8) -> Self {
+        let midi = midi.min(127);
+        Self {
+            octave: (midi / 12) as i8 - 1,
+            note: Note::from(midi % 12),
+        }
+    }
+
+    /// Move the pitch by +/- semitones, carrying into the octave
+    pub fn transpose(self, semitones: i8) -> Self {
+        let total = self.octave as i32 * 12 + self.note as u8 as i32 + semitones as i32;
+        Self {
+            octave: total.div_euclid(12) as i8,
+            note: Note::from(total.rem_euclid(12) as u8),
+        }
+    }
+
+    /// Voltage of the pitch at the jack, limited to what the range can output
+    pub fn as_volts(&self, range: Range) -> f32 {
+        let (min, max) = match range {
+            Range::_0_10V => (0.0, 10.0),
+            Range::_0_5V => (0.0, 5.0),
+            Range::_Neg5_5V => (-5.0, 5.0),
+        };
+        self.as_v_oct().clamp(min, max)
+    }
+
     pub fn as_v_oct(&self) -> f32 {
         self.octave as f32 + (self.note as u8 as f32 / 12.0)
     }
@@ -35,14 +70,73 @@ impl Pitch {
     }
 }
 
+/// Which scale tone an input between two tones goes to
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum QuantizeMode {
+    /// The closest tone
+    #[default]
+    Nearest,
+    /// The tone at or above the input
+    Up,
+    /// The tone at or below the input
+    Down,
+}
+
+impl QuantizeMode {
+    /// Mode of an enum param with the variants "Nearest", "Up" and "Down"
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            1 => Self::Up,
+            2 => Self::Down,
+            _ => Self::Nearest,
+        }
+    }
+}
+
+/// Where `Quantizer::get_transposed_note` applies a transposition
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum TransposeMode {
+    /// Semitones added to the input, the result stays in the scale
+    #[default]
+    Before,
+    /// Semitones added to the quantized note, the result can leave the scale
+    After,
+    /// Steps through the scale from the quantized note
+    Degrees,
+}
+
+impl TransposeMode {
+    /// Mode of an enum param with the variants "Before", "After" and "Scale Degrees"
+    pub fn from_index(index: usize) -> Self {
+        match index {
+            1 => Self::After,
+            2 => Self::Degrees,
+            _ => Self::Before,
+        }
+    }
+}
+
 pub struct QuantizerState {
     codeword: i16,
     next_boundary: i32,
     previous_boundary: i32,
     version: u64,
+    mode: QuantizeMode,
 }
 
 impl QuantizerState {
+    pub fn set_mode(&mut self, mode: QuantizeMode) {
+        if mode != self.mode {
+            self.mode = mode;
+            // Force a search with the new mode
+            self.reset(self.version);
+        }
+    }
+
+    pub fn mode(&self) -> QuantizeMode {
+        self.mode
+    }
+
     pub fn reset(&mut self, version: u64) {
         // Reset hysteresis when the scale changes
         // Invert boundaries to force a search on the first call
@@ -60,10 +154,109 @@ impl Default for QuantizerState {
             previous_boundary: i32::MAX,
             next_boundary: i32::MIN,
             version: 0,
+            mode: QuantizeMode::Nearest,
         }
     }
 }
 
+/// Scales an app can use instead of the global quantizer scale
+pub const SCALE_KEYS: [Key; 16] = [
+    Key::Chromatic,
+    Key::Ionian,
+    Key::Dorian,
+    Key::Phrygian,
+    Key::Lydian,
+    Key::Mixolydian,
+    Key::Aeolian,
+    Key::Locrian,
+    Key::BluesMaj,
+    Key::BluesMin,
+    Key::PentatonicMaj,
+    Key::PentatonicMin,
+    Key::Folk,
+    Key::Japanese,
+    Key::Gamelan,
+    Key::HungarianMin,
+];
+
+/// Variants of a scale override param, "Global" followed by the names of `SCALE_KEYS`
+pub const SCALE_OVERRIDE_NAMES: [&str; 17] = [
+    "Global",
+    "Chromatic",
+    "Ionian",
+    "Dorian",
+    "Phrygian",
+    "Lydian",
+    "Mixolydian",
+    "Aeolian",
+    "Locrian",
+    "Blues Major",
+    "Blues Minor",
+    "Pentatonic Major",
+    "Pentatonic Minor",
+    "Folk",
+    "Japanese",
+    "Gamelan",
+    "Hungarian Minor",
+];
+
+/// Key of a scale override param value, `None` follows the global quantizer
+pub fn scale_override(index: usize) -> Option<Key> {
+    index
+        .checked_sub(1)
+        .and_then(|i| SCALE_KEYS.get(i).copied())
+}
+
+/// Semitones above C of the notes in a 12-bit scale mask
+fn mask_notes(mask: u16) -> Vec<i16, 12> {
+    (0..12)
+        .filter(|i| (mask >> (11 - i)) & 1 != 0) // Read from MSB (C) to LSB (B)
+        .map(|i| i as i16)
+        .collect()
+}
+
+/// Notes held on a MIDI input, as a pitch class mask for `Quantizer::set_active_notes`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct HeldNotes {
+    held: u128,
+    chord: u128,
+    latch: bool,
+}
+
+impl HeldNotes {
+    pub fn note_on(&mut self, note: MidiNote) {
+        // The first note after all keys were released starts a new chord
+        if self.held == 0 {
+            self.chord = 0;
+        }
+        let bit = 1 << u7::from(note).as_int();
+        self.held |= bit;
+        self.chord |= bit;
+    }
+
+    pub fn note_off(&mut self, note: MidiNote) {
+        self.held &= !(1 << u7::from(note).as_int());
+        if !self.latch {
+            self.chord = self.held;
+        }
+    }
+
+    /// Keep the last chord after its keys are released, until the next one is played
+    pub fn set_latch(&mut self, latch: bool) {
+        self.latch = latch;
+        if !latch {
+            self.chord = self.held;
+        }
+    }
+
+    /// Pitch classes of the chord, bit 11 is C and bit 0 is B like `Key::as_u16_key`
+    pub fn mask(&self) -> u16 {
+        (0..128)
+            .filter(|note| (self.chord >> note) & 1 != 0)
+            .fold(0, |mask, note| mask | 1 << (11 - note % 12))
+    }
+}
+
 pub struct Quantizer {
     codebook: [i16; CODEBOOK_SIZE],
     version: u64,
@@ -72,30 +265,46 @@ pub struct Quantizer {
 }
 
 impl Quantizer {
+    pub fn new(key: Key, tonic: Note) -> Self {
+        let mut q = Self::default();
+        q.set_scale(key, tonic);
+        q
+    }
+
     pub fn set_scale(&mut self, key: Key, tonic: Note) {
         // Store the key and tonic
         self.key = key;
         self.tonic = tonic;
 
-        let mask = key.as_u16_key();
-        let notes: Vec<i16, 12> = (0..12)
-            .filter(|i| (mask >> (11 - i)) & 1 != 0) // Read from MSB (C) to LSB (B)
-            .map(|i| i as i16)
-            .collect();
+        let notes = mask_notes(key.as_u16_key());
         if notes.is_empty() {
             // Fallback to chromatic for an empty scale
             self.set_scale(Key::Chromatic, tonic);
             return;
         }
 
-        let tonic_offset = tonic as i16;
+        self.build_codebook(&notes, tonic as i16);
+    }
 
+    /// Quantize to the pitch classes in `mask` instead of a scale, e.g. the notes held on a MIDI
+    /// input (see `HeldNotes`). Bit 11 is C and bit 0 is B like `Key::as_u16_key`. An empty mask
+    /// is chromatic. The key and tonic are left as they were.
+    pub fn set_active_notes(&mut self, mask: u16) {
+        let notes = mask_notes(mask);
+        if notes.is_empty() {
+            self.build_codebook(&mask_notes(Key::Chromatic.as_u16_key()), 0);
+        } else {
+            self.build_codebook(&notes, 0);
+        }
+    }
+
+    fn build_codebook(&mut self, notes: &[i16], tonic_offset: i16) {
         // Build codebook directly with scale notes spanning useful range
         let mut codebook_idx = 0;
 
         // Cover a wide range of octaves to ensure we can quantize any reasonable input
         for octave in -6..=11 {
-            for &note_offset in &notes {
+            for &note_offset in notes {
                 if codebook_idx >= CODEBOOK_SIZE {
                     break;
                 }
@@ -136,6 +345,11 @@ impl Quantizer {
         self.tonic
     }
 
+    /// Changes every time the scale is set
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
     pub fn get_quantized_note(
         &self,
         state: &mut QuantizerState,
@@ -161,19 +375,34 @@ impl Quantizer {
             // Input is outside the current note's hysteresis boundary; find a new note
             let upper_bound_index = self.codebook.partition_point(|&x| (x as i32) < pitch);
 
+            let last_index = self.codebook.len() - 1;
+
             let best_index = if upper_bound_index == 0 {
                 0
-            } else if upper_bound_index >= self.codebook.len() {
-                self.codebook.len() - 1
+            } else if upper_bound_index > last_index {
+                last_index
             } else {
                 let lower_bound_index = upper_bound_index - 1;
-                let dist_lo = (pitch - self.codebook[lower_bound_index] as i32).abs();
-                let dist_hi = (pitch - self.codebook[upper_bound_index] as i32).abs();
-
-                if dist_lo <= dist_hi {
-                    lower_bound_index
-                } else {
-                    upper_bound_index
+                match state.mode {
+                    QuantizeMode::Nearest => {
+                        let dist_lo = (pitch - self.codebook[lower_bound_index] as i32).abs();
+                        let dist_hi = (pitch - self.codebook[upper_bound_index] as i32).abs();
+
+                        if dist_lo <= dist_hi {
+                            lower_bound_index
+                        } else {
+                            upper_bound_index
+                        }
+                    }
+                    QuantizeMode::Up => upper_bound_index,
+                    QuantizeMode::Down => {
+                        // Exactly on a tone is that tone
+                        if self.codebook[upper_bound_index] as i32 == pitch {
+                            upper_bound_index
+                        } else {
+                            lower_bound_index
+                        }
+                    }
                 }
             };
 
@@ -181,13 +410,26 @@ impl Quantizer {
 
             // Update hysteresis boundaries for the new codeword
             let prev_idx = best_index.saturating_sub(1);
-            let next_idx = (best_index + 1).min(self.codebook.len() - 1);
+            let next_idx = (best_index + 1).min(last_index);
             let prev_codeword = self.codebook[prev_idx] as i32;
             let next_codeword = self.codebook[next_idx] as i32;
+            let codeword = state.codeword as i32;
 
-            // Weighted average places the boundary closer to the neighbor note
-            state.previous_boundary = (9 * prev_codeword + 7 * state.codeword as i32) / 16;
-            state.next_boundary = (9 * next_codeword + 7 * state.codeword as i32) / 16;
+            match state.mode {
+                QuantizeMode::Nearest => {
+                    // Weighted average places the boundary closer to the neighbor note
+                    state.previous_boundary = (9 * prev_codeword + 7 * codeword) / 16;
+                    state.next_boundary = (9 * next_codeword + 7 * codeword) / 16;
+                }
+                QuantizeMode::Up => {
+                    state.previous_boundary = prev_codeword + 1;
+                    state.next_boundary = codeword + DIRECTED_HYSTERESIS;
+                }
+                QuantizeMode::Down => {
+                    state.previous_boundary = codeword - DIRECTED_HYSTERESIS;
+                    state.next_boundary = next_codeword - 1;
+                }
+            }
         }
 
         let final_semitones = roundf(state.codeword as f32 / 128.0) as i32;
@@ -199,6 +441,42 @@ impl Quantizer {
             note: note.into(),
         }
     }
+
+    /// Quantize `value` transposed by `steps`, semitones or scale degrees depending on `mode`
+    pub fn get_transposed_note(
+        &self,
+        state: &mut QuantizerState,
+        value: u16,
+        range: Range,
+        steps: i32,
+        mode: TransposeMode,
+    ) -> Pitch {
+        if mode == TransposeMode::Before {
+            let octave_volts = match range {
+                Range::_0_5V => 5.0,
+                _ => 10.0,
+            };
+            let offset = roundf(steps as f32 * 4095.0 / (12.0 * octave_volts)) as i32;
+            let value = (value as i32 + offset).clamp(0, 4095) as u16;
+            return self.get_quantized_note(state, value, range);
+        }
+
+        let pitch = self.get_quantized_note(state, value, range);
+        let semitones = pitch.octave as i32 * 12 + pitch.note as u8 as i32;
+        let semitones = if mode == TransposeMode::Degrees {
+            let index = self
+                .codebook
+                .partition_point(|&x| (x as i32) < semitones * 128) as i32;
+            let index = (index + steps).clamp(0, CODEBOOK_SIZE as i32 - 1) as usize;
+            self.codebook[index] as i32 / 128
+        } else {
+            semitones + steps
+        };
+        Pitch {
+            octave: semitones.div_euclid(12) as i8,
+            note: (semitones.rem_euclid(12) as u8).into(),
+        }
+    }
 }
 
 impl Default for Quantizer {
@@ -215,9 +493,37 @@ impl Default for Quantizer {
     }
 }
 
+/// Ramps a quantized output to its new value when the scale changes, instead of jumping. New
+/// notes from the input still jump, only a scale change glides.
+pub struct ScaleCrossfade {
+    portamento: Portamento,
+    version: Option<u64>,
+}
+
+impl ScaleCrossfade {
+    /// `fade_ms` is how long the ramp to the new scale takes, 0 jumps like without a crossfade
+    pub fn new(fade_ms: u16) -> Self {
+        Self {
+            portamento: Portamento::new(fade_ms, GlideMode::Fingered),
+            version: None,
+        }
+    }
+
+    /// Output for the quantized `counts`, `version` being the quantizer's scale version and
+    /// `dt_ms` the time since the last call
+    pub fn process(&mut self, counts: u16, version: u64, dt_ms: f32) -> u16 {
+        let scale_changed = self.version.is_some_and(|v| v != version);
+        self.version = Some(version);
+        // The glide primitive glides legato notes only, a scale change counts as legato
+        self.portamento.set_legato(scale_changed);
+        roundf(self.portamento.process(counts as f32, dt_ms)).clamp(0.0, 4095.0) as u16
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::utils::semitone_to_counts;
 
     #[test]
     fn test_quantize_c_major_unipolar() {
@@ -389,6 +695,102 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_scale_override_index() {
+        assert_eq!(scale_override(0), None);
+        assert_eq!(scale_override(1), Some(Key::Chromatic));
+        assert_eq!(scale_override(2), Some(Key::Ionian));
+        assert_eq!(scale_override(16), Some(Key::HungarianMin));
+        assert_eq!(scale_override(17), None);
+        for (i, key) in SCALE_KEYS.iter().enumerate() {
+            assert_eq!(*key as usize, i);
+            assert_eq!(scale_override(i + 1), Some(*key));
+        }
+    }
+
+    #[test]
+    fn test_scale_override_quantizes_independently() {
+        let global = Quantizer::default();
+        let local = Quantizer::new(Key::PentatonicMin, Note::A);
+        assert_eq!(local.get_key(), Key::PentatonicMin);
+        assert_eq!(local.get_tonic(), Note::A);
+
+        // A minor pentatonic: A, C, D, E, G
+        let in_scale = [Note::A, Note::C, Note::D, Note::E, Note::G];
+        let mut local_state = QuantizerState::default();
+        let mut global_state = QuantizerState::default();
+        let mut differs = false;
+        for value in (0..=4095).step_by(7) {
+            let pitch = local.get_quantized_note(&mut local_state, value, Range::_0_10V);
+            assert!(in_scale.contains(&pitch.note), "{value}: {pitch:?}");
+            let chromatic = global.get_quantized_note(&mut global_state, value, Range::_0_10V);
+            differs |= pitch != chromatic;
+        }
+        assert!(differs);
+
+        // B is between A and C, the hysteresis state starts fresh for every value
+        let b3 = Pitch {
+            octave: 3,
+            note: Note::B,
+        }
+        .as_counts(Range::_0_10V);
+        let mut state = QuantizerState::default();
+        assert_eq!(
+            global
+                .get_quantized_note(&mut state, b3, Range::_0_10V)
+                .note,
+            Note::B
+        );
+        let mut state = QuantizerState::default();
+        let snapped = local.get_quantized_note(&mut state, b3, Range::_0_10V);
+        assert!(snapped.note == Note::A || snapped.note == Note::C);
+    }
+
+    #[test]
+    fn test_scale_override_tonic_transposes_scale() {
+        let c_major = Quantizer::new(Key::Ionian, Note::C);
+        let d_major = Quantizer::new(Key::Ionian, Note::D);
+        let c_major_notes = [0, 2, 4, 5, 7, 9, 11];
+        for semitone in (0..5).flat_map(|oct| c_major_notes.map(|note| oct * 12 + note)) {
+            let mut c_state = QuantizerState::default();
+            let mut d_state = QuantizerState::default();
+            let c = c_major.get_quantized_note(
+                &mut c_state,
+                semitone_to_counts(semitone),
+                Range::_0_10V,
+            );
+            let d = d_major.get_quantized_note(
+                &mut d_state,
+                semitone_to_counts(semitone + 2),
+                Range::_0_10V,
+            );
+            // D major is C major two semitones up
+            assert_eq!(
+                d.octave as i32 * 12 + d.note as i32,
+                c.octave as i32 * 12 + c.note as i32 + 2
+            );
+        }
+    }
+
+    #[test]
+    fn test_scale_change_resets_state() {
+        let mut q = Quantizer::new(Key::Ionian, Note::C);
+        let mut state = QuantizerState::default();
+        let e = Pitch {
+            octave: 2,
+            note: Note::E,
+        }
+        .as_counts(Range::_0_10V);
+        assert_eq!(
+            q.get_quantized_note(&mut state, e, Range::_0_10V).note,
+            Note::E
+        );
+        // E is not in C minor pentatonic, the held note must not survive the change
+        q.set_scale(Key::PentatonicMin, Note::C);
+        let pitch = q.get_quantized_note(&mut state, e, Range::_0_10V);
+        assert_ne!(pitch.note, Note::E);
+    }
+
     // The Pitch helper function tests are unaffected by the quantizer change
     #[test]
     fn test_pitch_as_counts() {
@@ -482,4 +884,370 @@ mod tests {
         assert_eq!(q.get_key(), Key::Mixolydian);
         assert_eq!(q.get_tonic(), Note::G);
     }
+
+    #[test]
+    fn test_pitch_from_midi_round_trips() {
+        let c4 = Pitch::from_midi(60);
+        assert_eq!(
+            c4,
+            Pitch {
+                octave: 4,
+                note: Note::C
+            }
+        );
+        for midi in 0..=127u8 {
+            assert_eq!(Pitch::from_midi(midi).as_midi(), MidiNote::from(midi));
+        }
+        assert_eq!(Pitch::from_midi(0).octave, -1);
+        // Out of range notes are clamped to the highest MIDI note
+        assert_eq!(Pitch::from_midi(200), Pitch::from_midi(127));
+    }
+
+    #[test]
+    fn test_pitch_transpose_carries_octaves() {
+        let b3 = Pitch {
+            octave: 3,
+            note: Note::B,
+        };
+        assert_eq!(
+            b3.transpose(1),
+            Pitch {
+                octave: 4,
+                note: Note::C
+            }
+        );
+        assert_eq!(
+            b3.transpose(13),
+            Pitch {
+                octave: 5,
+                note: Note::C
+            }
+        );
+        let c0 = Pitch {
+            octave: 0,
+            note: Note::C,
+        };
+        assert_eq!(
+            c0.transpose(-1),
+            Pitch {
+                octave: -1,
+                note: Note::B
+            }
+        );
+        assert_eq!(
+            c0.transpose(-25),
+            Pitch {
+                octave: -3,
+                note: Note::B
+            }
+        );
+        assert_eq!(b3.transpose(0), b3);
+        // Transposing matches MIDI note math
+        for semitones in -24..=24i8 {
+            let midi = 60 + semitones as i32;
+            assert_eq!(
+                Pitch::from_midi(60).transpose(semitones).as_midi(),
+                MidiNote::from(midi)
+            );
+        }
+    }
+
+    #[test]
+    fn test_pitch_as_volts() {
+        let a1 = Pitch {
+            octave: 1,
+            note: Note::A,
+        };
+        assert_eq!(a1.as_volts(Range::_0_10V), 1.75);
+        assert_eq!(a1.as_volts(Range::_Neg5_5V), 1.75);
+        let c7 = Pitch {
+            octave: 7,
+            note: Note::C,
+        };
+        assert_eq!(c7.as_volts(Range::_0_10V), 7.0);
+        // Limited to what the range can output
+        assert_eq!(c7.as_volts(Range::_0_5V), 5.0);
+        assert_eq!(c7.as_volts(Range::_Neg5_5V), 5.0);
+        let low = Pitch {
+            octave: -6,
+            note: Note::C,
+        };
+        assert_eq!(low.as_volts(Range::_Neg5_5V), -5.0);
+        assert_eq!(low.as_volts(Range::_0_10V), 0.0);
+    }
+
+    // Output of a crossfade that holds an input on C# through a change from chromatic to
+    // C major, one sample per ms after the change
+    fn scale_change(fade_ms: u16) -> [u16; 20] {
+        let mut q = Quantizer::new(Key::Chromatic, Note::C);
+        let mut state = QuantizerState::default();
+        let mut fade = ScaleCrossfade::new(fade_ms);
+        let input = semitone_to_counts(49);
+        let mut quantize = |q: &Quantizer, fade: &mut ScaleCrossfade| {
+            let counts = q
+                .get_quantized_note(&mut state, input, Range::_0_10V)
+                .as_counts(Range::_0_10V);
+            fade.process(counts, q.version(), 1.0)
+        };
+        for _ in 0..10 {
+            quantize(&q, &mut fade);
+        }
+        q.set_scale(Key::Ionian, Note::C);
+        core::array::from_fn(|_| quantize(&q, &mut fade))
+    }
+
+    // Tone chosen in C major for an input `fraction` eighths of a semitone above an E
+    fn quantize_between(state: &mut QuantizerState, fraction: u16) -> Note {
+        let q = Quantizer::new(Key::Ionian, Note::C);
+        // 64 semitones above 0V is an E
+        let value = (64 * 8 + fraction as u32) * 4095 / 120 / 8;
+        q.get_quantized_note(state, value as u16, Range::_0_10V)
+            .note
+    }
+
+    #[test]
+    fn test_quantize_modes_between_tones() {
+        use Note::{E, F, G};
+        // Inputs on E, in the E-F semitone and in the F-G whole tone
+        let fractions = [0, 3, 5, 10, 14, 18, 22];
+        let expected = [
+            (QuantizeMode::Nearest, [E, E, F, F, F, G, G]),
+            (QuantizeMode::Up, [E, F, F, G, G, G, G]),
+            (QuantizeMode::Down, [E, E, E, F, F, F, F]),
+        ];
+        for (mode, notes) in expected {
+            for (fraction, note) in fractions.into_iter().zip(notes) {
+                // Fresh state so hysteresis doesn't carry over
+                let mut state = QuantizerState::default();
+                state.set_mode(mode);
+                assert_eq!(
+                    quantize_between(&mut state, fraction),
+                    note,
+                    "{mode:?} at {fraction}/8"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_quantize_mode_change_takes_effect() {
+        let mut state = QuantizerState::default();
+        assert_eq!(state.mode(), QuantizeMode::Nearest);
+        assert_eq!(quantize_between(&mut state, 2), Note::E);
+        state.set_mode(QuantizeMode::Up);
+        assert_eq!(quantize_between(&mut state, 2), Note::F);
+        state.set_mode(QuantizeMode::Down);
+        assert_eq!(quantize_between(&mut state, 6), Note::E);
+        assert_eq!(QuantizeMode::from_index(0), QuantizeMode::Nearest);
+        assert_eq!(QuantizeMode::from_index(1), QuantizeMode::Up);
+        assert_eq!(QuantizeMode::from_index(2), QuantizeMode::Down);
+    }
+
+    // Notes the quantizer puts out over the whole input range
+    fn output_notes(q: &Quantizer) -> u16 {
+        let mut state = QuantizerState::default();
+        (0..=4095)
+            .step_by(5)
+            .map(|value| q.get_quantized_note(&mut state, value, Range::_0_10V).note as u16)
+            .fold(0, |mask, note| mask | 1 << (11 - note))
+    }
+
+    #[test]
+    fn test_active_notes_restrict_output() {
+        let mut q = Quantizer::new(Key::Ionian, Note::D);
+        // C, E and G
+        let chord = 0b100010010000;
+        q.set_active_notes(chord);
+        assert_eq!(output_notes(&q), chord);
+        // A single note is quantized to in every octave
+        q.set_active_notes(0b000000000100);
+        assert_eq!(output_notes(&q), 0b000000000100);
+        // The key and tonic are left alone
+        assert_eq!(q.get_key(), Key::Ionian);
+        assert_eq!(q.get_tonic(), Note::D);
+    }
+
+    #[test]
+    fn test_no_active_notes_is_chromatic() {
+        let mut q = Quantizer::new(Key::PentatonicMin, Note::C);
+        q.set_active_notes(0);
+        assert_eq!(output_notes(&q), 0b111111111111);
+        // Bits above B are ignored
+        q.set_active_notes(0b1111_0000_0000_0000);
+        assert_eq!(output_notes(&q), 0b111111111111);
+    }
+
+    #[test]
+    fn test_held_notes_mask() {
+        let mut held = HeldNotes::default();
+        // C4, E4 and G5
+        for note in [60u8, 64, 79] {
+            held.note_on(MidiNote::from(note));
+        }
+        assert_eq!(held.mask(), 0b100010010000);
+        // C3 folds onto the C that is already there
+        held.note_on(MidiNote::from(48u8));
+        held.note_off(MidiNote::from(60u8));
+        assert_eq!(held.mask(), 0b100010010000);
+        held.note_off(MidiNote::from(48u8));
+        assert_eq!(held.mask(), 0b000010010000);
+        held.note_off(MidiNote::from(64u8));
+        held.note_off(MidiNote::from(79u8));
+        assert_eq!(held.mask(), 0);
+    }
+
+    #[test]
+    fn test_held_notes_latch() {
+        let mut held = HeldNotes::default();
+        held.set_latch(true);
+        for note in [60u8, 64, 67] {
+            held.note_on(MidiNote::from(note));
+        }
+        for note in [60u8, 64, 67] {
+            held.note_off(MidiNote::from(note));
+        }
+        // The chord stays until the next one is played
+        assert_eq!(held.mask(), 0b100010010000);
+        held.note_on(MidiNote::from(62u8));
+        assert_eq!(held.mask(), 0b001000000000);
+        held.note_off(MidiNote::from(62u8));
+        assert_eq!(held.mask(), 0b001000000000);
+        // Letting go of the latch drops the released notes
+        held.set_latch(false);
+        assert_eq!(held.mask(), 0);
+    }
+
+    #[test]
+    fn test_scale_change_without_crossfade_jumps() {
+        let before = semitone_to_counts(49);
+        let out = scale_change(0);
+        assert_ne!(out[0], before);
+        assert!(out.iter().all(|&v| v == out[0]));
+    }
+
+    #[test]
+    fn test_scale_change_with_crossfade_ramps() {
+        let before = semitone_to_counts(49);
+        let after = scale_change(0)[0];
+        let out = scale_change(10);
+        // Moves towards the new note a bit at a time
+        assert!(out[0] != before && out[0] != after);
+        assert!(out[0].abs_diff(before) < before.abs_diff(after) / 2);
+        for pair in out.windows(2) {
+            assert!(pair[1].abs_diff(after) <= pair[0].abs_diff(after));
+        }
+        // Lands on the new note once the fade time is over
+        assert!(out[10].abs_diff(after) <= 1);
+        assert_eq!(out[19], after);
+    }
+
+    #[test]
+    fn test_crossfade_jumps_to_new_notes() {
+        let mut fade = ScaleCrossfade::new(100);
+        assert_eq!(fade.process(1000, 1, 1.0), 1000);
+        assert_eq!(fade.process(2000, 1, 1.0), 2000);
+        // Only the scale version changing glides
+        assert!(fade.process(1500, 2, 1.0) > 1900);
+    }
+
+    fn semitones(pitch: Pitch) -> i32 {
+        pitch.octave as i32 * 12 + pitch.note as i32
+    }
+
+    fn transposed(q: &Quantizer, semitone: u8, steps: i32, mode: TransposeMode) -> i32 {
+        let mut state = QuantizerState::default();
+        let value = Pitch {
+            octave: (semitone / 12) as i8,
+            note: (semitone % 12).into(),
+        }
+        .as_counts(Range::_0_10V);
+        semitones(q.get_transposed_note(&mut state, value, Range::_0_10V, steps, mode))
+    }
+
+    fn c_major_quantizer() -> Quantizer {
+        let mut q = Quantizer::default();
+        q.set_scale(Key::Ionian, Note::C);
+        q
+    }
+
+    #[test]
+    fn test_transpose_before_and_after_quantization() {
+        let q = c_major_quantizer();
+        // D up a semitone is D#, which is not in C major
+        assert_eq!(transposed(&q, 26, 1, TransposeMode::Before), 26);
+        assert_eq!(transposed(&q, 26, 1, TransposeMode::After), 27);
+        assert_eq!(transposed(&q, 26, 1, TransposeMode::Degrees), 28);
+        // A fifth up from D stays in the scale either way
+        assert_eq!(transposed(&q, 26, 7, TransposeMode::Before), 33);
+        assert_eq!(transposed(&q, 26, 7, TransposeMode::After), 33);
+        // Between two tones, before snaps the transposed input, after the input itself
+        assert_eq!(transposed(&q, 27, 2, TransposeMode::Before), 29);
+        assert_eq!(transposed(&q, 27, 2, TransposeMode::After), 28);
+        // Down as well
+        assert_eq!(transposed(&q, 26, -3, TransposeMode::Before), 23);
+        assert_eq!(transposed(&q, 26, -3, TransposeMode::After), 23);
+        assert_eq!(transposed(&q, 26, -2, TransposeMode::After), 24);
+        assert_eq!(transposed(&q, 26, -1, TransposeMode::Before), 24);
+    }
+
+    #[test]
+    fn test_transpose_by_scale_degrees() {
+        let q = c_major_quantizer();
+        let c_major = [0, 2, 4, 5, 7, 9, 11];
+        for (degree, &note) in c_major.iter().enumerate() {
+            // A degree up is the next tone of the scale, carrying into the next octave
+            let next = c_major.get(degree + 1).copied().unwrap_or(12);
+            assert_eq!(
+                transposed(&q, 24 + note, 1, TransposeMode::Degrees),
+                24 + next as i32
+            );
+            // An octave is seven degrees
+            assert_eq!(
+                transposed(&q, 24 + note, 7, TransposeMode::Degrees),
+                36 + note as i32
+            );
+            assert_eq!(
+                transposed(&q, 24 + note, -7, TransposeMode::Degrees),
+                12 + note as i32
+            );
+        }
+        assert_eq!(transposed(&q, 24, -1, TransposeMode::Degrees), 23);
+        assert_eq!(transposed(&q, 24, 0, TransposeMode::Degrees), 24);
+    }
+
+    #[test]
+    fn test_chromatic_transpose_is_the_same_in_every_mode() {
+        let q = Quantizer::default();
+        for semitone in [0, 13, 30, 61] {
+            for steps in [-12, -5, 0, 1, 7, 24] {
+                let expected = semitone as i32 + steps;
+                if !(0..=120).contains(&expected) {
+                    continue;
+                }
+                for mode in [
+                    TransposeMode::Before,
+                    TransposeMode::After,
+                    TransposeMode::Degrees,
+                ] {
+                    assert_eq!(
+                        transposed(&q, semitone, steps, mode),
+                        expected,
+                        "{semitone} {steps} {mode:?}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_transpose_before_clamps_to_range() {
+        let q = Quantizer::default();
+        assert_eq!(transposed(&q, 110, 24, TransposeMode::Before), 120);
+        assert_eq!(transposed(&q, 5, -24, TransposeMode::Before), 0);
+        // Transposing after can go past the range, the output clamps it
+        assert_eq!(transposed(&q, 110, 24, TransposeMode::After), 134);
+        assert_eq!(TransposeMode::from_index(1), TransposeMode::After);
+        assert_eq!(TransposeMode::from_index(2), TransposeMode::Degrees);
+        assert_eq!(TransposeMode::from_index(9), TransposeMode::Before);
+    }
 }
@@ -0,0 +1,133 @@
+use crate::utils::slew_limiter;
+
+/// Sample and hold with an optional slewed output that glides towards the held step.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SampleAndHold {
+    held: u16,
+    out: f32,
+}
+
+impl SampleAndHold {
+    pub fn new(initial: u16) -> Self {
+        let initial = initial.min(4095);
+        Self {
+            held: initial,
+            out: initial as f32,
+        }
+    }
+
+    /// Latch a new step. The slewed output moves towards it on the next calls to `update`.
+    pub fn sample(&mut self, value: u16) {
+        self.held = value.min(4095);
+    }
+
+    /// The stepped (unslewed) value
+    pub fn held(&self) -> u16 {
+        self.held
+    }
+
+    /// The current slewed value
+    pub fn output(&self) -> u16 {
+        self.out as u16
+    }
+
+    /// Advance the slewed output by one step and return it.
+    /// A `slew` of `0` jumps straight to the held value, `4095` is the slowest glide.
+    pub fn update(&mut self, slew: u16) -> u16 {
+        self.out = slew_limiter(self.out, self.held, slew, slew);
+        self.output()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_holds_initial_value() {
+        let sh = SampleAndHold::new(1000);
+        assert_eq!(sh.held(), 1000);
+        assert_eq!(sh.output(), 1000);
+
+        let sh = SampleAndHold::new(5000);
+        assert_eq!(sh.held(), 4095);
+    }
+
+    #[test]
+    fn test_no_slew_jumps_to_step() {
+        let mut sh = SampleAndHold::new(0);
+        sh.sample(3000);
+        assert_eq!(sh.held(), 3000);
+        assert_eq!(sh.update(0), 3000);
+
+        sh.sample(100);
+        assert_eq!(sh.update(0), 100);
+    }
+
+    #[test]
+    fn test_held_value_stays_stepped_while_slewing() {
+        let mut sh = SampleAndHold::new(0);
+        sh.sample(4000);
+        for _ in 0..10 {
+            sh.update(4095);
+            assert_eq!(sh.held(), 4000);
+        }
+        assert!(sh.output() < 4000);
+    }
+
+    #[test]
+    fn test_slew_rises_monotonically_without_overshoot() {
+        let mut sh = SampleAndHold::new(0);
+        sh.sample(2000);
+        let mut prev = sh.output();
+        for _ in 0..20_000 {
+            let out = sh.update(2000);
+            assert!(out >= prev);
+            assert!(out <= 2000);
+            prev = out;
+        }
+        assert_eq!(prev, 2000);
+    }
+
+    #[test]
+    fn test_slew_falls_monotonically_without_undershoot() {
+        let mut sh = SampleAndHold::new(4095);
+        sh.sample(500);
+        let mut prev = sh.output();
+        for _ in 0..20_000 {
+            let out = sh.update(2000);
+            assert!(out <= prev);
+            assert!(out >= 500);
+            prev = out;
+        }
+        assert_eq!(prev, 500);
+    }
+
+    #[test]
+    fn test_more_slew_is_slower() {
+        let mut fast = SampleAndHold::new(0);
+        let mut slow = SampleAndHold::new(0);
+        fast.sample(4095);
+        slow.sample(4095);
+        for _ in 0..50 {
+            fast.update(1000);
+            slow.update(3500);
+        }
+        assert!(fast.output() > slow.output());
+    }
+
+    #[test]
+    fn test_new_step_while_slewing_changes_direction() {
+        let mut sh = SampleAndHold::new(2000);
+        sh.sample(4000);
+        for _ in 0..10 {
+            sh.update(4095);
+        }
+        let mid = sh.output();
+        assert!(mid > 2000);
+
+        sh.sample(0);
+        let out = sh.update(4095);
+        assert!(out < mid);
+    }
+}
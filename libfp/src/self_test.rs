@@ -0,0 +1,322 @@
+use crate::{Color, GLOBAL_CHANNELS};
+
+/// Colors all LEDs cycle through
+pub const SELF_TEST_COLORS: [Color; 4] = [Color::Red, Color::Green, Color::Blue, Color::White];
+/// How often the gate outputs go high
+pub const SELF_TEST_GATE_PULSES: usize = 4;
+/// Number of steps of the DAC ramp in each direction
+pub const SELF_TEST_RAMP_STEPS: u16 = 64;
+/// Values each jack is held at in the per-channel self-test: 0V, 1V, 5V and 10V on the 0-10V
+/// range
+pub const SELF_TEST_DAC_VALUES: [u16; 4] = [0, 410, 2048, 4095];
+/// LEDs of a channel, indexed like `CHAN_LED_MAP`: top, bottom, button
+pub const SELF_TEST_LEDS_PER_CHANNEL: usize = 3;
+
+/// How the self-test drives the channels
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelfTestMode {
+    /// All channels at once, for a quick check of the whole unit
+    AllChannels,
+    /// One channel at a time, so a fault can be pinned on a single LED or jack
+    PerChannel,
+}
+
+/// The phases of the self-test, in the order they run
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelfTestPhase {
+    Leds,
+    Gates,
+    Dac,
+}
+
+/// One step of the hardware self-test
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SelfTestStep {
+    /// Light all LEDs in a color
+    Leds(Color),
+    /// Set all jacks as gate outputs high or low
+    Gates(bool),
+    /// Set all jacks as 0-10V outputs to a DAC value
+    Dac(u16),
+    /// Light a single LED, `led` indexes `CHAN_LED_MAP`
+    ChannelLed { channel: usize, led: usize },
+    /// Set a single jack as a gate output high or low
+    ChannelGate { channel: usize, high: bool },
+    /// Hold a single jack as a 0-10V output at a DAC value, all others at 0V
+    ChannelDac { channel: usize, value: u16 },
+}
+
+impl SelfTestStep {
+    pub fn phase(&self) -> SelfTestPhase {
+        match self {
+            SelfTestStep::Leds(_) | SelfTestStep::ChannelLed { .. } => SelfTestPhase::Leds,
+            SelfTestStep::Gates(_) | SelfTestStep::ChannelGate { .. } => SelfTestPhase::Gates,
+            SelfTestStep::Dac(_) | SelfTestStep::ChannelDac { .. } => SelfTestPhase::Dac,
+        }
+    }
+
+    /// The channel under test, `None` if the step drives all channels
+    pub fn channel(&self) -> Option<usize> {
+        match *self {
+            SelfTestStep::ChannelLed { channel, .. }
+            | SelfTestStep::ChannelGate { channel, .. }
+            | SelfTestStep::ChannelDac { channel, .. } => Some(channel),
+            _ => None,
+        }
+    }
+
+    /// How long the step is held before the next one, in milliseconds. Per-channel DAC values
+    /// are held long enough to be measured.
+    pub fn duration_ms(&self) -> u64 {
+        match self {
+            SelfTestStep::Leds(_) => 500,
+            SelfTestStep::Gates(_) => 250,
+            SelfTestStep::Dac(_) => 20,
+            SelfTestStep::ChannelLed { .. } => 200,
+            SelfTestStep::ChannelGate { .. } => 500,
+            SelfTestStep::ChannelDac { .. } => 1500,
+        }
+    }
+}
+
+/// The steps of the self-test in `mode`.
+///
+/// For all channels at once the LEDs cycle through `SELF_TEST_COLORS`, the gates pulse
+/// `SELF_TEST_GATE_PULSES` times and the DAC outputs ramp up from 0 to full scale and back.
+///
+/// Per channel every LED lights up on its own, then every jack pulses once as a gate and then
+/// goes through `SELF_TEST_DAC_VALUES`, one channel after the other.
+pub fn self_test_steps(mode: SelfTestMode) -> impl Iterator<Item = SelfTestStep> {
+    // Only the sequence of the mode runs, the other one is cut down to no steps
+    let (all, per_channel) = match mode {
+        SelfTestMode::AllChannels => (usize::MAX, 0),
+        SelfTestMode::PerChannel => (0, usize::MAX),
+    };
+    all_channel_steps()
+        .take(all)
+        .chain(per_channel_steps().take(per_channel))
+}
+
+fn all_channel_steps() -> impl Iterator<Item = SelfTestStep> {
+    let leds = SELF_TEST_COLORS.into_iter().map(SelfTestStep::Leds);
+    let gates = (0..SELF_TEST_GATE_PULSES * 2).map(|i| SelfTestStep::Gates(i % 2 == 0));
+    let ramp = |i: u16| (i as u32 * 4095 / SELF_TEST_RAMP_STEPS as u32) as u16;
+    let up = (0..=SELF_TEST_RAMP_STEPS).map(move |i| SelfTestStep::Dac(ramp(i)));
+    let down = (0..SELF_TEST_RAMP_STEPS)
+        .rev()
+        .map(move |i| SelfTestStep::Dac(ramp(i)));
+    leds.chain(gates).chain(up).chain(down)
+}
+
+fn per_channel_steps() -> impl Iterator<Item = SelfTestStep> {
+    let leds = (0..GLOBAL_CHANNELS).flat_map(|channel| {
+        (0..SELF_TEST_LEDS_PER_CHANNEL).map(move |led| SelfTestStep::ChannelLed { channel, led })
+    });
+    let gates = (0..GLOBAL_CHANNELS)
+        .flat_map(|channel| [true, false].map(|high| SelfTestStep::ChannelGate { channel, high }));
+    let dac = (0..GLOBAL_CHANNELS).flat_map(|channel| {
+        SELF_TEST_DAC_VALUES.map(|value| SelfTestStep::ChannelDac { channel, value })
+    });
+    leds.chain(gates).chain(dac)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phases_in_order() {
+        let mut phases: heapless::Vec<SelfTestPhase, 3> = heapless::Vec::new();
+        for step in self_test_steps(SelfTestMode::AllChannels) {
+            if phases.last() != Some(&step.phase()) {
+                phases.push(step.phase()).unwrap();
+            }
+        }
+        assert_eq!(
+            phases.as_slice(),
+            &[
+                SelfTestPhase::Leds,
+                SelfTestPhase::Gates,
+                SelfTestPhase::Dac
+            ]
+        );
+    }
+
+    #[test]
+    fn test_leds_cycle_all_colors() {
+        let colors = self_test_steps(SelfTestMode::AllChannels).filter_map(|step| match step {
+            SelfTestStep::Leds(color) => Some(color),
+            _ => None,
+        });
+        assert!(colors.eq(SELF_TEST_COLORS));
+    }
+
+    #[test]
+    fn test_gates_pulse_and_end_low() {
+        let gates: heapless::Vec<bool, 16> = self_test_steps(SelfTestMode::AllChannels)
+            .filter_map(|step| match step {
+                SelfTestStep::Gates(high) => Some(high),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(gates.len(), SELF_TEST_GATE_PULSES * 2);
+        assert_eq!(
+            gates.iter().filter(|&&high| high).count(),
+            SELF_TEST_GATE_PULSES
+        );
+        // Alternating, starting high
+        for (i, &high) in gates.iter().enumerate() {
+            assert_eq!(high, i % 2 == 0);
+        }
+        assert_eq!(gates.last(), Some(&false));
+    }
+
+    #[test]
+    fn test_dac_ramps_up_and_down() {
+        let values: heapless::Vec<u16, 256> = self_test_steps(SelfTestMode::AllChannels)
+            .filter_map(|step| match step {
+                SelfTestStep::Dac(value) => Some(value),
+                _ => None,
+            })
+            .collect();
+        let top = SELF_TEST_RAMP_STEPS as usize;
+        assert_eq!(values.len(), top * 2 + 1);
+        assert_eq!(values[0], 0);
+        assert_eq!(values[top], 4095);
+        assert_eq!(values.last(), Some(&0));
+        assert!(values[..=top].windows(2).all(|w| w[0] < w[1]));
+        assert!(values[top..].windows(2).all(|w| w[0] > w[1]));
+    }
+
+    #[test]
+    fn test_sequence_is_finite() {
+        let steps = self_test_steps(SelfTestMode::AllChannels).count();
+        assert_eq!(
+            steps,
+            SELF_TEST_COLORS.len()
+                + SELF_TEST_GATE_PULSES * 2
+                + SELF_TEST_RAMP_STEPS as usize * 2
+                + 1
+        );
+        let duration: u64 = self_test_steps(SelfTestMode::AllChannels)
+            .map(|step| step.duration_ms())
+            .sum();
+        // Short enough to run on every unit
+        assert!(duration < 10_000);
+    }
+
+    #[test]
+    fn test_per_channel_channel_and_value_at_each_step() {
+        let steps: heapless::Vec<SelfTestStep, 256> =
+            self_test_steps(SelfTestMode::PerChannel).collect();
+        assert_eq!(
+            steps.len(),
+            16 * 3 + 16 * 2 + 16 * SELF_TEST_DAC_VALUES.len()
+        );
+        assert_eq!(steps[0], SelfTestStep::ChannelLed { channel: 0, led: 0 });
+        assert_eq!(steps[2], SelfTestStep::ChannelLed { channel: 0, led: 2 });
+        assert_eq!(steps[3], SelfTestStep::ChannelLed { channel: 1, led: 0 });
+        assert_eq!(
+            steps[47],
+            SelfTestStep::ChannelLed {
+                channel: 15,
+                led: 2
+            }
+        );
+        assert_eq!(
+            steps[48],
+            SelfTestStep::ChannelGate {
+                channel: 0,
+                high: true
+            }
+        );
+        assert_eq!(
+            steps[49],
+            SelfTestStep::ChannelGate {
+                channel: 0,
+                high: false
+            }
+        );
+        assert_eq!(
+            steps[79],
+            SelfTestStep::ChannelGate {
+                channel: 15,
+                high: false
+            }
+        );
+        assert_eq!(
+            steps[80],
+            SelfTestStep::ChannelDac {
+                channel: 0,
+                value: 0
+            }
+        );
+        assert_eq!(
+            steps[83],
+            SelfTestStep::ChannelDac {
+                channel: 0,
+                value: 4095
+            }
+        );
+        assert_eq!(
+            steps[84],
+            SelfTestStep::ChannelDac {
+                channel: 1,
+                value: 0
+            }
+        );
+        assert_eq!(
+            steps.last(),
+            Some(&SelfTestStep::ChannelDac {
+                channel: 15,
+                value: 4095
+            })
+        );
+    }
+
+    #[test]
+    fn test_per_channel_visit_every_channel_once_per_phase() {
+        for phase in [
+            SelfTestPhase::Leds,
+            SelfTestPhase::Gates,
+            SelfTestPhase::Dac,
+        ] {
+            let mut channels: heapless::Vec<usize, 16> = heapless::Vec::new();
+            for step in
+                self_test_steps(SelfTestMode::PerChannel).filter(|step| step.phase() == phase)
+            {
+                if step.channel() != channels.last().copied() {
+                    channels.push(step.channel().unwrap()).unwrap();
+                }
+            }
+            assert!(channels.iter().copied().eq(0..16), "{phase:?}");
+        }
+        // Gates always end low, every DAC value is hit on every jack
+        let gates = self_test_steps(SelfTestMode::PerChannel)
+            .filter(|step| step.phase() == SelfTestPhase::Gates);
+        assert!(gates
+            .skip(1)
+            .step_by(2)
+            .all(|step| matches!(step, SelfTestStep::ChannelGate { high: false, .. })));
+        for value in SELF_TEST_DAC_VALUES {
+            let hits = self_test_steps(SelfTestMode::PerChannel)
+                .filter(
+                    |step| matches!(step, SelfTestStep::ChannelDac { value: v, .. } if *v == value),
+                )
+                .count();
+            assert_eq!(hits, 16);
+        }
+    }
+
+    #[test]
+    fn test_per_channel_dac_values_are_known_voltages() {
+        let millivolts = SELF_TEST_DAC_VALUES.map(|value| value as u32 * 10_000 / 4095);
+        assert_eq!(millivolts, [0, 1001, 5001, 10_000]);
+    }
+
+    #[test]
+    fn test_modes_dont_mix() {
+        assert!(self_test_steps(SelfTestMode::AllChannels).all(|step| step.channel().is_none()));
+        assert!(self_test_steps(SelfTestMode::PerChannel).all(|step| step.channel().is_some()));
+    }
+}
@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+
+use crate::types::Arr;
+
+/// Steps of the four sequences together, 16 per track
+pub const SEQ_STEPS: usize = 64;
+
+/// One flag per step, stored as the bits of a `u64` instead of a byte per step
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StepFlags(u64);
+
+impl StepFlags {
+    pub fn from_steps(steps: &[bool; SEQ_STEPS]) -> Self {
+        let bits = steps
+            .iter()
+            .enumerate()
+            .filter(|(_, &set)| set)
+            .fold(0, |bits, (step, _)| bits | (1 << step));
+        Self(bits)
+    }
+
+    pub fn to_steps(self) -> [bool; SEQ_STEPS] {
+        core::array::from_fn(|step| self.0 & (1 << step) != 0)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Storage {
+    pub seq: Arr<u16, SEQ_STEPS>,
+    pub gateseq: StepFlags,
+    pub legato_seq: StepFlags,
+    // Per-step probability, 255 always plays
+    pub gate_prob: Arr<u8, SEQ_STEPS>,
+    // Accented steps play with a higher velocity
+    pub accent_seq: StepFlags,
+    // Alt layer - fader-scale values (0-4095)
+    pub length_fader: [u16; 4], // F0: derive seq_length = val/256+1
+    pub gate_fader: [u16; 4],   // F1: derive gate_length
+    pub oct_fader: [u16; 4],    // F2: derive oct = val/1000
+    pub range_fader: [u16; 4],  // F3: derive range = val/1000+1
+    pub res_fader: [u16; 4],    // F4: derive res_index = val/512
+    pub dir_fader: [u16; 4],    // F6: derive direction = val/1024
+                                // F5: sets gate_prob of the last pressed step
+}
+
+impl Storage {
+    /// Decode storage saved in the layout of `LegacyStorage`. The whole data has to be used up,
+    /// so a different layout isn't misread as this one.
+    pub fn decode_legacy(data: &[u8]) -> Option<Self> {
+        match postcard::take_from_bytes::<LegacyStorage>(data) {
+            Ok((legacy, [])) => Some(legacy.into()),
+            _ => None,
+        }
+    }
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Self {
+            seq: Arr::new([0; SEQ_STEPS]),
+            gateseq: StepFlags::from_steps(&[true; SEQ_STEPS]),
+            legato_seq: StepFlags::default(),
+            gate_prob: Arr::new([255; SEQ_STEPS]),
+            accent_seq: StepFlags::default(),
+            // Default fader values - positioned to produce sensible defaults
+            length_fader: [3840; 4], // -> length 16 (3840/256+1 = 16)
+            gate_fader: [2032; 4],   // -> gate_length 127 (127*16 = 2032)
+            oct_fader: [0; 4],       // -> oct 0
+            range_fader: [2000; 4],  // -> range 3 (2000/1000+1 = 3)
+            res_fader: [2048; 4],    // -> res_index 4 (2048/512 = 4)
+            dir_fader: [0; 4],       // -> forward
+        }
+    }
+}
+
+/// Storage as it was saved before the step flags were packed into bits, with a bool per step and
+/// without gate probability, accents and playback direction
+#[derive(Deserialize)]
+pub struct LegacyStorage {
+    pub seq: Arr<u16, SEQ_STEPS>,
+    pub gateseq: Arr<bool, SEQ_STEPS>,
+    pub legato_seq: Arr<bool, SEQ_STEPS>,
+    pub length_fader: [u16; 4],
+    pub gate_fader: [u16; 4],
+    pub oct_fader: [u16; 4],
+    pub range_fader: [u16; 4],
+    pub res_fader: [u16; 4],
+}
+
+impl From<LegacyStorage> for Storage {
+    fn from(old: LegacyStorage) -> Self {
+        Self {
+            seq: old.seq,
+            gateseq: StepFlags::from_steps(&old.gateseq.get()),
+            legato_seq: StepFlags::from_steps(&old.legato_seq.get()),
+            length_fader: old.length_fader,
+            gate_fader: old.gate_fader,
+            oct_fader: old.oct_fader,
+            range_fader: old.range_fader,
+            res_fader: old.res_fader,
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_flags_round_trip() {
+        let mut steps = [false; SEQ_STEPS];
+        for step in [0, 1, 7, 8, 31, 32, 62, 63] {
+            steps[step] = true;
+        }
+        assert_eq!(StepFlags::from_steps(&steps).to_steps(), steps);
+        assert_eq!(
+            StepFlags::from_steps(&[true; SEQ_STEPS]).to_steps(),
+            [true; SEQ_STEPS]
+        );
+        assert_eq!(StepFlags::default().to_steps(), [false; SEQ_STEPS]);
+    }
+
+    #[test]
+    fn largest_storage_fits_in_one_blob() {
+        let storage = Storage {
+            seq: Arr::new([4095; SEQ_STEPS]),
+            gateseq: StepFlags::from_steps(&[true; SEQ_STEPS]),
+            legato_seq: StepFlags::from_steps(&[true; SEQ_STEPS]),
+            gate_prob: Arr::new([255; SEQ_STEPS]),
+            accent_seq: StepFlags::from_steps(&[true; SEQ_STEPS]),
+            length_fader: [4095; 4],
+            gate_fader: [4095; 4],
+            oct_fader: [4095; 4],
+            range_fader: [4095; 4],
+            res_fader: [4095; 4],
+            dir_fader: [4095; 4],
+        };
+        let mut buf = [0u8; 512];
+        let len = postcard::to_slice(&storage, &mut buf).unwrap().len();
+        // The FRAM task stores up to 384 bytes, one of them is the app id
+        assert!(len <= 383, "{len} bytes");
+    }
+
+    /// Storage as the firmware before packed step flags saved it: every step CV at 4095, gates on
+    /// the even steps, legato on step 3 and all faders at 100
+    fn legacy_blob() -> heapless::Vec<u8, 512> {
+        let mut data = heapless::Vec::new();
+        // Arrays are stored like a Vec, with their length first
+        data.push(SEQ_STEPS as u8).unwrap();
+        for _ in 0..SEQ_STEPS {
+            data.extend_from_slice(&[0xff, 0x1f]).unwrap();
+        }
+        data.push(SEQ_STEPS as u8).unwrap();
+        for step in 0..SEQ_STEPS {
+            data.push((step % 2 == 0) as u8).unwrap();
+        }
+        data.push(SEQ_STEPS as u8).unwrap();
+        for step in 0..SEQ_STEPS {
+            data.push((step == 3) as u8).unwrap();
+        }
+        for _ in 0..5 * 4 {
+            data.push(100).unwrap();
+        }
+        data
+    }
+
+    #[test]
+    fn legacy_storage_is_converted() {
+        let data = legacy_blob();
+        assert!(postcard::from_bytes::<Storage>(&data).is_err());
+
+        let storage = Storage::decode_legacy(&data).unwrap();
+        assert_eq!(storage.seq.get(), [4095; SEQ_STEPS]);
+        let gates = storage.gateseq.to_steps();
+        assert!((0..SEQ_STEPS).all(|step| gates[step] == (step % 2 == 0)));
+        let legato = storage.legato_seq.to_steps();
+        assert!((0..SEQ_STEPS).all(|step| legato[step] == (step == 3)));
+        for faders in [
+            storage.length_fader,
+            storage.gate_fader,
+            storage.oct_fader,
+            storage.range_fader,
+            storage.res_fader,
+        ] {
+            assert_eq!(faders, [100; 4]);
+        }
+        // What the old layout didn't have starts out at the defaults
+        assert!(storage.gate_prob.get().iter().all(|&prob| prob == 255));
+        assert_eq!(storage.accent_seq.to_steps(), [false; SEQ_STEPS]);
+        assert_eq!(storage.dir_fader, [0; 4]);
+    }
+
+    #[test]
+    fn legacy_storage_has_to_match_exactly() {
+        let data = legacy_blob();
+        assert!(Storage::decode_legacy(&data[..data.len() - 1]).is_none());
+        let mut longer = data.clone();
+        longer.push(0).unwrap();
+        assert!(Storage::decode_legacy(&longer).is_none());
+
+        let mut buf = [0u8; 512];
+        let current = postcard::to_slice(&Storage::default(), &mut buf).unwrap();
+        assert!(Storage::decode_legacy(current).is_none());
+    }
+
+    #[test]
+    fn defaults_survive_a_round_trip() {
+        let mut buf = [0u8; 512];
+        let bytes = postcard::to_slice(&Storage::default(), &mut buf).unwrap();
+        let storage: Storage = postcard::from_bytes(bytes).unwrap();
+        assert_eq!(storage.gateseq.to_steps(), [true; SEQ_STEPS]);
+        assert_eq!(storage.accent_seq.to_steps(), [false; SEQ_STEPS]);
+        assert!(storage.gate_prob.get().iter().all(|&prob| prob == 255));
+        assert_eq!(storage.length_fader, [3840; 4]);
+    }
+}
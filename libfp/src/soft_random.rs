@@ -0,0 +1,139 @@
+/// Smoothstep interpolation between two 12-bit values, with `t` running from 0 to 1
+pub fn smooth_interpolate(from: u16, to: u16, t: f32) -> u16 {
+    let t = t.clamp(0.0, 1.0);
+    let eased = t * t * (3.0 - 2.0 * t);
+    (from as f32 + (to as f32 - from as f32) * eased + 0.5) as u16
+}
+
+/// Random CV that glides smoothly from one random target to the next.
+#[derive(Clone, Copy, Debug)]
+pub struct SoftRandom {
+    from: u16,
+    to: u16,
+    phase: f32,
+}
+
+impl SoftRandom {
+    /// Start at `value`, the first tick picks a new target
+    pub fn new(value: u16) -> Self {
+        Self {
+            from: value,
+            to: value,
+            phase: 1.0,
+        }
+    }
+
+    pub fn value(&self) -> u16 {
+        smooth_interpolate(self.from, self.to, self.phase)
+    }
+
+    pub fn target(&self) -> u16 {
+        self.to
+    }
+
+    /// Advance by `speed` (fraction of a glide per tick) and return the new value.
+    /// `roll` is called for the next 12-bit target whenever a glide completes.
+    pub fn tick(&mut self, speed: f32, roll: impl FnOnce() -> u16) -> u16 {
+        if self.phase >= 1.0 {
+            self.from = self.to;
+            self.to = roll().min(4095);
+            self.phase = 0.0;
+        }
+        self.phase = (self.phase + speed.max(0.0)).min(1.0);
+        self.value()
+    }
+}
+
+impl Default for SoftRandom {
+    fn default() -> Self {
+        Self::new(2047)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interpolation_endpoints() {
+        assert_eq!(smooth_interpolate(100, 3000, 0.0), 100);
+        assert_eq!(smooth_interpolate(100, 3000, 1.0), 3000);
+        assert_eq!(smooth_interpolate(3000, 100, 1.0), 100);
+        assert_eq!(smooth_interpolate(0, 4095, 0.5), 2048);
+        // Out of range phases are clamped
+        assert_eq!(smooth_interpolate(100, 3000, -1.0), 100);
+        assert_eq!(smooth_interpolate(100, 3000, 2.0), 3000);
+    }
+
+    #[test]
+    fn test_interpolation_is_monotonic_and_eased() {
+        let mut prev = 0;
+        for step in 0..=100 {
+            let value = smooth_interpolate(0, 4095, step as f32 / 100.0);
+            assert!(value >= prev);
+            prev = value;
+        }
+        // Slow at the ends, fast in the middle
+        let start = smooth_interpolate(0, 4095, 0.1);
+        let middle = smooth_interpolate(0, 4095, 0.55) - smooth_interpolate(0, 4095, 0.45);
+        assert!(start < middle);
+
+        let mut prev = 4095;
+        for step in 0..=100 {
+            let value = smooth_interpolate(4095, 0, step as f32 / 100.0);
+            assert!(value <= prev);
+            prev = value;
+        }
+    }
+
+    #[test]
+    fn test_glides_between_targets() {
+        let targets = [4000, 500, 2500];
+        let mut rolls = targets.iter().copied();
+        let mut rnd = SoftRandom::new(2047);
+
+        // A speed of 0.1 completes a glide in ten ticks
+        let mut values = heapless::Vec::<u16, 30>::new();
+        for _ in 0..30 {
+            values
+                .push(rnd.tick(0.1, || rolls.next().unwrap()))
+                .unwrap();
+        }
+        assert_eq!(values[9], 4000);
+        assert_eq!(values[19], 500);
+        assert_eq!(values[29], 2500);
+        assert_eq!(rnd.target(), 2500);
+        // Rising towards the first target, falling towards the second
+        assert!(values[..10].windows(2).all(|w| w[0] <= w[1]));
+        assert!(values[10..20].windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn test_output_has_no_jumps() {
+        let mut seed: u16 = 1234;
+        let mut rnd = SoftRandom::new(0);
+        let mut prev = rnd.value();
+        for _ in 0..2000 {
+            let value = rnd.tick(0.01, || {
+                seed = seed.wrapping_mul(25173).wrapping_add(13849);
+                seed % 4096
+            });
+            // Smoothstep peaks at 1.5x the linear slope
+            assert!((value as i32 - prev as i32).abs() <= 62);
+            prev = value;
+        }
+    }
+
+    #[test]
+    fn test_zero_speed_holds() {
+        let mut rnd = SoftRandom::new(1000);
+        let first = rnd.tick(0.0, || 3000);
+        assert_eq!(first, 1000);
+        for _ in 0..10 {
+            assert_eq!(
+                rnd.tick(0.0, || panic!("no new target while holding")),
+                1000
+            );
+        }
+    }
+}
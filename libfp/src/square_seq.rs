@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+
+/// Number of steps, edited as two pages of four
+pub const SQUARE_STEPS: usize = 8;
+/// Steps on a page, one per channel
+pub const SQUARE_PAGE_STEPS: usize = 4;
+/// Velocity from which a step is accented
+pub const ACCENT_VELOCITY: u16 = 3072;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    pub note: u16,
+    pub velocity: u16,
+    pub gate: bool,
+}
+
+impl Step {
+    pub fn is_accent(&self) -> bool {
+        self.velocity >= ACCENT_VELOCITY
+    }
+}
+
+impl Default for Step {
+    fn default() -> Self {
+        Self {
+            note: 0,
+            velocity: 2048,
+            gate: true,
+        }
+    }
+}
+
+/// Index of the step a channel edits on `page`
+pub fn square_step_index(page: usize, chan: usize) -> usize {
+    (page * SQUARE_PAGE_STEPS + chan % SQUARE_PAGE_STEPS) % SQUARE_STEPS
+}
+
+/// Ticks a gate stays high for a step of `division` ticks, always leaving a gap before the
+/// next step
+pub fn square_gate_ticks(division: u32, gate_percent: u32) -> u32 {
+    (division * gate_percent / 100).clamp(1, division.saturating_sub(1).max(1))
+}
+
+/// Position of the sequencer, nothing is playing before the first step
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Playhead {
+    position: Option<usize>,
+}
+
+impl Playhead {
+    /// Move to the next step of a sequence `length` steps long and return it
+    pub fn advance(&mut self, length: usize) -> usize {
+        let length = length.clamp(1, SQUARE_STEPS);
+        let next = match self.position {
+            Some(pos) => (pos + 1) % length,
+            None => 0,
+        };
+        self.position = Some(next);
+        next
+    }
+
+    pub fn position(&self) -> Option<usize> {
+        self.position
+    }
+
+    /// Start again from the first step
+    pub fn reset(&mut self) {
+        self.position = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_playhead_starts_at_first_step() {
+        let mut playhead = Playhead::default();
+        assert_eq!(playhead.position(), None);
+        assert_eq!(playhead.advance(8), 0);
+        assert_eq!(playhead.position(), Some(0));
+    }
+
+    #[test]
+    fn test_playhead_loops_over_length() {
+        let mut playhead = Playhead::default();
+        let steps: [usize; 12] = core::array::from_fn(|_| playhead.advance(5));
+        assert_eq!(steps, [0, 1, 2, 3, 4, 0, 1, 2, 3, 4, 0, 1]);
+
+        let mut playhead = Playhead::default();
+        let steps: [usize; 3] = core::array::from_fn(|_| playhead.advance(1));
+        assert_eq!(steps, [0, 0, 0]);
+        // Lengths are clamped to the available steps
+        let mut playhead = Playhead::default();
+        let steps: [usize; 10] = core::array::from_fn(|_| playhead.advance(20));
+        assert_eq!(steps, [0, 1, 2, 3, 4, 5, 6, 7, 0, 1]);
+    }
+
+    #[test]
+    fn test_playhead_follows_shorter_length() {
+        let mut playhead = Playhead::default();
+        for _ in 0..7 {
+            playhead.advance(8);
+        }
+        assert_eq!(playhead.position(), Some(6));
+        // Shrinking the sequence wraps right away
+        assert_eq!(playhead.advance(4), 3);
+        assert_eq!(playhead.advance(4), 0);
+    }
+
+    #[test]
+    fn test_playhead_reset() {
+        let mut playhead = Playhead::default();
+        playhead.advance(8);
+        playhead.advance(8);
+        playhead.reset();
+        assert_eq!(playhead.position(), None);
+        assert_eq!(playhead.advance(8), 0);
+    }
+
+    #[test]
+    fn test_step_index_per_page() {
+        assert_eq!(square_step_index(0, 0), 0);
+        assert_eq!(square_step_index(0, 3), 3);
+        assert_eq!(square_step_index(1, 0), 4);
+        assert_eq!(square_step_index(1, 3), 7);
+        // Out of range pages and channels stay within the steps
+        assert!(square_step_index(5, 9) < SQUARE_STEPS);
+    }
+
+    #[test]
+    fn test_gate_ticks() {
+        assert_eq!(square_gate_ticks(6, 50), 3);
+        assert_eq!(square_gate_ticks(24, 25), 6);
+        // Never shorter than a tick, never as long as the step
+        assert_eq!(square_gate_ticks(6, 1), 1);
+        assert_eq!(square_gate_ticks(6, 100), 5);
+        assert_eq!(square_gate_ticks(2, 100), 1);
+        assert_eq!(square_gate_ticks(1, 100), 1);
+    }
+
+    #[test]
+    fn test_accent() {
+        let mut step = Step::default();
+        assert!(step.gate);
+        assert!(!step.is_accent());
+        step.velocity = ACCENT_VELOCITY;
+        assert!(step.is_accent());
+        step.velocity = ACCENT_VELOCITY - 1;
+        assert!(!step.is_accent());
+    }
+}
@@ -0,0 +1,106 @@
+/// Longest delay of the late side of a stereo pair, in samples
+pub const STEREO_MAX_DELAY: usize = 64;
+
+/// Spread a mono 12-bit value into a (left, right) pair. At full `width` the two sides are
+/// half the range apart. Near the edges of the range the pair is shifted back in rather than
+/// clipped, so the spread between the sides is kept.
+pub fn stereo_offset(mono: u16, width: u16) -> (u16, u16) {
+    let mono = mono.min(4095) as i32;
+    let offset = width.min(4095) as i32 / 4;
+    let center = mono.clamp(offset, 4095 - offset);
+    ((center - offset) as u16, (center + offset) as u16)
+}
+
+/// Delay line for the late side of a stereo pair, like the Haas effect does for audio
+#[derive(Clone, Copy, Debug)]
+pub struct HaasDelay {
+    buf: [u16; STEREO_MAX_DELAY],
+    pos: usize,
+}
+
+impl HaasDelay {
+    pub fn new(initial: u16) -> Self {
+        Self {
+            buf: [initial; STEREO_MAX_DELAY],
+            pos: 0,
+        }
+    }
+
+    /// Store a new sample and return the one from `delay` samples ago.
+    /// A `delay` of `0` returns the new sample right away.
+    pub fn tick(&mut self, value: u16, delay: usize) -> u16 {
+        self.buf[self.pos] = value;
+        let delay = delay.min(STEREO_MAX_DELAY - 1);
+        let out = self.buf[(self.pos + STEREO_MAX_DELAY - delay) % STEREO_MAX_DELAY];
+        self.pos = (self.pos + 1) % STEREO_MAX_DELAY;
+        out
+    }
+}
+
+impl Default for HaasDelay {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_width_is_mono() {
+        for mono in [0, 1, 1000, 2047, 4095] {
+            assert_eq!(stereo_offset(mono, 0), (mono, mono));
+        }
+        assert_eq!(stereo_offset(u16::MAX, 0), (4095, 4095));
+    }
+
+    #[test]
+    fn test_spread_is_centered_on_input() {
+        assert_eq!(stereo_offset(2047, 4095), (1024, 3070));
+        assert_eq!(stereo_offset(2000, 400), (1900, 2100));
+    }
+
+    #[test]
+    fn test_spread_is_kept_at_the_edges() {
+        assert_eq!(stereo_offset(0, 400), (0, 200));
+        assert_eq!(stereo_offset(4095, 400), (3895, 4095));
+        assert_eq!(stereo_offset(50, 4095), (0, 2046));
+        for mono in (0..4096).step_by(7) {
+            for width in (0..4096).step_by(13) {
+                let (left, right) = stereo_offset(mono, width);
+                assert!(right <= 4095);
+                assert_eq!(right - left, width / 4 * 2, "{mono} {width}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_haas_delay() {
+        let mut delay = HaasDelay::new(100);
+        // Before the line is filled the initial value comes out
+        assert_eq!(delay.tick(1, 3), 100);
+        assert_eq!(delay.tick(2, 3), 100);
+        assert_eq!(delay.tick(3, 3), 100);
+        assert_eq!(delay.tick(4, 3), 1);
+        assert_eq!(delay.tick(5, 3), 2);
+        // No delay passes the input through
+        assert_eq!(delay.tick(6, 0), 6);
+        // Shortening the delay picks up more recent samples
+        assert_eq!(delay.tick(7, 1), 6);
+    }
+
+    #[test]
+    fn test_haas_delay_wraps_and_clamps() {
+        let mut delay = HaasDelay::default();
+        let outs: [u16; 200] = core::array::from_fn(|i| delay.tick(i as u16, 10));
+        for (i, &out) in outs.iter().enumerate().skip(10) {
+            assert_eq!(out, i as u16 - 10);
+        }
+        // Delays past the line length use the longest one
+        let mut delay = HaasDelay::default();
+        let outs: [u16; 200] = core::array::from_fn(|i| delay.tick(i as u16, 1000));
+        assert_eq!(outs[STEREO_MAX_DELAY - 2], 0);
+        assert_eq!(outs[150], 150 - (STEREO_MAX_DELAY as u16 - 1));
+    }
+}
@@ -0,0 +1,151 @@
+use crate::edge::Edge;
+
+/// Clock ticks between stutter repeats, 1/32 notes at 24 ppqn
+pub const STUTTER_TICKS: u32 = 3;
+
+/// Stutter effect for sequencer gates. While it is engaged, the gate of the last step is
+/// repeated every `division` ticks instead of playing the sequence, which keeps running in the
+/// background. Each repeat is high for half of the division.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Stutter {
+    engaged: bool,
+    high: bool,
+    count: u32,
+}
+
+impl Stutter {
+    /// Engage or release the stutter, the first repeat comes on the next tick. Returns the
+    /// falling edge when a repeat was high while releasing.
+    pub fn set_engaged(&mut self, engaged: bool) -> Option<Edge> {
+        if engaged == self.engaged {
+            return None;
+        }
+        self.engaged = engaged;
+        self.count = 0;
+        if !engaged && self.high {
+            self.high = false;
+            return Some(Edge::Falling);
+        }
+        None
+    }
+
+    pub fn is_engaged(&self) -> bool {
+        self.engaged
+    }
+
+    /// Advance by one clock tick, returns when the repeated gate goes high or low
+    pub fn tick(&mut self, division: u32) -> Option<Edge> {
+        if !self.engaged {
+            return None;
+        }
+        let division = division.max(2);
+        let count = self.count;
+        self.count = (self.count + 1) % division;
+        if count == 0 {
+            self.high = true;
+            Some(Edge::Rising)
+        } else if count == division / 2 && self.high {
+            self.high = false;
+            Some(Edge::Falling)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_until_engaged() {
+        let mut stutter = Stutter::default();
+        for _ in 0..24 {
+            assert_eq!(stutter.tick(STUTTER_TICKS), None);
+        }
+        assert!(!stutter.is_engaged());
+    }
+
+    #[test]
+    fn test_repeats_at_division() {
+        let mut stutter = Stutter::default();
+        assert_eq!(stutter.set_engaged(true), None);
+        let edges: [Option<Edge>; 12] = core::array::from_fn(|_| stutter.tick(6));
+        assert_eq!(
+            edges,
+            [
+                Some(Edge::Rising),
+                None,
+                None,
+                Some(Edge::Falling),
+                None,
+                None,
+                Some(Edge::Rising),
+                None,
+                None,
+                Some(Edge::Falling),
+                None,
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_repeat_rate() {
+        for division in [2, 3, 4, 6, 12] {
+            let mut stutter = Stutter::default();
+            stutter.set_engaged(true);
+            let edges: [Option<Edge>; 96] = core::array::from_fn(|_| stutter.tick(division));
+            let rising = edges.iter().filter(|&&e| e == Some(Edge::Rising)).count();
+            let falling = edges.iter().filter(|&&e| e == Some(Edge::Falling)).count();
+            assert_eq!(rising as u32, 96 / division);
+            assert_eq!(falling, rising);
+            // Every repeat goes low before the next one
+            let mut high = false;
+            for edge in edges.iter().flatten() {
+                assert_eq!(*edge == Edge::Rising, !high);
+                high = *edge == Edge::Rising;
+            }
+        }
+    }
+
+    #[test]
+    fn test_release_ends_repeat() {
+        let mut stutter = Stutter::default();
+        stutter.set_engaged(true);
+        assert_eq!(stutter.tick(6), Some(Edge::Rising));
+        assert_eq!(stutter.set_engaged(false), Some(Edge::Falling));
+        assert_eq!(stutter.tick(6), None);
+        // Releasing while low doesn't need a falling edge
+        stutter.set_engaged(true);
+        for _ in 0..4 {
+            stutter.tick(6);
+        }
+        assert_eq!(stutter.set_engaged(false), None);
+    }
+
+    #[test]
+    fn test_engaging_again_restarts() {
+        let mut stutter = Stutter::default();
+        stutter.set_engaged(true);
+        stutter.tick(6);
+        stutter.tick(6);
+        // Staying engaged changes nothing
+        assert_eq!(stutter.set_engaged(true), None);
+        assert_eq!(stutter.tick(6), None);
+        stutter.set_engaged(false);
+        stutter.set_engaged(true);
+        assert_eq!(stutter.tick(6), Some(Edge::Rising));
+    }
+
+    #[test]
+    fn test_shortest_division() {
+        let mut stutter = Stutter::default();
+        stutter.set_engaged(true);
+        // A division of 1 would never go low, so it is stretched to 2
+        for _ in 0..4 {
+            assert_eq!(stutter.tick(1), Some(Edge::Rising));
+            assert_eq!(stutter.tick(1), Some(Edge::Falling));
+        }
+    }
+}
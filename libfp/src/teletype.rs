@@ -0,0 +1,112 @@
+use midly::num::u7;
+
+use crate::MidiNote;
+
+/// Teletype CV value for 10V
+pub const TT_CV_MAX: i16 = 16383;
+/// Teletype CV value for a high gate (5V)
+pub const TT_CV_GATE_HIGH: i16 = 8192;
+/// Outputs per TXo and per Ansible
+const OUTPUTS_PER_DEVICE: usize = 4;
+
+/// Scale a 12-bit fader value to the 0-10V teletype CV range
+pub fn fader_to_tt_cv(value: u16) -> i16 {
+    (value.min(4095) as u32 * TT_CV_MAX as u32 / 4095) as i16
+}
+
+/// 1V/oct teletype CV for a note, C0 (note 0) is 0V. Notes above 10V are clamped.
+pub fn note_to_tt_cv(note: MidiNote) -> i16 {
+    let note = u7::from(note).as_int() as u32;
+    (note * (TT_CV_MAX as u32 + 1) / 120).min(TT_CV_MAX as u32) as i16
+}
+
+/// 1V/oct note as a 12-bit fader value, for devices that take fader values (Ansible)
+pub fn note_to_fader(note: MidiNote) -> u16 {
+    let note = u7::from(note).as_int() as u32;
+    (note * 4096 / 120).min(4095) as u16
+}
+
+/// Teletype CV for a gate, 5V when high
+pub fn gate_to_tt_cv(high: bool) -> i16 {
+    if high {
+        TT_CV_GATE_HIGH
+    } else {
+        0
+    }
+}
+
+/// 12-bit fader value for a gate, 5V when high
+pub fn gate_to_fader(high: bool) -> u16 {
+    if high {
+        2048
+    } else {
+        0
+    }
+}
+
+/// TXo address offset and port for a global output channel
+pub fn txo_port(chan: usize) -> (u8, u8) {
+    (
+        (chan / OUTPUTS_PER_DEVICE) as u8,
+        (chan % OUTPUTS_PER_DEVICE) as u8,
+    )
+}
+
+/// Ansible device port for a global output channel
+pub fn ansible_device_port(chan: usize) -> u8 {
+    ((chan / OUTPUTS_PER_DEVICE) << 1) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fader_scaling() {
+        assert_eq!(fader_to_tt_cv(0), 0);
+        assert_eq!(fader_to_tt_cv(4095), TT_CV_MAX);
+        assert_eq!(fader_to_tt_cv(u16::MAX), TT_CV_MAX);
+    }
+
+    #[test]
+    fn test_note_is_one_volt_per_octave() {
+        assert_eq!(note_to_tt_cv(MidiNote::from(0u8)), 0);
+        // One octave is 1V
+        let octave = note_to_tt_cv(MidiNote::from(12u8));
+        assert_eq!(octave, 1638);
+        assert_eq!(note_to_tt_cv(MidiNote::from(60u8)), 8192);
+        // Above 10V the note is clamped
+        assert_eq!(note_to_tt_cv(MidiNote::from(127u8)), TT_CV_MAX);
+        assert_eq!(note_to_fader(MidiNote::from(60u8)), 2048);
+        assert_eq!(note_to_fader(MidiNote::from(127u8)), 4095);
+    }
+
+    #[test]
+    fn test_notes_rise_monotonically() {
+        for n in 0..120u8 {
+            assert!(note_to_tt_cv(MidiNote::from(n)) < note_to_tt_cv(MidiNote::from(n + 1)));
+            assert!(note_to_fader(MidiNote::from(n)) < note_to_fader(MidiNote::from(n + 1)));
+        }
+    }
+
+    #[test]
+    fn test_gate_levels() {
+        assert_eq!(gate_to_tt_cv(false), 0);
+        assert_eq!(gate_to_tt_cv(true), TT_CV_GATE_HIGH);
+        assert_eq!(gate_to_fader(false), 0);
+        // Both are 5V
+        assert!((fader_to_tt_cv(gate_to_fader(true)) - TT_CV_GATE_HIGH).abs() <= 1);
+    }
+
+    #[test]
+    fn test_channel_offsets() {
+        assert_eq!(txo_port(0), (0, 0));
+        assert_eq!(txo_port(3), (0, 3));
+        assert_eq!(txo_port(4), (1, 0));
+        assert_eq!(txo_port(15), (3, 3));
+        assert_eq!(ansible_device_port(0), 0);
+        assert_eq!(ansible_device_port(3), 0);
+        assert_eq!(ansible_device_port(4), 2);
+        assert_eq!(ansible_device_port(12), 6);
+    }
+}
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+
+pub const GRID_TRACKS: usize = 8;
+pub const GRID_STEPS: usize = 16;
+/// Number of steps shown on the buttons at once
+pub const GRID_PAGE_STEPS: usize = 8;
+
+/// On/off trigger steps for a number of tracks, one bit per step.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TriggerGrid {
+    tracks: [u16; GRID_TRACKS],
+}
+
+impl TriggerGrid {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_set(&self, track: usize, step: usize) -> bool {
+        track < GRID_TRACKS && step < GRID_STEPS && self.tracks[track] & (1 << step) != 0
+    }
+
+    /// Flip a step and return its new state
+    pub fn toggle(&mut self, track: usize, step: usize) -> bool {
+        if track >= GRID_TRACKS || step >= GRID_STEPS {
+            return false;
+        }
+        self.tracks[track] ^= 1 << step;
+        self.is_set(track, step)
+    }
+
+    pub fn clear_track(&mut self, track: usize) {
+        if track < GRID_TRACKS {
+            self.tracks[track] = 0;
+        }
+    }
+
+    /// Bitmask of the tracks firing on `step`, bit N for track N
+    pub fn triggers_at(&self, step: usize) -> u8 {
+        self.tracks
+            .iter()
+            .enumerate()
+            .filter(|(_, steps)| step < GRID_STEPS && *steps & (1 << step) != 0)
+            .fold(0, |mask, (track, _)| mask | (1 << track))
+    }
+}
+
+/// Grid step addressed by a button on a page of `GRID_PAGE_STEPS` steps
+pub fn grid_step(page: usize, button: usize) -> usize {
+    (page * GRID_PAGE_STEPS + button) % GRID_STEPS
+}
+
+/// Page showing the given step
+pub fn grid_page(step: usize) -> usize {
+    (step % GRID_STEPS) / GRID_PAGE_STEPS
+}
+
+/// Returns the step to play if `clkn` (ticks since the last reset) falls on a step boundary
+pub fn grid_step_at(clkn: u32, div: u32, length: usize) -> Option<usize> {
+    let div = div.max(1);
+    if !clkn.is_multiple_of(div) {
+        return None;
+    }
+    let length = length.clamp(1, GRID_STEPS) as u32;
+    Some(((clkn / div) % length) as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_grid_is_empty() {
+        let grid = TriggerGrid::new();
+        for step in 0..GRID_STEPS {
+            assert_eq!(grid.triggers_at(step), 0);
+        }
+    }
+
+    #[test]
+    fn test_toggle_step() {
+        let mut grid = TriggerGrid::new();
+        assert!(grid.toggle(2, 5));
+        assert!(grid.is_set(2, 5));
+        assert!(!grid.is_set(2, 4));
+        assert!(!grid.is_set(1, 5));
+        assert!(!grid.toggle(2, 5));
+        assert!(!grid.is_set(2, 5));
+    }
+
+    #[test]
+    fn test_out_of_range_is_ignored() {
+        let mut grid = TriggerGrid::new();
+        assert!(!grid.toggle(GRID_TRACKS, 0));
+        assert!(!grid.toggle(0, GRID_STEPS));
+        assert_eq!(grid, TriggerGrid::new());
+        assert_eq!(grid.triggers_at(GRID_STEPS), 0);
+    }
+
+    #[test]
+    fn test_triggers_map_to_tracks() {
+        let mut grid = TriggerGrid::new();
+        grid.toggle(0, 0);
+        grid.toggle(3, 0);
+        grid.toggle(7, 0);
+        grid.toggle(3, 4);
+        assert_eq!(grid.triggers_at(0), 0b1000_1001);
+        assert_eq!(grid.triggers_at(4), 0b0000_1000);
+        assert_eq!(grid.triggers_at(1), 0);
+    }
+
+    #[test]
+    fn test_clear_track() {
+        let mut grid = TriggerGrid::new();
+        grid.toggle(1, 0);
+        grid.toggle(1, 15);
+        grid.toggle(2, 0);
+        grid.clear_track(1);
+        assert_eq!(grid.triggers_at(0), 0b100);
+        assert_eq!(grid.triggers_at(15), 0);
+    }
+
+    #[test]
+    fn test_pages() {
+        assert_eq!(grid_step(0, 0), 0);
+        assert_eq!(grid_step(0, 7), 7);
+        assert_eq!(grid_step(1, 0), 8);
+        assert_eq!(grid_step(1, 7), 15);
+        for step in 0..GRID_STEPS {
+            assert_eq!(grid_step(grid_page(step), step % GRID_PAGE_STEPS), step);
+        }
+    }
+
+    #[test]
+    fn test_step_advancement() {
+        let steps: heapless::Vec<Option<usize>, 16> =
+            (0..13).map(|clkn| grid_step_at(clkn, 6, 16)).collect();
+        assert_eq!(steps[0], Some(0));
+        assert_eq!(steps[1], None);
+        assert_eq!(steps[5], None);
+        assert_eq!(steps[6], Some(1));
+        assert_eq!(steps[12], Some(2));
+    }
+
+    #[test]
+    fn test_step_wraps_at_length() {
+        assert_eq!(grid_step_at(6 * 15, 6, 16), Some(15));
+        assert_eq!(grid_step_at(6 * 16, 6, 16), Some(0));
+        assert_eq!(grid_step_at(6 * 5, 6, 5), Some(0));
+        assert_eq!(grid_step_at(6 * 7, 6, 5), Some(2));
+        // Length is clamped to the grid
+        assert_eq!(grid_step_at(6 * 20, 6, 64), Some(4));
+        assert_eq!(grid_step_at(6 * 3, 6, 0), Some(0));
+    }
+
+    #[test]
+    fn test_zero_division_is_safe() {
+        assert_eq!(grid_step_at(3, 0, 16), Some(3));
+    }
+}
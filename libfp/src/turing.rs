@@ -0,0 +1,99 @@
+/// Maximum loop length of the 16-bit shift register
+pub const TURING_MAX_LENGTH: u16 = 16;
+
+/// Whether the fed back bit gets flipped, for a 12-bit probability and a die roll.
+/// A probability of `0` locks the loop, `4095` always flips and locks a loop of twice the length.
+pub fn turing_flips(probability: u16, roll: u16) -> bool {
+    probability > roll.clamp(100, 3900)
+}
+
+/// Advance the shift register by one step.
+/// The register shifts right and the bit leaving the `length` step loop is fed back into the
+/// MSB, inverted if `flip` is set.
+pub fn turing_step(register: u16, length: u16, flip: bool) -> u16 {
+    let bit_index = TURING_MAX_LENGTH - length.clamp(1, TURING_MAX_LENGTH);
+    let bit = ((register >> bit_index) & 1) ^ flip as u16;
+    (register >> 1) | (bit << 15)
+}
+
+/// Scale the top `length` bits of the register to a 12-bit value
+pub fn turing_value(register: u16, length: u8) -> u16 {
+    let length = length.clamp(1, TURING_MAX_LENGTH as u8);
+    let top_bits = register >> (TURING_MAX_LENGTH as u8 - length);
+    let max_val = (1u32 << length) - 1;
+    ((top_bits as u32 * 4095) / max_val) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(register: u16, length: u16, flip: bool, steps: usize) -> u16 {
+        (0..steps).fold(register, |reg, _| turing_step(reg, length, flip))
+    }
+
+    #[test]
+    fn test_flip_probability_extremes() {
+        for roll in 0..=4095 {
+            assert!(!turing_flips(0, roll));
+            assert!(turing_flips(4095, roll));
+        }
+        // Halfway flips about half of the time
+        let flips = (0..=4095).filter(|&roll| turing_flips(2048, roll)).count();
+        assert!((1900..2200).contains(&flips));
+    }
+
+    #[test]
+    fn test_locked_register_loops_over_length() {
+        let seed = 0b1011_0010_1110_0101;
+        for length in 1..=TURING_MAX_LENGTH {
+            let start = run(seed, length, false, TURING_MAX_LENGTH as usize);
+            let mut reg = start;
+            let mut values = heapless::Vec::<u16, 16>::new();
+            for _ in 0..length {
+                values.push(turing_value(reg, length as u8)).unwrap();
+                reg = turing_step(reg, length, false);
+            }
+            assert_eq!(reg, start, "length {length}");
+            for value in values {
+                assert_eq!(turing_value(reg, length as u8), value, "length {length}");
+                reg = turing_step(reg, length, false);
+            }
+        }
+    }
+
+    #[test]
+    fn test_always_flipping_doubles_the_loop() {
+        let seed = 0b0110_1001_0011_1100;
+        for length in 1..=TURING_MAX_LENGTH {
+            let start = run(seed, length, true, TURING_MAX_LENGTH as usize);
+            let window = TURING_MAX_LENGTH - length;
+            // After one pass the loop is inverted, after two it's back
+            let once = run(start, length, true, length as usize);
+            assert_eq!(once >> window, !start >> window & (u16::MAX >> window));
+            assert_eq!(run(start, length, true, 2 * length as usize), start);
+        }
+    }
+
+    #[test]
+    fn test_step_shifts_right() {
+        assert_eq!(turing_step(0b10, 16, false), 0b1);
+        assert_eq!(turing_step(0x8000, 16, false), 0x4000);
+        // Length 16 feeds back bit 0
+        assert_eq!(turing_step(0b1, 16, false), 0x8000);
+        assert_eq!(turing_step(0b1, 16, true), 0);
+        // Length 8 feeds back bit 8
+        assert_eq!(turing_step(0x0100, 8, false), 0x8080);
+    }
+
+    #[test]
+    fn test_value_scales_top_bits() {
+        assert_eq!(turing_value(0, 8), 0);
+        assert_eq!(turing_value(0xFFFF, 8), 4095);
+        assert_eq!(turing_value(0xFF00, 8), 4095);
+        assert_eq!(turing_value(0x00FF, 8), 0);
+        assert_eq!(turing_value(0x8000, 1), 4095);
+        assert_eq!(turing_value(0x8000, 2), 2730);
+        assert_eq!(turing_value(0xFFFF, 16), 4095);
+    }
+}
@@ -1,4 +1,5 @@
-use serde::{Deserialize, Serialize};
+use heapless::Vec;
+use serde::{de::Error as DeError, Deserialize, Deserializer, Serialize, Serializer};
 
 use crate::{CALIBRATION_SCALE_FACTOR, CALIBRATION_VERSION_LATEST, CALIB_FILE_MAGIC};
 
@@ -9,16 +10,129 @@ pub struct MaxCalibrationV1 {
     pub outputs: [[(f32, f32); 2]; 20],
 }
 
-// --- V2 (New) Format Definition ---
-// This is the canonical format for the application.
+// --- V2 Format Definition ---
 pub type RegressionValues = (i64, i64);
 pub type RegressionValuesInput = [RegressionValues; 2];
 pub type RegressionValuesOutput = [[RegressionValues; 2]; 20];
 
+#[derive(Deserialize, Default, Copy, Clone)]
+pub struct MaxCalibrationV2 {
+    pub inputs: RegressionValuesInput,
+    pub outputs: RegressionValuesOutput,
+}
+
+#[derive(Deserialize, Copy, Clone)]
+pub struct CalibFileV2 {
+    pub magic: [u8; 4],
+    pub version: u8,
+    pub data: MaxCalibrationV2,
+}
+
+// --- V3 (New) Format Definition ---
+// This is the canonical format for the application.
+
+/// Breakpoints per output curve, one every two volts of a DAC range. One per octave would not
+/// fit into a single FRAM blob for all outputs.
+pub const CALIBRATION_BREAKPOINTS: usize = 6;
+/// DAC counts between two breakpoints
+pub const CALIBRATION_BREAKPOINT_SPACING: u16 = 819;
+
+/// Calibration of an output in one of its DAC ranges: the DAC counts that put out the voltage
+/// of each breakpoint, with straight lines in between. Stored as the error to the ideal counts,
+/// so a breakpoint takes a single byte.
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Debug, PartialEq, Eq)]
+pub struct OutputCalibration {
+    /// Error at the lowest breakpoint, in DAC counts
+    pub offset: i16,
+    /// Change of the error from one breakpoint to the next
+    pub steps: [i8; CALIBRATION_BREAKPOINTS - 1],
+}
+
+pub type OutputCalibrations = [[OutputCalibration; 2]; 20];
+
+impl OutputCalibration {
+    /// Calibration from the DAC counts that put out the voltage of each breakpoint
+    pub fn from_breakpoints(breakpoints: [i32; CALIBRATION_BREAKPOINTS]) -> Self {
+        let error = |i: usize| breakpoints[i] - breakpoint_value(i);
+        let offset = error(0).clamp(i16::MIN as i32, i16::MAX as i32);
+        let mut steps = [0; CALIBRATION_BREAKPOINTS - 1];
+        // Errors beyond what a step can hold are spread over the following ones
+        let mut stored = offset;
+        for (i, step) in steps.iter_mut().enumerate() {
+            *step = (error(i + 1) - stored).clamp(i8::MIN as i32, i8::MAX as i32) as i8;
+            stored += *step as i32;
+        }
+        Self {
+            offset: offset as i16,
+            steps,
+        }
+    }
+
+    /// Calibration through measured `(ideal, raw)` points, `raw` being the DAC counts that put
+    /// out the voltage of the `ideal` counts. Breakpoints beyond the first or last point continue
+    /// the closest segment. `None` unless there are at least two points, in ascending order.
+    pub fn from_points(points: &[(u16, u16)]) -> Option<Self> {
+        if points.len() < 2 || points.windows(2).any(|w| w[0].0 >= w[1].0) {
+            return None;
+        }
+        let breakpoints = core::array::from_fn(|i| {
+            let x = breakpoint_value(i);
+            let segment = points
+                .windows(2)
+                .find(|w| x <= w[1].0 as i32)
+                .unwrap_or(&points[points.len() - 2..]);
+            let (x0, y0) = (segment[0].0 as i32, segment[0].1 as i32);
+            let (x1, y1) = (segment[1].0 as i32, segment[1].1 as i32);
+            y0 + div_round((y1 - y0) * (x - x0), x1 - x0)
+        });
+        Some(Self::from_breakpoints(breakpoints))
+    }
+
+    /// Calibration following a linear regression, `raw = slope * ideal + intercept` in
+    /// `CALIBRATION_SCALE_FACTOR` fixed point
+    pub fn from_regression((slope, intercept): RegressionValues) -> Self {
+        Self::from_breakpoints(core::array::from_fn(|i| {
+            ((breakpoint_value(i) as i64 * slope + intercept + (CALIBRATION_SCALE_FACTOR / 2))
+                >> 16) as i32
+        }))
+    }
+
+    /// The DAC counts that put out the voltage of each breakpoint
+    pub fn breakpoints(&self) -> [i32; CALIBRATION_BREAKPOINTS] {
+        let mut error = self.offset as i32;
+        core::array::from_fn(|i| {
+            if i > 0 {
+                error += self.steps[i - 1] as i32;
+            }
+            breakpoint_value(i) + error
+        })
+    }
+
+    /// DAC counts to write for the ideal `value`, interpolated between the breakpoints around it
+    pub fn apply(&self, value: u16) -> u16 {
+        let breakpoints = self.breakpoints();
+        let segment =
+            ((value / CALIBRATION_BREAKPOINT_SPACING) as usize).min(CALIBRATION_BREAKPOINTS - 2);
+        let (y0, y1) = (breakpoints[segment], breakpoints[segment + 1]);
+        let x = value as i32 - breakpoint_value(segment);
+        (y0 + div_round((y1 - y0) * x, CALIBRATION_BREAKPOINT_SPACING as i32)).clamp(0, 4095) as u16
+    }
+}
+
+/// Ideal DAC counts of breakpoint `i`
+fn breakpoint_value(i: usize) -> i32 {
+    i as i32 * CALIBRATION_BREAKPOINT_SPACING as i32
+}
+
+/// `n / d` rounded to the nearest integer, `d` must be positive
+fn div_round(n: i32, d: i32) -> i32 {
+    (2 * n + d).div_euclid(2 * d)
+}
+
 #[derive(Serialize, Deserialize, Default, Copy, Clone)]
 pub struct MaxCalibration {
     pub inputs: RegressionValuesInput,
-    pub outputs: RegressionValuesOutput,
+    pub outputs: OutputCalibrations,
 }
 
 #[derive(Serialize, Deserialize, Copy, Clone)]
@@ -39,10 +153,10 @@ impl CalibFile {
 }
 
 // --- Migration Logic ---
-// This function converts the old V1 data into the new V2 format.
-impl From<MaxCalibrationV1> for MaxCalibration {
+// This function converts the old V1 data into the V2 format.
+impl From<MaxCalibrationV1> for MaxCalibrationV2 {
     fn from(old: MaxCalibrationV1) -> Self {
-        let mut new = MaxCalibration::default();
+        let mut new = MaxCalibrationV2::default();
 
         // Convert inputs
         for i in 0..old.inputs.len() {
@@ -79,3 +193,228 @@ impl From<MaxCalibrationV1> for MaxCalibration {
         new
     }
 }
+
+// The V2 regressions become straight lines through the breakpoints
+impl From<MaxCalibrationV2> for MaxCalibration {
+    fn from(old: MaxCalibrationV2) -> Self {
+        Self {
+            inputs: old.inputs,
+            outputs: old
+                .outputs
+                .map(|ranges| ranges.map(OutputCalibration::from_regression)),
+        }
+    }
+}
+
+/// Fixed size array that serializes like a `Vec`, for arrays longer than serde supports
+#[derive(Clone, Copy)]
+pub struct Arr<T: Sized + Copy + Default, const N: usize>([T; N]);
+
+impl<T: Sized + Copy + Default, const N: usize> Default for Arr<T, N> {
+    fn default() -> Self {
+        Self([T::default(); N])
+    }
+}
+
+impl<T: Sized + Copy + Default, const N: usize> Arr<T, N> {
+    pub fn new(initial: [T; N]) -> Self {
+        Self(initial)
+    }
+
+    #[inline(always)]
+    #[allow(dead_code)]
+    pub fn at(&self, idx: usize) -> T {
+        self.0[idx]
+    }
+
+    #[inline(always)]
+    #[allow(dead_code)]
+    pub fn set_at(&mut self, idx: usize, value: T) {
+        self.0[idx] = value;
+    }
+
+    #[inline(always)]
+    pub fn get(&self) -> [T; N] {
+        self.0
+    }
+
+    #[inline(always)]
+    pub fn set(&mut self, value: [T; N]) {
+        self.0 = value;
+    }
+}
+
+impl<T, const N: usize> Serialize for Arr<T, N>
+where
+    T: Serialize + Sized + Copy + Default,
+{
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let vec = Vec::<T, N>::from_slice(&self.0).unwrap();
+        vec.serialize(serializer)
+    }
+}
+
+impl<'de, T, const N: usize> Deserialize<'de> for Arr<T, N>
+where
+    T: Deserialize<'de> + Sized + Copy + Default,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let vec = Vec::<T, N>::deserialize(deserializer)?;
+        if vec.len() != N {
+            return Err(D::Error::invalid_length(
+                vec.len(),
+                &"an array of exact length N",
+            ));
+        }
+        let mut arr = [T::default(); N];
+        arr.copy_from_slice(vec.as_slice()); // Safe due to length check above
+        Ok(Arr(arr))
+    }
+}
+
+impl<T: Sized + Copy + PartialEq + Default, const N: usize> PartialEq for Arr<T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Slightly too steep, like the outputs usually are, and bending up towards the top
+    const MEASURED: [i32; CALIBRATION_BREAKPOINTS] = [-12, 811, 1634, 2460, 3288, 4117];
+
+    #[test]
+    fn test_default_is_identity() {
+        let calibration = OutputCalibration::default();
+        for value in 0..=4095 {
+            assert_eq!(calibration.apply(value), value);
+        }
+    }
+
+    #[test]
+    fn test_breakpoints_round_trip() {
+        let calibration = OutputCalibration::from_breakpoints(MEASURED);
+        assert_eq!(calibration.breakpoints(), MEASURED);
+        assert_eq!(calibration.offset, -12);
+        assert_eq!(calibration.steps, [4, 4, 7, 9, 10]);
+    }
+
+    #[test]
+    fn test_interpolates_between_breakpoints() {
+        let calibration = OutputCalibration::from_breakpoints(MEASURED);
+        // On the breakpoints
+        assert_eq!(calibration.apply(819), 811);
+        assert_eq!(calibration.apply(1638), 1634);
+        assert_eq!(calibration.apply(3276), 3288);
+        // Halfway between them
+        assert_eq!(calibration.apply(1229), 1223);
+        assert_eq!(calibration.apply(2048), 2048);
+        assert_eq!(calibration.apply(3686), 3703);
+        // Every segment is a straight line
+        for value in 2457..3276 {
+            let expected = 2460.0 + (value - 2457) as f32 * 828.0 / 819.0;
+            let calibrated = calibration.apply(value) as f32;
+            assert!((calibrated - expected).abs() <= 0.5, "{value}");
+        }
+        let calibrated: [u16; 4095] = core::array::from_fn(|i| calibration.apply(i as u16 + 1));
+        assert!(calibrated.windows(2).all(|w| w[0] <= w[1]));
+    }
+
+    #[test]
+    fn test_clamps_to_dac_range() {
+        let calibration = OutputCalibration::from_breakpoints(MEASURED);
+        assert_eq!(calibration.apply(0), 0);
+        assert_eq!(calibration.apply(10), 0);
+        assert_eq!(calibration.apply(4095), 4095);
+        assert_eq!(calibration.apply(4080), 4095);
+    }
+
+    #[test]
+    fn test_from_measured_points() {
+        // The points measured by the manual calibration in the 0-10V range
+        let points = [(819, 815), (1638, 1640), (3276, 3290)];
+        let calibration = OutputCalibration::from_points(&points).unwrap();
+        for (ideal, raw) in points {
+            assert_eq!(calibration.apply(ideal), raw);
+        }
+        // Continues the closest segment below the first and above the last point
+        assert_eq!(
+            calibration.breakpoints(),
+            [-10, 815, 1640, 2465, 3290, 4115]
+        );
+        assert_eq!(calibration.apply(2457), 2465);
+
+        // And in the -5V to 5V range, where 0V is no breakpoint
+        let points = [(819, 822), (2048, 2044), (3276, 3268)];
+        let calibration = OutputCalibration::from_points(&points).unwrap();
+        for (ideal, raw) in points {
+            assert_eq!(calibration.apply(ideal), raw);
+        }
+    }
+
+    #[test]
+    fn test_from_points_needs_ascending_points() {
+        assert_eq!(OutputCalibration::from_points(&[]), None);
+        assert_eq!(OutputCalibration::from_points(&[(819, 815)]), None);
+        assert_eq!(
+            OutputCalibration::from_points(&[(1638, 1640), (819, 815)]),
+            None
+        );
+        assert_eq!(
+            OutputCalibration::from_points(&[(819, 815), (819, 820)]),
+            None
+        );
+    }
+
+    #[test]
+    fn test_large_errors_saturate() {
+        let calibration = OutputCalibration::from_breakpoints([0, 1200, 1638, 2457, 3276, 4095]);
+        let breakpoints = calibration.breakpoints();
+        // The step that is too large is caught up on by the next one
+        assert_eq!(breakpoints[1], 819 + 127);
+        assert_eq!(breakpoints[2..], [1638, 2457, 3276, 4095]);
+    }
+
+    #[test]
+    fn test_v2_migration_follows_regression() {
+        let regression = (
+            (1.004 * CALIBRATION_SCALE_FACTOR as f32) as i64,
+            (-6.5 * CALIBRATION_SCALE_FACTOR as f32) as i64,
+        );
+        let mut old = MaxCalibrationV2::default();
+        old.outputs[3][1] = regression;
+        let new = MaxCalibration::from(old);
+        let calibration = new.outputs[3][1];
+        for value in 1..=4095u16 {
+            let linear =
+                ((value as i64 * regression.0 + regression.1 + CALIBRATION_SCALE_FACTOR / 2) >> 16)
+                    .clamp(0, 4095);
+            let calibrated = calibration.apply(value) as i64;
+            assert!((calibrated - linear).abs() <= 1, "{value}");
+        }
+    }
+
+    #[test]
+    fn test_calib_file_fits_into_fram_blob() {
+        // Worst case, the FRAM task stores up to 384 bytes
+        let worst = OutputCalibration {
+            offset: i16::MIN,
+            steps: [i8::MIN; CALIBRATION_BREAKPOINTS - 1],
+        };
+        let file = CalibFile::new(MaxCalibration {
+            inputs: [(i64::MIN, i64::MIN); 2],
+            outputs: [[worst; 2]; 20],
+        });
+        let mut buf = [0; 1024];
+        let len = postcard::to_slice(&file, &mut buf).unwrap().len();
+        assert!(len <= 384, "{len} bytes");
+    }
+}
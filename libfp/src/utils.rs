@@ -1,12 +1,72 @@
-use embassy_time::Duration;
-use midly::num::u7;
+use embassy_time::{Duration, Instant};
+use libm::roundf;
+use midly::{num::u7, MidiMessage};
 
-use crate::Curve;
+use crate::{Curve, MidiCc, MidiNote, NoteEvent};
 
 pub const fn bpm_to_clock_duration(bpm: f32, ppqn: u8) -> Duration {
     Duration::from_nanos((1_000_000_000.0 / (bpm as f64 / 60.0 * ppqn as f64)) as u64)
 }
 
+/// Tempo of a clock at `ppqn` with ticks `tick` apart, the inverse of `bpm_to_clock_duration`
+pub fn clock_duration_to_bpm(tick: Duration, ppqn: u8) -> Option<f32> {
+    let micros = tick.as_micros();
+    if micros == 0 || ppqn == 0 {
+        return None;
+    }
+    Some((60_000_000.0 / (micros as f64 * ppqn as f64)) as f32)
+}
+
+/// Half of the swing window, in 24-PPQN ticks. With `H = 6`, the swing window
+/// is one 8th note (12 ticks) and swing is applied at the 16th-note level.
+pub const SWING_HALF_INTERVAL: u32 = 6;
+
+/// Swung absolute offset of tick `i` (in `[0, 2H]`) from the start of the swing
+/// window. Used by both the internal clock (to schedule the next tick directly)
+/// and the external clock (to schedule the whole window on its anchor pulse).
+///
+/// Swing moves the start of the second 16th note. The ticks within each 16th
+/// note are spread evenly over its swung length, so they stay in order and never
+/// bunch up at the end of the window.
+///
+/// The result is clamped to 500µs before the window boundary. Without this,
+/// heavy positive swing pushes the last ticks of the window past the boundary,
+/// causing the engine to fire tick 0 of the next window as an immediate
+/// catch-up. That catch-up creates two ticks in rapid succession: the
+/// gatekeeper processes both before any subscriber runs, incrementing
+/// TICK_COUNTER twice, so subscribers read the same stale counter for both
+/// events and double-fire notes on beat boundaries.
+pub fn swung_offset(i: u32, t: Duration, swing: i8) -> Duration {
+    let h = SWING_HALF_INTERVAL as i64;
+    let t_ticks = t.as_ticks() as i64;
+    let s = swing as i64;
+    let i = i as i64;
+
+    let boundary = h * t_ticks * (50 + s) / 50;
+    let raw = if i < h {
+        // First 16th note: stretched or squeezed up to the boundary
+        i * boundary / h
+    } else {
+        // Second 16th note: shifted start, squeezed or stretched up to the window end
+        boundary + (i - h) * (2 * h * t_ticks - boundary) / h
+    };
+
+    // Clamp to 500µs before the window end. A 1µs margin was insufficient:
+    // by the time the next loop iteration polls `Timer::at(next_tick_at)`,
+    // several µs of code execution have elapsed, consuming the gap and causing
+    // the timer to fire immediately — no executor yield, same race. 500µs is
+    // larger than any plausible round-trip of the clock engine's timer arm.
+    let window_end = 2 * h * t_ticks - 500;
+    Duration::from_ticks((raw.max(0) as u64).min(window_end as u64))
+}
+
+/// Start of the internal clock's swing window after a start or reset at `now`, which is also
+/// when its first tick fires. The window starts over `delay` later so apps can handle the reset
+/// first. Keeping the old anchor on a reset would schedule the rest of the window in the past.
+pub fn restart_swing_window(now: Instant, delay: Duration) -> Instant {
+    now + delay
+}
+
 /// Scale from 4095 u16 to 127 u7
 pub fn scale_bits_12_7(value: u16) -> u7 {
     u7::new(((value as u32 * 127) / 4095) as u8)
@@ -68,6 +128,19 @@ pub fn attenuate(signal: u16, level: u16) -> u16 {
     attenuated as u16
 }
 
+/// Shape a 12-bit fader value with `curve`. Bipolar values are shaped outwards from the center,
+/// so both halves of the fader travel follow the curve.
+pub fn curve_fader(curve: Curve, value: u16, bipolar: bool) -> u16 {
+    let value = value.min(4095);
+    if !bipolar {
+        curve.at(value)
+    } else if value > 2047 {
+        curve.at((value - 2047) * 2) / 2 + 2047
+    } else {
+        2047 - curve.at((2047 - value) * 2) / 2
+    }
+}
+
 /// Rescale a 12-bit value (`0..=4095`) into a `min..=max` interval.
 pub fn rescale_12bit_int(input: u16, min: u16, max: u16) -> u16 {
     let input = input.min(4095);
@@ -141,6 +214,273 @@ pub fn attenuverter(input: u16, modulation: u16) -> u16 {
     result.clamp(0.0, 4095.0) as u16
 }
 
+/// Attenuvert a bipolar 12-bit value and add a bipolar offset (both centered at 2047).
+/// The result is doubled around the center, so `att` spans a gain of -2 to +2 with
+/// unity gain at 3071 and no signal at 2047.
+pub fn offset_attenuvert(input: u16, att: u16, offset: u16) -> u16 {
+    let offset = offset as i32 - 2047;
+    let outval = (attenuverter(input, att) as i32 + offset).clamp(0, 4095);
+    ((outval - 2047) * 2 + 2047).clamp(0, 4094) as u16
+}
+
+/// Gain, invert and DC offset of one CV. Bipolar CVs are scaled around 0V with
+/// `attenuate_bipolar`, unipolar ones towards 0V. `offset` is centered at 2048 and moves the
+/// result by up to half the range either way.
+pub fn attenuvert_cv(input: u16, gain: u16, offset: u16, invert: bool, bipolar: bool) -> u16 {
+    let input = input.min(4095);
+    let input = if invert { 4095 - input } else { input };
+    let scaled = if bipolar {
+        attenuate_bipolar(input, gain)
+    } else {
+        attenuate(input, gain)
+    };
+    (scaled as i32 + offset as i32 - 2048).clamp(0, 4095) as u16
+}
+
+/// Scale a 12-bit CV input to a 12-bit CC value with an attenuation level and an offset.
+/// Unipolar inputs are doubled before attenuation, bipolar inputs attenuate around the
+/// center and take an offset centered at 2047.
+pub fn cv_to_cc_value(input: u16, att: u16, offset: u16, bipolar: bool) -> u16 {
+    if bipolar {
+        (attenuate_bipolar(input, att) as i32 + (offset as i32 - 2047)).clamp(0, 4095) as u16
+    } else {
+        (attenuate(input.min(4095) * 2, att) as u32 + offset as u32).min(4095) as u16
+    }
+}
+
+/// Whether two 12-bit values map to different 7-bit MIDI values
+pub fn midi_value_changed(prev: u16, value: u16) -> bool {
+    prev / 32 != value / 32
+}
+
+/// Transpose a 12-bit 0-10V CV value by an octave fader (-5 to +4 octaves, centered) and a
+/// semitone fader (0 to 12 semitones)
+pub fn transpose_cv(value: u16, octave: u16, semitone: u16) -> u16 {
+    let oct = (octave.min(4095) as i32 * 10 / 4095 - 5) * 410;
+    let st = (semitone.min(4095) as i32 * 12 / 4095) * 410 / 12;
+    (value as i32 + oct + st).clamp(0, 4095) as u16
+}
+
+/// Semitones of transposition for a 12-bit 1V/oct CV, rounded to the nearest semitone.
+/// Bipolar CV is centered at 0V so it can transpose down as well.
+pub fn cv_to_transpose(value: u16, bipolar: bool) -> i32 {
+    let center = if bipolar { 2048 } else { 0 };
+    let scaled = (value.min(4095) as i32 - center) * 12;
+    (scaled + 205 * scaled.signum()) / 410
+}
+
+/// 12-bit CV of a sequencer step, scaled to `range` octaves, raised by `octave` octaves and
+/// transposed by `transpose` semitones. The result still has to be quantized.
+pub fn seq_step_cv(step: u16, range: u16, octave: u16, transpose: i32) -> u16 {
+    let base = step.min(4095) as i32 * range as i32 * 410 / 4095 + octave as i32 * 410;
+    let offset = (transpose * 4095 + 60 * transpose.signum()) / 120;
+    (base + offset).clamp(0, 4095) as u16
+}
+
+/// Number of steps on a sequencer page
+pub const PAGE_STEPS: usize = 8;
+
+/// Give every step on `page` a random CV and gate. `roll` is called for a 12-bit value twice
+/// per step, first for the CV and then for the gate.
+pub fn randomize_page(
+    cv: &mut [u16],
+    gates: &mut [bool],
+    page: usize,
+    mut roll: impl FnMut() -> u16,
+) {
+    let steps = cv
+        .iter_mut()
+        .zip(gates.iter_mut())
+        .skip(page * PAGE_STEPS)
+        .take(PAGE_STEPS);
+    for (value, gate) in steps {
+        *value = roll().min(4095);
+        *gate = roll() >= 2048;
+    }
+}
+
+/// Reset the CV of every step on `page` to 0 and turn its gates off
+pub fn clear_page(cv: &mut [u16], gates: &mut [bool], page: usize) {
+    let steps = cv
+        .iter_mut()
+        .zip(gates.iter_mut())
+        .skip(page * PAGE_STEPS)
+        .take(PAGE_STEPS);
+    for (value, gate) in steps {
+        *value = 0;
+        *gate = false;
+    }
+}
+
+/// 12-bit MIDI velocity of a sequencer step without accent
+pub const STEP_VELOCITY: u16 = 3072;
+/// 12-bit MIDI velocity of an accented sequencer step
+pub const ACCENT_VELOCITY: u16 = 4095;
+
+/// Toggle the accent of `step`. An accented step is meant to be heard, so its gate is turned on.
+/// Returns whether the step is accented now.
+pub fn toggle_accent(accents: &mut [bool], gates: &mut [bool], step: usize) -> bool {
+    let Some(accent) = accents.get_mut(step) else {
+        return false;
+    };
+    *accent = !*accent;
+    if *accent {
+        if let Some(gate) = gates.get_mut(step) {
+            *gate = true;
+        }
+    }
+    *accent
+}
+
+/// 12-bit MIDI velocity of a sequencer step
+pub fn step_velocity(accent: bool) -> u16 {
+    if accent {
+        ACCENT_VELOCITY
+    } else {
+        STEP_VELOCITY
+    }
+}
+
+/// Level of a gate that shows the accent on a 0-10V output: 5V for plain steps, 10V when accented
+pub fn accent_gate_level(accent: bool) -> u16 {
+    if accent {
+        4095
+    } else {
+        2048
+    }
+}
+
+/// What a sequencer gate does at the start of a step
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StepGate {
+    Low,
+    High,
+    /// Drop the gate briefly before going high again
+    Retrigger,
+}
+
+/// Gate at the start of a step. A step that plays while the gate is still held by a legato step
+/// ties into it, unless `retrigger` is set.
+pub fn step_gate(held: bool, plays: bool, retrigger: bool) -> StepGate {
+    if !plays {
+        StepGate::Low
+    } else if held && retrigger {
+        StepGate::Retrigger
+    } else {
+        StepGate::High
+    }
+}
+
+/// What a sequencer does with its gates and MIDI notes when the clock stops
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum StopMode {
+    /// Turn all gates off and send note offs
+    #[default]
+    Release,
+    /// Leave gates and notes as they are, e.g. for drones. They are released on the next reset.
+    Hold,
+}
+
+impl StopMode {
+    pub fn from_hold(hold: bool) -> Self {
+        if hold {
+            Self::Hold
+        } else {
+            Self::Release
+        }
+    }
+
+    /// Whether a track's gate and note are left on after the clock stops, `gate` being whether
+    /// they are on now
+    pub fn keeps_gate(self, gate: bool) -> bool {
+        match self {
+            Self::Release => false,
+            Self::Hold => gate,
+        }
+    }
+}
+
+/// Tick count for a sequencer that is stepped by hand instead of by the clock. Every step
+/// jumps to the first tick of the next sequencer step.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ManualTicks {
+    steps: u32,
+}
+
+impl ManualTicks {
+    /// Tick to run the sequencer at for the next step, with `div` ticks per step
+    pub fn step(&mut self, div: u32) -> u32 {
+        let ticks = self.steps.wrapping_mul(div.max(1));
+        self.steps = self.steps.wrapping_add(1);
+        ticks
+    }
+
+    /// Start over, the next step is the first one
+    pub fn reset(&mut self) {
+        self.steps = 0;
+    }
+}
+
+/// Tick count of a sequencer track. In sync mode it follows the clock, so all tracks line up
+/// again on a reset. In free mode each track counts its own ticks and keeps going through resets,
+/// letting tracks of different lengths and divisions drift apart.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TrackClock {
+    free: bool,
+    ticks: u32,
+}
+
+impl TrackClock {
+    pub fn new(free: bool) -> Self {
+        Self { free, ticks: 0 }
+    }
+
+    /// Tick to run the track at for a clock tick, `clock_ticks` being the clock's tick count
+    pub fn tick(&mut self, clock_ticks: u32) -> u32 {
+        if !self.free {
+            return clock_ticks;
+        }
+        let ticks = self.ticks;
+        self.ticks = self.ticks.wrapping_add(1);
+        ticks
+    }
+
+    /// Called on a clock reset, only a track in free mode keeps its phase
+    pub fn reset(&mut self) {
+        if !self.free {
+            self.ticks = 0;
+        }
+    }
+}
+
+/// Tells a tap on a channel button apart from holding it to reach another fader layer. A press
+/// is a tap if no fader moved while the button was held.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ButtonTaps {
+    held: u32,
+    used: u32,
+}
+
+impl ButtonTaps {
+    pub fn press(&mut self, chan: usize) {
+        self.held |= 1 << chan;
+        self.used &= !(1 << chan);
+    }
+
+    /// A fader moved, none of the buttons held right now is a tap anymore
+    pub fn fader_moved(&mut self) {
+        self.used |= self.held;
+    }
+
+    /// Whether the press of `chan` that ends here was a tap
+    pub fn release(&mut self, chan: usize) -> bool {
+        let tap = self.held & !self.used & (1 << chan) != 0;
+        self.held &= !(1 << chan);
+        self.used &= !(1 << chan);
+        tap
+    }
+}
+
 /// Slew limiter
 pub fn slew_limiter(prev: f32, input: u16, rise_rate: u16, fall_rate: u16) -> f32 {
     let curve = Curve::Exponential;
@@ -184,10 +524,34 @@ pub fn slew_2(prev: u16, input: u16, slew: u16, snap: i32) -> u16 {
     }
 }
 
-/// Rotate a bit pattern left within a given bit width
+/// Move a DAC value towards its target, limited by the global output slew. `slew_ms` is the
+/// time a full scale jump takes and `elapsed_us` the time since the last write. A slew of 0
+/// jumps to the target right away.
+pub fn output_slew(prev: u16, target: u16, slew_ms: u16, elapsed_us: u32) -> u16 {
+    if slew_ms == 0 {
+        return target;
+    }
+    let max_step = (4095 * elapsed_us as u64 / (slew_ms as u64 * 1000)).clamp(1, 4095) as u16;
+    if target > prev {
+        prev.saturating_add(max_step).min(target)
+    } else {
+        prev.saturating_sub(max_step).max(target)
+    }
+}
+
+/// Apply an input gain and offset to a 12-bit value, clamped to the 12-bit range
+pub fn scale_input(value: u16, gain: f32, offset: i16) -> u16 {
+    (roundf(value as f32 * gain) as i32 + offset as i32).clamp(0, 4095) as u16
+}
+
+/// Rotate a bit pattern left within a given bit width (up to 32)
 pub fn euclidean_rotl(value: u32, width: u8, rotation: u8) -> u32 {
-    let rotation = rotation % width;
-    ((value << rotation) | (value >> (width - rotation))) & ((1 << width) - 1)
+    let width = width.clamp(1, 32) as u32;
+    let rotation = rotation as u32 % width;
+    // Widen so a full 32 step pattern doesn't overflow the shifts
+    let mask = (1u64 << width) - 1;
+    let value = value as u64 & mask;
+    (((value << rotation) | (value >> (width - rotation))) & mask) as u32
 }
 
 /// Return the Bjorklund/Euclidean pattern for `num_beats` in `num_steps` as a bitmask.
@@ -213,6 +577,32 @@ pub fn euclidean_at(num_steps: u8, num_beats: u8, rotation: u8, clock: u32) -> b
     (pattern & (1 << pos)) != 0
 }
 
+/// Map a 12-bit value to a Euclidean length in `1..=max_length`.
+pub fn euclidean_length_from_value(value: u16, max_length: u8) -> u8 {
+    let max_length = max_length.max(1);
+    (value.min(4095) as u32 * (max_length as u32 - 1) / 4095) as u8 + 1
+}
+
+/// Map a 12-bit value to a Euclidean fill (number of beats) in `0..=length`.
+pub fn euclidean_fill_from_value(value: u16, length: u8) -> u8 {
+    ((value.min(4095) as u32 * length as u32) / 4095) as u8
+}
+
+/// Map a 12-bit value to a Euclidean rotation in `0..length`.
+pub fn euclidean_rotation_from_value(value: u16, length: u8) -> u8 {
+    euclidean_fill_from_value(value, length).min(length.saturating_sub(1))
+}
+
+/// Map a 12-bit value to a semitone in `0..=span`, giving every semitone an equal share of the range.
+pub fn fader_to_semitone(value: u16, span: u8) -> u8 {
+    (value.min(4095) as u32 * (span as u32 + 1) / 4096) as u8
+}
+
+/// 12-bit value of a semitone above 0V in the 0-10V range (1V/oct).
+pub fn semitone_to_counts(semitone: u8) -> u16 {
+    ((semitone.min(120) as u32 * 4095 + 60) / 120) as u16
+}
+
 /// Very short slew meant to avoid clicks
 pub fn clickless(prev: u16, input: u16) -> u16 {
     // Snap threshold: if the difference is small, jump to input
@@ -222,3 +612,1154 @@ pub fn clickless(prev: u16, input: u16) -> u16 {
         ((prev as u32 * 15 + input as u32) / 16) as u16
     }
 }
+
+/// Decide whether a random gate fires for the given density.
+/// Both values are 12-bit: a density of `0` never fires and `4095` always fires.
+pub fn random_gate_fires(density: u16, roll: u16) -> bool {
+    density.min(4095) > roll.min(4094)
+}
+
+/// Decide whether an event with an 8-bit probability passes for a 12-bit die roll.
+/// A probability of `0` never passes and `255` always does.
+pub fn probability_passes(probability: u8, roll: u16) -> bool {
+    (probability as u32 * 4096 / 255) > roll.min(4095) as u32
+}
+
+/// Toss a coin for a Bernoulli gate, returning whether the (heads, tails) outputs fire.
+/// Heads fires with the 12-bit `probability` and tails is its complement.
+pub fn bernoulli_gate(probability: u16, roll: u16) -> (bool, bool) {
+    let heads = random_gate_fires(probability, roll);
+    (heads, !heads)
+}
+
+/// Return a random gate pattern of `num_steps` (up to 32) as a bitmask, rolling once per step.
+/// Bit N is set if step N fires.
+pub fn random_gate_pattern(num_steps: u8, density: u16, mut roll: impl FnMut() -> u16) -> u32 {
+    (0..num_steps.min(32)).fold(0, |pattern, step| {
+        if random_gate_fires(density, roll()) {
+            pattern | (1 << step)
+        } else {
+            pattern
+        }
+    })
+}
+
+/// How far past the edge of a detent the fader has to travel to snap to the next one
+pub const DETENT_HYSTERESIS: u16 = 48;
+
+/// Snap a 12-bit fader value to one of `steps` detents, each taking an equal share of the
+/// travel. The fader has to move `DETENT_HYSTERESIS` past the edge of the `current` detent
+/// to leave it, so it doesn't flicker between two detents.
+pub fn snap_detent(value: u16, steps: usize, current: usize) -> usize {
+    let steps = steps.max(1);
+    let value = value.min(4095) as usize;
+    let zone = value * steps / 4096;
+    if current >= steps || zone == current {
+        return zone;
+    }
+    let lower = current * 4096 / steps;
+    let upper = (current + 1) * 4096 / steps;
+    let hysteresis = DETENT_HYSTERESIS as usize;
+    if value + hysteresis >= lower && value < upper + hysteresis {
+        current
+    } else {
+        zone
+    }
+}
+
+/// 12-bit value of a control change message for `cc`
+pub fn match_cc(message: &MidiMessage, cc: MidiCc) -> Option<u16> {
+    match message {
+        MidiMessage::Controller { controller, value }
+            if controller.as_int() as u16 == cc.as_u16() =>
+        {
+            Some(scale_bits_7_12(*value))
+        }
+        _ => None,
+    }
+}
+
+/// Note and 12-bit velocity of a note on message. Note ons with velocity 0 are note offs.
+pub fn match_note_on(message: &MidiMessage) -> Option<(MidiNote, u16)> {
+    match NoteEvent::from_message(message)? {
+        NoteEvent::On { note, vel } => Some((note, vel)),
+        NoteEvent::Off { .. } => None,
+    }
+}
+
+/// 12-bit value of detent `index` out of `steps`, from 0 for the first to 4095 for the last
+pub fn detent_value(index: usize, steps: usize) -> u16 {
+    if steps <= 1 {
+        return 0;
+    }
+    (index.min(steps - 1) * 4095 / (steps - 1)) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use midly::num::u14;
+
+    #[test]
+    fn split_unsigned_value_meter_points() {
+        // Top LED above the center, bottom LED below, both dark at the center
+        assert_eq!(split_unsigned_value(0), [0, 255]);
+        assert_eq!(split_unsigned_value(1024), [0, 127]);
+        assert_eq!(split_unsigned_value(2047), [0, 0]);
+        assert_eq!(split_unsigned_value(2048), [0, 0]);
+        assert_eq!(split_unsigned_value(3071), [128, 0]);
+        assert_eq!(split_unsigned_value(4095), [255, 0]);
+        // Out of range values are shown as full scale
+        assert_eq!(split_unsigned_value(u16::MAX), [255, 0]);
+    }
+
+    #[test]
+    fn split_unsigned_value_is_monotonic_outwards() {
+        let mut last = [0, 255];
+        for value in 0..=4095 {
+            let [top, bottom] = split_unsigned_value(value);
+            assert!(top == 0 || bottom == 0, "{value} lights both LEDs");
+            assert!(bottom <= last[1] && top >= last[0], "{value}");
+            last = [top, bottom];
+        }
+    }
+
+    fn midi_stream() -> [MidiMessage; 7] {
+        [
+            MidiMessage::NoteOff {
+                key: u7::new(60),
+                vel: u7::new(0),
+            },
+            MidiMessage::Controller {
+                controller: u7::new(1),
+                value: u7::new(64),
+            },
+            MidiMessage::NoteOn {
+                key: u7::new(62),
+                vel: u7::new(0),
+            },
+            MidiMessage::PitchBend {
+                bend: midly::PitchBend(u14::new(8192)),
+            },
+            MidiMessage::Controller {
+                controller: u7::new(74),
+                value: u7::new(127),
+            },
+            MidiMessage::NoteOn {
+                key: u7::new(64),
+                vel: u7::new(127),
+            },
+            MidiMessage::Controller {
+                controller: u7::new(1),
+                value: u7::new(0),
+            },
+        ]
+    }
+
+    #[test]
+    fn match_cc_picks_its_controller() {
+        let stream = midi_stream();
+        let first = |cc: u8| {
+            stream
+                .iter()
+                .find_map(|msg| match_cc(msg, MidiCc::from(cc)))
+        };
+        assert_eq!(first(74), Some(4095));
+        assert_eq!(first(1), Some(2063));
+        assert_eq!(first(7), None);
+        // NRPN numbers past the 7-bit range never match a plain CC
+        assert_eq!(
+            stream
+                .iter()
+                .find_map(|msg| match_cc(msg, MidiCc::from(128 + 74_u16))),
+            None
+        );
+    }
+
+    #[test]
+    fn match_note_on_skips_note_offs() {
+        let stream = midi_stream();
+        let notes: heapless::Vec<(MidiNote, u16), 8> =
+            stream.iter().filter_map(match_note_on).collect();
+        assert_eq!(notes.as_slice(), &[(MidiNote::from(64_u8), 4095)]);
+    }
+
+    #[test]
+    fn clock_duration_to_bpm_from_intervals() {
+        // 120 BPM at 24 ppqn is a tick every 20.833ms
+        let bpm = clock_duration_to_bpm(Duration::from_micros(20_833), 24).unwrap();
+        assert!((bpm - 120.0).abs() < 0.01);
+        // One pulse per quarter note
+        let bpm = clock_duration_to_bpm(Duration::from_millis(500), 1).unwrap();
+        assert!((bpm - 120.0).abs() < 0.01);
+        let bpm = clock_duration_to_bpm(Duration::from_millis(10), 24).unwrap();
+        assert!((bpm - 250.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn clock_duration_to_bpm_round_trips() {
+        for bpm in [30.0, 45.5, 90.0, 120.0, 174.0, 300.0] {
+            for ppqn in [1, 4, 24, 48] {
+                let tick = bpm_to_clock_duration(bpm, ppqn);
+                let measured = clock_duration_to_bpm(tick, ppqn).unwrap();
+                assert!((measured - bpm).abs() < 0.05, "{bpm} {ppqn} {measured}");
+            }
+        }
+    }
+
+    #[test]
+    fn clock_duration_to_bpm_needs_an_interval() {
+        assert_eq!(clock_duration_to_bpm(Duration::from_ticks(0), 24), None);
+        assert_eq!(clock_duration_to_bpm(Duration::from_millis(20), 0), None);
+    }
+
+    #[test]
+    fn fader_to_semitone_covers_span() {
+        for span in [1, 12, 24, 60, 120] {
+            assert_eq!(fader_to_semitone(0, span), 0);
+            assert_eq!(fader_to_semitone(4095, span), span);
+            let mut prev = 0;
+            for value in 0..=4095 {
+                let semitone = fader_to_semitone(value, span);
+                assert!(semitone >= prev && semitone - prev <= 1);
+                prev = semitone;
+            }
+        }
+    }
+
+    #[test]
+    fn fader_to_semitone_zones_are_even() {
+        // 25 semitones (two octaves and the top note) share the fader equally
+        let mut counts = [0_u32; 25];
+        for value in 0..4096 {
+            counts[fader_to_semitone(value, 24) as usize] += 1;
+        }
+        let min = counts.iter().min().unwrap();
+        let max = counts.iter().max().unwrap();
+        assert!(max - min <= 1);
+    }
+
+    #[test]
+    fn semitone_counts_quantize_to_the_same_note() {
+        use crate::{
+            quantizer::{Quantizer, QuantizerState},
+            Range,
+        };
+        let quantizer = Quantizer::default();
+        for semitone in 0..=120 {
+            let mut state = QuantizerState::default();
+            let pitch = quantizer.get_quantized_note(
+                &mut state,
+                semitone_to_counts(semitone),
+                Range::_0_10V,
+            );
+            assert_eq!(pitch.octave as u8 * 12 + pitch.note as u8, semitone);
+        }
+        assert_eq!(semitone_to_counts(0), 0);
+        assert_eq!(semitone_to_counts(120), 4095);
+        assert_eq!(semitone_to_counts(200), 4095);
+    }
+
+    fn assert_near(value: u16, expected: u16) {
+        assert!(
+            (value as i32 - expected as i32).abs() <= 2,
+            "{value} is not close to {expected}"
+        );
+    }
+
+    #[test]
+    fn offset_attenuvert_unity_gain() {
+        for input in [0, 1000, 2047, 3000, 4094] {
+            assert_near(offset_attenuvert(input, 3071, 2047), input);
+        }
+    }
+
+    #[test]
+    fn offset_attenuvert_zero_gain_outputs_offset() {
+        for input in [0, 1000, 2047, 3000, 4095] {
+            assert_near(offset_attenuvert(input, 2047, 2047), 2047);
+        }
+        // Offset is doubled like the signal
+        assert_near(offset_attenuvert(2047, 2047, 2559), 3071);
+        assert_near(offset_attenuvert(2047, 2047, 1535), 1023);
+        assert_eq!(offset_attenuvert(2047, 2047, 4095), 4094);
+        assert_eq!(offset_attenuvert(2047, 2047, 0), 0);
+    }
+
+    #[test]
+    fn offset_attenuvert_inverts() {
+        assert_near(offset_attenuvert(3047, 1023, 2047), 1047);
+        assert_near(offset_attenuvert(1047, 1023, 2047), 3047);
+    }
+
+    #[test]
+    fn offset_attenuvert_double_gain_clamps() {
+        assert_near(offset_attenuvert(2547, 4095, 2047), 3047);
+        assert_eq!(offset_attenuvert(4095, 4095, 2047), 4094);
+        assert_eq!(offset_attenuvert(0, 4095, 2047), 0);
+        assert_eq!(offset_attenuvert(4095, 3071, 4095), 4094);
+        assert_eq!(offset_attenuvert(0, 3071, 0), 0);
+    }
+
+    #[test]
+    fn attenuvert_cv_unity_gain_passes_through() {
+        for bipolar in [false, true] {
+            for input in [0, 1000, 2048, 3000, 4095] {
+                assert_eq!(attenuvert_cv(input, 4095, 2048, false, bipolar), input);
+            }
+        }
+    }
+
+    #[test]
+    fn attenuvert_cv_gain_scales_towards_zero_volts() {
+        // 0-10V: 8V at half gain is 4V, no gain is 0V
+        assert_near(attenuvert_cv(3276, 2048, 2048, false, false), 1638);
+        assert_eq!(attenuvert_cv(3276, 0, 2048, false, false), 0);
+        // -5-5V: 4V at half gain is 2V, -4V is -2V, no gain is 0V
+        assert_near(attenuvert_cv(3686, 2048, 2048, false, true), 2867);
+        assert_near(attenuvert_cv(410, 2048, 2048, false, true), 1229);
+        assert_eq!(attenuvert_cv(3686, 0, 2048, false, true), 2048);
+    }
+
+    #[test]
+    fn attenuvert_cv_inverts() {
+        // Mirrored in the 0-10V range, around 0V in the -5-5V range
+        assert_eq!(attenuvert_cv(1000, 4095, 2048, true, false), 3095);
+        assert_near(attenuvert_cv(3686, 4095, 2048, true, true), 410);
+        assert_near(attenuvert_cv(3686, 2048, 2048, true, true), 1229);
+    }
+
+    #[test]
+    fn attenuvert_cv_offset_shifts_and_clamps() {
+        for bipolar in [false, true] {
+            assert_eq!(attenuvert_cv(1000, 4095, 2548, false, bipolar), 1500);
+            assert_eq!(attenuvert_cv(1000, 4095, 1548, false, bipolar), 500);
+            assert_eq!(attenuvert_cv(4000, 4095, 4095, false, bipolar), 4095);
+            assert_eq!(attenuvert_cv(500, 4095, 0, false, bipolar), 0);
+        }
+        // Without gain the offset sets the output on its own
+        assert_eq!(attenuvert_cv(3000, 0, 3048, false, false), 1000);
+        assert_eq!(attenuvert_cv(3000, 0, 3048, false, true), 3048);
+    }
+
+    #[test]
+    fn euclidean_rotation_of_full_width_pattern() {
+        assert_eq!(euclidean_rotl(0x8000_0001, 32, 1), 0x0000_0003);
+        assert_eq!(euclidean_rotl(0x8000_0001, 32, 32), 0x8000_0001);
+        assert_eq!(euclidean_rotl(0b1001, 4, 1), 0b0011);
+        let pattern = euclidean_pattern(32, 5, 7, 0);
+        assert_eq!(pattern.count_ones(), 5);
+        assert_eq!(pattern, euclidean_pattern(32, 5, 0, 0).rotate_left(7));
+    }
+
+    #[test]
+    fn euclidean_length_covers_full_range() {
+        assert_eq!(euclidean_length_from_value(0, 32), 1);
+        assert_eq!(euclidean_length_from_value(4095, 32), 32);
+        assert_eq!(euclidean_length_from_value(u16::MAX, 32), 32);
+        assert_eq!(euclidean_length_from_value(2048, 32), 16);
+        assert_eq!(euclidean_length_from_value(4095, 0), 1);
+    }
+
+    #[test]
+    fn euclidean_length_is_monotonic() {
+        let mut prev = 0;
+        for value in 0..=4095 {
+            let length = euclidean_length_from_value(value, 32);
+            assert!(length >= prev);
+            assert!((1..=32).contains(&length));
+            prev = length;
+        }
+    }
+
+    #[test]
+    fn euclidean_fill_is_bounded_by_length() {
+        for length in 1..=32 {
+            assert_eq!(euclidean_fill_from_value(0, length), 0);
+            assert_eq!(euclidean_fill_from_value(4095, length), length);
+            for value in (0..=4095).step_by(7) {
+                assert!(euclidean_fill_from_value(value, length) <= length);
+            }
+        }
+    }
+
+    #[test]
+    fn euclidean_rotation_stays_below_length() {
+        for length in 1..=32 {
+            assert_eq!(euclidean_rotation_from_value(0, length), 0);
+            assert_eq!(euclidean_rotation_from_value(4095, length), length - 1);
+        }
+        assert_eq!(euclidean_rotation_from_value(4095, 0), 0);
+    }
+
+    #[test]
+    fn euclidean_sixteen_step_fader_mapping() {
+        // FP Grids and Euclid map a fader to 1..16 steps as `value * 15 / 4095 + 1`
+        for value in (0..=4095).step_by(7) {
+            let expected = (value as u32 * 15 / 4095) as u8 + 1;
+            assert_eq!(euclidean_length_from_value(value, 16), expected);
+        }
+        assert_eq!(euclidean_length_from_value(0, 16), 1);
+        assert_eq!(euclidean_length_from_value(272, 16), 1);
+        assert_eq!(euclidean_length_from_value(273, 16), 2);
+        assert_eq!(euclidean_length_from_value(4095, 16), 16);
+    }
+
+    #[test]
+    fn euclidean_fill_follows_length() {
+        assert_eq!(euclidean_fill_from_value(2048, 16), 8);
+        assert_eq!(euclidean_fill_from_value(2048, 8), 4);
+        assert_eq!(euclidean_fill_from_value(4095, 16), 16);
+        assert_eq!(euclidean_fill_from_value(4094, 16), 15);
+        assert_eq!(euclidean_fill_from_value(4095, 1), 1);
+        assert_eq!(euclidean_fill_from_value(4094, 1), 0);
+    }
+
+    #[test]
+    fn random_gate_density_bounds() {
+        for roll in [0, 1, 2047, 4094, 4095, u16::MAX] {
+            assert!(!random_gate_fires(0, roll));
+            assert!(random_gate_fires(4095, roll));
+        }
+    }
+
+    #[test]
+    fn random_gate_fires_below_density() {
+        assert!(random_gate_fires(2048, 0));
+        assert!(random_gate_fires(2048, 2047));
+        assert!(!random_gate_fires(2048, 2048));
+        assert!(!random_gate_fires(2048, 4095));
+    }
+
+    #[test]
+    fn random_gate_rate_follows_density() {
+        // Sweeping every possible roll, the number of hits equals the density
+        for density in [0, 1, 1024, 2048, 3000, 4094] {
+            let hits = (0..4096)
+                .filter(|&roll| random_gate_fires(density, roll))
+                .count();
+            assert_eq!(hits, density as usize);
+        }
+    }
+
+    #[test]
+    fn random_gate_pattern_uses_one_roll_per_step() {
+        let rolls = [0, 4095, 100, 3000, 2000, 4095, 0, 1];
+        let mut iter = rolls.iter().copied();
+        let pattern = random_gate_pattern(8, 2048, || iter.next().unwrap());
+        assert_eq!(pattern, 0b1101_0101);
+        assert!(iter.next().is_none());
+    }
+
+    #[test]
+    fn random_gate_pattern_extremes() {
+        assert_eq!(random_gate_pattern(16, 0, || 0), 0);
+        assert_eq!(random_gate_pattern(16, 4095, || 4095), 0xFFFF);
+        assert_eq!(random_gate_pattern(40, 4095, || 0), u32::MAX);
+    }
+
+    #[test]
+    fn cv_to_cc_unipolar_scaling() {
+        // Full attenuation level doubles the 0-10V input into the full CC range at 5V
+        assert_eq!(cv_to_cc_value(0, 4095, 0, false), 0);
+        assert_eq!(cv_to_cc_value(1024, 4095, 0, false), 2048);
+        assert_eq!(cv_to_cc_value(2048, 4095, 0, false), 4095);
+        assert_eq!(cv_to_cc_value(4095, 4095, 0, false), 4095);
+        // Attenuation scales, offset shifts
+        assert_eq!(cv_to_cc_value(1024, 0, 0, false), 0);
+        assert_eq!(cv_to_cc_value(0, 4095, 1000, false), 1000);
+        assert_eq!(cv_to_cc_value(4095, 4095, 4095, false), 4095);
+    }
+
+    #[test]
+    fn cv_to_cc_bipolar_scaling() {
+        // Centered offset and full level pass the input through
+        assert_eq!(cv_to_cc_value(2048, 4095, 2047, true), 2048);
+        assert_eq!(cv_to_cc_value(0, 4095, 2047, true), 0);
+        assert_eq!(cv_to_cc_value(4095, 4095, 2047, true), 4095);
+        // No level holds the center
+        assert_eq!(cv_to_cc_value(0, 0, 2047, true), 2048);
+        // Offset shifts and clamps
+        assert_eq!(cv_to_cc_value(0, 4095, 0, true), 0);
+        assert_eq!(cv_to_cc_value(4095, 4095, 4095, true), 4095);
+    }
+
+    #[test]
+    fn midi_value_change_detection() {
+        assert!(!midi_value_changed(0, 31));
+        assert!(midi_value_changed(31, 32));
+        assert!(!midi_value_changed(4064, 4095));
+        assert!(midi_value_changed(4095, 0));
+        // Every CC value is reachable exactly once over a full sweep
+        let changes = (1..4096).filter(|&v| midi_value_changed(v - 1, v)).count();
+        assert_eq!(changes, 127);
+    }
+
+    #[test]
+    fn transpose_cv_by_octave_and_semitone() {
+        // Octave fader at the bottom is -5 octaves, near the top is +5
+        assert_eq!(transpose_cv(2050, 0, 0), 0);
+        assert_eq!(transpose_cv(2050, 2048, 0), 2050);
+        assert_eq!(transpose_cv(2050, 2457, 0), 2460);
+        assert_eq!(transpose_cv(2050, 4095, 0), 4095);
+        // Semitone fader spans one octave
+        assert_eq!(transpose_cv(0, 2048, 4095), 410);
+        assert_eq!(transpose_cv(0, 2048, 342), 34);
+        assert_eq!(transpose_cv(0, 0, 4095), 0);
+    }
+
+    #[test]
+    fn transpose_rounds_to_semitones() {
+        assert_eq!(cv_to_transpose(0, false), 0);
+        assert_eq!(cv_to_transpose(410, false), 12);
+        assert_eq!(cv_to_transpose(4095, false), 120);
+        for semitone in 0..=120 {
+            assert_eq!(
+                cv_to_transpose(semitone_to_counts(semitone), false),
+                semitone as i32
+            );
+        }
+        // Just off a semitone still rounds to it
+        assert_eq!(cv_to_transpose(semitone_to_counts(7) + 10, false), 7);
+        assert_eq!(cv_to_transpose(semitone_to_counts(7) - 10, false), 7);
+        // Bipolar CV transposes around 0V
+        assert_eq!(cv_to_transpose(2048, true), 0);
+        assert_eq!(cv_to_transpose(2048 - 410, true), -12);
+        assert_eq!(cv_to_transpose(2048 + 410, true), 12);
+        assert_eq!(cv_to_transpose(2048 - 34, true), -1);
+        assert_eq!(cv_to_transpose(0, true), -60);
+        assert_eq!(cv_to_transpose(4095, true), 60);
+    }
+
+    #[test]
+    fn seq_step_cv_without_transpose() {
+        assert_eq!(seq_step_cv(0, 1, 0, 0), 0);
+        assert_eq!(seq_step_cv(4095, 1, 0, 0), 410);
+        assert_eq!(seq_step_cv(4095, 3, 0, 0), 1230);
+        assert_eq!(seq_step_cv(4095, 3, 2, 0), 2050);
+        assert_eq!(seq_step_cv(4095, 10, 4, 0), 4095);
+    }
+
+    #[test]
+    fn seq_step_transpose_then_quantize() {
+        use crate::{
+            quantizer::{Quantizer, QuantizerState},
+            Key, Note, Range,
+        };
+
+        let quantize = |quantizer: &Quantizer, value: u16| {
+            let mut state = QuantizerState::default();
+            let pitch = quantizer.get_quantized_note(&mut state, value, Range::_0_10V);
+            pitch.octave as i32 * 12 + pitch.note as i32
+        };
+
+        // Steps on the semitones of a one octave range
+        let steps = (0..12).map(|semitone| semitone * 4095 / 12);
+
+        // Chromatic: every step lands exactly `transpose` semitones higher
+        let chromatic = Quantizer::default();
+        for step in steps {
+            let plain = quantize(&chromatic, seq_step_cv(step, 1, 2, 0));
+            for transpose in [-24, -5, -1, 1, 5, 7, 12] {
+                let transposed = quantize(&chromatic, seq_step_cv(step, 1, 2, transpose));
+                assert_eq!(transposed, plain + transpose, "step {step} by {transpose}");
+            }
+        }
+
+        // The offset is added before quantizing, so the result stays in the scale
+        let mut major = Quantizer::default();
+        major.set_scale(Key::Ionian, Note::C);
+        let in_scale = [0, 2, 4, 5, 7, 9, 11];
+        for step in (0..=4095).step_by(97) {
+            for transpose in [-3, 1, 2, 6] {
+                let note = quantize(&major, seq_step_cv(step, 3, 2, transpose));
+                assert!(in_scale.contains(&note.rem_euclid(12)), "{note}");
+            }
+        }
+        // Octave transpositions keep the melody
+        for step in in_scale.map(|semitone| semitone as u16 * 4095 / 12) {
+            let plain = quantize(&major, seq_step_cv(step, 1, 2, 0));
+            assert_eq!(quantize(&major, seq_step_cv(step, 1, 2, 12)), plain + 12);
+            assert_eq!(quantize(&major, seq_step_cv(step, 1, 2, -12)), plain - 12);
+        }
+
+        // CV from the transpose jack
+        let fifth = cv_to_transpose(semitone_to_counts(7), false);
+        assert_eq!(
+            quantize(&chromatic, seq_step_cv(0, 1, 2, fifth)),
+            quantize(&chromatic, seq_step_cv(0, 1, 2, 0)) + 7
+        );
+
+        // Transposing past the ends clamps to the range
+        assert_eq!(seq_step_cv(4095, 3, 8, 12), 4095);
+        assert_eq!(seq_step_cv(0, 3, 0, -12), 0);
+    }
+
+    #[test]
+    fn randomize_page_only_touches_its_steps() {
+        let mut cv = [1000; 64];
+        let mut gates = [true; 64];
+        let mut rolls = [4095, 0, 2048, 2048, 5000, 2047].iter().copied().cycle();
+        randomize_page(&mut cv, &mut gates, 3, || rolls.next().unwrap());
+
+        for step in 0..64 {
+            if (24..32).contains(&step) {
+                continue;
+            }
+            assert_eq!(cv[step], 1000);
+            assert!(gates[step]);
+        }
+        // Two rolls per step, out of range rolls are clamped
+        assert_eq!(cv[24..27], [4095, 2048, 4095]);
+        assert_eq!(gates[24..27], [false, true, false]);
+    }
+
+    #[test]
+    fn randomize_page_uses_two_rolls_per_step() {
+        let mut cv = [0; 64];
+        let mut gates = [false; 64];
+        let mut count = 0;
+        randomize_page(&mut cv, &mut gates, 0, || {
+            count += 1;
+            count * 100
+        });
+        assert_eq!(count, 2 * PAGE_STEPS as u16);
+        assert_eq!(
+            cv[..PAGE_STEPS],
+            [100, 300, 500, 700, 900, 1100, 1300, 1500]
+        );
+        assert_eq!(gates[..4], [false, false, false, false]);
+        // Pages past the end of the sequence are ignored
+        randomize_page(&mut cv, &mut gates, 8, || panic!("no steps on page 8"));
+    }
+
+    #[test]
+    fn clear_page_resets_its_steps() {
+        let mut cv = [3000; 64];
+        let mut gates = [true; 64];
+        clear_page(&mut cv, &mut gates, 1);
+        assert!(cv[8..16].iter().all(|&v| v == 0));
+        assert!(gates[8..16].iter().all(|&g| !g));
+        assert!(cv[..8].iter().chain(&cv[16..]).all(|&v| v == 3000));
+        assert!(gates[..8].iter().chain(&gates[16..]).all(|&g| g));
+        clear_page(&mut cv, &mut gates, 8);
+        assert_eq!(cv.iter().filter(|&&v| v == 0).count(), 8);
+    }
+
+    #[test]
+    fn toggle_accent_turns_the_gate_on() {
+        let mut accents = [false; 64];
+        let mut gates = [false; 64];
+        assert!(toggle_accent(&mut accents, &mut gates, 9));
+        assert!(accents[9] && gates[9]);
+        // Removing the accent leaves the gate alone
+        assert!(!toggle_accent(&mut accents, &mut gates, 9));
+        assert!(!accents[9] && gates[9]);
+        assert_eq!(accents.iter().filter(|&&a| a).count(), 0);
+        assert_eq!(gates.iter().filter(|&&g| g).count(), 1);
+        // Steps past the end of the sequence are ignored
+        assert!(!toggle_accent(&mut accents, &mut gates, 64));
+    }
+
+    #[test]
+    fn accent_raises_velocity_and_gate() {
+        assert_eq!(step_velocity(true), ACCENT_VELOCITY);
+        assert!(step_velocity(false) < step_velocity(true));
+        // Plain steps are still clearly audible, at MIDI velocity 95
+        assert_eq!(scale_bits_12_7(step_velocity(false)), u7::from(95));
+        assert_eq!(scale_bits_12_7(step_velocity(true)), u7::from(127));
+        assert_eq!(accent_gate_level(false), 2048);
+        assert_eq!(accent_gate_level(true), 4095);
+    }
+
+    const CURVES: [Curve; 3] = [Curve::Linear, Curve::Logarithmic, Curve::Exponential];
+
+    #[test]
+    fn curve_fader_linear_is_unchanged() {
+        for value in 0..=4095 {
+            assert_eq!(curve_fader(Curve::Linear, value, false), value);
+        }
+        assert_eq!(curve_fader(Curve::Linear, 0, true), 0);
+        assert_eq!(curve_fader(Curve::Linear, 1024, true), 1024);
+        assert_eq!(curve_fader(Curve::Linear, 2047, true), 2047);
+        assert_eq!(curve_fader(Curve::Linear, 3071, true), 3071);
+        assert_eq!(curve_fader(Curve::Linear, 4095, true), 4094);
+    }
+
+    #[test]
+    fn curve_fader_covers_the_range() {
+        for curve in CURVES {
+            assert_eq!(curve_fader(curve, 0, false), 0);
+            assert!(curve_fader(curve, 4095, false) >= 4094);
+            assert!(curve_fader(curve, 0, true) <= 1);
+            assert_eq!(curve_fader(curve, 2047, true), 2047);
+            assert!(curve_fader(curve, 4095, true) >= 4093);
+            // Out of range fader values are clamped
+            assert_eq!(
+                curve_fader(curve, u16::MAX, false),
+                curve_fader(curve, 4095, false)
+            );
+        }
+    }
+
+    #[test]
+    fn curve_fader_is_monotonic() {
+        for curve in CURVES {
+            for bipolar in [false, true] {
+                let mut prev = 0;
+                for value in 0..=4095 {
+                    let out = curve_fader(curve, value, bipolar);
+                    assert!(out >= prev, "{curve:?} at {value}");
+                    prev = out;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn curve_fader_shapes_the_travel() {
+        let exp = curve_fader(Curve::Exponential, 2048, false);
+        let log = curve_fader(Curve::Logarithmic, 2048, false);
+        assert!(exp < 2048);
+        assert!(log > 2048);
+        // Bipolar curves bend away from the center on both sides
+        for offset in [256, 1024, 1800] {
+            let up = curve_fader(Curve::Exponential, 2047 + offset, true) - 2047;
+            let down = 2047 - curve_fader(Curve::Exponential, 2047 - offset, true);
+            assert_eq!(up, down);
+            assert!(up < offset);
+        }
+    }
+
+    #[test]
+    fn bernoulli_gate_fires_exactly_one_output() {
+        for probability in [0, 1, 2048, 4094, 4095] {
+            for roll in (0..4096).step_by(7) {
+                let (heads, tails) = bernoulli_gate(probability, roll);
+                assert_ne!(heads, tails);
+            }
+        }
+    }
+
+    #[test]
+    fn bernoulli_gate_rate_follows_probability() {
+        for probability in [0, 1, 1024, 2048, 3000, 4094] {
+            let (heads, tails) = (0..4096).fold((0, 0), |(h, t), roll| {
+                let (heads, tails) = bernoulli_gate(probability, roll);
+                (h + heads as usize, t + tails as usize)
+            });
+            assert_eq!(heads, probability as usize);
+            assert_eq!(tails, 4096 - probability as usize);
+        }
+    }
+
+    #[test]
+    fn bernoulli_gate_extremes() {
+        for roll in 0..4096 {
+            assert_eq!(bernoulli_gate(0, roll), (false, true));
+            assert_eq!(bernoulli_gate(4095, roll), (true, false));
+        }
+    }
+
+    #[test]
+    fn probability_extremes() {
+        for roll in 0..4096 {
+            assert!(!probability_passes(0, roll));
+            assert!(probability_passes(255, roll));
+        }
+        assert!(probability_passes(255, u16::MAX));
+    }
+
+    #[test]
+    fn probability_rate_follows_value() {
+        for probability in [1, 64, 128, 192, 254] {
+            let passes = (0..4096)
+                .filter(|&roll| probability_passes(probability, roll))
+                .count();
+            let expected = probability as usize * 4096 / 255;
+            assert_eq!(passes, expected);
+        }
+        // Halfway passes half of the rolls
+        let half = (0..4096)
+            .filter(|&roll| probability_passes(128, roll))
+            .count();
+        assert!((2040..2070).contains(&half));
+        assert!(probability_passes(128, 2000));
+        assert!(!probability_passes(128, 2100));
+    }
+
+    #[test]
+    fn test_snap_detent_zones() {
+        // Coming from nowhere, every detent takes an equal share of the travel
+        assert_eq!(snap_detent(0, 4, usize::MAX), 0);
+        assert_eq!(snap_detent(1023, 4, usize::MAX), 0);
+        assert_eq!(snap_detent(1024, 4, usize::MAX), 1);
+        assert_eq!(snap_detent(3072, 4, usize::MAX), 3);
+        assert_eq!(snap_detent(4095, 4, usize::MAX), 3);
+        assert_eq!(snap_detent(u16::MAX, 4, usize::MAX), 3);
+        for steps in 1..=16 {
+            let mut prev = 0;
+            for value in 0..4096 {
+                let detent = snap_detent(value, steps, usize::MAX);
+                assert!(detent < steps);
+                assert!(detent == prev || detent == prev + 1);
+                prev = detent;
+            }
+            assert_eq!(prev, steps - 1);
+        }
+        // A single detent can't be left
+        assert_eq!(snap_detent(4095, 1, 0), 0);
+        assert_eq!(snap_detent(4095, 0, 0), 0);
+    }
+
+    #[test]
+    fn test_snap_detent_hysteresis() {
+        // Detent 1 of 4 spans 1024..2048
+        assert_eq!(snap_detent(2048, 4, 1), 1);
+        assert_eq!(snap_detent(2048 + DETENT_HYSTERESIS - 1, 4, 1), 1);
+        assert_eq!(snap_detent(2048 + DETENT_HYSTERESIS, 4, 1), 2);
+        assert_eq!(snap_detent(1024 - DETENT_HYSTERESIS, 4, 1), 1);
+        assert_eq!(snap_detent(1024 - DETENT_HYSTERESIS - 1, 4, 1), 0);
+        // Moving back needs the same travel past the edge
+        assert_eq!(snap_detent(2047, 4, 2), 2);
+        assert_eq!(snap_detent(2048 - DETENT_HYSTERESIS, 4, 2), 2);
+        assert_eq!(snap_detent(2048 - DETENT_HYSTERESIS - 1, 4, 2), 1);
+        // Jumps over several detents land on the fader position
+        assert_eq!(snap_detent(4095, 4, 0), 3);
+        assert_eq!(snap_detent(0, 4, 3), 0);
+    }
+
+    #[test]
+    fn test_detent_value() {
+        assert_eq!(detent_value(0, 5), 0);
+        assert_eq!(detent_value(1, 5), 1023);
+        assert_eq!(detent_value(2, 5), 2047);
+        assert_eq!(detent_value(4, 5), 4095);
+        assert_eq!(detent_value(9, 5), 4095);
+        assert_eq!(detent_value(1, 2), 4095);
+        assert_eq!(detent_value(3, 1), 0);
+        assert_eq!(detent_value(0, 0), 0);
+        // Detents are spread evenly
+        for steps in 2..=16 {
+            let gap = 4095 / (steps as u16 - 1);
+            for index in 1..steps {
+                let diff = detent_value(index, steps) - detent_value(index - 1, steps);
+                assert!(diff == gap || diff == gap + 1, "{steps} {index}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_clickless_converges_without_overshoot() {
+        for (start, target) in [(0, 4095), (4095, 0), (2047, 2100), (1000, 990), (300, 300)] {
+            let mut value = start;
+            let mut ticks = 0;
+            while value != target {
+                let next = clickless(value, target);
+                // Always moving towards the target, never past it
+                if start < target {
+                    assert!(next > value && next <= target, "{start} {target}");
+                } else {
+                    assert!(next < value && next >= target, "{start} {target}");
+                }
+                value = next;
+                ticks += 1;
+                assert!(ticks <= 100, "{start} {target}");
+            }
+            // And staying there
+            assert_eq!(clickless(value, target), target);
+        }
+    }
+
+    #[test]
+    fn test_swung_offset_straight() {
+        let t = bpm_to_clock_duration(120.0, 24);
+        for i in 0..2 * SWING_HALF_INTERVAL {
+            assert_eq!(swung_offset(i, t, 0), t * i);
+        }
+    }
+
+    #[test]
+    fn test_swung_offset_moves_second_sixteenth() {
+        let t = Duration::from_micros(20_000);
+        // The second 16th note starts at 120ms when straight
+        for (swing, start) in [(10, 144_000), (25, 180_000), (-10, 96_000), (-35, 36_000)] {
+            assert_eq!(swung_offset(0, t, swing), Duration::from_ticks(0));
+            assert_eq!(
+                swung_offset(SWING_HALF_INTERVAL, t, swing),
+                Duration::from_micros(start),
+                "{swing}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_swung_offset_spreads_ticks_evenly() {
+        let t = Duration::from_micros(20_000);
+        // With a swing of 25 the first 16th is 180ms long and the second one 60ms
+        let offsets: [u64; 12] =
+            core::array::from_fn(|i| swung_offset(i as u32, t, 25).as_micros());
+        for i in 0..6 {
+            assert_eq!(offsets[i], i as u64 * 30_000);
+            assert_eq!(offsets[i + 6], 180_000 + i as u64 * 10_000);
+        }
+    }
+
+    #[test]
+    fn test_swung_offset_stays_in_window() {
+        for bpm in [60.0, 120.0, 200.0, 300.0] {
+            let t = bpm_to_clock_duration(bpm, 24);
+            let window = t * (2 * SWING_HALF_INTERVAL);
+            for swing in -35..=35 {
+                let offsets: [Duration; 12] =
+                    core::array::from_fn(|i| swung_offset(i as u32, t, swing));
+                // In order, and leaving room before the next window starts
+                assert!(offsets.windows(2).all(|w| w[0] < w[1]), "{bpm} {swing}");
+                assert!(
+                    offsets[11] + Duration::from_ticks(500) <= window,
+                    "{bpm} {swing}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_swing_window_reset_schedules_no_ticks_in_the_past() {
+        let t = Duration::from_micros(20_000);
+        let delay = Duration::from_millis(2);
+        for swing in [-35, 0, 25, 35] {
+            let window_start = Instant::from_ticks(0);
+            // Reset pulse between the 5th and 6th tick of the window
+            let now = window_start + swung_offset(5, t, swing) + Duration::from_micros(100);
+            assert!(window_start + swung_offset(0, t, swing) < now);
+
+            let window_start = restart_swing_window(now, delay);
+            assert_eq!(window_start + swung_offset(0, t, swing), now + delay);
+            let ticks: [Instant; 12] =
+                core::array::from_fn(|i| window_start + swung_offset(i as u32, t, swing));
+            assert!(ticks.iter().all(|&tick| tick > now), "{swing}");
+            assert!(ticks.windows(2).all(|w| w[0] < w[1]), "{swing}");
+        }
+    }
+
+    #[test]
+    fn test_swung_offset_clamps_to_window_end() {
+        // Beyond the swing range the second 16th would start on the next window
+        let t = Duration::from_micros(1_000);
+        let window_end = t * (2 * SWING_HALF_INTERVAL) - Duration::from_ticks(500);
+        assert_eq!(swung_offset(SWING_HALF_INTERVAL, t, 50), window_end);
+        assert_eq!(swung_offset(11, t, 50), window_end);
+    }
+
+    #[test]
+    fn test_output_slew_off_jumps() {
+        assert_eq!(output_slew(0, 4095, 0, 500), 4095);
+        assert_eq!(output_slew(4095, 0, 0, 500), 0);
+    }
+
+    #[test]
+    fn test_output_slew_step_change() {
+        // A full scale jump with a 100ms slew, written every 500us, takes 200 writes
+        for (start, target) in [(0, 4095), (4095, 0)] {
+            let mut value = start;
+            let mut writes = 0;
+            while value != target {
+                let next = output_slew(value, target, 100, 500);
+                assert!(next.abs_diff(value) <= 20);
+                if start < target {
+                    assert!(next > value && next <= target);
+                } else {
+                    assert!(next < value && next >= target);
+                }
+                value = next;
+                writes += 1;
+            }
+            assert_eq!(writes, 205);
+            assert_eq!(output_slew(value, target, 100, 500), target);
+        }
+    }
+
+    #[test]
+    fn test_output_slew_follows_elapsed_time() {
+        // A late write makes up for the time it missed
+        assert_eq!(output_slew(0, 4095, 100, 500), 20);
+        assert_eq!(output_slew(0, 4095, 100, 1000), 40);
+        // Even a long slew keeps moving
+        assert_eq!(output_slew(2000, 2100, 1000, 100), 2001);
+        assert_eq!(output_slew(2000, 1900, 1000, 100), 1999);
+        // Small steps land on the target
+        assert_eq!(output_slew(2000, 2005, 100, 500), 2005);
+    }
+
+    #[test]
+    fn test_scale_input_identity() {
+        for value in [0, 1, 2048, 4094, 4095] {
+            assert_eq!(scale_input(value, 1.0, 0), value);
+        }
+    }
+
+    #[test]
+    fn test_scale_input_gain_and_offset() {
+        assert_eq!(scale_input(1000, 2.0, 0), 2000);
+        assert_eq!(scale_input(1000, 0.5, 0), 500);
+        assert_eq!(scale_input(1000, 1.0, 100), 1100);
+        assert_eq!(scale_input(1000, 1.0, -100), 900);
+        // Gain is applied before the offset
+        assert_eq!(scale_input(1000, 2.0, -500), 1500);
+        assert_eq!(scale_input(3, 0.5, 0), 2);
+    }
+
+    #[test]
+    fn test_scale_input_clamps() {
+        assert_eq!(scale_input(4095, 2.0, 0), 4095);
+        assert_eq!(scale_input(4095, 1.0, 1), 4095);
+        assert_eq!(scale_input(0, 1.0, -1), 0);
+        assert_eq!(scale_input(100, 1.0, -200), 0);
+        assert_eq!(scale_input(2048, -1.0, 0), 0);
+        assert_eq!(scale_input(2048, -1.0, 4095), 2047);
+        assert_eq!(scale_input(0, 1.0, i16::MAX), 4095);
+        assert_eq!(scale_input(4095, 1.0, i16::MIN), 0);
+    }
+
+    // Gate level over a run of steps that all play, with a low sample for every gate that ends
+    // before the step is over. Returns the number of rising edges.
+    fn gate_edges(legato: &[bool], retrigger: bool) -> usize {
+        let mut held = false;
+        let mut edges = 0;
+        for &tie in legato {
+            match step_gate(held, true, retrigger) {
+                StepGate::Low => held = false,
+                StepGate::High => {
+                    if !held {
+                        edges += 1;
+                    }
+                    held = true;
+                }
+                StepGate::Retrigger => {
+                    edges += 1;
+                    held = true;
+                }
+            }
+            if !tie {
+                held = false;
+            }
+        }
+        edges
+    }
+
+    #[test]
+    fn test_step_gate() {
+        assert_eq!(step_gate(false, false, false), StepGate::Low);
+        assert_eq!(step_gate(true, false, true), StepGate::Low);
+        assert_eq!(step_gate(false, true, false), StepGate::High);
+        assert_eq!(step_gate(false, true, true), StepGate::High);
+        assert_eq!(step_gate(true, true, false), StepGate::High);
+        assert_eq!(step_gate(true, true, true), StepGate::Retrigger);
+    }
+
+    #[test]
+    fn test_legato_steps_tie_unless_retriggered() {
+        let tied = [true, true, true, false];
+        assert_eq!(gate_edges(&tied, false), 1);
+        assert_eq!(gate_edges(&tied, true), 4);
+        // Steps that end their gate are retriggered either way
+        let detached = [false; 4];
+        assert_eq!(gate_edges(&detached, false), 4);
+        assert_eq!(gate_edges(&detached, true), 4);
+        let mixed = [true, false, true, false];
+        assert_eq!(gate_edges(&mixed, false), 2);
+        assert_eq!(gate_edges(&mixed, true), 4);
+    }
+
+    // Gate and sounding MIDI note of a sequencer track after the clock stops
+    fn stop_track(mode: StopMode, gate: bool, note: Option<u8>) -> (bool, Option<u8>) {
+        if mode.keeps_gate(gate) {
+            (gate, note)
+        } else {
+            (false, None)
+        }
+    }
+
+    #[test]
+    fn test_stop_release() {
+        let mode = StopMode::from_hold(false);
+        assert_eq!(mode, StopMode::Release);
+        assert_eq!(stop_track(mode, true, Some(60)), (false, None));
+        assert_eq!(stop_track(mode, false, None), (false, None));
+    }
+
+    #[test]
+    fn test_stop_hold() {
+        let mode = StopMode::from_hold(true);
+        assert_eq!(mode, StopMode::Hold);
+        assert_eq!(stop_track(mode, true, Some(60)), (true, Some(60)));
+        // A gate that already ended stays off
+        assert_eq!(stop_track(mode, false, None), (false, None));
+    }
+
+    // Steps of two tracks with different lengths and divisions, sampled on the first tick after
+    // a reset that comes after `ticks_before_reset` ticks
+    fn steps_after_reset(free: bool, ticks_before_reset: u32) -> [u32; 2] {
+        const LENGTHS: [u32; 2] = [3, 4];
+        const DIVISIONS: [u32; 2] = [2, 3];
+        let mut tracks = [TrackClock::new(free); 2];
+        let mut clock_ticks = 0;
+        for _ in 0..ticks_before_reset {
+            for track in tracks.iter_mut() {
+                track.tick(clock_ticks);
+            }
+            clock_ticks += 1;
+        }
+        // The clock's own count starts over
+        clock_ticks = 0;
+        tracks.iter_mut().for_each(TrackClock::reset);
+        core::array::from_fn(|n| tracks[n].tick(clock_ticks) / DIVISIONS[n] % LENGTHS[n])
+    }
+
+    #[test]
+    fn test_track_clock_sync_realigns_on_reset() {
+        for ticks in [0, 5, 13, 100] {
+            assert_eq!(steps_after_reset(false, ticks), [0, 0], "{ticks} ticks");
+        }
+        let mut track = TrackClock::new(false);
+        assert_eq!(track.tick(42), 42);
+    }
+
+    #[test]
+    fn test_button_taps() {
+        let mut taps = ButtonTaps::default();
+        taps.press(2);
+        assert!(taps.release(2));
+
+        // Moving a fader while the button is held is a hold, not a tap
+        taps.press(2);
+        taps.fader_moved();
+        assert!(!taps.release(2));
+
+        // The next press starts over
+        taps.press(2);
+        assert!(taps.release(2));
+
+        // A release without a press is not a tap
+        assert!(!taps.release(1));
+
+        // Only the buttons held while the fader moved are used up
+        taps.press(0);
+        taps.fader_moved();
+        taps.press(3);
+        assert!(!taps.release(0));
+        assert!(taps.release(3));
+    }
+
+    #[test]
+    fn test_track_clock_free_drifts_through_reset() {
+        assert_eq!(steps_after_reset(true, 0), [0, 0]);
+        assert_eq!(steps_after_reset(true, 13), [0, 0]);
+        assert_eq!(steps_after_reset(true, 5), [2, 1]);
+        assert_eq!(steps_after_reset(true, 100), [2, 1]);
+        // The clock's count doesn't matter, the track counts its own ticks
+        let mut track = TrackClock::new(true);
+        assert_eq!(track.tick(42), 0);
+        assert_eq!(track.tick(0), 1);
+    }
+}